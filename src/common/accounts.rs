@@ -2,3 +2,5 @@ use solana_sdk::pubkey;
 use solana_sdk::pubkey::Pubkey;
 
 pub const PUBKEY_WSOL: Pubkey = pubkey!("So11111111111111111111111111111111111111112");
+pub const PUBKEY_USDC: Pubkey = pubkey!("EPjFWdd5AufqSSqeM2qN1xzybapC8G4wEGGkZwyTDt1v");
+pub const PUBKEY_USD1: Pubkey = pubkey!("USD1ttGY1N17NEEHLmELoaybftRBUSErhqYiQzvEmuB");
@@ -0,0 +1,478 @@
+use super::slot_clock::SlotClock;
+use crate::{
+    errors::trading_endpoint_error::TradingEndpointError,
+    swqos::{SWQoSRuntime, SWQoSType},
+};
+use parking_lot::RwLock;
+use serde::{Deserialize, Serialize};
+use solana_client::nonblocking::rpc_client::RpcClient;
+use solana_sdk::{commitment_config::CommitmentConfig, pubkey::Pubkey};
+use std::{
+    collections::{HashMap, HashSet},
+    str::FromStr,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Weak,
+    },
+    time::Duration,
+};
+use tokio::task::JoinHandle;
+use tracing::warn;
+
+pub const JITO_VALIDATORS_API: &str = "https://kobe.mainnet.jito.network/api/v1/validators";
+pub const DEFAULT_LOOKAHEAD_SLOTS: u64 = 8;
+const REFRESH_BACKOFF_MIN: Duration = Duration::from_secs(1);
+const REFRESH_BACKOFF_MAX: Duration = Duration::from_secs(60);
+
+/// Block producing client run by a validator
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum ValidatorClient {
+    Jito,
+    Other,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LeaderRequirement {
+    /// Lands with any leader
+    Any,
+    /// Lands only when a Jito validator leads
+    Jito,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+pub enum RouteReason {
+    /// No leader schedule available, every SWQoS is used
+    Unrouted,
+    /// A leader able to land the transaction is upcoming
+    LeaderUpcoming,
+    /// No Jito leader within the lookahead window
+    NoJitoLeader,
+    /// No SWQoS matched the upcoming leaders, every SWQoS is used
+    Fallback,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct UpcomingLeader {
+    pub slot: u64,
+    pub identity: Pubkey,
+    pub client: ValidatorClient,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct SWQoSRoute {
+    pub name: String,
+    pub selected: bool,
+    pub reason: RouteReason,
+}
+
+/// Which SWQoS runtimes a transaction was sent to and why
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct RoutingDecision {
+    pub slot: Option<u64>,
+    pub upcoming_leaders: Vec<UpcomingLeader>,
+    pub routes: Vec<SWQoSRoute>,
+}
+
+impl RoutingDecision {
+    pub fn unrouted(swqos: &[Arc<SWQoSRuntime>]) -> Self {
+        Self {
+            slot: None,
+            upcoming_leaders: vec![],
+            routes: swqos
+                .iter()
+                .map(|w| SWQoSRoute {
+                    name: w.get_client_name().to_string(),
+                    selected: true,
+                    reason: RouteReason::Unrouted,
+                })
+                .collect(),
+        }
+    }
+
+    pub fn is_selected(&self, index: usize) -> bool {
+        self.routes.get(index).map(|route| route.selected).unwrap_or(true)
+    }
+}
+
+impl SWQoSType {
+    pub fn leader_requirement(&self) -> LeaderRequirement {
+        match self {
+            SWQoSType::Jito(_) => LeaderRequirement::Jito,
+            _ => LeaderRequirement::Any,
+        }
+    }
+}
+
+struct EpochSchedule {
+    first_slot: u64,
+    /// Last slot of the current epoch, the schedule being refreshed past it
+    last_slot: u64,
+    /// Last slot with a known leader, in the next epoch when its schedule was already published
+    last_leader_slot: u64,
+    leaders: HashMap<u64, Pubkey>,
+    clients: HashMap<Pubkey, ValidatorClient>,
+}
+
+#[derive(Deserialize)]
+struct JitoValidators {
+    validators: Vec<JitoValidator>,
+}
+
+#[derive(Deserialize)]
+struct JitoValidator {
+    vote_account: String,
+    #[serde(default)]
+    running_jito: bool,
+}
+
+/// Routes transactions to the SWQoS runtimes able to land with the upcoming leaders
+pub struct LeaderRouter {
+    pub rpc: Arc<RpcClient>,
+    pub slot_clock: Arc<SlotClock>,
+    pub lookahead_slots: u64,
+    http: reqwest::Client,
+    schedule: RwLock<Option<EpochSchedule>>,
+    refreshing: AtomicBool,
+}
+
+impl LeaderRouter {
+    pub fn new(rpc: Arc<RpcClient>, slot_clock: Arc<SlotClock>, lookahead_slots: u64) -> Self {
+        Self {
+            rpc,
+            slot_clock,
+            lookahead_slots: lookahead_slots.max(1),
+            http: reqwest::Client::new(),
+            schedule: RwLock::new(None),
+            refreshing: AtomicBool::new(false),
+        }
+    }
+
+    /// Load the leader schedule of the current and next epoch and the client run by each leader
+    pub async fn refresh(&self) -> Result<(), TradingEndpointError> {
+        let epoch_info = self.rpc.get_epoch_info_with_commitment(CommitmentConfig::processed()).await?;
+        let first_slot = epoch_info.absolute_slot - epoch_info.slot_index;
+        let last_slot = first_slot + epoch_info.slots_in_epoch - 1;
+        let (mut schedule, next_schedule, jito_identities) = tokio::try_join!(
+            self.load_leader_schedule(first_slot),
+            async {
+                // Lookahead near the end of the epoch reaches into the next one, routing there is skipped when its schedule is missing
+                Ok(self
+                    .load_leader_schedule(last_slot + 1)
+                    .await
+                    .inspect_err(|e| warn!("Failed to load the next epoch leader schedule: {:?}", e))
+                    .ok())
+            },
+            self.load_jito_identities()
+        )?;
+        let last_leader_slot = match next_schedule {
+            Some(next_schedule) => {
+                schedule.extend(next_schedule);
+                last_slot + epoch_info.slots_in_epoch
+            }
+            None => last_slot,
+        };
+
+        let clients = schedule
+            .values()
+            .map(|identity| {
+                let client = match jito_identities.contains(identity) {
+                    true => ValidatorClient::Jito,
+                    false => ValidatorClient::Other,
+                };
+                (*identity, client)
+            })
+            .collect();
+
+        *self.schedule.write() = Some(EpochSchedule {
+            first_slot,
+            last_slot,
+            last_leader_slot,
+            leaders: schedule,
+            clients,
+        });
+        self.slot_clock.observe(epoch_info.absolute_slot);
+        Ok(())
+    }
+
+    async fn load_leader_schedule(&self, first_slot: u64) -> Result<HashMap<u64, Pubkey>, TradingEndpointError> {
+        let schedule = self
+            .rpc
+            .get_leader_schedule(Some(first_slot))
+            .await?
+            .ok_or_else(|| TradingEndpointError::CustomError(format!("Leader schedule not found for slot: {}", first_slot)))?;
+
+        let mut leaders = HashMap::new();
+        for (identity, slots) in schedule {
+            let identity = Pubkey::from_str(&identity).map_err(|e| TradingEndpointError::CustomError(e.to_string()))?;
+            for slot in slots {
+                leaders.insert(first_slot + slot as u64, identity);
+            }
+        }
+        Ok(leaders)
+    }
+
+    async fn load_jito_identities(&self) -> Result<HashSet<Pubkey>, TradingEndpointError> {
+        let get_validators = async {
+            let response = self.http.get(JITO_VALIDATORS_API).send().await?;
+            response.json::<JitoValidators>().await
+        };
+        let (validators, vote_accounts) = tokio::try_join!(
+            async {
+                get_validators
+                    .await
+                    .map_err(|e| TradingEndpointError::CustomError(format!("Failed to load Jito validators: {}", e)))
+            },
+            async { self.rpc.get_vote_accounts().await.map_err(TradingEndpointError::from) },
+        )?;
+
+        let jito_votes = validators
+            .validators
+            .into_iter()
+            .filter(|v| v.running_jito)
+            .map(|v| v.vote_account)
+            .collect::<HashSet<_>>();
+
+        Ok(vote_accounts
+            .current
+            .into_iter()
+            .chain(vote_accounts.delinquent)
+            .filter(|v| jito_votes.contains(&v.vote_pubkey))
+            .filter_map(|v| Pubkey::from_str(&v.node_pubkey).ok())
+            .collect())
+    }
+
+    /// Refresh the schedule in the background with backoff until it loads, unless a refresh is already running
+    pub fn spawn_refresh(self: &Arc<Self>) -> Option<JoinHandle<()>> {
+        if self.refreshing.swap(true, Ordering::AcqRel) {
+            return None;
+        }
+
+        let weak_router: Weak<Self> = Arc::downgrade(self);
+        Some(tokio::spawn(async move {
+            let mut backoff = REFRESH_BACKOFF_MIN;
+            while let Some(router) = weak_router.upgrade() {
+                match router.refresh().await {
+                    Ok(()) => {
+                        router.refreshing.store(false, Ordering::Release);
+                        return;
+                    }
+                    Err(e) => warn!("Failed to refresh the leader schedule, retrying in {:?}: {:?}", backoff, e),
+                }
+                drop(router);
+                tokio::time::sleep(backoff).await;
+                backoff = (backoff * 2).min(REFRESH_BACKOFF_MAX);
+            }
+        }))
+    }
+
+    /// Current slot when the schedule covers it, kicking off a background refresh once the current epoch is over
+    async fn current_slot(self: &Arc<Self>) -> Result<Option<u64>, TradingEndpointError> {
+        let slot = self.slot_clock.current_slot_or_sync(&self.rpc).await?;
+        let (expired, covered) = match self.schedule.read().as_ref() {
+            Some(schedule) => (slot > schedule.last_slot, slot <= schedule.last_leader_slot),
+            None => (true, false),
+        };
+        if expired {
+            self.spawn_refresh();
+        }
+        if !covered {
+            return Ok(None);
+        }
+
+        Ok(Some(self.slot_clock.current_slot().unwrap_or(slot)))
+    }
+
+    /// Leaders of the next `lookahead_slots` slots starting at `slot`, none when the schedule does not cover all of them
+    pub fn upcoming_leaders(&self, slot: u64) -> Vec<UpcomingLeader> {
+        let schedule = self.schedule.read();
+        let Some(schedule) = schedule.as_ref() else {
+            return vec![];
+        };
+        if slot + self.lookahead_slots - 1 > schedule.last_leader_slot {
+            return vec![];
+        }
+
+        (slot.max(schedule.first_slot)..slot + self.lookahead_slots)
+            .filter_map(|slot| {
+                let identity = schedule.leaders.get(&slot)?;
+                let client = schedule.clients.get(identity).copied().unwrap_or(ValidatorClient::Other);
+                Some(UpcomingLeader {
+                    slot,
+                    identity: *identity,
+                    client,
+                })
+            })
+            .collect()
+    }
+
+    /// Pick the SWQoS runtimes likely to land in the upcoming slots
+    pub async fn route(self: &Arc<Self>, swqos: &[Arc<SWQoSRuntime>]) -> RoutingDecision {
+        let slot = match self.current_slot().await {
+            Ok(Some(slot)) => slot,
+            Ok(None) => return RoutingDecision::unrouted(swqos),
+            Err(e) => {
+                warn!("Leader routing unavailable, sending to every SWQoS: {:?}", e);
+                return RoutingDecision::unrouted(swqos);
+            }
+        };
+
+        let upcoming_leaders = self.upcoming_leaders(slot);
+        if upcoming_leaders.is_empty() {
+            return RoutingDecision::unrouted(swqos);
+        }
+
+        let jito_upcoming = upcoming_leaders.iter().any(|leader| leader.client == ValidatorClient::Jito);
+        let mut routes = swqos
+            .iter()
+            .map(|w| {
+                let (selected, reason) = match (w.config.kind.leader_requirement(), jito_upcoming) {
+                    (LeaderRequirement::Jito, false) => (false, RouteReason::NoJitoLeader),
+                    _ => (true, RouteReason::LeaderUpcoming),
+                };
+                SWQoSRoute {
+                    name: w.get_client_name().to_string(),
+                    selected,
+                    reason,
+                }
+            })
+            .collect::<Vec<_>>();
+
+        if !routes.iter().any(|route| route.selected) {
+            routes.iter_mut().for_each(|route| {
+                route.selected = true;
+                route.reason = RouteReason::Fallback;
+            });
+        }
+
+        RoutingDecision {
+            slot: Some(slot),
+            upcoming_leaders,
+            routes,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        common::slot_clock::MockTimeSource,
+        swqos::{SWQoSConfig, SWQoSType},
+    };
+
+    const FIRST_SLOT: u64 = 1_000;
+    const SLOTS_IN_EPOCH: u64 = 100;
+    const LOOKAHEAD_SLOTS: u64 = 8;
+
+    fn rpc() -> Arc<RpcClient> {
+        Arc::new(RpcClient::new("http://127.0.0.1:8899".to_string()))
+    }
+
+    /// Router at `slot` with `jito_slots` led by a Jito validator and every other slot of the known epochs by another one
+    fn router(slot: u64, epochs: u64, jito_slots: &[u64]) -> Arc<LeaderRouter> {
+        let clock = Arc::new(SlotClock::with_time_source(Arc::new(MockTimeSource::default())));
+        clock.observe(slot);
+        let router = Arc::new(LeaderRouter::new(rpc(), clock, LOOKAHEAD_SLOTS));
+        // Keep expired schedules from refreshing against the offline RPC
+        router.refreshing.store(true, Ordering::Release);
+
+        let (jito, other) = (Pubkey::new_unique(), Pubkey::new_unique());
+        let last_leader_slot = FIRST_SLOT + SLOTS_IN_EPOCH * epochs - 1;
+        *router.schedule.write() = Some(EpochSchedule {
+            first_slot: FIRST_SLOT,
+            last_slot: FIRST_SLOT + SLOTS_IN_EPOCH - 1,
+            last_leader_slot,
+            leaders: (FIRST_SLOT..=last_leader_slot)
+                .map(|slot| (slot, if jito_slots.contains(&slot) { jito } else { other }))
+                .collect(),
+            clients: HashMap::from([(jito, ValidatorClient::Jito), (other, ValidatorClient::Other)]),
+        });
+        router
+    }
+
+    fn runtimes(kinds: &[SWQoSType]) -> Vec<Arc<SWQoSRuntime>> {
+        kinds
+            .iter()
+            .map(|kind| Arc::new(SWQoSRuntime::new_single(SWQoSConfig::new(kind.clone()), rpc())))
+            .collect()
+    }
+
+    fn jito_and_nextblock() -> Vec<Arc<SWQoSRuntime>> {
+        runtimes(&[SWQoSType::Jito(String::new()), SWQoSType::NextBlock(String::new(), String::new())])
+    }
+
+    fn routes(decision: &RoutingDecision) -> Vec<(bool, RouteReason)> {
+        decision.routes.iter().map(|route| (route.selected, route.reason)).collect()
+    }
+
+    #[tokio::test]
+    async fn sends_to_jito_when_a_jito_leader_is_upcoming() {
+        let decision = router(1_010, 1, &[1_015]).route(&jito_and_nextblock()).await;
+
+        assert_eq!(decision.slot, Some(1_010));
+        assert_eq!(decision.upcoming_leaders.len() as u64, LOOKAHEAD_SLOTS);
+        assert_eq!(decision.upcoming_leaders[5].client, ValidatorClient::Jito);
+        assert_eq!(
+            routes(&decision),
+            vec![(true, RouteReason::LeaderUpcoming), (true, RouteReason::LeaderUpcoming)]
+        );
+    }
+
+    #[tokio::test]
+    async fn skips_jito_without_an_upcoming_jito_leader() {
+        // The Jito leader is just past the lookahead window
+        let decision = router(1_010, 1, &[1_018]).route(&jito_and_nextblock()).await;
+
+        assert!(decision.upcoming_leaders.iter().all(|leader| leader.client == ValidatorClient::Other));
+        assert_eq!(routes(&decision), vec![(false, RouteReason::NoJitoLeader), (true, RouteReason::LeaderUpcoming)]);
+        assert!(!decision.is_selected(0));
+        assert!(decision.is_selected(1));
+    }
+
+    #[tokio::test]
+    async fn falls_back_to_every_swqos_when_none_matches() {
+        let swqos = runtimes(&[SWQoSType::Jito(String::new()), SWQoSType::Jito(String::new())]);
+        let decision = router(1_010, 1, &[]).route(&swqos).await;
+
+        assert_eq!(decision.slot, Some(1_010));
+        assert_eq!(routes(&decision), vec![(true, RouteReason::Fallback), (true, RouteReason::Fallback)]);
+    }
+
+    #[tokio::test]
+    async fn sends_to_every_swqos_without_a_schedule() {
+        let router = router(1_010, 1, &[]);
+        *router.schedule.write() = None;
+        let decision = router.route(&jito_and_nextblock()).await;
+
+        assert_eq!(decision.slot, None);
+        assert_eq!(routes(&decision), vec![(true, RouteReason::Unrouted), (true, RouteReason::Unrouted)]);
+    }
+
+    #[tokio::test]
+    async fn sends_to_every_swqos_when_the_lookahead_crosses_into_an_unknown_epoch() {
+        let decision = router(1_095, 1, &[]).route(&jito_and_nextblock()).await;
+
+        assert_eq!(decision.slot, None);
+        assert!(decision.upcoming_leaders.is_empty());
+        assert_eq!(routes(&decision), vec![(true, RouteReason::Unrouted), (true, RouteReason::Unrouted)]);
+    }
+
+    #[tokio::test]
+    async fn routes_across_the_epoch_boundary_with_the_next_schedule() {
+        let router = router(1_095, 2, &[1_101]);
+        let decision = router.route(&jito_and_nextblock()).await;
+
+        let slots = decision.upcoming_leaders.iter().map(|leader| leader.slot).collect::<Vec<_>>();
+        assert_eq!(slots, (1_095..1_103).collect::<Vec<_>>());
+        assert_eq!(
+            routes(&decision),
+            vec![(true, RouteReason::LeaderUpcoming), (true, RouteReason::LeaderUpcoming)]
+        );
+
+        // Past the current epoch the next schedule keeps routing while it refreshes
+        router.slot_clock.observe(1_150);
+        let decision = router.route(&jito_and_nextblock()).await;
+        assert_eq!(decision.slot, Some(1_150));
+        assert_eq!(routes(&decision), vec![(false, RouteReason::NoJitoLeader), (true, RouteReason::LeaderUpcoming)]);
+    }
+}
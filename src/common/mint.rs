@@ -0,0 +1,170 @@
+use crate::errors::trading_endpoint_error::TradingEndpointError;
+use solana_sdk::{account::Account, epoch_schedule::EpochSchedule, pubkey::Pubkey};
+use spl_associated_token_account::get_associated_token_address_with_program_id;
+use spl_token_2022::{
+    extension::{transfer_fee::TransferFeeConfig, BaseStateWithExtensions, StateWithExtensions},
+    state::Mint,
+};
+
+/// Token program and transfer fee of a mint
+#[derive(Debug, Clone, Copy)]
+pub struct MintInfo {
+    pub mint: Pubkey,
+    pub token_program: Pubkey,
+    pub decimals: u8,
+    pub transfer_fee_config: Option<TransferFeeConfig>,
+}
+
+impl MintInfo {
+    /// Mint owned by the legacy token program, used when the mint is known without fetching it
+    pub fn spl_token(mint: Pubkey, decimals: u8) -> Self {
+        Self {
+            mint,
+            token_program: spl_token::ID,
+            decimals,
+            transfer_fee_config: None,
+        }
+    }
+
+    pub fn from_account(mint: &Pubkey, account: &Account) -> Result<Self, TradingEndpointError> {
+        if account.owner != spl_token::ID && account.owner != spl_token_2022::ID {
+            return Err(TradingEndpointError::CustomError(format!(
+                "Mint {} is owned by {}, not a token program",
+                mint, account.owner
+            )));
+        }
+
+        let state = StateWithExtensions::<Mint>::unpack(&account.data).map_err(|e| TradingEndpointError::CustomError(e.to_string()))?;
+        let transfer_fee_config = match account.owner == spl_token_2022::ID {
+            true => state.get_extension::<TransferFeeConfig>().ok().copied(),
+            false => None,
+        };
+
+        Ok(Self {
+            mint: *mint,
+            token_program: account.owner,
+            decimals: state.base.decimals,
+            transfer_fee_config,
+        })
+    }
+
+    pub fn is_token_2022(&self) -> bool {
+        self.token_program == spl_token_2022::ID
+    }
+
+    pub fn get_associated_token_address(&self, owner: &Pubkey) -> Pubkey {
+        get_associated_token_address_with_program_id(owner, &self.mint, &self.token_program)
+    }
+
+    /// Tokens withheld when `amount` is transferred at `slot`
+    pub fn get_transfer_fee(&self, slot: u64, amount: u64) -> u64 {
+        self.transfer_fee_config
+            .and_then(|config| config.calculate_epoch_fee(Self::get_epoch(slot), amount))
+            .unwrap_or_default()
+    }
+
+    /// Tokens withheld when a transfer at `slot` has to deliver `amount`
+    pub fn get_inverse_transfer_fee(&self, slot: u64, amount: u64) -> u64 {
+        self.transfer_fee_config
+            .and_then(|config| config.calculate_inverse_epoch_fee(Self::get_epoch(slot), amount))
+            .unwrap_or_default()
+    }
+
+    fn get_epoch(slot: u64) -> u64 {
+        // Mainnet epochs have a fixed length
+        EpochSchedule::without_warmup().get_epoch(slot)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_program::program_pack::Pack;
+    use spl_token_2022::extension::{transfer_fee::TransferFee, BaseStateWithExtensionsMut, ExtensionType, StateWithExtensionsMut};
+
+    fn mint_account(owner: Pubkey, data: Vec<u8>) -> Account {
+        Account {
+            lamports: 1_461_600,
+            data,
+            owner,
+            executable: false,
+            rent_epoch: 0,
+        }
+    }
+
+    fn legacy_mint_data(decimals: u8) -> Vec<u8> {
+        let mut data = vec![0; spl_token::state::Mint::LEN];
+        let mint = spl_token::state::Mint {
+            decimals,
+            is_initialized: true,
+            ..Default::default()
+        };
+        spl_token::state::Mint::pack(mint, &mut data).unwrap();
+        data
+    }
+
+    fn token_2022_mint_data(decimals: u8, transfer_fee_basis_points: u16, maximum_fee: u64) -> Vec<u8> {
+        let len = ExtensionType::try_calculate_account_len::<Mint>(&[ExtensionType::TransferFeeConfig]).unwrap();
+        let mut data = vec![0; len];
+        let mut state = StateWithExtensionsMut::<Mint>::unpack_uninitialized(&mut data).unwrap();
+        let transfer_fee = TransferFee {
+            epoch: 0.into(),
+            maximum_fee: maximum_fee.into(),
+            transfer_fee_basis_points: transfer_fee_basis_points.into(),
+        };
+        let config = state.init_extension::<TransferFeeConfig>(true).unwrap();
+        config.older_transfer_fee = transfer_fee;
+        config.newer_transfer_fee = transfer_fee;
+        state.base = Mint {
+            decimals,
+            is_initialized: true,
+            ..Default::default()
+        };
+        state.pack_base();
+        state.init_account_type().unwrap();
+        data
+    }
+
+    #[test]
+    fn legacy_mint() {
+        let mint = Pubkey::new_unique();
+        let owner = Pubkey::new_unique();
+        let info = MintInfo::from_account(&mint, &mint_account(spl_token::ID, legacy_mint_data(6))).unwrap();
+
+        assert_eq!(info.token_program, spl_token::ID);
+        assert_eq!(info.decimals, 6);
+        assert!(!info.is_token_2022());
+        assert!(info.transfer_fee_config.is_none());
+        assert_eq!(info.get_transfer_fee(0, 1_000_000), 0);
+        assert_eq!(
+            info.get_associated_token_address(&owner),
+            get_associated_token_address_with_program_id(&owner, &mint, &spl_token::ID)
+        );
+    }
+
+    #[test]
+    fn token_2022_mint_with_transfer_fee() {
+        let mint = Pubkey::new_unique();
+        let owner = Pubkey::new_unique();
+        let info = MintInfo::from_account(&mint, &mint_account(spl_token_2022::ID, token_2022_mint_data(9, 100, 5_000))).unwrap();
+
+        assert_eq!(info.token_program, spl_token_2022::ID);
+        assert_eq!(info.decimals, 9);
+        assert!(info.is_token_2022());
+        assert_eq!(
+            info.get_associated_token_address(&owner),
+            get_associated_token_address_with_program_id(&owner, &mint, &spl_token_2022::ID)
+        );
+
+        // 1% capped at 5_000
+        assert_eq!(info.get_transfer_fee(0, 100_000), 1_000);
+        assert_eq!(info.get_transfer_fee(0, 1_000_000), 5_000);
+        assert_eq!(info.get_inverse_transfer_fee(0, 99_000), 1_000);
+    }
+
+    #[test]
+    fn rejects_accounts_outside_the_token_programs() {
+        let mint = Pubkey::new_unique();
+        assert!(MintInfo::from_account(&mint, &mint_account(Pubkey::new_unique(), legacy_mint_data(6))).is_err());
+    }
+}
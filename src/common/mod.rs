@@ -1,5 +1,9 @@
 pub mod accounts;
 pub mod lamports;
+pub mod leader_schedule;
+pub mod mint;
+pub mod resubmission;
+pub mod slot_clock;
 pub mod trading_client;
 pub mod trading_endpoint;
 pub mod transaction;
@@ -0,0 +1,527 @@
+use super::{
+    leader_schedule::RoutingDecision,
+    trading_endpoint::{AdditionalFees, TradingEndpoint, TransactionType, TxSettings},
+};
+use crate::{errors::trading_endpoint_error::TradingEndpointError, instruction::builder::PriorityFee};
+use serde::{Deserialize, Serialize};
+use solana_client::nonblocking::nonce_utils;
+use solana_sdk::{
+    commitment_config::CommitmentConfig,
+    hash::Hash,
+    instruction::Instruction,
+    pubkey::Pubkey,
+    signature::{Keypair, Signature},
+    signer::Signer,
+    transaction::Transaction,
+};
+use tracing::{debug, info, warn};
+
+const MAX_SIGNATURE_STATUSES: usize = 256;
+
+/// Durable nonce shared by every escalation of a trade, so at most one of them can execute
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct DurableNonce {
+    pub account: Pubkey,
+    pub authority: Pubkey,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct EscalationConfig {
+    /// Broadcasts including the first one
+    pub max_attempts: u32,
+    /// Slots to wait for a landing before escalating
+    pub slots_per_attempt: u64,
+    /// Unit price multiplier applied on every escalation, 15000 = x1.5
+    pub fee_multiplier_bps: u64,
+    /// Tip multiplier applied on every escalation, 15000 = x1.5
+    pub tip_multiplier_bps: u64,
+    /// Ceiling on priority fee plus tip of a single attempt, in lamports
+    pub max_cost: u64,
+}
+
+impl Default for EscalationConfig {
+    fn default() -> Self {
+        Self {
+            max_attempts: 5,
+            slots_per_attempt: 4,
+            fee_multiplier_bps: 15_000,
+            tip_multiplier_bps: 15_000,
+            max_cost: 10_000_000,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub enum ResubmissionOutcome {
+    /// One of the attempts confirmed
+    Confirmed(Signature),
+    /// One of the attempts landed with an error, the nonce is consumed
+    Failed(Signature, String),
+    /// The nonce was advanced without any attempt landing
+    NonceExpired,
+    /// The next escalation would exceed `max_cost`, reported once the nonce moved on without any attempt landing
+    CostCeiling,
+    /// Every attempt was sent without landing within its window, reported once the nonce moved on without any attempt landing
+    AttemptsExhausted,
+}
+
+/// Trade re-signed on every escalation
+pub struct ResubmissionRequest<'a> {
+    pub tx_type: TransactionType,
+    pub payer: &'a Keypair,
+    pub instructions: Vec<Instruction>,
+    pub other_signers: Option<Vec<&'a Keypair>>,
+    pub nonce: DurableNonce,
+    /// Priority fee of the first attempt, the highest configured one when unset
+    pub additional_fee: Option<PriorityFee>,
+    /// Tip of the first attempt on top of the configured tips
+    pub additional_tip: u64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ResubmissionAttempt {
+    pub attempt: u32,
+    pub slot: Option<u64>,
+    pub fee: Option<PriorityFee>,
+    pub tip: u64,
+    pub cost: u64,
+    pub signatures: Vec<Signature>,
+    pub routing: Option<RoutingDecision>,
+    /// Broadcast and RPC errors met while sending or waiting on this attempt
+    pub errors: Vec<String>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ResubmissionReport {
+    pub nonce: Hash,
+    pub outcome: ResubmissionOutcome,
+    pub attempts: Vec<ResubmissionAttempt>,
+    /// Errors met while making sure no attempt can land after the last one
+    pub errors: Vec<String>,
+}
+
+/// Whether any of the signatures landed
+enum Landing {
+    Landed(ResubmissionOutcome),
+    /// Processed but not yet confirmed
+    Pending,
+    None,
+}
+
+/// Priority fee, tip and cost of a planned attempt
+#[derive(Debug, Clone, Copy)]
+struct PlannedAttempt {
+    fee: Option<PriorityFee>,
+    tip: u64,
+    cost: u64,
+}
+
+impl EscalationConfig {
+    fn escalate(value: u64, multiplier_bps: u64) -> u64 {
+        let escalated = (value as u128 * multiplier_bps as u128 / 10_000) as u64;
+        escalated.max(value.saturating_add(1))
+    }
+
+    fn escalate_fee(&self, fee: Option<PriorityFee>) -> Option<PriorityFee> {
+        fee.map(|fee| PriorityFee {
+            unit_limit: fee.unit_limit,
+            unit_price: Self::escalate(fee.unit_price, self.fee_multiplier_bps),
+        })
+    }
+
+    /// Escalate the whole tip paid, `base_tip` being the configured part of it
+    fn escalate_tip(&self, tip: u64, base_tip: u64) -> u64 {
+        Self::escalate(base_tip + tip, self.tip_multiplier_bps) - base_tip
+    }
+
+    /// Attempts allowed by the config and the outcome once all of them failed to land,
+    /// `cost` giving the highest priority fee plus tip of an attempt
+    fn plan(
+        &self,
+        fee: Option<PriorityFee>,
+        tip: u64,
+        base_tip: u64,
+        cost: impl Fn(Option<PriorityFee>, u64) -> u64,
+    ) -> (Vec<PlannedAttempt>, ResubmissionOutcome) {
+        let (mut fee, mut tip) = (fee, tip);
+        let mut attempts = vec![];
+        while attempts.len() < self.max_attempts.max(1) as usize {
+            let cost = cost(fee, tip);
+            if cost > self.max_cost {
+                return (attempts, ResubmissionOutcome::CostCeiling);
+            }
+            attempts.push(PlannedAttempt { fee, tip, cost });
+            fee = self.escalate_fee(fee);
+            tip = self.escalate_tip(tip, base_tip);
+        }
+        (attempts, ResubmissionOutcome::AttemptsExhausted)
+    }
+}
+
+impl TradingEndpoint {
+    /// Read the current value of a durable nonce
+    pub async fn get_nonce_hash(&self, nonce_account: &Pubkey) -> Result<Hash, TradingEndpointError> {
+        let account = nonce_utils::get_account(&self.rpc, nonce_account)
+            .await
+            .map_err(|e| TradingEndpointError::CustomError(e.to_string()))?;
+        let data = nonce_utils::data_from_account(&account).map_err(|e| TradingEndpointError::CustomError(e.to_string()))?;
+        Ok(data.blockhash())
+    }
+
+    /// Highest configured priority fee, as an additional fee that keeps the configured unit limits
+    fn get_base_fee(&self, tx_type: TransactionType) -> Option<PriorityFee> {
+        self.swqos
+            .iter()
+            .filter_map(|swqos| self.get_fee_config(swqos, tx_type, None))
+            .max_by_key(|fee| fee.unit_price)
+            .map(|fee| PriorityFee {
+                unit_limit: 0,
+                unit_price: fee.unit_price,
+            })
+    }
+
+    /// Highest configured tip
+    fn get_base_tip(&self, tx_type: TransactionType) -> u64 {
+        self.swqos
+            .iter()
+            .filter(|swqos| swqos.get_tip_account().is_some())
+            .filter_map(|swqos| self.get_default_tip_for_tx_type(swqos, tx_type).ok())
+            .map(|tip| tip.0)
+            .max()
+            .unwrap_or_default()
+    }
+
+    /// Highest priority fee plus tip paid by any SWQoS for the given additional fee and tip
+    fn get_attempt_cost(&self, tx_type: TransactionType, additional_fee: Option<PriorityFee>, additional_tip: u64) -> u64 {
+        self.swqos
+            .iter()
+            .map(|swqos| {
+                let fee = self
+                    .get_fee_config(swqos, tx_type, additional_fee)
+                    .map(|fee| (fee.unit_limit as u128 * fee.unit_price as u128 / 1_000_000) as u64)
+                    .unwrap_or_default();
+                let tip = match swqos.get_tip_account() {
+                    Some(_) => self.get_default_tip_for_tx_type(swqos, tx_type).map(|w| w.0).unwrap_or_default() + additional_tip,
+                    None => 0,
+                };
+                fee + tip
+            })
+            .max()
+            .unwrap_or_default()
+    }
+
+    /// First landed signature, checking at the confirmed commitment
+    async fn find_landed(&self, signatures: &[Signature]) -> Result<Landing, TradingEndpointError> {
+        let mut landing = Landing::None;
+        for chunk in signatures.chunks(MAX_SIGNATURE_STATUSES) {
+            let statuses = self.rpc.get_signature_statuses(chunk).await?.value;
+            for (signature, status) in chunk.iter().zip(statuses) {
+                let Some(status) = status else {
+                    continue;
+                };
+                if let Some(err) = status.err {
+                    return Ok(Landing::Landed(ResubmissionOutcome::Failed(*signature, err.to_string())));
+                }
+                if status.satisfies_commitment(CommitmentConfig::confirmed()) {
+                    return Ok(Landing::Landed(ResubmissionOutcome::Confirmed(*signature)));
+                }
+                landing = Landing::Pending;
+            }
+        }
+        Ok(landing)
+    }
+
+    /// Outcome of the landed attempt once the nonce moved on, waiting for processed attempts to confirm
+    async fn find_landed_after_nonce(&self, signatures: &[Signature], errors: &mut Vec<String>) -> Option<ResubmissionOutcome> {
+        loop {
+            match self.find_landed(signatures).await {
+                Ok(Landing::Landed(outcome)) => return Some(outcome),
+                Ok(Landing::None) => return None,
+                Ok(Landing::Pending) => {}
+                Err(e) => errors.push(e.to_string()),
+            }
+            tokio::time::sleep(self.slot_clock.slot_duration()).await;
+        }
+    }
+
+    /// Advance the nonce with its own transaction, needing the nonce authority among `signers`
+    async fn advance_nonce(&self, nonce: &DurableNonce, payer: &Keypair, signers: &[&Keypair]) -> Result<Signature, TradingEndpointError> {
+        let authority = std::iter::once(payer)
+            .chain(signers.iter().copied())
+            .find(|signer| signer.pubkey() == nonce.authority)
+            .ok_or_else(|| TradingEndpointError::CustomError(format!("Nonce authority {} is not a signer", nonce.authority)))?;
+        let instruction = solana_sdk::system_instruction::advance_nonce_account(&nonce.account, &nonce.authority);
+        let blockhash = self.get_latest_blockhash().await?;
+        let transaction = Transaction::new_signed_with_payer(&[instruction], Some(&payer.pubkey()), &[payer, authority], blockhash);
+        Ok(self.rpc.send_and_confirm_transaction(&transaction).await?)
+    }
+
+    /// Make sure none of `signatures` can land anymore: advance the nonce when holding its authority,
+    /// otherwise wait until it moves on. Returns the outcome of an attempt that landed in the meantime.
+    async fn settle_nonce(
+        &self,
+        nonce: &DurableNonce,
+        nonce_hash: Hash,
+        payer: &Keypair,
+        signers: &[&Keypair],
+        signatures: &[Signature],
+        errors: &mut Vec<String>,
+    ) -> Option<ResubmissionOutcome> {
+        if let Err(e) = self.advance_nonce(nonce, payer, signers).await {
+            warn!("Failed to advance nonce {}, waiting for it to move on: {:?}", nonce.account, e);
+            errors.push(e.to_string());
+            loop {
+                match self.find_landed(signatures).await {
+                    Ok(Landing::Landed(outcome)) => return Some(outcome),
+                    Ok(_) => {}
+                    Err(e) => errors.push(e.to_string()),
+                }
+                match self.get_nonce_hash(&nonce.account).await {
+                    Ok(hash) if hash != nonce_hash => break,
+                    Ok(_) => {}
+                    Err(e) => errors.push(e.to_string()),
+                }
+                tokio::time::sleep(self.slot_clock.slot_duration()).await;
+            }
+        }
+
+        // An attempt may have landed before the nonce moved on, and only then can it no longer land
+        self.find_landed_after_nonce(signatures, errors).await
+    }
+
+    /// Broadcast until an attempt confirms, re-signing with a higher priority fee and tip every
+    /// `slots_per_attempt` slots. Every attempt advances the same durable nonce, so only one can land,
+    /// and no attempt can land anymore once a non-landed outcome is reported.
+    pub async fn build_and_broadcast_tx_until_confirmed(
+        &self,
+        request: ResubmissionRequest<'_>,
+        escalation: EscalationConfig,
+    ) -> Result<ResubmissionReport, TradingEndpointError> {
+        let ResubmissionRequest {
+            tx_type,
+            payer,
+            instructions,
+            other_signers,
+            nonce,
+            additional_fee,
+            additional_tip,
+        } = request;
+        let nonce_hash = self.get_nonce_hash(&nonce.account).await?;
+        let nonce_ix = solana_sdk::system_instruction::advance_nonce_account(&nonce.account, &nonce.authority);
+        let fee = additional_fee.or_else(|| self.get_base_fee(tx_type));
+        let (planned, exhausted) = escalation.plan(fee, additional_tip, self.get_base_tip(tx_type), |fee, tip| {
+            self.get_attempt_cost(tx_type, fee, tip)
+        });
+        let mut attempts: Vec<ResubmissionAttempt> = vec![];
+        let mut signatures = vec![];
+        let mut errors = vec![];
+
+        let landed = 'attempts: {
+            for (index, planned) in planned.into_iter().enumerate() {
+                debug!("Resubmission attempt {} with fee {:?} and tip {}", index, planned.fee, planned.tip);
+                let slot = self.get_current_slot().await.ok();
+                let broadcast = self
+                    .build_and_broadcast_tx_with_report(
+                        tx_type,
+                        payer,
+                        instructions.clone(),
+                        TxSettings::with_nonce(
+                            nonce_ix.clone(),
+                            nonce_hash,
+                            AdditionalFees {
+                                fee: planned.fee,
+                                tip: planned.tip,
+                            },
+                        ),
+                        other_signers.clone(),
+                    )
+                    .await;
+
+                let mut attempt = ResubmissionAttempt {
+                    attempt: index as u32,
+                    slot,
+                    fee: planned.fee,
+                    tip: planned.tip,
+                    cost: planned.cost,
+                    signatures: vec![],
+                    routing: None,
+                    errors: vec![],
+                };
+                match broadcast {
+                    Ok(report) => {
+                        signatures.extend(report.signatures.iter().copied());
+                        attempt.signatures = report.signatures;
+                        attempt.routing = Some(report.routing);
+                    }
+                    // A rejected broadcast is escalated like one that did not land
+                    Err(e) => attempt.errors.push(e.to_string()),
+                }
+                info!("Resubmission attempt {} sent: {:?}", attempt.attempt, attempt.signatures);
+
+                // Without a slot, every poll is counted as one slot
+                let slots_per_attempt = escalation.slots_per_attempt.max(1);
+                let mut polls = 0;
+                loop {
+                    tokio::time::sleep(self.slot_clock.slot_duration()).await;
+                    polls += 1;
+
+                    match self.find_landed(&signatures).await {
+                        Ok(Landing::Landed(outcome)) => {
+                            attempts.push(attempt);
+                            break 'attempts Some(outcome);
+                        }
+                        Ok(_) => {}
+                        Err(e) => attempt.errors.push(e.to_string()),
+                    }
+
+                    let elapsed = match (slot, self.get_current_slot().await) {
+                        (Some(slot), Ok(current)) => current.saturating_sub(slot),
+                        (_, Err(e)) => {
+                            attempt.errors.push(e.to_string());
+                            polls
+                        }
+                        (None, Ok(_)) => polls,
+                    };
+                    if elapsed >= slots_per_attempt {
+                        break;
+                    }
+                }
+
+                let nonce_moved = match self.get_nonce_hash(&nonce.account).await {
+                    Ok(hash) => hash != nonce_hash,
+                    Err(e) => {
+                        attempt.errors.push(e.to_string());
+                        false
+                    }
+                };
+                attempts.push(attempt);
+                if nonce_moved {
+                    // The nonce may have been advanced by one of our attempts landing in the meantime
+                    let outcome = self.find_landed_after_nonce(&signatures, &mut errors).await;
+                    break 'attempts Some(outcome.unwrap_or(ResubmissionOutcome::NonceExpired));
+                }
+            }
+            None
+        };
+
+        let outcome = match landed {
+            Some(outcome) => outcome,
+            // Nothing was signed, so nothing can land
+            None if attempts.is_empty() => exhausted,
+            None => {
+                let signers = other_signers.unwrap_or_default();
+                self.settle_nonce(&nonce, nonce_hash, payer, &signers, &signatures, &mut errors)
+                    .await
+                    .unwrap_or(exhausted)
+            }
+        };
+
+        Ok(ResubmissionReport {
+            nonce: nonce_hash,
+            outcome,
+            attempts,
+            errors,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        common::lamports::Lamports,
+        swqos::{jito::JITO_ENDPOINT_MAINNET, SWQoSConfig, SWQoSRuntime, SWQoSType},
+    };
+    use solana_client::nonblocking::rpc_client::RpcClient;
+    use std::sync::Arc;
+
+    const UNIT_LIMIT: u32 = 200_000;
+
+    /// Jito with a 1_000_000 lamports tip and a 200_000 units priority fee of `unit_price`
+    fn endpoint(unit_price: u64) -> TradingEndpoint {
+        let rpc = Arc::new(RpcClient::new("http://127.0.0.1:8899".to_string()));
+        let config = SWQoSConfig::new(SWQoSType::Jito(JITO_ENDPOINT_MAINNET.to_string()))
+            .with_buy_tip(Lamports(1_000_000))
+            .with_buy_fee(PriorityFee {
+                unit_limit: UNIT_LIMIT,
+                unit_price,
+            });
+        TradingEndpoint::new(rpc.clone(), vec![Arc::new(SWQoSRuntime::new_single(config, rpc))])
+    }
+
+    fn plan(endpoint: &TradingEndpoint, escalation: &EscalationConfig) -> (Vec<PlannedAttempt>, ResubmissionOutcome) {
+        let tx_type = TransactionType::Buy;
+        escalation.plan(endpoint.get_base_fee(tx_type), 0, endpoint.get_base_tip(tx_type), |fee, tip| {
+            endpoint.get_attempt_cost(tx_type, fee, tip)
+        })
+    }
+
+    #[test]
+    fn escalates_priority_fee() {
+        let escalation = EscalationConfig::default();
+        let fee = escalation.escalate_fee(Some(PriorityFee {
+            unit_limit: UNIT_LIMIT,
+            unit_price: 1_000,
+        }));
+        assert_eq!(fee.map(|fee| (fee.unit_limit, fee.unit_price)), Some((UNIT_LIMIT, 1_500)));
+
+        // Escalating always raises the price, even from zero or with a multiplier of one
+        assert_eq!(escalation.escalate_fee(Some(PriorityFee::default())).map(|fee| fee.unit_price), Some(1));
+        let flat = EscalationConfig {
+            fee_multiplier_bps: 10_000,
+            ..Default::default()
+        };
+        assert_eq!(flat.escalate_fee(fee).map(|fee| fee.unit_price), Some(1_501));
+        assert!(escalation.escalate_fee(None).is_none());
+    }
+
+    #[test]
+    fn escalates_whole_tip() {
+        let escalation = EscalationConfig::default();
+        // The configured 1_000_000 lamports are paid anyway, so the additional tip covers the escalation of both
+        assert_eq!(escalation.escalate_tip(0, 1_000_000), 500_000);
+        assert_eq!(escalation.escalate_tip(500_000, 1_000_000), 1_250_000);
+        assert_eq!(escalation.escalate_tip(0, 0), 1);
+    }
+
+    #[test]
+    fn plans_escalating_attempts() {
+        let endpoint = endpoint(1_000_000);
+        let escalation = EscalationConfig {
+            max_attempts: 3,
+            ..Default::default()
+        };
+        let (attempts, outcome) = plan(&endpoint, &escalation);
+        assert!(matches!(outcome, ResubmissionOutcome::AttemptsExhausted));
+
+        let prices = attempts.iter().map(|a| a.fee.unwrap().unit_price).collect::<Vec<_>>();
+        let tips = attempts.iter().map(|a| a.tip).collect::<Vec<_>>();
+        let costs = attempts.iter().map(|a| a.cost).collect::<Vec<_>>();
+        assert_eq!(prices, vec![1_000_000, 1_500_000, 2_250_000]);
+        assert_eq!(tips, vec![0, 500_000, 1_250_000]);
+        // 200_000 units at the unit price plus the whole tip
+        assert_eq!(costs, vec![1_200_000, 1_800_000, 2_700_000]);
+    }
+
+    #[test]
+    fn stops_at_cost_ceiling() {
+        let endpoint = endpoint(1_000_000);
+        let escalation = EscalationConfig {
+            max_cost: 3_000_000,
+            ..Default::default()
+        };
+        let (attempts, outcome) = plan(&endpoint, &escalation);
+        assert!(matches!(outcome, ResubmissionOutcome::CostCeiling));
+        assert_eq!(attempts.len(), 3);
+        assert!(attempts.iter().all(|a| a.cost <= escalation.max_cost));
+
+        // Nothing is sent when the first attempt is already above the ceiling
+        let escalation = EscalationConfig {
+            max_cost: 1_199_999,
+            ..Default::default()
+        };
+        let (attempts, outcome) = plan(&endpoint, &escalation);
+        assert!(matches!(outcome, ResubmissionOutcome::CostCeiling));
+        assert!(attempts.is_empty());
+    }
+}
@@ -0,0 +1,277 @@
+use crate::errors::trading_endpoint_error::TradingEndpointError;
+use futures::StreamExt;
+use parking_lot::{Mutex, RwLock};
+use solana_client::nonblocking::{pubsub_client::PubsubClient, rpc_client::RpcClient};
+use solana_sdk::commitment_config::CommitmentConfig;
+use std::{
+    sync::{Arc, Weak},
+    time::{Duration, Instant},
+};
+use tokio::task::JoinHandle;
+use tracing::warn;
+
+pub const DEFAULT_SLOT_DURATION: Duration = Duration::from_millis(400);
+pub const SLOT_RESYNC_INTERVAL: Duration = Duration::from_secs(10);
+
+const MIN_SLOT_DURATION: Duration = Duration::from_millis(50);
+const MAX_SLOT_DURATION: Duration = Duration::from_millis(2_000);
+const SLOT_DURATION_SMOOTHING: u32 = 8;
+const RESUBSCRIBE_DELAY: Duration = Duration::from_secs(1);
+
+/// Monotonic time used by the slot clock, replaceable for deterministic tests
+pub trait TimeSource: Send + Sync {
+    /// Time elapsed since a fixed origin
+    fn now(&self) -> Duration;
+}
+
+pub struct SystemTimeSource {
+    origin: Instant,
+}
+
+impl Default for SystemTimeSource {
+    fn default() -> Self {
+        Self { origin: Instant::now() }
+    }
+}
+
+impl TimeSource for SystemTimeSource {
+    fn now(&self) -> Duration {
+        self.origin.elapsed()
+    }
+}
+
+/// Time source that only moves when told to
+#[derive(Default)]
+pub struct MockTimeSource {
+    now: Mutex<Duration>,
+}
+
+impl MockTimeSource {
+    pub fn set(&self, now: Duration) {
+        *self.now.lock() = now;
+    }
+
+    pub fn advance(&self, elapsed: Duration) {
+        *self.now.lock() += elapsed;
+    }
+}
+
+impl TimeSource for MockTimeSource {
+    fn now(&self) -> Duration {
+        *self.now.lock()
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct SlotObservation {
+    slot: u64,
+    at: Duration,
+    slot_duration: Duration,
+    drift: i64,
+}
+
+impl SlotObservation {
+    fn estimate(&self, now: Duration) -> u64 {
+        let elapsed = now.saturating_sub(self.at);
+        self.slot + (elapsed.as_nanos() / self.slot_duration.as_nanos()) as u64
+    }
+}
+
+/// Local estimate of the current slot, extrapolated from the last observed slot
+pub struct SlotClock {
+    time: Arc<dyn TimeSource>,
+    observation: RwLock<Option<SlotObservation>>,
+}
+
+impl Default for SlotClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl SlotClock {
+    pub fn new() -> Self {
+        Self::with_time_source(Arc::new(SystemTimeSource::default()))
+    }
+
+    pub fn with_time_source(time: Arc<dyn TimeSource>) -> Self {
+        Self {
+            time,
+            observation: RwLock::new(None),
+        }
+    }
+
+    /// Record a slot seen on chain, updating the observed slot time and the drift
+    pub fn observe(&self, slot: u64) {
+        let now = self.time.now();
+        let mut observation = self.observation.write();
+
+        let Some(last) = observation.as_mut() else {
+            *observation = Some(SlotObservation {
+                slot,
+                at: now,
+                slot_duration: DEFAULT_SLOT_DURATION,
+                drift: 0,
+            });
+            return;
+        };
+
+        // Older or repeated slots carry no timing information
+        if slot <= last.slot {
+            return;
+        }
+
+        let slots = (slot - last.slot).min(u32::MAX as u64) as u32;
+        let observed_duration = (now.saturating_sub(last.at) / slots).clamp(MIN_SLOT_DURATION, MAX_SLOT_DURATION);
+
+        last.drift = last.estimate(now) as i64 - slot as i64;
+        last.slot_duration = (last.slot_duration * (SLOT_DURATION_SMOOTHING - 1) + observed_duration) / SLOT_DURATION_SMOOTHING;
+        last.slot = slot;
+        last.at = now;
+    }
+
+    /// Estimated current slot, `None` before the first observation
+    pub fn current_slot(&self) -> Option<u64> {
+        let now = self.time.now();
+        self.observation.read().map(|o| o.estimate(now))
+    }
+
+    /// Estimated time until `slot` starts, zero when it already started
+    pub fn time_to_slot(&self, slot: u64) -> Option<Duration> {
+        let now = self.time.now();
+        let observation = (*self.observation.read())?;
+        let slot_start = observation.at + observation.slot_duration * slot.saturating_sub(observation.slot).min(u32::MAX as u64) as u32;
+        Some(slot_start.saturating_sub(now))
+    }
+
+    /// Slots the estimate was ahead (positive) or behind (negative) of the last observed slot
+    pub fn drift(&self) -> i64 {
+        self.observation.read().map(|o| o.drift).unwrap_or_default()
+    }
+
+    /// Observed average slot time
+    pub fn slot_duration(&self) -> Duration {
+        self.observation.read().map(|o| o.slot_duration).unwrap_or(DEFAULT_SLOT_DURATION)
+    }
+
+    /// Time since the last observed slot, `None` before the first observation
+    pub fn since_last_observation(&self) -> Option<Duration> {
+        let now = self.time.now();
+        self.observation.read().map(|o| now.saturating_sub(o.at))
+    }
+
+    /// Observe the slot returned by `getSlot` at processed commitment, the tip rather than the finalized slot
+    pub async fn sync(&self, rpc: &RpcClient) -> Result<u64, TradingEndpointError> {
+        let slot = rpc.get_slot_with_commitment(CommitmentConfig::processed()).await?;
+        self.observe(slot);
+        Ok(slot)
+    }
+
+    /// Current slot, syncing with `getSlot` first when no slot was observed recently
+    pub async fn current_slot_or_sync(&self, rpc: &RpcClient) -> Result<u64, TradingEndpointError> {
+        match (self.current_slot(), self.since_last_observation()) {
+            (Some(slot), Some(age)) if age <= SLOT_RESYNC_INTERVAL => Ok(slot),
+            _ => {
+                self.sync(rpc).await?;
+                Ok(self.current_slot().unwrap_or_default())
+            }
+        }
+    }
+
+    /// Feed the clock from `slotSubscribe`, reconnecting on errors until the clock is dropped
+    pub fn spawn_slot_subscribe(self: &Arc<Self>, ws_url: String) -> JoinHandle<()> {
+        let clock: Weak<Self> = Arc::downgrade(self);
+        tokio::spawn(async move {
+            while clock.strong_count() > 0 {
+                if let Err(e) = Self::subscribe_slots(&clock, &ws_url).await {
+                    warn!("slotSubscribe failed on {}: {:?}", ws_url, e);
+                }
+                tokio::time::sleep(RESUBSCRIBE_DELAY).await;
+            }
+        })
+    }
+
+    async fn subscribe_slots(clock: &Weak<Self>, ws_url: &str) -> Result<(), TradingEndpointError> {
+        let pubsub = PubsubClient::new(ws_url).await.map_err(|e| TradingEndpointError::CustomError(e.to_string()))?;
+        let (mut slots, unsubscribe) = pubsub.slot_subscribe().await.map_err(|e| TradingEndpointError::CustomError(e.to_string()))?;
+
+        while let Some(slot_info) = slots.next().await {
+            match clock.upgrade() {
+                Some(clock) => clock.observe(slot_info.slot),
+                None => break,
+            }
+        }
+
+        unsubscribe().await;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mock_clock() -> (Arc<MockTimeSource>, SlotClock) {
+        let time = Arc::new(MockTimeSource::default());
+        let clock = SlotClock::with_time_source(time.clone());
+        (time, clock)
+    }
+
+    #[test]
+    fn no_estimate_before_first_observation() {
+        let (_, clock) = mock_clock();
+        assert_eq!(clock.current_slot(), None);
+        assert_eq!(clock.time_to_slot(1), None);
+        assert_eq!(clock.since_last_observation(), None);
+        assert_eq!(clock.drift(), 0);
+        assert_eq!(clock.slot_duration(), DEFAULT_SLOT_DURATION);
+    }
+
+    #[test]
+    fn extrapolates_from_last_observation() {
+        let (time, clock) = mock_clock();
+        clock.observe(100);
+        assert_eq!(clock.current_slot(), Some(100));
+
+        time.advance(Duration::from_millis(1_200));
+        assert_eq!(clock.current_slot(), Some(103));
+        assert_eq!(clock.since_last_observation(), Some(Duration::from_millis(1_200)));
+        assert_eq!(clock.time_to_slot(105), Some(Duration::from_millis(800)));
+        assert_eq!(clock.time_to_slot(102), Some(Duration::ZERO));
+    }
+
+    #[test]
+    fn tracks_drift_and_smooths_slot_duration() {
+        let (time, clock) = mock_clock();
+        clock.observe(100);
+
+        // Slots took 600ms, so the 400ms estimate ran one slot ahead
+        time.advance(Duration::from_millis(1_200));
+        clock.observe(102);
+        assert_eq!(clock.drift(), 1);
+        assert_eq!(clock.slot_duration(), Duration::from_millis(425));
+        assert_eq!(clock.current_slot(), Some(102));
+
+        // Slots took 300ms, so the 425ms estimate ran behind
+        time.advance(Duration::from_millis(1_200));
+        clock.observe(106);
+        assert_eq!(clock.drift(), -2);
+        assert_eq!(clock.slot_duration(), Duration::from_nanos(409_375_000));
+    }
+
+    #[test]
+    fn ignores_old_slots_and_clamps_duration() {
+        let (time, clock) = mock_clock();
+        clock.observe(100);
+
+        time.advance(Duration::from_millis(400));
+        clock.observe(99);
+        clock.observe(100);
+        assert_eq!(clock.since_last_observation(), Some(Duration::from_millis(400)));
+        assert_eq!(clock.slot_duration(), DEFAULT_SLOT_DURATION);
+
+        // A burst of slots at once is clamped to the minimum slot time
+        clock.observe(200);
+        assert_eq!(clock.slot_duration(), (DEFAULT_SLOT_DURATION * 7 + MIN_SLOT_DURATION) / 8);
+    }
+}
@@ -1,42 +1,727 @@
-use super::trading_endpoint::TradingEndpoint;
-use crate::dex::{dex_traits::DexTrait, types::DexType};
+use super::{
+    accounts::{PUBKEY_USD1, PUBKEY_USDC, PUBKEY_WSOL},
+    trading_endpoint::{BatchTxItem, TradingEndpoint, TransactionType},
+};
+use crate::dex::{
+    boopfun::Boopfun,
+    boopfun_types::{self, BondingCurveStatus, BoopfunError},
+    dex_traits::DexTrait,
+    meteora_dbc::MeteoraDBC,
+    moonit::Moonit,
+    moonit_math::CurveState,
+    moonit_types::{MigrationTarget, MoonitError, PUBKEY_MOONIT_CONFIG},
+    pumpfun::Pumpfun,
+    pumpfun_types::PumpfunError,
+    pumpswap::PumpSwap,
+    raydium_amm::RaydiumAmm,
+    raydium_bonk::RaydiumBonk,
+    raydium_bonk_types::{RaydiumBonkPlatform, MIGRATE_TYPE_AMM, POOL_STATUS_FUND, POOL_STATUS_MIGRATE},
+    router::{RoutedDex, Router},
+    types::{DexType, LifecycleStage, ResolvedMint},
+};
 use crate::errors::trading_endpoint_error::TradingEndpointError;
+use crate::instruction::builder::PriorityFee;
 use crate::swqos::SWQoSConfig;
+use parking_lot::RwLock;
 use serde::{Deserialize, Serialize};
 use solana_client::nonblocking::rpc_client::RpcClient;
-use std::{collections::HashMap, sync::Arc};
+use solana_sdk::{
+    account::Account,
+    pubkey::Pubkey,
+    signature::{Keypair, Signature},
+    signer::Signer,
+};
+use std::{
+    collections::HashMap,
+    sync::Arc,
+    time::{Duration, Instant},
+};
+use tracing::warn;
 
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct TradingConfig {
     pub rpc_url: String,
     pub swqos: Vec<SWQoSConfig>,
+    #[serde(skip)]
+    pub tip_payer: Option<Arc<Keypair>>,
+    #[serde(default)]
+    pub leader_lookahead_slots: Option<u64>,
+    #[serde(default)]
+    pub ws_url: Option<String>,
 }
 
 pub struct TradingClient {
     pub endpoint: Arc<TradingEndpoint>,
     pub dexs: HashMap<DexType, Arc<dyn DexTrait>>,
+    /// DBC configs whose pools `resolve_mint` probes
+    dbc_configs: Vec<Pubkey>,
+    resolved_mints: RwLock<HashMap<Pubkey, CachedMint>>,
+}
+
+/// How long `resolve_mint` returns a resolved mint as is, before reloading its pool and, unless it migrated, detecting its venue again
+pub const RESOLVED_MINT_TTL: Duration = Duration::from_secs(10);
+/// Quote mints LaunchLab pools are probed with
+const LAUNCHLAB_QUOTE_MINTS: [Pubkey; 3] = [PUBKEY_WSOL, PUBKEY_USD1, PUBKEY_USDC];
+/// Pumpfun curve, PumpSwap pool, Boopfun curve, Moonit curve and Moonit config, leading the venue candidates
+const LAUNCHPAD_ACCOUNTS: usize = 5;
+
+#[derive(Debug, Clone)]
+struct CachedMint {
+    resolved: ResolvedMint,
+    resolved_at: Instant,
 }
 
 impl TradingClient {
     pub fn new(config: &TradingConfig) -> anyhow::Result<Self> {
+        for swqos in &config.swqos {
+            swqos.validate()?;
+        }
         let rpc = Arc::new(RpcClient::new(config.rpc_url.clone()));
         let swqos = config
             .swqos
             .clone()
             .into_iter()
             .flat_map(|w| w.build_runtimes(rpc.clone()))
-            .map(|w| Arc::new(w))
+            .map(Arc::new)
             .collect();
-        let endpoint = Arc::new(TradingEndpoint::new(rpc, swqos));
+        let endpoint = Arc::new(
+            TradingEndpoint::new(rpc, swqos)
+                .with_tip_payer(config.tip_payer.clone())
+                .with_ws_url(config.ws_url.clone())
+                .with_leader_routing(config.leader_lookahead_slots),
+        );
         let dexs = DexType::all().into_iter().map(|dex| (dex, dex.instantiate(endpoint.clone()))).collect();
 
-        Ok(Self { endpoint, dexs })
+        Ok(Self {
+            endpoint,
+            dexs,
+            dbc_configs: vec![],
+            resolved_mints: RwLock::new(HashMap::new()),
+        })
+    }
+
+    /// Send the buys and sells of `dex_type` through `router`
+    pub fn with_router(mut self, dex_type: DexType, router: Router) -> Self {
+        if let Some(dex) = self.dexs.remove(&dex_type) {
+            self.dexs.insert(dex_type, Arc::new(RoutedDex::new(dex, router)));
+        }
+        self
+    }
+
+    /// Let `resolve_mint` find DBC pools launched from `configs`, their pool addresses being seeded by the config.
+    /// Mints launched from any other config or against another quote mint are not detected, trade them through `DexType::MeteoraDBC`,
+    /// which looks pools up by base mint.
+    pub fn with_dbc_configs(mut self, configs: Vec<Pubkey>) -> Self {
+        self.dbc_configs = configs;
+        self
     }
 
     pub async fn initialize(&self) -> Result<(), TradingEndpointError> {
-        for (_, dex) in &self.dexs {
+        self.endpoint.refresh_tip_accounts().await;
+        if let Err(e) = self.endpoint.start_slot_clock().await {
+            warn!("Failed to start the slot clock, syncing on demand: {:?}", e);
+        }
+        if let Err(e) = self.endpoint.refresh_leader_schedule().await {
+            warn!("Failed to refresh the leader schedule, broadcasting to every SWQoS: {:?}", e);
+        }
+        for dex in self.dexs.values() {
             dex.initialize().await?;
         }
         Ok(())
     }
+
+    pub fn get_dex(&self, dex_type: &DexType) -> Result<Arc<dyn DexTrait>, TradingEndpointError> {
+        self.dexs
+            .get(dex_type)
+            .cloned()
+            .ok_or_else(|| TradingEndpointError::CustomError(format!("Dex not found: {:?}", dex_type)))
+    }
+
+    /// Pumpfun while the bonding curve of `mint` is live, PumpSwap once it migrated to its canonical pool
+    pub async fn get_pump_dex(&self, mint: &Pubkey) -> Result<Arc<dyn DexTrait>, TradingEndpointError> {
+        let bonding_curve = Pumpfun::get_bonding_curve_pda(mint)?;
+        let pool = PumpSwap::get_pool_address(mint)?;
+        let accounts = self.endpoint.rpc.get_multiple_accounts(&[bonding_curve, pool]).await?;
+        self.get_dex(&Self::route_pump_dex(mint, accounts[0].as_ref(), accounts[1].as_ref())?)
+    }
+
+    /// Pump venue of `mint` from its bonding curve and canonical PumpSwap pool accounts
+    fn route_pump_dex(mint: &Pubkey, bonding_curve: Option<&Account>, pool: Option<&Account>) -> Result<DexType, TradingEndpointError> {
+        match Self::classify_pump_accounts(bonding_curve, pool)? {
+            Some((_, LifecycleStage::Complete)) => Err(PumpfunError::Migrated { mint: *mint }.into()),
+            Some((dex_type, _)) => Ok(dex_type),
+            None => Err(TradingEndpointError::CustomError(format!(
+                "No Pumpfun bonding curve or PumpSwap pool found: {}",
+                mint
+            ))),
+        }
+    }
+
+    /// Pump venue and stage from the bonding curve and canonical PumpSwap pool accounts, `None` when neither exists
+    fn classify_pump_accounts(bonding_curve: Option<&Account>, pool: Option<&Account>) -> Result<Option<(DexType, LifecycleStage)>, TradingEndpointError> {
+        let complete = match bonding_curve {
+            Some(account) => Some(Pumpfun::decode_bonding_curve(&account.data)?.complete),
+            None => None,
+        };
+        let pool_exists = pool.is_some_and(|account| !account.data.is_empty());
+
+        Ok(match (complete, pool_exists) {
+            (Some(false), _) => Some((DexType::Pumpfun(None), LifecycleStage::Curve)),
+            (_, true) => Some((DexType::PumpSwap, LifecycleStage::Migrated)),
+            (Some(true), false) => Some((DexType::Pumpfun(None), LifecycleStage::Complete)),
+            (None, false) => None,
+        })
+    }
+
+    /// Boopfun while the bonding curve of `mint` is trading, Raydium CPMM once its liquidity migrated there, a typed error in between
+    pub async fn get_boop_dex(&self, mint: &Pubkey) -> Result<Arc<dyn DexTrait>, TradingEndpointError> {
+        let bonding_curve = Boopfun::new(self.endpoint.clone()).get_bonding_curve(mint).await?;
+        self.get_dex(&Self::route_boop_dex(mint, &bonding_curve)?)
+    }
+
+    /// Boopfun venue of `mint` from the status of its bonding curve
+    fn route_boop_dex(mint: &Pubkey, bonding_curve: &boopfun_types::BondingCurveAccount) -> Result<DexType, TradingEndpointError> {
+        match Self::classify_boop_curve(bonding_curve) {
+            (_, LifecycleStage::Complete) => Err(BoopfunError::Graduated {
+                mint: *mint,
+                status: bonding_curve.status,
+            }
+            .into()),
+            (dex_type, _) => Ok(dex_type),
+        }
+    }
+
+    /// Boopfun venue and stage, complete between graduation and the liquidity reaching Raydium CPMM
+    fn classify_boop_curve(bonding_curve: &boopfun_types::BondingCurveAccount) -> (DexType, LifecycleStage) {
+        match bonding_curve.status {
+            BondingCurveStatus::Trading => (DexType::Boopfun, LifecycleStage::Curve),
+            _ if bonding_curve.has_migrated_liquidity() => (DexType::RaydiumCpmm, LifecycleStage::Migrated),
+            _ => (DexType::Boopfun, LifecycleStage::Complete),
+        }
+    }
+
+    /// Venue `mint` trades on with its current pool, cached for `RESOLVED_MINT_TTL`.
+    /// Past it a migrated mint only has its pool reloaded, any other one is looked up again.
+    /// Only DBC pools of the configs given to `with_dbc_configs` are detected.
+    pub async fn resolve_mint(&self, mint: &Pubkey) -> Result<ResolvedMint, TradingEndpointError> {
+        let cached = self.resolved_mints.read().get(mint).cloned();
+        match cached {
+            Some(cached) if cached.resolved_at.elapsed() < RESOLVED_MINT_TTL => Ok(cached.resolved),
+            Some(cached) if cached.resolved.stage == LifecycleStage::Migrated => {
+                let ResolvedMint { dex_type, stage, slot, .. } = cached.resolved;
+                let resolved = self.load_resolved_mint(mint, dex_type, stage, slot).await?;
+                self.cache_resolved_mint(mint, &resolved);
+                Ok(resolved)
+            }
+            _ => self.refresh_mint(mint).await,
+        }
+    }
+
+    /// Look `mint` up again and replace its cached venue, e.g. once its curve completed
+    pub async fn refresh_mint(&self, mint: &Pubkey) -> Result<ResolvedMint, TradingEndpointError> {
+        let (dex_type, stage, slot) = self.detect_venue(mint).await?;
+        let resolved = self.load_resolved_mint(mint, dex_type, stage, slot).await?;
+        self.cache_resolved_mint(mint, &resolved);
+        Ok(resolved)
+    }
+
+    /// Keep `resolved` unless a lookup that finished first read a later slot
+    fn cache_resolved_mint(&self, mint: &Pubkey, resolved: &ResolvedMint) {
+        let mut resolved_mints = self.resolved_mints.write();
+        if resolved_mints.get(mint).is_some_and(|cached| cached.resolved.slot > resolved.slot) {
+            return;
+        }
+        let cached = CachedMint {
+            resolved: resolved.clone(),
+            resolved_at: Instant::now(),
+        };
+        resolved_mints.insert(*mint, cached);
+    }
+
+    /// Current pool of `mint` on its venue, LaunchLab platforms other than the default one being loaded on demand
+    async fn load_resolved_mint(&self, mint: &Pubkey, dex_type: DexType, stage: LifecycleStage, slot: u64) -> Result<ResolvedMint, TradingEndpointError> {
+        let pool_info = match stage {
+            LifecycleStage::Complete => None,
+            _ => {
+                let dex = match self.dexs.get(&dex_type) {
+                    Some(dex) => dex.clone(),
+                    None => {
+                        let dex = dex_type.instantiate(self.endpoint.clone());
+                        dex.initialize().await?;
+                        dex
+                    }
+                };
+                Some(dex.get_pool(mint).await?)
+            }
+        };
+        Ok(ResolvedMint {
+            dex_type,
+            stage,
+            slot: pool_info.as_ref().map_or(slot, |pool_info| pool_info.slot),
+            pool_info,
+        })
+    }
+
+    /// Fetches the launchpad PDAs of `mint` in one request, with the slot they were read at.
+    /// A Moonit curve migrating to Raydium is only reported as migrated once a Raydium AMM pool of `mint` exists.
+    async fn detect_venue(&self, mint: &Pubkey) -> Result<(DexType, LifecycleStage, u64), TradingEndpointError> {
+        let candidates = self.get_venue_candidates(mint)?;
+        let (accounts, slot) = self.endpoint.get_multiple_accounts_with_slot(&candidates).await?;
+        let (dex_type, stage) = match Self::classify_venue(mint, &accounts)? {
+            (DexType::Moonit, LifecycleStage::Complete) if !RaydiumAmm::new(self.endpoint.clone()).get_pools(mint).await?.is_empty() => {
+                (DexType::RaydiumAmm, LifecycleStage::Migrated)
+            }
+            venue => venue,
+        };
+        Ok((dex_type, stage, slot))
+    }
+
+    /// Accounts `classify_venue` reads: the `LAUNCHPAD_ACCOUNTS`, the LaunchLab pool of each quote mint,
+    /// then the WSOL pool and the config of each DBC config.
+    /// DBC pools are seeded by their config and quote mint, so pools of other configs or quote mints are not among them.
+    fn get_venue_candidates(&self, mint: &Pubkey) -> Result<Vec<Pubkey>, TradingEndpointError> {
+        let mut candidates = vec![
+            Pumpfun::get_bonding_curve_pda(mint)?,
+            PumpSwap::get_pool_address(mint)?,
+            Boopfun::get_bonding_curve_pda(mint)?,
+            Moonit::get_bonding_curve_pda(mint)?,
+            PUBKEY_MOONIT_CONFIG,
+        ];
+        for quote_mint in LAUNCHLAB_QUOTE_MINTS {
+            candidates.push(RaydiumBonk::get_pool_pda(mint, &quote_mint)?);
+        }
+        for config in &self.dbc_configs {
+            candidates.extend([MeteoraDBC::get_virtual_pool_pda(mint, config, &PUBKEY_WSOL)?, *config]);
+        }
+        Ok(candidates)
+    }
+
+    /// Venue and stage of `mint` from the accounts of `get_venue_candidates`, in the same order.
+    /// A Moonit curve past its market cap threshold is complete until its Raydium pool is found.
+    fn classify_venue(mint: &Pubkey, accounts: &[Option<Account>]) -> Result<(DexType, LifecycleStage), TradingEndpointError> {
+        let (launchpad_accounts, pool_accounts) = accounts.split_at(LAUNCHPAD_ACCOUNTS.min(accounts.len()));
+        let (launchlab_pools, dbc_accounts) = pool_accounts.split_at(LAUNCHLAB_QUOTE_MINTS.len().min(pool_accounts.len()));
+        let [pumpfun_curve, pumpswap_pool, boop_curve, moonit_curve, moonit_config] = launchpad_accounts else {
+            return Err(TradingEndpointError::CustomError(format!("Venue accounts not found: {mint}")));
+        };
+
+        if let Some(venue) = Self::classify_pump_accounts(pumpfun_curve.as_ref(), pumpswap_pool.as_ref())? {
+            return Ok(venue);
+        }
+
+        if let Some(account) = launchlab_pools.iter().flatten().next() {
+            let pool = RaydiumBonk::decode_pool_state(&account.data)?;
+            let platform = RaydiumBonkPlatform {
+                global_config: pool.global_config,
+                platform_config: pool.platform_config,
+                quote_mint: pool.quote_mint,
+            };
+            let launchlab = DexType::RayBonk((platform != RaydiumBonkPlatform::default()).then_some(platform));
+            return Ok(match pool.status {
+                POOL_STATUS_FUND => (launchlab, LifecycleStage::Curve),
+                POOL_STATUS_MIGRATE => (launchlab, LifecycleStage::Complete),
+                _ if pool.migrate_type == MIGRATE_TYPE_AMM => (DexType::RaydiumAmm, LifecycleStage::Migrated),
+                _ => (DexType::RaydiumCpmm, LifecycleStage::Migrated),
+            });
+        }
+
+        if let Some(account) = boop_curve {
+            return Ok(Self::classify_boop_curve(&Boopfun::decode_bonding_curve(&account.data)?));
+        }
+
+        if let Some(account) = moonit_curve {
+            let curve = Moonit::decode_curve(&account.data)?;
+            let config = moonit_config
+                .as_ref()
+                .ok_or_else(|| TradingEndpointError::CustomError("Moonit config not found".to_string()))?;
+            let state = CurveState::new(&curve, Moonit::decode_config(&config.data)?.coef_a);
+            if state.get_market_cap() < curve.marketcap_threshold {
+                return Ok((DexType::Moonit, LifecycleStage::Curve));
+            }
+            return match curve.migration_target {
+                MigrationTarget::Raydium => Ok((DexType::Moonit, LifecycleStage::Complete)),
+                target => Err(MoonitError::Migrated { mint: *mint, target }.into()),
+            };
+        }
+
+        // Migrated DBC tokens stay on MeteoraDBC, which routes them to DAMM v2
+        for accounts in dbc_accounts.chunks_exact(2) {
+            let (Some(pool), Some(config)) = (&accounts[0], &accounts[1]) else {
+                continue;
+            };
+            let pool = MeteoraDBC::decode_virtual_pool(&pool.data)?;
+            let config = MeteoraDBC::decode_pool_config(&config.data)?;
+            let stage = match (pool.is_migrated != 0, pool.is_curve_complete(&config)) {
+                (true, _) => LifecycleStage::Migrated,
+                (false, true) => LifecycleStage::Complete,
+                (false, false) => LifecycleStage::Curve,
+            };
+            return Ok((DexType::MeteoraDBC, stage));
+        }
+
+        Err(TradingEndpointError::CustomError(format!("No launchpad venue found for mint: {mint}")))
+    }
+
+    /// Withdraw the Pumpfun and PumpSwap creator fees of every creator in one batch, skipping empty vaults
+    pub async fn collect_all_creator_fees(&self, creators: Vec<Keypair>, fee: PriorityFee, tip: u64) -> Result<Vec<Signature>, TradingEndpointError> {
+        let vaults = creators
+            .iter()
+            .map(|creator| {
+                Ok([
+                    Pumpfun::get_creator_vault_pda(&creator.pubkey())?,
+                    PumpSwap::get_coin_creator_vault_ata(&creator.pubkey())?,
+                ])
+            })
+            .collect::<Result<Vec<_>, TradingEndpointError>>()?
+            .concat();
+        let mut accounts = Vec::with_capacity(vaults.len());
+        for chunk in vaults.chunks(100) {
+            accounts.extend(self.endpoint.rpc.get_multiple_accounts(chunk).await?);
+        }
+
+        let mut batch_items = vec![];
+        for (creator, vaults) in creators.into_iter().zip(accounts.chunks(2)) {
+            let mut instructions = vec![];
+            if Pumpfun::decode_creator_vault_balance(vaults[0].as_ref()) > 0 {
+                instructions.push(Pumpfun::build_collect_creator_fee_instruction(&creator.pubkey())?);
+            }
+            if PumpSwap::decode_coin_creator_vault_balance(vaults[1].as_ref())? > 0 {
+                instructions.extend(PumpSwap::build_collect_coin_creator_fee_instructions(&creator.pubkey())?);
+            }
+            if !instructions.is_empty() {
+                batch_items.push(BatchTxItem { payer: creator, instructions });
+            }
+        }
+        if batch_items.is_empty() {
+            return Ok(vec![]);
+        }
+
+        let blockhash = self.endpoint.get_latest_blockhash().await?;
+        self.endpoint
+            .build_and_broadcast_batch_txs(TransactionType::Sell, batch_items, blockhash, Some(fee), tip)
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dex::{
+        boopfun_types::PUBKEY_BOOPFUN,
+        meteora_dbc_types::PUBKEY_METEORA_DBC,
+        moonit_types::{ConfigAccount, Currency, CurveAccount, CurveType, PUBKEY_MOONIT},
+        pumpfun_types::{BondingCurveAccount, PUMPFUN_PROGRAM},
+        pumpswap_types::PUBKEY_PUMPSWAP,
+        raydium_bonk_types::{PoolState, VestingSchedule, MIGRATE_TYPE_CPSWAP, POOL_STATUS_TRADE, PUBKEY_RAYDIUM_BONK},
+    };
+
+    /// Indexes of the launchpad accounts among those `get_venue_candidates` lists
+    const PUMPFUN_CURVE: usize = 0;
+    const PUMPSWAP_POOL: usize = 1;
+    const BOOP_CURVE: usize = 2;
+    const MOONIT_CURVE: usize = 3;
+    const MOONIT_CONFIG: usize = 4;
+    const LAUNCHLAB_POOL: usize = LAUNCHPAD_ACCOUNTS;
+    const DBC_POOL: usize = LAUNCHPAD_ACCOUNTS + LAUNCHLAB_QUOTE_MINTS.len();
+
+    fn account(owner: Pubkey, data: Vec<u8>) -> Account {
+        Account {
+            lamports: 1_000_000_000,
+            data,
+            owner,
+            executable: false,
+            rent_epoch: 0,
+        }
+    }
+
+    /// Bonding curve account as the program lays it out, completed or still trading
+    fn bonding_curve(complete: bool) -> Account {
+        let curve = BondingCurveAccount {
+            discriminator: u64::from_le_bytes([23, 183, 248, 55, 96, 216, 172, 96]),
+            virtual_token_reserves: 1_073_000_000_000_000,
+            virtual_sol_reserves: 30_000_000_000,
+            real_token_reserves: 793_100_000_000_000,
+            real_sol_reserves: 0,
+            token_total_supply: 1_000_000_000_000_000,
+            complete,
+            creator: Pubkey::new_unique(),
+        };
+        let mut data = bincode::serialize(&curve).unwrap();
+        data.resize(150, 0);
+        account(PUMPFUN_PROGRAM, data)
+    }
+
+    fn pool() -> Account {
+        account(PUBKEY_PUMPSWAP, vec![1; 300])
+    }
+
+    /// Boopfun bonding curve decoded from the bytes the program stores, 60 of 85 SOL raised
+    fn boop_curve(status: BondingCurveStatus) -> boopfun_types::BondingCurveAccount {
+        Boopfun::decode_bonding_curve(&boop_curve_account(status).data).unwrap()
+    }
+
+    fn boop_curve_account(status: BondingCurveStatus) -> Account {
+        let curve = boopfun_types::BondingCurveAccount {
+            discriminator: 0,
+            creator: Pubkey::new_unique(),
+            mint: Pubkey::new_unique(),
+            virtual_sol_reserves: 90_000_000_000,
+            virtual_token_reserves: 357_000_000_000_000_000,
+            graduation_target: 85_000_000_000,
+            graduation_fee: 1_000_000_000,
+            sol_reserves: 60_000_000_000,
+            token_reserves: 300_000_000_000_000_000,
+            damping_term: 3,
+            swap_fee_basis_points: 100,
+            token_for_stakers_basis_points: 0,
+            status,
+        };
+        account(PUBKEY_BOOPFUN, bincode::serialize(&curve).unwrap())
+    }
+
+    /// Moonit linear curve with 200M of 1B tokens sold, migrating to Raydium at `marketcap_threshold`
+    fn moonit_accounts(mint: &Pubkey, marketcap_threshold: u64) -> (Account, Account) {
+        let curve = CurveAccount {
+            discriminator: 0,
+            total_supply: 1_000_000_000_000_000_000,
+            curve_amount: 800_000_000_000_000_000,
+            mint: *mint,
+            decimals: 9,
+            collateral_currency: Currency::Sol,
+            curve_type: CurveType::LinearV1,
+            marketcap_threshold,
+            marketcap_currency: Currency::Sol,
+            migration_fee: 1_000_000,
+            coef_b: 25,
+            bump: 253,
+            migration_target: MigrationTarget::Raydium,
+        };
+        let config = ConfigAccount {
+            discriminator: 0,
+            migration_authority: Pubkey::new_unique(),
+            backend_authority: Pubkey::new_unique(),
+            config_authority: Pubkey::new_unique(),
+            helio_fee: Pubkey::new_unique(),
+            dex_fee: Pubkey::new_unique(),
+            fee_bps: 100,
+            dex_fee_share: 90,
+            migration_fee: 1_000_000,
+            marketcap_threshold,
+            marketcap_currency: Currency::Sol,
+            min_supported_decimal_places: 6,
+            max_supported_decimal_places: 9,
+            min_supported_token_supply: 100_000_000_000,
+            max_supported_token_supply: 10_000_000_000_000_000_000,
+            bump: 254,
+            coef_a: 1_000,
+            coef_b: 25,
+        };
+        (
+            account(PUBKEY_MOONIT, borsh::to_vec(&curve).unwrap()),
+            account(PUBKEY_MOONIT, borsh::to_vec(&config).unwrap()),
+        )
+    }
+
+    fn launchlab_pool(mint: &Pubkey, platform: RaydiumBonkPlatform, status: u8, migrate_type: u8) -> Account {
+        let pool = PoolState {
+            discriminator: 0,
+            epoch: 0,
+            auth_bump: 255,
+            status,
+            base_decimals: 6,
+            quote_decimals: 9,
+            migrate_type,
+            supply: 1_000_000_000_000_000,
+            total_base_sell: 793_100_000_000_000,
+            virtual_base: 1_073_025_605_596_382,
+            virtual_quote: 30_000_852_951,
+            real_base: 0,
+            real_quote: 0,
+            total_quote_fund_raising: 85_000_000_000,
+            quote_protocol_fee: 0,
+            platform_fee: 0,
+            migrate_fee: 0,
+            vesting_schedule: VestingSchedule {
+                total_locked_amount: 0,
+                cliff_period: 0,
+                unlock_period: 0,
+                start_time: 0,
+                allocated_share_amount: 0,
+            },
+            global_config: platform.global_config,
+            platform_config: platform.platform_config,
+            base_mint: *mint,
+            quote_mint: platform.quote_mint,
+            base_vault: Pubkey::new_unique(),
+            quote_vault: Pubkey::new_unique(),
+            creator: Pubkey::new_unique(),
+            padding: [0; 8],
+        };
+        account(PUBKEY_RAYDIUM_BONK, bincode::serialize(&pool).unwrap())
+    }
+
+    /// DBC virtual pool holding `quote_reserve` and its config migrating at 85 SOL, laid out field by field
+    fn dbc_accounts(quote_reserve: u64, is_migrated: bool) -> (Account, Account) {
+        let mut pool = vec![0; 424];
+        pool[240..248].copy_from_slice(&quote_reserve.to_le_bytes());
+        pool[305] = is_migrated as u8;
+        let mut config = vec![0; 1048];
+        config[264..272].copy_from_slice(&85_000_000_000u64.to_le_bytes());
+
+        let decoded = MeteoraDBC::decode_virtual_pool(&pool).unwrap();
+        assert_eq!((decoded.quote_reserve, decoded.is_migrated), (quote_reserve, is_migrated as u8));
+        assert_eq!(MeteoraDBC::decode_pool_config(&config).unwrap().migration_quote_threshold, 85_000_000_000);
+        (account(PUBKEY_METEORA_DBC, pool), account(PUBKEY_METEORA_DBC, config))
+    }
+
+    /// Fetched venue candidates of a client probing `dbc_configs` configs, none of them existing
+    fn venue_accounts(dbc_configs: usize) -> Vec<Option<Account>> {
+        vec![None; DBC_POOL + 2 * dbc_configs]
+    }
+
+    #[test]
+    fn routes_boop_mints_by_curve_status() {
+        let mint = Pubkey::new_unique();
+        let route = |status| TradingClient::route_boop_dex(&mint, &boop_curve(status));
+
+        assert_eq!(route(BondingCurveStatus::Trading).unwrap(), DexType::Boopfun);
+        assert_eq!(route(BondingCurveStatus::LiquidityProvisioned).unwrap(), DexType::RaydiumCpmm);
+        assert_eq!(route(BondingCurveStatus::LiquidityLocked).unwrap(), DexType::RaydiumCpmm);
+        for status in [BondingCurveStatus::Graduated, BondingCurveStatus::PoolPriceCorrected] {
+            assert!(matches!(
+                route(status),
+                Err(TradingEndpointError::Boopfun(BoopfunError::Graduated { mint: graduated, status: reported })) if graduated == mint && reported == status
+            ));
+        }
+    }
+
+    #[test]
+    fn routes_pump_mints_by_curve_and_pool() {
+        let mint = Pubkey::new_unique();
+        let route = |curve: Option<Account>, pool: Option<Account>| TradingClient::route_pump_dex(&mint, curve.as_ref(), pool.as_ref());
+
+        assert_eq!(route(Some(bonding_curve(false)), None).unwrap(), DexType::Pumpfun(None));
+        // A pool created for the mint ahead of migration does not take trades off a live curve
+        assert_eq!(route(Some(bonding_curve(false)), Some(pool())).unwrap(), DexType::Pumpfun(None));
+        assert_eq!(route(Some(bonding_curve(true)), Some(pool())).unwrap(), DexType::PumpSwap);
+        assert_eq!(route(None, Some(pool())).unwrap(), DexType::PumpSwap);
+        assert!(matches!(
+            route(Some(bonding_curve(true)), None),
+            Err(TradingEndpointError::Pumpfun(PumpfunError::Migrated { mint: migrated })) if migrated == mint
+        ));
+        assert!(matches!(route(None, None), Err(TradingEndpointError::CustomError(_))));
+        assert!(matches!(
+            route(None, Some(account(PUBKEY_PUMPSWAP, vec![]))),
+            Err(TradingEndpointError::CustomError(_))
+        ));
+    }
+
+    #[test]
+    fn classifies_pump_venues_before_other_launchpads() {
+        let mint = Pubkey::new_unique();
+        let classify = |accounts: &[Option<Account>]| TradingClient::classify_venue(&mint, accounts).unwrap();
+
+        let mut accounts = venue_accounts(0);
+        accounts[PUMPFUN_CURVE] = Some(bonding_curve(false));
+        accounts[BOOP_CURVE] = Some(boop_curve_account(BondingCurveStatus::Trading));
+        assert_eq!(classify(&accounts), (DexType::Pumpfun(None), LifecycleStage::Curve));
+
+        accounts[PUMPFUN_CURVE] = Some(bonding_curve(true));
+        assert_eq!(classify(&accounts), (DexType::Pumpfun(None), LifecycleStage::Complete));
+        accounts[PUMPSWAP_POOL] = Some(pool());
+        assert_eq!(classify(&accounts), (DexType::PumpSwap, LifecycleStage::Migrated));
+        accounts[PUMPFUN_CURVE] = None;
+        assert_eq!(classify(&accounts), (DexType::PumpSwap, LifecycleStage::Migrated));
+    }
+
+    #[test]
+    fn classifies_launchlab_pools_by_status_and_platform() {
+        let mint = Pubkey::new_unique();
+        let classify = |pool: Account| {
+            let mut accounts = venue_accounts(0);
+            accounts[LAUNCHLAB_POOL + 1] = Some(pool);
+            TradingClient::classify_venue(&mint, &accounts).unwrap()
+        };
+        let default_platform = RaydiumBonkPlatform::default();
+        let platform = RaydiumBonkPlatform {
+            platform_config: Pubkey::new_unique(),
+            ..default_platform
+        };
+
+        assert_eq!(
+            classify(launchlab_pool(&mint, default_platform, POOL_STATUS_FUND, MIGRATE_TYPE_AMM)),
+            (DexType::RayBonk(None), LifecycleStage::Curve)
+        );
+        assert_eq!(
+            classify(launchlab_pool(&mint, platform, POOL_STATUS_MIGRATE, MIGRATE_TYPE_AMM)),
+            (DexType::RayBonk(Some(platform)), LifecycleStage::Complete)
+        );
+        assert_eq!(
+            classify(launchlab_pool(&mint, platform, POOL_STATUS_TRADE, MIGRATE_TYPE_AMM)),
+            (DexType::RaydiumAmm, LifecycleStage::Migrated)
+        );
+        assert_eq!(
+            classify(launchlab_pool(&mint, platform, POOL_STATUS_TRADE, MIGRATE_TYPE_CPSWAP)),
+            (DexType::RaydiumCpmm, LifecycleStage::Migrated)
+        );
+    }
+
+    #[test]
+    fn classifies_boop_curves_like_the_router() {
+        let mint = Pubkey::new_unique();
+        let classify = |status| {
+            let mut accounts = venue_accounts(0);
+            accounts[BOOP_CURVE] = Some(boop_curve_account(status));
+            TradingClient::classify_venue(&mint, &accounts).unwrap()
+        };
+
+        assert_eq!(classify(BondingCurveStatus::Trading), (DexType::Boopfun, LifecycleStage::Curve));
+        assert_eq!(classify(BondingCurveStatus::Graduated), (DexType::Boopfun, LifecycleStage::Complete));
+        assert_eq!(classify(BondingCurveStatus::LiquidityLocked), (DexType::RaydiumCpmm, LifecycleStage::Migrated));
+    }
+
+    #[test]
+    fn moonit_curve_past_its_threshold_waits_for_the_raydium_probe() {
+        let mint = Pubkey::new_unique();
+        let classify = |marketcap_threshold| {
+            let (curve, config) = moonit_accounts(&mint, marketcap_threshold);
+            let mut accounts = venue_accounts(0);
+            accounts[MOONIT_CURVE] = Some(curve);
+            accounts[MOONIT_CONFIG] = Some(config);
+            TradingClient::classify_venue(&mint, &accounts)
+        };
+
+        assert_eq!(classify(u64::MAX).unwrap(), (DexType::Moonit, LifecycleStage::Curve));
+        assert_eq!(classify(0).unwrap(), (DexType::Moonit, LifecycleStage::Complete));
+
+        let (curve, _) = moonit_accounts(&mint, u64::MAX);
+        let mut accounts = venue_accounts(0);
+        accounts[MOONIT_CURVE] = Some(curve);
+        assert!(matches!(
+            TradingClient::classify_venue(&mint, &accounts),
+            Err(TradingEndpointError::CustomError(_))
+        ));
+    }
+
+    #[test]
+    fn classifies_dbc_pools_of_the_probed_configs() {
+        let mint = Pubkey::new_unique();
+        let classify = |quote_reserve, is_migrated| {
+            let (pool, config) = dbc_accounts(quote_reserve, is_migrated);
+            // The pool is found under the second config only
+            let mut accounts = venue_accounts(2);
+            accounts[DBC_POOL + 1] = Some(config.clone());
+            accounts[DBC_POOL + 2] = Some(pool);
+            accounts[DBC_POOL + 3] = Some(config);
+            TradingClient::classify_venue(&mint, &accounts).unwrap()
+        };
+
+        assert_eq!(classify(60_000_000_000, false), (DexType::MeteoraDBC, LifecycleStage::Curve));
+        assert_eq!(classify(85_000_000_000, false), (DexType::MeteoraDBC, LifecycleStage::Complete));
+        assert_eq!(classify(0, true), (DexType::MeteoraDBC, LifecycleStage::Migrated));
+        assert!(matches!(
+            TradingClient::classify_venue(&mint, &venue_accounts(2)),
+            Err(TradingEndpointError::CustomError(_))
+        ));
+    }
 }
@@ -1,25 +1,29 @@
 use crate::common::lamports::Lamports;
+use crate::common::leader_schedule::{LeaderRouter, RoutingDecision};
+use crate::common::mint::MintInfo;
+use crate::common::slot_clock::SlotClock;
 use crate::instruction::builder::build_legacy_transaction;
-use crate::swqos::SWQoSRuntime;
+use crate::swqos::{SWQoSRuntime, TipError, TipPlacement};
 use crate::{
     common::transaction::Transaction,
     errors::trading_endpoint_error::TradingEndpointError,
     instruction::builder::{build_transaction, PriorityFee, TipFee},
-    swqos::SWQoSTrait,
 };
-use solana_client::nonblocking::rpc_client::RpcClient;
+use parking_lot::RwLock;
+use solana_client::{client_error::ClientError, nonblocking::rpc_client::RpcClient, rpc_request::RpcError};
 use solana_sdk::compute_budget::ComputeBudgetInstruction;
 use solana_sdk::signature::Signer;
 use solana_sdk::{
+    account::Account,
     hash::Hash,
     instruction::Instruction,
+    pubkey::Pubkey,
     signature::{Keypair, Signature},
 };
+use std::collections::HashMap;
 use std::ops::Add;
 use std::sync::Arc;
-use tracing::debug;
-
-type Tip = u64;
+use tracing::{debug, warn};
 
 #[derive(Debug, Clone, Copy)]
 pub enum TransactionType {
@@ -31,6 +35,52 @@ pub enum TransactionType {
 pub struct TradingEndpoint {
     pub rpc: Arc<RpcClient>,
     pub swqos: Vec<Arc<SWQoSRuntime>>,
+    pub tip_payer: Option<Arc<Keypair>>,
+    pub leader_router: Option<Arc<LeaderRouter>>,
+    pub slot_clock: Arc<SlotClock>,
+    pub ws_url: Option<String>,
+    mints: RwLock<HashMap<Pubkey, MintInfo>>,
+}
+
+#[derive(Debug, Clone)]
+pub struct BroadcastReport {
+    pub signatures: Vec<Signature>,
+    pub routing: RoutingDecision,
+}
+
+/// Priority fee and tip paid on top of the SWQoS config
+#[derive(Debug, Clone, Copy, Default)]
+pub struct AdditionalFees {
+    pub fee: Option<PriorityFee>,
+    pub tip: u64,
+}
+
+/// Blockhashes or durable nonce a transaction is signed against, with the fees added on top of the SWQoS config
+#[derive(Debug, Clone, Default)]
+pub struct TxSettings {
+    pub nonce_ix: Option<Instruction>,
+    /// Cycled through the SWQoS runtimes, the nonce hash when `nonce_ix` is set
+    pub blockhashes: Vec<Hash>,
+    pub fees: AdditionalFees,
+}
+
+impl TxSettings {
+    pub fn new(blockhash: Hash, fee: Option<PriorityFee>, tip: u64) -> Self {
+        Self {
+            nonce_ix: None,
+            blockhashes: vec![blockhash],
+            fees: AdditionalFees { fee, tip },
+        }
+    }
+
+    /// Sign against a durable nonce, `nonce_ix` advancing it and `nonce_hash` being its stored blockhash
+    pub fn with_nonce(nonce_ix: Instruction, nonce_hash: Hash, fees: AdditionalFees) -> Self {
+        Self {
+            nonce_ix: Some(nonce_ix),
+            blockhashes: vec![nonce_hash],
+            fees,
+        }
+    }
 }
 
 pub struct BatchTxItem {
@@ -40,7 +90,76 @@ pub struct BatchTxItem {
 
 impl TradingEndpoint {
     pub fn new(rpc: Arc<RpcClient>, swqos: Vec<Arc<SWQoSRuntime>>) -> Self {
-        Self { rpc, swqos }
+        Self {
+            rpc,
+            swqos,
+            tip_payer: None,
+            leader_router: None,
+            slot_clock: Arc::new(SlotClock::new()),
+            ws_url: None,
+            mints: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// Use a custom slot clock, e.g. one driven by a mocked time source, set before enabling leader routing
+    pub fn with_slot_clock(mut self, slot_clock: Arc<SlotClock>) -> Self {
+        self.slot_clock = slot_clock;
+        self
+    }
+
+    /// Keep the slot clock in sync through `slotSubscribe` on `ws_url` instead of polling `getSlot`
+    pub fn with_ws_url(mut self, ws_url: Option<String>) -> Self {
+        self.ws_url = ws_url;
+        self
+    }
+
+    /// Route transactions by the leader schedule, looking `lookahead_slots` slots ahead
+    pub fn with_leader_routing(mut self, lookahead_slots: Option<u64>) -> Self {
+        self.leader_router = lookahead_slots.map(|slots| Arc::new(LeaderRouter::new(self.rpc.clone(), self.slot_clock.clone(), slots)));
+        self
+    }
+
+    /// Pay tips from a dedicated wallet instead of the trading wallet
+    pub fn with_tip_payer(mut self, tip_payer: Option<Arc<Keypair>>) -> Self {
+        self.tip_payer = tip_payer;
+        self
+    }
+
+    /// Get the wallet paying the tip, defaulting to the trading wallet
+    fn get_tip_payer<'a>(&'a self, payer: &'a Keypair) -> &'a Keypair {
+        self.tip_payer.as_deref().unwrap_or(payer)
+    }
+
+    /// Token program and transfer fee of `mint`, fetched once and cached
+    pub async fn get_mint_info(&self, mint: &Pubkey) -> Result<MintInfo, TradingEndpointError> {
+        if let Some(mint_info) = self.mints.read().get(mint) {
+            return Ok(*mint_info);
+        }
+
+        let account = self.rpc.get_account(mint).await?;
+        let mint_info = MintInfo::from_account(mint, &account)?;
+        self.cache_mint_info(mint_info);
+        Ok(mint_info)
+    }
+
+    /// Seed the mint cache, e.g. from a create event, so trades on a new mint skip the lookup
+    pub fn cache_mint_info(&self, mint_info: MintInfo) {
+        self.mints.write().insert(mint_info.mint, mint_info);
+    }
+
+    /// Accounts with the slot the RPC read them at, `None` for accounts that do not exist
+    pub async fn get_multiple_accounts_with_slot(&self, pubkeys: &[Pubkey]) -> Result<(Vec<Option<Account>>, u64), TradingEndpointError> {
+        let response = self.rpc.get_multiple_accounts_with_commitment(pubkeys, self.rpc.commitment()).await?;
+        Ok((response.value, response.context.slot))
+    }
+
+    /// Account with the slot the RPC read it at, failing like `getAccountInfo` when it does not exist
+    pub async fn get_account_with_slot(&self, pubkey: &Pubkey) -> Result<(Account, u64), TradingEndpointError> {
+        let response = self.rpc.get_account_with_commitment(pubkey, self.rpc.commitment()).await?;
+        let account = response
+            .value
+            .ok_or_else(|| ClientError::from(RpcError::ForUser(format!("AccountNotFound: pubkey={}", pubkey))))?;
+        Ok((account, response.context.slot))
     }
 
     pub async fn get_latest_blockhash(&self) -> Result<Hash, TradingEndpointError> {
@@ -48,6 +167,51 @@ impl TradingEndpoint {
         Ok(blockhash)
     }
 
+    /// Refresh the tip accounts of every SWQoS, keeping the built-in accounts for providers that fail
+    pub async fn refresh_tip_accounts(&self) {
+        let tasks = self
+            .swqos
+            .iter()
+            .map(|swqos| async move { (swqos.get_client_name(), swqos.refresh_tip_accounts().await) });
+        for (name, result) in futures::future::join_all(tasks).await {
+            if let Err(e) = result {
+                warn!("Failed to refresh tip accounts for SWQoS {}, using built-in accounts: {:?}", name, e);
+            }
+        }
+    }
+
+    /// Sync the slot clock once and keep it fed from `slotSubscribe` when a websocket url is set
+    pub async fn start_slot_clock(&self) -> Result<(), TradingEndpointError> {
+        if let Some(ws_url) = self.ws_url.as_ref() {
+            self.slot_clock.spawn_slot_subscribe(ws_url.clone());
+        }
+        self.slot_clock.sync(&self.rpc).await?;
+        Ok(())
+    }
+
+    /// Estimated current slot, syncing with `getSlot` when the clock went stale
+    pub async fn get_current_slot(&self) -> Result<u64, TradingEndpointError> {
+        self.slot_clock.current_slot_or_sync(&self.rpc).await
+    }
+
+    /// Load the leader schedule, retrying in the background when it fails so broadcasts go unrouted meanwhile
+    pub async fn refresh_leader_schedule(&self) -> Result<(), TradingEndpointError> {
+        let Some(router) = self.leader_router.as_ref() else {
+            return Ok(());
+        };
+        router.refresh().await.inspect_err(|_| {
+            router.spawn_refresh();
+        })
+    }
+
+    /// Pick the SWQoS runtimes to send to, every runtime when leader routing is disabled
+    pub async fn route(&self) -> RoutingDecision {
+        match self.leader_router.as_ref() {
+            Some(router) => router.route(&self.swqos).await,
+            None => RoutingDecision::unrouted(&self.swqos),
+        }
+    }
+
     /// Get the appropriate tip configuration based on transaction type
     fn get_tip_config(&self, swqos: &SWQoSRuntime, tx_type: TransactionType, additional_tip: u64) -> Result<Option<TipFee>, TradingEndpointError> {
         let tip_account = match swqos.get_tip_account() {
@@ -56,12 +220,40 @@ impl TradingEndpoint {
         };
 
         let tip_lamports = self.get_default_tip_for_tx_type(swqos, tx_type)?.0.add(additional_tip);
+        match swqos.get_min_tip() {
+            Some(Lamports(min_tip)) if tip_lamports < min_tip => {
+                return Err(TipError::BelowMinimum {
+                    provider: swqos.get_client_name().to_string(),
+                    tip: tip_lamports,
+                    min_tip,
+                }
+                .into())
+            }
+            None if tip_lamports < swqos.config.kind.default_min_tip().0 => warn!(
+                "Tip of {} lamports is below the {} lamports SWQoS {} usually forwards",
+                tip_lamports,
+                swqos.config.kind.default_min_tip().0,
+                swqos.get_client_name()
+            ),
+            _ => {}
+        }
 
         Ok(Some(TipFee { tip_account, tip_lamports }))
     }
 
+    /// Reject a separate tip on a provider without atomic bundles, where it could land without the trade
+    fn check_tip_placement(swqos: &SWQoSRuntime, tip_placement: TipPlacement) -> Result<(), TradingEndpointError> {
+        if tip_placement == TipPlacement::Separate && !swqos.config.kind.supports_bundles() {
+            return Err(TipError::SeparateUnsupported {
+                provider: swqos.get_client_name().to_string(),
+            }
+            .into());
+        }
+        Ok(())
+    }
+
     /// Get the default tip amount based on transaction type
-    fn get_default_tip_for_tx_type(&self, swqos: &SWQoSRuntime, tx_type: TransactionType) -> Result<Lamports, TradingEndpointError> {
+    pub(crate) fn get_default_tip_for_tx_type(&self, swqos: &SWQoSRuntime, tx_type: TransactionType) -> Result<Lamports, TradingEndpointError> {
         let tip = match tx_type {
             TransactionType::Buy => swqos.config.buy_tip,
             TransactionType::Sell => swqos.config.sell_tip.or(swqos.config.buy_tip), // Fallback to buy_tip if sell_tip not configured
@@ -78,7 +270,7 @@ impl TradingEndpoint {
     }
 
     /// Get the appropriate fee configuration based on transaction type
-    fn get_fee_config(&self, swqos: &SWQoSRuntime, tx_type: TransactionType, additional_fee: Option<PriorityFee>) -> Option<PriorityFee> {
+    pub(crate) fn get_fee_config(&self, swqos: &SWQoSRuntime, tx_type: TransactionType, additional_fee: Option<PriorityFee>) -> Option<PriorityFee> {
         let base_fee = match tx_type {
             TransactionType::Buy => swqos.config.buy_fee,
             TransactionType::Sell => swqos.config.sell_fee.or(swqos.config.buy_fee),
@@ -115,17 +307,63 @@ impl TradingEndpoint {
         tx_type: TransactionType,
         payer: &Keypair,
         instructions: Vec<Instruction>,
-        nonce_ix: Option<Instruction>,
-        blockhashes: Vec<Hash>,
-        additional_fee: Option<PriorityFee>,
-        additional_tip: u64,
+        settings: TxSettings,
         other_signers: Option<Vec<&Keypair>>,
     ) -> Result<Vec<Signature>, TradingEndpointError> {
+        let report = self
+            .build_and_broadcast_tx_with_report(tx_type, payer, instructions, settings, other_signers)
+            .await?;
+
+        Ok(report.signatures)
+    }
+
+    pub async fn build_and_broadcast_tx_with_report(
+        &self,
+        tx_type: TransactionType,
+        payer: &Keypair,
+        instructions: Vec<Instruction>,
+        settings: TxSettings,
+        other_signers: Option<Vec<&Keypair>>,
+    ) -> Result<BroadcastReport, TradingEndpointError> {
+        let TxSettings {
+            nonce_ix,
+            blockhashes,
+            fees: AdditionalFees {
+                fee: additional_fee,
+                tip: additional_tip,
+            },
+        } = settings;
         let mut signatures = vec![];
         let mut txs_to_send = Vec::new();
+        let mut skipped = vec![];
+        let tip_payer = self.get_tip_payer(payer);
+        let routing = self.route().await;
 
         for (index, swqos) in self.swqos.iter().enumerate() {
+            if !routing.is_selected(index) {
+                continue;
+            }
+
             let mut transaction_instructions = vec![];
+            let tip_placement = match (swqos.config.tip_placement, nonce_ix.is_some()) {
+                // A nonce validates a single transaction, so the tip stays in it and is paid at most once across resubmissions
+                (TipPlacement::Separate, true) => {
+                    warn!(
+                        "SWQoS {} tips in a separate transaction, but the trade uses a durable nonce, so the tip goes last in the trade",
+                        swqos.get_client_name()
+                    );
+                    TipPlacement::InlineLast
+                }
+                (tip_placement, _) => tip_placement,
+            };
+            let tip_config = match Self::check_tip_placement(swqos, tip_placement).and_then(|_| self.get_tip_config(swqos, tx_type, additional_tip)) {
+                Ok(tip_config) => tip_config,
+                Err(e) => {
+                    warn!("Skipping SWQoS {}: {}", swqos.get_client_name(), e);
+                    skipped.push(e);
+                    continue;
+                }
+            };
 
             // Add nonce instruction if provided
             if let Some(ix) = nonce_ix.as_ref() {
@@ -137,23 +375,27 @@ impl TradingEndpoint {
             transaction_instructions.extend(fee_instructions);
 
             // Add tip instruction if configured
-            let tip_config = self.get_tip_config(swqos, tx_type, additional_tip)?;
-            if let Some(tip_instruction) = self.build_tip_instruction(payer, tip_config) {
-                transaction_instructions.push(tip_instruction);
+            let tip_instruction = self.build_tip_instruction(tip_payer, tip_config);
+            if let (TipPlacement::InlineFirst, Some(ix)) = (tip_placement, tip_instruction.as_ref()) {
+                transaction_instructions.push(ix.clone());
             }
 
             // Add main instructions
             transaction_instructions.extend(instructions.clone());
 
+            if let (TipPlacement::InlineLast, Some(ix)) = (tip_placement, tip_instruction.as_ref()) {
+                transaction_instructions.push(ix.clone());
+            }
+
             // Get blockhash for this transaction, cycling through available hashes
             let blockhash = blockhashes[index % blockhashes.len()];
 
-            let tx = build_legacy_transaction(
-                payer,
-                transaction_instructions,
-                blockhash,
-                other_signers.as_ref().map(|v| v.to_vec())
-            )
+            let mut signers = other_signers.clone().unwrap_or_default();
+            if tip_instruction.is_some() && tip_placement != TipPlacement::Separate && tip_payer.pubkey() != payer.pubkey() {
+                signers.push(tip_payer);
+            }
+
+            let tx = build_legacy_transaction(payer, transaction_instructions, blockhash, Some(signers))
                 .map_err(|e| TradingEndpointError::TransactionError(e.to_string()))?;
 
             let signature = match tx {
@@ -162,13 +404,30 @@ impl TradingEndpoint {
             };
 
             signatures.push(signature);
-            txs_to_send.push((swqos, tx));
+
+            let mut txs = vec![tx];
+            if let (TipPlacement::Separate, Some(ix)) = (tip_placement, tip_instruction) {
+                let tip_tx =
+                    build_legacy_transaction(tip_payer, vec![ix], blockhash, None).map_err(|e| TradingEndpointError::TransactionError(e.to_string()))?;
+                txs.push(tip_tx);
+            }
+
+            txs_to_send.push((swqos, txs));
+        }
+
+        if txs_to_send.is_empty() && !skipped.is_empty() {
+            return Err(skipped.remove(0));
         }
 
-        // Send all transactions concurrently
+        // Send all transactions concurrently, trade and tip transactions go out together as a bundle
         let tasks: Vec<_> = txs_to_send
             .into_iter()
-            .map(|(swqos, tx)| async move { swqos.send_transaction(tx).await })
+            .map(|(swqos, mut txs)| async move {
+                match txs.len() {
+                    1 => swqos.send_transaction(txs.remove(0)).await,
+                    _ => swqos.send_transactions(txs).await,
+                }
+            })
             .collect();
 
         let results = futures::future::join_all(tasks).await;
@@ -182,7 +441,7 @@ impl TradingEndpoint {
             )));
         }
 
-        Ok(signatures)
+        Ok(BroadcastReport { signatures, routing })
     }
 
     pub async fn build_and_broadcast_batch_txs(
@@ -195,33 +454,70 @@ impl TradingEndpoint {
     ) -> Result<Vec<Signature>, TradingEndpointError> {
         let mut tasks = vec![];
         let mut signatures = vec![];
+        let mut skipped = vec![];
+        let routing = self.route().await;
+
+        for (index, swqos) in self.swqos.iter().enumerate() {
+            if !routing.is_selected(index) {
+                continue;
+            }
 
-        for swqos in self.swqos.iter() {
-            let tip_config = self.get_tip_config(swqos, tx_type, custom_tip)?;
+            let tip_placement = swqos.config.tip_placement;
+            let tip_config = match Self::check_tip_placement(swqos, tip_placement).and_then(|_| self.get_tip_config(swqos, tx_type, custom_tip)) {
+                Ok(tip_config) => tip_config,
+                Err(e) => {
+                    warn!("Skipping SWQoS {}: {}", swqos.get_client_name(), e);
+                    skipped.push(e);
+                    continue;
+                }
+            };
             let fee_instructions = self.build_fee_instructions(swqos, tx_type, custom_fee);
 
-            let txs = items
+            let mut txs = items
                 .iter()
                 .map(|item| {
                     let mut transaction_instructions = vec![];
+                    let tip_payer = self.get_tip_payer(&item.payer);
+                    let tip_instruction = match tip_placement {
+                        TipPlacement::Separate => None,
+                        _ => self.build_tip_instruction(tip_payer, tip_config),
+                    };
 
                     // Add fee instructions
                     transaction_instructions.extend(fee_instructions.clone());
 
                     // Add tip instruction if configured
-                    if let Some(tip_instruction) = self.build_tip_instruction(&item.payer, tip_config) {
-                        transaction_instructions.push(tip_instruction);
+                    if let (TipPlacement::InlineFirst, Some(ix)) = (tip_placement, tip_instruction.as_ref()) {
+                        transaction_instructions.push(ix.clone());
                     }
 
                     // Add main instructions
                     transaction_instructions.extend(item.instructions.clone());
 
-                    build_transaction(&item.payer, transaction_instructions, blockhash, None)
+                    if let (TipPlacement::InlineLast, Some(ix)) = (tip_placement, tip_instruction.as_ref()) {
+                        transaction_instructions.push(ix.clone());
+                    }
+
+                    let other_signers = match tip_instruction.is_some() && tip_payer.pubkey() != item.payer.pubkey() {
+                        true => Some(vec![tip_payer]),
+                        false => None,
+                    };
+
+                    build_transaction(&item.payer, transaction_instructions, blockhash, other_signers)
                 })
                 .collect::<Result<Vec<_>, _>>()
                 .map_err(|e| TradingEndpointError::TransactionError(e.to_string()))?;
 
-            signatures.extend(txs.iter().map(|tx| match tx {
+            // A single tip transaction covers the whole batch
+            if let (TipPlacement::Separate, Some(item)) = (tip_placement, items.first()) {
+                let tip_payer = self.get_tip_payer(&item.payer);
+                if let Some(ix) = self.build_tip_instruction(tip_payer, tip_config) {
+                    let tip_tx = build_transaction(tip_payer, vec![ix], blockhash, None).map_err(|e| TradingEndpointError::TransactionError(e.to_string()))?;
+                    txs.push(tip_tx);
+                }
+            }
+
+            signatures.extend(txs.iter().take(items.len()).map(|tx| match tx {
                 Transaction::Legacy(ref tx) => tx.signatures[0],
                 Transaction::Versioned(ref tx) => tx.signatures[0],
             }));
@@ -229,6 +525,10 @@ impl TradingEndpoint {
             tasks.push(swqos.send_transactions(txs));
         }
 
+        if tasks.is_empty() && !skipped.is_empty() {
+            return Err(skipped.remove(0));
+        }
+
         let results = futures::future::join_all(tasks).await;
         let errors: Vec<_> = results.into_iter().filter_map(Result::err).collect();
 
@@ -242,3 +542,335 @@ impl TradingEndpoint {
         Ok(signatures)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        errors::swqos_error::SWQoSError,
+        swqos::{SWQoSConfig, SWQoSTrait, SWQoSType},
+    };
+    use std::sync::Mutex;
+
+    /// SWQoS client recording what it was asked to send
+    struct RecordingClient {
+        tip_account: Pubkey,
+        sent: Mutex<Vec<Vec<Transaction>>>,
+    }
+
+    #[async_trait::async_trait]
+    impl SWQoSTrait for RecordingClient {
+        async fn send_transaction(&self, transaction: Transaction) -> Result<(), SWQoSError> {
+            self.sent.lock().unwrap().push(vec![transaction]);
+            Ok(())
+        }
+
+        async fn send_transactions(&self, transactions: Vec<Transaction>) -> Result<(), SWQoSError> {
+            self.sent.lock().unwrap().push(transactions);
+            Ok(())
+        }
+
+        fn get_tip_account(&self) -> Option<Pubkey> {
+            Some(self.tip_account)
+        }
+
+        fn get_name(&self) -> &str {
+            "recording"
+        }
+    }
+
+    fn runtime(config: SWQoSConfig) -> (Arc<SWQoSRuntime>, Arc<RecordingClient>) {
+        let client = Arc::new(RecordingClient {
+            tip_account: Pubkey::new_unique(),
+            sent: Mutex::new(vec![]),
+        });
+        (
+            Arc::new(SWQoSRuntime {
+                config,
+                client: client.clone(),
+            }),
+            client,
+        )
+    }
+
+    fn nextblock(tip: u64) -> SWQoSConfig {
+        SWQoSConfig::new(SWQoSType::NextBlock(String::new(), String::new())).with_buy_tip(Lamports(tip))
+    }
+
+    fn endpoint(swqos: Vec<Arc<SWQoSRuntime>>) -> TradingEndpoint {
+        TradingEndpoint::new(Arc::new(RpcClient::new("http://127.0.0.1:8899".to_string())), swqos)
+    }
+
+    /// Receiver of the transfer standing in for the trade
+    const TRADE_RECEIVER: Pubkey = Pubkey::new_from_array([7; 32]);
+
+    fn trade(payer: &Keypair) -> Vec<Instruction> {
+        vec![solana_sdk::system_instruction::transfer(&payer.pubkey(), &TRADE_RECEIVER, 1)]
+    }
+
+    async fn broadcast(endpoint: &TradingEndpoint, payer: &Keypair) -> Result<BroadcastReport, TradingEndpointError> {
+        endpoint
+            .build_and_broadcast_tx_with_report(TransactionType::Buy, payer, trade(payer), TxSettings::new(Hash::default(), None, 0), None)
+            .await
+    }
+
+    fn jito(tip: u64, tip_placement: TipPlacement) -> SWQoSConfig {
+        SWQoSConfig::new(SWQoSType::Jito(String::new()))
+            .with_buy_tip(Lamports(tip))
+            .with_tip_placement(tip_placement)
+    }
+
+    /// Program and accounts of every instruction of `transaction`
+    fn instructions(transaction: &Transaction) -> Vec<(Pubkey, Vec<Pubkey>)> {
+        let (keys, instructions) = match transaction {
+            Transaction::Legacy(tx) => (tx.message.account_keys.clone(), tx.message.instructions.clone()),
+            Transaction::Versioned(tx) => (tx.message.static_account_keys().to_vec(), tx.message.instructions().to_vec()),
+        };
+        instructions
+            .iter()
+            .map(|ix| {
+                let accounts = ix.accounts.iter().map(|index| keys[*index as usize]).collect();
+                (keys[ix.program_id_index as usize], accounts)
+            })
+            .collect()
+    }
+
+    fn signers(transaction: &Transaction) -> Vec<Pubkey> {
+        match transaction {
+            Transaction::Legacy(tx) => tx.message.account_keys[..tx.message.header.num_required_signatures as usize].to_vec(),
+            Transaction::Versioned(tx) => tx.message.static_account_keys()[..tx.message.header().num_required_signatures as usize].to_vec(),
+        }
+    }
+
+    fn signature(transaction: &Transaction) -> Signature {
+        match transaction {
+            Transaction::Legacy(tx) => tx.signatures[0],
+            Transaction::Versioned(tx) => tx.signatures[0],
+        }
+    }
+
+    fn is_tip(instruction: &(Pubkey, Vec<Pubkey>), client: &RecordingClient) -> bool {
+        instruction.0 == solana_program::system_program::ID && instruction.1[1] == client.tip_account
+    }
+
+    fn is_trade(instruction: &(Pubkey, Vec<Pubkey>), payer: &Keypair) -> bool {
+        instruction.0 == solana_program::system_program::ID && instruction.1 == vec![payer.pubkey(), TRADE_RECEIVER]
+    }
+
+    #[tokio::test]
+    async fn inline_tips_go_before_or_after_the_trade() {
+        let payer = Keypair::new();
+        let fee = PriorityFee {
+            unit_limit: 200_000,
+            unit_price: 1_000,
+        };
+        let (first, first_client) = runtime(jito(1_000, TipPlacement::InlineFirst).with_buy_fee(fee));
+        let (last, last_client) = runtime(jito(1_000, TipPlacement::InlineLast));
+        let endpoint = endpoint(vec![first, last]);
+
+        broadcast(&endpoint, &payer).await.unwrap();
+
+        let sent = first_client.sent.lock().unwrap();
+        assert_eq!(sent.len(), 1);
+        let first_instructions = instructions(&sent[0][0]);
+        assert_eq!(first_instructions.len(), 4);
+        assert!(first_instructions[..2].iter().all(|ix| ix.0 == solana_sdk::compute_budget::ID));
+        assert!(is_tip(&first_instructions[2], &first_client));
+        assert!(is_trade(&first_instructions[3], &payer));
+
+        let sent = last_client.sent.lock().unwrap();
+        let last_instructions = instructions(&sent[0][0]);
+        assert_eq!(last_instructions.len(), 2);
+        assert!(is_trade(&last_instructions[0], &payer));
+        assert!(is_tip(&last_instructions[1], &last_client));
+    }
+
+    #[tokio::test]
+    async fn separate_tips_follow_the_trade_in_one_bundle_paid_by_the_tip_payer() {
+        let payer = Keypair::new();
+        let tip_payer = Arc::new(Keypair::new());
+        let (separate, client) = runtime(jito(1_000, TipPlacement::Separate));
+        let endpoint = endpoint(vec![separate]).with_tip_payer(Some(tip_payer.clone()));
+
+        let report = broadcast(&endpoint, &payer).await.unwrap();
+
+        let sent = client.sent.lock().unwrap();
+        assert_eq!(sent.len(), 1);
+        let [trade_tx, tip_tx] = sent[0].as_slice() else {
+            panic!("expected a trade and a tip transaction, got {:?}", sent[0]);
+        };
+        assert_eq!(report.signatures, vec![signature(trade_tx)]);
+        assert_eq!(instructions(trade_tx).len(), 1);
+        assert_eq!(signers(trade_tx), vec![payer.pubkey()]);
+        let tip_instructions = instructions(tip_tx);
+        assert_eq!(tip_instructions.len(), 1);
+        assert!(is_tip(&tip_instructions[0], &client));
+        assert_eq!(tip_instructions[0].1[0], tip_payer.pubkey());
+        assert_eq!(signers(tip_tx), vec![tip_payer.pubkey()]);
+    }
+
+    #[tokio::test]
+    async fn inline_tips_from_a_tip_payer_are_signed_by_it() {
+        let payer = Keypair::new();
+        let tip_payer = Arc::new(Keypair::new());
+        let (inline, client) = runtime(jito(1_000, TipPlacement::InlineFirst));
+        let endpoint = endpoint(vec![inline]).with_tip_payer(Some(tip_payer.clone()));
+
+        broadcast(&endpoint, &payer).await.unwrap();
+
+        let sent = client.sent.lock().unwrap();
+        let tx_instructions = instructions(&sent[0][0]);
+        assert_eq!(tx_instructions[0].1[0], tip_payer.pubkey());
+        assert_eq!(signers(&sent[0][0]), vec![payer.pubkey(), tip_payer.pubkey()]);
+    }
+
+    #[tokio::test]
+    async fn separate_tips_stay_in_nonce_trades() {
+        let payer = Keypair::new();
+        let nonce_account = Pubkey::new_unique();
+        let (separate, client) = runtime(jito(1_000, TipPlacement::Separate));
+        let endpoint = endpoint(vec![separate]);
+        let nonce_ix = solana_sdk::system_instruction::advance_nonce_account(&nonce_account, &payer.pubkey());
+
+        endpoint
+            .build_and_broadcast_tx(
+                TransactionType::Buy,
+                &payer,
+                trade(&payer),
+                TxSettings::with_nonce(nonce_ix, Hash::default(), AdditionalFees::default()),
+                None,
+            )
+            .await
+            .unwrap();
+
+        let sent = client.sent.lock().unwrap();
+        assert_eq!(sent[0].len(), 1);
+        let tx_instructions = instructions(&sent[0][0]);
+        assert_eq!(tx_instructions.len(), 3);
+        assert_eq!(tx_instructions[0].1[0], nonce_account);
+        assert!(is_trade(&tx_instructions[1], &payer));
+        assert!(is_tip(&tx_instructions[2], &client));
+    }
+
+    fn batch_items(count: usize) -> Vec<BatchTxItem> {
+        (0..count)
+            .map(|_| {
+                let payer = Keypair::new();
+                let instructions = trade(&payer);
+                BatchTxItem { payer, instructions }
+            })
+            .collect()
+    }
+
+    #[tokio::test]
+    async fn separate_batch_tips_are_one_transaction_for_the_whole_batch() {
+        let (separate, client) = runtime(jito(1_000, TipPlacement::Separate));
+        let endpoint = endpoint(vec![separate]);
+        let items = batch_items(3);
+        let payers = items.iter().map(|item| item.payer.pubkey()).collect::<Vec<_>>();
+
+        let signatures = endpoint
+            .build_and_broadcast_batch_txs(TransactionType::Buy, items, Hash::default(), None, 0)
+            .await
+            .unwrap();
+
+        let sent = client.sent.lock().unwrap();
+        assert_eq!(sent.len(), 1);
+        assert_eq!(sent[0].len(), 4);
+        assert_eq!(signatures, sent[0][..3].iter().map(signature).collect::<Vec<_>>());
+        for (tx, payer) in sent[0][..3].iter().zip(&payers) {
+            let tx_instructions = instructions(tx);
+            assert_eq!(tx_instructions.len(), 1);
+            assert_eq!(tx_instructions[0].1[0], *payer);
+        }
+        let tip_instructions = instructions(&sent[0][3]);
+        assert_eq!(tip_instructions.len(), 1);
+        assert!(is_tip(&tip_instructions[0], &client));
+        assert_eq!(signers(&sent[0][3]), vec![payers[0]]);
+    }
+
+    #[tokio::test]
+    async fn inline_batch_tips_are_paid_by_every_item() {
+        let (inline, client) = runtime(jito(1_000, TipPlacement::InlineLast));
+        let endpoint = endpoint(vec![inline]);
+
+        endpoint
+            .build_and_broadcast_batch_txs(TransactionType::Buy, batch_items(2), Hash::default(), None, 0)
+            .await
+            .unwrap();
+
+        let sent = client.sent.lock().unwrap();
+        assert_eq!(sent[0].len(), 2);
+        for tx in &sent[0] {
+            let tx_instructions = instructions(tx);
+            assert_eq!(tx_instructions.len(), 2);
+            assert!(is_tip(&tx_instructions[1], &client));
+        }
+    }
+
+    #[tokio::test]
+    async fn separate_tips_skip_providers_without_bundles() {
+        let payer = Keypair::new();
+        let (unsupported, unsupported_client) = runtime(nextblock(1_000_000).with_tip_placement(TipPlacement::Separate));
+        let (supported, supported_client) = runtime(jito(1_000, TipPlacement::Separate));
+        let endpoint = endpoint(vec![unsupported, supported]);
+
+        broadcast(&endpoint, &payer).await.unwrap();
+        assert!(unsupported_client.sent.lock().unwrap().is_empty());
+        assert_eq!(supported_client.sent.lock().unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn tips_below_the_provider_default_are_sent_without_a_configured_minimum() {
+        let payer = Keypair::new();
+        let (runtime, client) = runtime(nextblock(1_000));
+        let endpoint = endpoint(vec![runtime]);
+
+        broadcast(&endpoint, &payer).await.unwrap();
+        assert_eq!(client.sent.lock().unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn providers_below_their_configured_minimum_are_skipped() {
+        let payer = Keypair::new();
+        let (below, below_client) = runtime(nextblock(1_000).with_min_tip(Lamports(1_000_000)));
+        let (above, above_client) = runtime(nextblock(1_000_000).with_min_tip(Lamports(1_000_000)));
+        let endpoint = endpoint(vec![below, above]);
+
+        let report = broadcast(&endpoint, &payer).await.unwrap();
+        assert_eq!(report.signatures.len(), 1);
+        assert!(below_client.sent.lock().unwrap().is_empty());
+        assert_eq!(above_client.sent.lock().unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn broadcast_fails_when_every_provider_is_skipped() {
+        let payer = Keypair::new();
+        let (below, client) = runtime(nextblock(1_000).with_min_tip(Lamports(1_000_000)));
+        let endpoint = endpoint(vec![below]);
+
+        let error = broadcast(&endpoint, &payer).await.unwrap_err();
+        assert!(matches!(
+            error,
+            TradingEndpointError::Tip(TipError::BelowMinimum {
+                tip: 1_000,
+                min_tip: 1_000_000,
+                ..
+            })
+        ));
+        assert!(client.sent.lock().unwrap().is_empty());
+    }
+
+    #[test]
+    fn tip_transfers_go_to_the_provider_tip_account() {
+        let payer = Keypair::new();
+        let (runtime, client) = runtime(nextblock(5_000));
+        let endpoint = endpoint(vec![]);
+
+        let tip_config = endpoint.get_tip_config(&runtime, TransactionType::Buy, 1_000).unwrap().unwrap();
+        assert_eq!((tip_config.tip_account, tip_config.tip_lamports), (client.tip_account, 6_000));
+        let ix = endpoint.build_tip_instruction(&payer, Some(tip_config)).unwrap();
+        assert_eq!(ix.program_id, solana_program::system_program::ID);
+        assert_eq!(ix.accounts[1].pubkey, client.tip_account);
+    }
+}
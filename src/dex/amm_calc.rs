@@ -42,6 +42,145 @@ pub fn amm_sell_get_sol_out(sol_reserve: u64, token_reserve: u64, token_in: u64)
     sol_out as u64
 }
 
+pub const FEE_RATE_DENOMINATOR: u64 = 1_000_000;
+
+/// Fees charged on the SOL side of a swap, in millionths of the amount
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct FeeModel {
+    pub protocol_fee_rate: u64,
+    pub lp_fee_rate: u64,
+    pub creator_fee_rate: u64,
+    pub platform_fee_rate: u64,
+    pub share_fee_rate: u64,
+    /// Buy fees are charged on top of the SOL swapped instead of being taken out of the SOL paid
+    pub charged_on_top: bool,
+}
+
+/// Fee paid to each recipient, in lamports
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct FeeAmounts {
+    pub protocol: u64,
+    pub lp: u64,
+    pub creator: u64,
+    pub platform: u64,
+    pub share: u64,
+}
+
+impl FeeAmounts {
+    pub fn total(&self) -> u64 {
+        self.protocol + self.lp + self.creator + self.platform + self.share
+    }
+}
+
+impl FeeModel {
+    /// Fee rate in millionths for a rate in basis points
+    pub const fn rate_from_basis_points(basis_points: u64) -> u64 {
+        basis_points * (FEE_RATE_DENOMINATOR / 10_000)
+    }
+
+    pub fn total_fee_rate(&self) -> u64 {
+        self.protocol_fee_rate + self.lp_fee_rate + self.creator_fee_rate + self.platform_fee_rate + self.share_fee_rate
+    }
+
+    /// Fee charged on `amount` by recipient, each rounded up as the programs do
+    pub fn get_fee_amounts(&self, amount: u64) -> FeeAmounts {
+        let fee = |rate: u64| (amount as u128 * rate as u128).div_ceil(FEE_RATE_DENOMINATOR as u128) as u64;
+        FeeAmounts {
+            protocol: fee(self.protocol_fee_rate),
+            lp: fee(self.lp_fee_rate),
+            creator: fee(self.creator_fee_rate),
+            platform: fee(self.platform_fee_rate),
+            share: fee(self.share_fee_rate),
+        }
+    }
+
+    /// Fee charged on `amount`
+    pub fn get_fee(&self, amount: u64) -> u64 {
+        self.get_fee_amounts(amount).total()
+    }
+
+    /// Fees paid by a buyer paying `sol_in`
+    pub fn get_buy_fee_amounts(&self, sol_in: u64) -> FeeAmounts {
+        match self.charged_on_top {
+            true => self.get_fee_amounts(self.buy_amount_after_fees(sol_in)),
+            false => self.get_fee_amounts(sol_in),
+        }
+    }
+
+    /// SOL reaching the curve when the buyer pays `sol_in`
+    pub fn buy_amount_after_fees(&self, sol_in: u64) -> u64 {
+        if !self.charged_on_top {
+            return sol_in.saturating_sub(self.get_fee(sol_in));
+        }
+
+        let denominator = (FEE_RATE_DENOMINATOR + self.total_fee_rate()) as u128;
+        let mut amount = (sol_in as u128 * FEE_RATE_DENOMINATOR as u128 / denominator) as u64;
+        // Rounded up fees can push the total past `sol_in` by a few lamports
+        while amount > 0 && amount + self.get_fee(amount) > sol_in {
+            amount -= 1;
+        }
+        amount
+    }
+
+    /// SOL the buyer pays for `sol_in` to reach the curve
+    pub fn buy_amount_with_fees(&self, sol_in: u64) -> u64 {
+        if self.charged_on_top {
+            return sol_in + self.get_fee(sol_in);
+        }
+
+        if self.total_fee_rate() >= FEE_RATE_DENOMINATOR {
+            return u64::MAX;
+        }
+
+        let denominator = (FEE_RATE_DENOMINATOR - self.total_fee_rate()) as u128;
+        let mut amount = (sol_in as u128 * FEE_RATE_DENOMINATOR as u128).div_ceil(denominator) as u64;
+        while self.buy_amount_after_fees(amount) < sol_in {
+            amount += 1;
+        }
+        while amount > 0 && self.buy_amount_after_fees(amount - 1) >= sol_in {
+            amount -= 1;
+        }
+        amount
+    }
+
+    /// SOL received by the seller when `sol_out` leaves the curve
+    pub fn sell_amount_after_fees(&self, sol_out: u64) -> u64 {
+        sol_out.saturating_sub(self.get_fee(sol_out))
+    }
+}
+
+pub fn amm_buy_get_sol_in_with_fees(sol_reserve: u64, token_reserve: u64, token_out: u64, fees: &FeeModel) -> u64 {
+    match amm_buy_get_sol_in(sol_reserve, token_reserve, token_out) {
+        0 => 0,
+        sol_in => fees.buy_amount_with_fees(sol_in),
+    }
+}
+
+pub fn amm_buy_get_token_out_with_fees(sol_reserve: u64, token_reserve: u64, sol_in: u64, fees: &FeeModel) -> u64 {
+    amm_buy_get_token_out(sol_reserve, token_reserve, fees.buy_amount_after_fees(sol_in))
+}
+
+pub fn amm_sell_get_sol_out_with_fees(sol_reserve: u64, token_reserve: u64, token_in: u64, fees: &FeeModel) -> u64 {
+    fees.sell_amount_after_fees(amm_sell_get_sol_out(sol_reserve, token_reserve, token_in))
+}
+
+/// Spot price in lamports per token unit
+pub fn amm_get_price(sol_reserve: u64, token_reserve: u64) -> f64 {
+    if token_reserve == 0 {
+        return 0.0;
+    }
+
+    sol_reserve as f64 / token_reserve as f64
+}
+
+pub fn calculate_price_impact_bps(price_before: f64, price_after: f64) -> u64 {
+    if price_before <= 0.0 {
+        return 0;
+    }
+
+    ((price_after - price_before).abs() / price_before * 10_000.0).round() as u64
+}
+
 pub fn calculate_with_slippage_buy(amount: u64, basis_points: u64) -> u64 {
     amount + (amount * basis_points) / 10000
 }
@@ -49,3 +188,246 @@ pub fn calculate_with_slippage_buy(amount: u64, basis_points: u64) -> u64 {
 pub fn calculate_with_slippage_sell(amount: u64, basis_points: u64) -> u64 {
     amount - (amount * basis_points) / 10000
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{rngs::StdRng, Rng, SeedableRng};
+
+    /// `fee` of the Pumpfun and PumpSwap programs, basis points rounded up
+    fn pump_fee(amount: u64, basis_points: u64) -> u64 {
+        (amount as u128 * basis_points as u128).div_ceil(10_000) as u64
+    }
+
+    /// `calculate_fee` of the LaunchLab program, millionths rounded up
+    fn launchlab_fee(amount: u64, rate: u64) -> u64 {
+        (amount as u128 * rate as u128).div_ceil(1_000_000) as u64
+    }
+
+    fn pumpfun_fees(fee_basis_points: u64, creator_fee_basis_points: u64) -> FeeModel {
+        FeeModel {
+            protocol_fee_rate: FeeModel::rate_from_basis_points(fee_basis_points),
+            creator_fee_rate: FeeModel::rate_from_basis_points(creator_fee_basis_points),
+            charged_on_top: true,
+            ..Default::default()
+        }
+    }
+
+    fn pumpswap_fees(lp_fee_basis_points: u64, protocol_fee_basis_points: u64, coin_creator_fee_basis_points: u64) -> FeeModel {
+        FeeModel {
+            protocol_fee_rate: FeeModel::rate_from_basis_points(protocol_fee_basis_points),
+            lp_fee_rate: FeeModel::rate_from_basis_points(lp_fee_basis_points),
+            creator_fee_rate: FeeModel::rate_from_basis_points(coin_creator_fee_basis_points),
+            charged_on_top: true,
+            ..Default::default()
+        }
+    }
+
+    fn launchlab_fees(trade_fee_rate: u64, platform_fee_rate: u64, creator_fee_rate: u64, share_fee_rate: u64) -> FeeModel {
+        FeeModel {
+            protocol_fee_rate: trade_fee_rate,
+            platform_fee_rate,
+            creator_fee_rate,
+            share_fee_rate,
+            charged_on_top: false,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn pumpfun_matches_program_fees() {
+        let mut rng = StdRng::seed_from_u64(1);
+        for _ in 0..10_000 {
+            let (fee_bps, creator_bps) = (rng.random_range(0..=200), rng.random_range(0..=100));
+            let fees = pumpfun_fees(fee_bps, creator_bps);
+            let amount = rng.random_range(0..=1_000_000_000_000);
+
+            // `buy`: fees are added on top of the SOL cost of the tokens
+            let expected = amount + pump_fee(amount, fee_bps) + pump_fee(amount, creator_bps);
+            assert_eq!(fees.buy_amount_with_fees(amount), expected);
+
+            // `sell`: fees are taken out of the SOL leaving the curve
+            let expected = amount - pump_fee(amount, fee_bps) - pump_fee(amount, creator_bps);
+            assert_eq!(fees.sell_amount_after_fees(amount), expected);
+
+            // Buying with a fixed SOL budget spends as much as the budget allows on the curve
+            let to_curve = fees.buy_amount_after_fees(amount);
+            assert!(fees.buy_amount_with_fees(to_curve) <= amount);
+            assert!(fees.buy_amount_with_fees(to_curve + 1) > amount);
+        }
+    }
+
+    #[test]
+    fn pumpswap_matches_program_fees() {
+        let mut rng = StdRng::seed_from_u64(2);
+        for _ in 0..10_000 {
+            let (lp_bps, protocol_bps, creator_bps) = (rng.random_range(0..=100), rng.random_range(0..=100), rng.random_range(0..=100));
+            let fees = pumpswap_fees(lp_bps, protocol_bps, creator_bps);
+            let amount = rng.random_range(0..=1_000_000_000_000);
+
+            let amounts = fees.get_fee_amounts(amount);
+            assert_eq!(amounts.lp, pump_fee(amount, lp_bps));
+            assert_eq!(amounts.protocol, pump_fee(amount, protocol_bps));
+            assert_eq!(amounts.creator, pump_fee(amount, creator_bps));
+
+            let expected = amount + pump_fee(amount, lp_bps) + pump_fee(amount, protocol_bps) + pump_fee(amount, creator_bps);
+            assert_eq!(fees.buy_amount_with_fees(amount), expected);
+            let expected = amount - pump_fee(amount, lp_bps) - pump_fee(amount, protocol_bps) - pump_fee(amount, creator_bps);
+            assert_eq!(fees.sell_amount_after_fees(amount), expected);
+        }
+    }
+
+    #[test]
+    fn launchlab_matches_program_fees() {
+        let mut rng = StdRng::seed_from_u64(3);
+        for _ in 0..10_000 {
+            let rates = (
+                rng.random_range(0..=10_000),
+                rng.random_range(0..=10_000),
+                rng.random_range(0..=10_000),
+                rng.random_range(0..=10_000),
+            );
+            let fees = launchlab_fees(rates.0, rates.1, rates.2, rates.3);
+            let amount = rng.random_range(0..=1_000_000_000_000);
+
+            // `buy_exact_in` and `sell_exact_in`: each fee is taken out of the amount on its own
+            let total_fee = launchlab_fee(amount, rates.0) + launchlab_fee(amount, rates.1) + launchlab_fee(amount, rates.2) + launchlab_fee(amount, rates.3);
+            assert_eq!(fees.buy_amount_after_fees(amount), amount - total_fee);
+            assert_eq!(fees.sell_amount_after_fees(amount), amount - total_fee);
+
+            // `buy_exact_out`: the smallest amount in that leaves `amount` for the curve
+            let amount_in = fees.buy_amount_with_fees(amount);
+            assert!(fees.buy_amount_after_fees(amount_in) >= amount);
+            assert!(amount_in == 0 || fees.buy_amount_after_fees(amount_in - 1) < amount);
+        }
+    }
+
+    #[test]
+    fn launchlab_fee_rates_are_exact() {
+        // Rates that are not whole basis points
+        let fees = launchlab_fees(2_550, 10_000, 550, 1_250);
+        let amounts = fees.get_fee_amounts(1_000_000_000);
+        assert_eq!(amounts.protocol, 2_550_000);
+        assert_eq!(amounts.platform, 10_000_000);
+        assert_eq!(amounts.creator, 550_000);
+        assert_eq!(amounts.share, 1_250_000);
+        assert_eq!(fees.buy_amount_after_fees(1_000_000_000), 985_650_000);
+
+        // Each fee is rounded up on its own
+        assert_eq!(fees.get_fee(1), 4);
+    }
+
+    /// A trade against round-number reserves with its amounts computed by hand from the program's integer math,
+    /// not captured from a transaction, so it only pins this module to our reading of the program
+    struct TradeFixture {
+        sol_reserve: u64,
+        token_reserve: u64,
+        fees: FeeModel,
+        amount_in: u64,
+        amount_out: u64,
+        fee: u64,
+    }
+
+    fn check_buy(fixture: &TradeFixture) {
+        let token_out = amm_buy_get_token_out_with_fees(fixture.sol_reserve, fixture.token_reserve, fixture.amount_in, &fixture.fees);
+        assert_eq!(token_out, fixture.amount_out);
+        assert_eq!(fixture.fees.get_buy_fee_amounts(fixture.amount_in).total(), fixture.fee);
+    }
+
+    fn check_sell(fixture: &TradeFixture) {
+        let sol_out = amm_sell_get_sol_out_with_fees(fixture.sol_reserve, fixture.token_reserve, fixture.amount_in, &fixture.fees);
+        assert_eq!(sol_out, fixture.amount_out);
+        let gross = amm_sell_get_sol_out(fixture.sol_reserve, fixture.token_reserve, fixture.amount_in);
+        assert_eq!(fixture.fees.get_fee(gross), fixture.fee);
+    }
+
+    #[test]
+    fn pumpfun_trade_fixtures() {
+        // 1 SOL into a fresh curve, 95 bps protocol and 5 bps creator fees on top:
+        // 990_099_009 lamports reach the curve and 9_900_991 go to fees
+        check_buy(&TradeFixture {
+            sol_reserve: 30_000_000_000,
+            token_reserve: 1_073_000_000_000_000,
+            fees: pumpfun_fees(95, 5),
+            amount_in: 1_000_000_000,
+            amount_out: 34_281_150_129_546,
+            fee: 9_900_991,
+        });
+        // 10M tokens back into the curve left by that buy
+        check_sell(&TradeFixture {
+            sol_reserve: 30_990_099_009,
+            token_reserve: 1_038_718_849_870_454,
+            fees: pumpfun_fees(95, 5),
+            amount_in: 10_000_000_000_000,
+            amount_out: 292_549_312,
+            fee: 2_955_045,
+        });
+    }
+
+    #[test]
+    fn pumpswap_trade_fixtures() {
+        // Canonical pool right after migration, 20 bps LP, 5 bps protocol and 5 bps creator fees
+        check_buy(&TradeFixture {
+            sol_reserve: 85_000_000_000,
+            token_reserve: 206_900_000_000_000,
+            fees: pumpswap_fees(20, 5, 5),
+            amount_in: 500_000_000,
+            amount_out: 1_206_343_649_726,
+            fee: 1_495_515,
+        });
+        check_sell(&TradeFixture {
+            sol_reserve: 85_000_000_000,
+            token_reserve: 206_900_000_000_000,
+            fees: pumpswap_fees(20, 5, 5),
+            amount_in: 1_000_000_000_000,
+            amount_out: 407_623_855,
+            fee: 1_226_553,
+        });
+    }
+
+    #[test]
+    fn boopfun_trade_fixtures() {
+        // 100 bps swap fee taken out of the SOL side
+        let fees = FeeModel {
+            protocol_fee_rate: FeeModel::rate_from_basis_points(100),
+            ..Default::default()
+        };
+        check_buy(&TradeFixture {
+            sol_reserve: 45_000_000_000,
+            token_reserve: 700_000_000_000_000,
+            fees,
+            amount_in: 2_000_000_000,
+            amount_out: 29_501_915_708_813,
+            fee: 20_000_000,
+        });
+        check_sell(&TradeFixture {
+            sol_reserve: 45_000_000_000,
+            token_reserve: 700_000_000_000_000,
+            fees,
+            amount_in: 25_000_000_000_000,
+            amount_out: 1_536_206_895,
+            fee: 15_517_242,
+        });
+    }
+
+    #[test]
+    fn launchlab_trade_fixtures() {
+        // Default LetsBonk curve at launch, 0.25% trade fee and 1% platform fee
+        check_buy(&TradeFixture {
+            sol_reserve: 30_000_852_951,
+            token_reserve: 1_073_025_605_596_382,
+            fees: launchlab_fees(2_500, 10_000, 0, 0),
+            amount_in: 1_000_000_000,
+            amount_out: 34_193_904_632_555,
+            fee: 12_500_000,
+        });
+        check_sell(&TradeFixture {
+            sol_reserve: 30_000_852_951,
+            token_reserve: 1_073_025_605_596_382,
+            fees: launchlab_fees(2_500, 10_000, 0, 0),
+            amount_in: 10_000_000_000_000,
+            amount_out: 273_547_014,
+            fee: 3_462_622,
+        });
+    }
+}
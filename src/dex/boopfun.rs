@@ -1,9 +1,17 @@
-use super::{boopfun_types::*, dex_traits::DexTrait, types::Create};
+use super::{
+    amm_calc::{amm_buy_get_token_out_with_fees, calculate_with_slippage_sell, FeeModel},
+    boopfun_types::*,
+    dex_traits::DexTrait,
+    types::{Create, CreateATA},
+};
 use crate::{
-    common::{accounts::PUBKEY_WSOL, trading_endpoint::TradingEndpoint},
+    common::{
+        accounts::PUBKEY_WSOL,
+        trading_endpoint::{TradingEndpoint, TransactionType, TxSettings},
+    },
     dex::types::{PoolInfo, SwapInfo},
     errors::trading_endpoint_error::TradingEndpointError,
-    instruction::builder::PriorityFee,
+    instruction::builder::{build_token_account_instructions, PriorityFee},
 };
 use solana_sdk::{
     instruction::{AccountMeta, Instruction},
@@ -11,7 +19,7 @@ use solana_sdk::{
     signature::{Keypair, Signature},
     signer::Signer,
 };
-use spl_associated_token_account::get_associated_token_address;
+use spl_associated_token_account::get_associated_token_address_with_program_id;
 use std::sync::Arc;
 
 pub struct Boopfun {
@@ -36,39 +44,63 @@ impl DexTrait for Boopfun {
         false
     }
 
+    fn buy_is_exact_in(&self) -> bool {
+        true
+    }
+
     async fn get_pool(&self, mint: &Pubkey) -> Result<PoolInfo, TradingEndpointError> {
-        let pool = Self::get_bonding_curve_pda(mint)?;
-        let account = self.endpoint.rpc.get_account(&pool).await?;
-        if account.data.is_empty() {
-            return Err(TradingEndpointError::CustomError(format!("Bonding curve not found: {}", mint.to_string())));
+        let (bonding_curve, slot) = self.get_bonding_curve_with_slot(mint).await?;
+        if bonding_curve.status != BondingCurveStatus::Trading {
+            return Err(BoopfunError::Graduated {
+                mint: *mint,
+                status: bonding_curve.status,
+            }
+            .into());
         }
 
-        let bonding_curve = bincode::deserialize::<BondingCurveAccount>(&account.data).map_err(|e| TradingEndpointError::CustomError(e.to_string()))?;
-
         Ok(PoolInfo {
-            pool,
+            pool: Self::get_bonding_curve_pda(mint)?,
             creator: Some(bonding_curve.creator),
             creator_vault: None,
             config: None,
             token_reserves: bonding_curve.virtual_token_reserves,
             sol_reserves: bonding_curve.virtual_sol_reserves,
+            fees: Self::get_fee_model(bonding_curve.swap_fee_basis_points as u64),
+            slot,
         })
     }
 
-    async fn create(&self, _: Keypair, _: Create, _: Option<PriorityFee>, _: Option<u64>) -> Result<Vec<Signature>, TradingEndpointError> {
-        Err(TradingEndpointError::CustomError("Not supported".to_string()))
+    async fn create(&self, payer: Keypair, create: Create, fee: Option<PriorityFee>, tip: Option<u64>) -> Result<Vec<Signature>, TradingEndpointError> {
+        let blockhash = self.endpoint.get_latest_blockhash().await?;
+        let instructions = self.build_create_instructions(&payer, &create)?;
+
+        self.endpoint
+            .build_and_broadcast_tx(
+                TransactionType::Create,
+                &payer,
+                instructions,
+                TxSettings::new(blockhash, fee, tip.unwrap_or_default()),
+                Some(vec![&create.mint_private_key]),
+            )
+            .await
     }
 
     fn build_buy_instruction(
         &self,
-        _payer: &Keypair,
+        payer: &Keypair,
         mint: &Pubkey,
-        _: Option<&Pubkey>,
-        _token_program_account: &Pubkey,
+        custom_ata: Option<&Pubkey>,
+        _: &[AccountMeta],
+        token_program: &Pubkey,
         buy: SwapInfo,
     ) -> Result<Instruction, TradingEndpointError> {
         self.initialized()?;
 
+        let ata = match custom_ata {
+            None => get_associated_token_address_with_program_id(&payer.pubkey(), mint, token_program),
+            Some(t) => *t,
+        };
+
         let buy_info: BuyInfo = buy.into();
         let buffer = buy_info.to_buffer().map_err(|e| TradingEndpointError::CustomError(e.to_string()))?;
         let bonding_curve = Self::get_bonding_curve_pda(mint)?;
@@ -85,13 +117,13 @@ impl DexTrait for Boopfun {
                 AccountMeta::new(trading_fee_vault, false),
                 AccountMeta::new(bonding_curve_vault, false),
                 AccountMeta::new(bonding_curve_sol_vault, false),
-                AccountMeta::new(get_associated_token_address(&_payer.pubkey(), mint), false),
-                AccountMeta::new(_payer.pubkey(), true),
+                AccountMeta::new(ata, false),
+                AccountMeta::new(payer.pubkey(), true),
                 AccountMeta::new_readonly(PUBKEY_BOOPFUN_CONFIG, false),
                 AccountMeta::new_readonly(PUBKEY_BOOPFUN_VAULT_AUTHORITY, false),
                 AccountMeta::new_readonly(PUBKEY_WSOL, false),
                 AccountMeta::new_readonly(solana_program::system_program::ID, false),
-                AccountMeta::new_readonly(spl_token::ID, false),
+                AccountMeta::new_readonly(*token_program, false),
                 AccountMeta::new_readonly(spl_associated_token_account::ID, false),
             ],
         ))
@@ -102,13 +134,14 @@ impl DexTrait for Boopfun {
         payer: &Keypair,
         mint: &Pubkey,
         custom_ata: Option<&Pubkey>,
-        _: Option<&Pubkey>,
+        _: &[AccountMeta],
+        token_program: &Pubkey,
         sell: SwapInfo,
     ) -> Result<Instruction, TradingEndpointError> {
         self.initialized()?;
 
         let ata = match custom_ata {
-            None => get_associated_token_address(&payer.pubkey(), mint),
+            None => get_associated_token_address_with_program_id(&payer.pubkey(), mint, token_program),
             Some(t) => *t,
         };
 
@@ -133,7 +166,7 @@ impl DexTrait for Boopfun {
                 AccountMeta::new(payer.pubkey(), true),
                 AccountMeta::new_readonly(PUBKEY_BOOPFUN_CONFIG, false),
                 AccountMeta::new_readonly(solana_program::system_program::ID, false),
-                AccountMeta::new_readonly(spl_token::ID, false),
+                AccountMeta::new_readonly(*token_program, false),
                 AccountMeta::new_readonly(spl_associated_token_account::ID, false),
             ],
         ))
@@ -145,6 +178,121 @@ impl Boopfun {
         Self { endpoint }
     }
 
+    pub async fn get_bonding_curve(&self, mint: &Pubkey) -> Result<BondingCurveAccount, TradingEndpointError> {
+        Ok(self.get_bonding_curve_with_slot(mint).await?.0)
+    }
+
+    /// Bonding curve with the slot it was read at
+    pub async fn get_bonding_curve_with_slot(&self, mint: &Pubkey) -> Result<(BondingCurveAccount, u64), TradingEndpointError> {
+        let (account, slot) = self.endpoint.get_account_with_slot(&Self::get_bonding_curve_pda(mint)?).await?;
+        if account.data.is_empty() {
+            return Err(TradingEndpointError::CustomError(format!("Bonding curve not found: {mint}")));
+        }
+
+        Ok((Self::decode_bonding_curve(&account.data)?, slot))
+    }
+
+    pub fn decode_bonding_curve(data: &[u8]) -> Result<BondingCurveAccount, TradingEndpointError> {
+        bincode::deserialize::<BondingCurveAccount>(data).map_err(|e| TradingEndpointError::CustomError(e.to_string()))
+    }
+
+    /// Share of the graduation target the curve of `mint` raised so far
+    pub async fn get_graduation_progress(&self, mint: &Pubkey) -> Result<f64, TradingEndpointError> {
+        Ok(self.get_bonding_curve(mint).await?.graduation_progress())
+    }
+
+    /// The swap fee goes to the protocol, taken from the SOL side
+    pub fn get_fee_model(swap_fee_basis_points: u64) -> FeeModel {
+        FeeModel {
+            protocol_fee_rate: FeeModel::rate_from_basis_points(swap_fee_basis_points),
+            ..Default::default()
+        }
+    }
+
+    /// `create_token` and `deploy_bonding_curve` for `create`, then the creator buy when `create.buy_sol_amount` is set,
+    /// priced against the curve's initial virtual reserves
+    pub fn build_create_instructions(&self, payer: &Keypair, create: &Create) -> Result<Vec<Instruction>, TradingEndpointError> {
+        let mint = create.mint_private_key.pubkey();
+        let salt = rand::random::<u64>();
+        let mut instructions = vec![
+            Self::build_create_token_instruction(payer, &mint, CreateTokenInfo::from_create(create, salt))?,
+            Self::build_deploy_bonding_curve_instruction(
+                payer,
+                &mint,
+                DeployBondingCurveInfo {
+                    discriminator: BOOPFUN_DEPLOY_BONDING_CURVE_DISCRIMINATOR,
+                    creator: payer.pubkey(),
+                    salt,
+                },
+            )?,
+        ];
+
+        if let Some(buy_sol_amount) = create.buy_sol_amount {
+            let (token_account, ata_instructions) = build_token_account_instructions(payer, &mint, &spl_token::ID, CreateATA::Create)
+                .map_err(|e| TradingEndpointError::CustomError(e.to_string()))?;
+            instructions.extend(ata_instructions);
+            let token_amount = amm_buy_get_token_out_with_fees(
+                BOOPFUN_INITIAL_VIRTUAL_SOL_RESERVES,
+                BOOPFUN_INITIAL_VIRTUAL_TOKEN_RESERVES,
+                buy_sol_amount,
+                &Self::get_fee_model(BOOPFUN_INITIAL_SWAP_FEE_BASIS_POINTS),
+            );
+            instructions.push(self.build_buy_instruction(
+                payer,
+                &mint,
+                Some(&token_account),
+                &[],
+                &spl_token::ID,
+                SwapInfo {
+                    token_amount: calculate_with_slippage_sell(token_amount, create.slippage_basis_points.unwrap_or(0)),
+                    sol_amount: buy_sol_amount,
+                },
+            )?);
+        }
+
+        Ok(instructions)
+    }
+
+    pub fn build_create_token_instruction(payer: &Keypair, mint: &Pubkey, create_token: CreateTokenInfo) -> Result<Instruction, TradingEndpointError> {
+        let buffer = create_token.to_buffer().map_err(|e| TradingEndpointError::CustomError(e.to_string()))?;
+
+        Ok(Instruction::new_with_bytes(
+            PUBKEY_BOOPFUN,
+            &buffer,
+            vec![
+                AccountMeta::new_readonly(PUBKEY_BOOPFUN_CONFIG, false),
+                AccountMeta::new(mpl_token_metadata::accounts::Metadata::find_pda(mint).0, false),
+                AccountMeta::new(*mint, true),
+                AccountMeta::new(payer.pubkey(), true),
+                AccountMeta::new_readonly(solana_program::sysvar::rent::ID, false),
+                AccountMeta::new_readonly(solana_program::system_program::ID, false),
+                AccountMeta::new_readonly(spl_token::ID, false),
+                AccountMeta::new_readonly(mpl_token_metadata::ID, false),
+            ],
+        ))
+    }
+
+    pub fn build_deploy_bonding_curve_instruction(payer: &Keypair, mint: &Pubkey, deploy: DeployBondingCurveInfo) -> Result<Instruction, TradingEndpointError> {
+        let buffer = deploy.to_buffer().map_err(|e| TradingEndpointError::CustomError(e.to_string()))?;
+
+        Ok(Instruction::new_with_bytes(
+            PUBKEY_BOOPFUN,
+            &buffer,
+            vec![
+                AccountMeta::new(*mint, false),
+                AccountMeta::new_readonly(PUBKEY_BOOPFUN_VAULT_AUTHORITY, false),
+                AccountMeta::new(Self::get_bonding_curve_pda(mint)?, false),
+                AccountMeta::new(Self::get_bonding_curve_sol_vault(mint)?, false),
+                AccountMeta::new(Self::get_bonding_curve_vault(mint)?, false),
+                AccountMeta::new_readonly(PUBKEY_BOOPFUN_CONFIG, false),
+                AccountMeta::new(payer.pubkey(), true),
+                AccountMeta::new_readonly(solana_program::system_program::ID, false),
+                AccountMeta::new_readonly(spl_token::ID, false),
+                AccountMeta::new_readonly(spl_associated_token_account::ID, false),
+            ],
+        ))
+    }
+
     pub fn get_bonding_curve_pda(mint: &Pubkey) -> Result<Pubkey, TradingEndpointError> {
         let seeds: &[&[u8]; 2] = &[BONDING_CURVE_SEED, mint.as_ref()];
         let pda = Pubkey::try_find_program_address(seeds, &PUBKEY_BOOPFUN)
@@ -173,3 +321,161 @@ impl Boopfun {
         Ok(pda.0)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_client::nonblocking::rpc_client::RpcClient;
+
+    fn boopfun() -> Boopfun {
+        let rpc = Arc::new(RpcClient::new("http://127.0.0.1:8899".to_string()));
+        Boopfun::new(Arc::new(TradingEndpoint::new(rpc, vec![])))
+    }
+
+    fn create(buy_sol_amount: Option<u64>) -> Create {
+        Create {
+            name: "Boop Dog".to_string(),
+            symbol: "BOOP".to_string(),
+            uri: "https://example.com/boop.json".to_string(),
+            mint_private_key: Keypair::new(),
+            buy_sol_amount,
+            slippage_basis_points: Some(100),
+        }
+    }
+
+    fn pda(seeds: &[&[u8]], program: &Pubkey) -> Pubkey {
+        Pubkey::find_program_address(seeds, program).0
+    }
+
+    fn borsh_string(data: &mut Vec<u8>, value: &str) {
+        data.extend_from_slice(&(value.len() as u32).to_le_bytes());
+        data.extend_from_slice(value.as_bytes());
+    }
+
+    fn keys(instruction: &Instruction) -> Vec<Pubkey> {
+        instruction.accounts.iter().map(|w| w.pubkey).collect()
+    }
+
+    fn flags(instruction: &Instruction) -> Vec<(bool, bool)> {
+        instruction.accounts.iter().map(|w| (w.is_signer, w.is_writable)).collect()
+    }
+
+    fn bonding_curve(sol_reserves: u64, graduation_target: u64, status: BondingCurveStatus) -> BondingCurveAccount {
+        BondingCurveAccount {
+            discriminator: 0,
+            creator: Pubkey::new_unique(),
+            mint: Pubkey::new_unique(),
+            virtual_sol_reserves: 30_000_000_000 + sol_reserves,
+            virtual_token_reserves: 1_073_000_000_000_000_000,
+            graduation_target,
+            graduation_fee: 1_000_000_000,
+            sol_reserves,
+            token_reserves: 800_000_000_000_000_000,
+            damping_term: 3,
+            swap_fee_basis_points: 100,
+            token_for_stakers_basis_points: 0,
+            status,
+        }
+    }
+
+    #[test]
+    fn graduation_progress_and_migrated_liquidity() {
+        let progress = |sol_reserves, target| bonding_curve(sol_reserves, target, BondingCurveStatus::Trading).graduation_progress();
+        assert_eq!(progress(0, 85_000_000_000), 0.0);
+        assert_eq!(progress(42_500_000_000, 85_000_000_000), 0.5);
+        // Overshooting the target on the last buy still reads as graduated, as does a curve without a target
+        assert_eq!(progress(90_000_000_000, 85_000_000_000), 1.0);
+        assert_eq!(progress(0, 0), 1.0);
+
+        let migrated = |status| bonding_curve(85_000_000_000, 85_000_000_000, status).has_migrated_liquidity();
+        assert!(!migrated(BondingCurveStatus::Trading));
+        assert!(!migrated(BondingCurveStatus::Graduated));
+        assert!(!migrated(BondingCurveStatus::PoolPriceCorrected));
+        assert!(migrated(BondingCurveStatus::LiquidityProvisioned));
+        assert!(migrated(BondingCurveStatus::LiquidityLocked));
+    }
+
+    #[test]
+    fn create_token_and_deploy_bonding_curve_layout() {
+        let payer = Keypair::new();
+        let create = create(None);
+        let mint = create.mint_private_key.pubkey();
+
+        let instructions = boopfun().build_create_instructions(&payer, &create).unwrap();
+        assert_eq!(instructions.len(), 2);
+        let (create_token, deploy) = (&instructions[0], &instructions[1]);
+        assert_eq!((create_token.program_id, deploy.program_id), (PUBKEY_BOOPFUN, PUBKEY_BOOPFUN));
+
+        assert_eq!(
+            keys(create_token),
+            vec![
+                PUBKEY_BOOPFUN_CONFIG,
+                pda(&[b"metadata", mpl_token_metadata::ID.as_ref(), mint.as_ref()], &mpl_token_metadata::ID),
+                mint,
+                payer.pubkey(),
+                solana_program::sysvar::rent::ID,
+                solana_program::system_program::ID,
+                spl_token::ID,
+                mpl_token_metadata::ID,
+            ]
+        );
+        // The new mint and the payer both sign, the metadata is written
+        let mut expected_flags = vec![(false, false); 8];
+        expected_flags[1] = (false, true);
+        expected_flags[2] = (true, true);
+        expected_flags[3] = (true, true);
+        assert_eq!(flags(create_token), expected_flags);
+
+        // Both instructions carry the same salt, which the program checks the mint against
+        let salt = &create_token.data[8..16];
+        let mut data = BOOPFUN_CREATE_TOKEN_DISCRIMINATOR.to_le_bytes().to_vec();
+        data.extend_from_slice(salt);
+        borsh_string(&mut data, "Boop Dog");
+        borsh_string(&mut data, "BOOP");
+        borsh_string(&mut data, "https://example.com/boop.json");
+        assert_eq!(create_token.data, data);
+
+        assert_eq!(
+            keys(deploy),
+            vec![
+                mint,
+                PUBKEY_BOOPFUN_VAULT_AUTHORITY,
+                pda(&[b"bonding_curve", mint.as_ref()], &PUBKEY_BOOPFUN),
+                pda(&[b"bonding_curve_sol_vault", mint.as_ref()], &PUBKEY_BOOPFUN),
+                pda(&[b"bonding_curve_vault", mint.as_ref()], &PUBKEY_BOOPFUN),
+                PUBKEY_BOOPFUN_CONFIG,
+                payer.pubkey(),
+                solana_program::system_program::ID,
+                spl_token::ID,
+                spl_associated_token_account::ID,
+            ]
+        );
+        let mut expected_flags = vec![(false, false); 10];
+        for writable in [0, 2, 3, 4] {
+            expected_flags[writable] = (false, true);
+        }
+        expected_flags[6] = (true, true);
+        assert_eq!(flags(deploy), expected_flags);
+
+        let mut data = BOOPFUN_DEPLOY_BONDING_CURVE_DISCRIMINATOR.to_le_bytes().to_vec();
+        data.extend_from_slice(payer.pubkey().as_ref());
+        data.extend_from_slice(salt);
+        assert_eq!(deploy.data, data);
+    }
+
+    #[test]
+    fn creator_buy_prices_the_initial_curve() {
+        let payer = Keypair::new();
+        let create = create(Some(1_000_000_000));
+
+        let instructions = boopfun().build_create_instructions(&payer, &create).unwrap();
+        let buy = instructions.last().unwrap();
+        assert_eq!(buy.program_id, PUBKEY_BOOPFUN);
+
+        // 1 SOL less the 1% swap fee against the initial 30 SOL / 1.073B token reserves, with the new token reserve rounded down, less 1% slippage
+        let mut data = 7598512818552209290u64.to_le_bytes().to_vec();
+        data.extend_from_slice(&1_000_000_000u64.to_le_bytes());
+        data.extend_from_slice(&33_935_053_242_981_608u64.to_le_bytes());
+        assert_eq!(buy.data, data);
+    }
+}
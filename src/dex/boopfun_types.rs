@@ -1,4 +1,4 @@
-use super::types::SwapInfo;
+use super::types::{Create, SwapInfo};
 use borsh::{BorshDeserialize, BorshSerialize};
 use serde::{Deserialize, Serialize};
 use solana_sdk::{pubkey, pubkey::Pubkey};
@@ -12,8 +12,16 @@ pub const BONDING_CURVE_VAULT_SEED: &[u8] = b"bonding_curve_vault";
 pub const BONDING_CURVE_SOL_VAULT_SEED: &[u8] = b"bonding_curve_sol_vault";
 pub const TRADING_FEE_VAULT_SEED: &[u8] = b"trading_fees_vault";
 
+/// Virtual reserves and swap fee a newly deployed curve starts from
+pub const BOOPFUN_INITIAL_VIRTUAL_SOL_RESERVES: u64 = 30_000_000_000;
+pub const BOOPFUN_INITIAL_VIRTUAL_TOKEN_RESERVES: u64 = 1_073_000_000_000_000_000;
+pub const BOOPFUN_INITIAL_SWAP_FEE_BASIS_POINTS: u64 = 100;
+
+pub const BOOPFUN_CREATE_TOKEN_DISCRIMINATOR: u64 = 5470338735940580436;
+pub const BOOPFUN_DEPLOY_BONDING_CURVE_DISCRIMINATOR: u64 = 10005288254767061428;
+
 #[repr(u8)]
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum BondingCurveStatus {
     Trading = 0,
     Graduated = 1,
@@ -23,7 +31,6 @@ pub enum BondingCurveStatus {
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
-
 pub struct BondingCurveAccount {
     pub discriminator: u64,
     pub creator: Pubkey,
@@ -40,12 +47,33 @@ pub struct BondingCurveAccount {
     pub status: BondingCurveStatus,
 }
 
+impl BondingCurveAccount {
+    /// Share of the graduation target raised so far, 1.0 once the curve can graduate
+    pub fn graduation_progress(&self) -> f64 {
+        match self.graduation_target {
+            0 => 1.0,
+            target => (self.sol_reserves as f64 / target as f64).min(1.0),
+        }
+    }
+
+    /// Whether the migrated pool holds liquidity, so trades can move to it
+    pub fn has_migrated_liquidity(&self) -> bool {
+        matches!(self.status, BondingCurveStatus::LiquidityProvisioned | BondingCurveStatus::LiquidityLocked)
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum BoopfunError {
+    #[error("Bonding curve of {mint} is no longer trading, status {status:?}")]
+    Graduated { mint: Pubkey, status: BondingCurveStatus },
+}
+
 impl Serialize for BondingCurveStatus {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
     where
         S: serde::Serializer,
     {
-        serde::Serialize::serialize(&(self.clone() as u8), serializer)
+        serde::Serialize::serialize(&(*self as u8), serializer)
     }
 }
 
@@ -123,3 +151,45 @@ impl SellInfo {
         Ok(buffer)
     }
 }
+
+#[derive(Debug, Clone, PartialEq, Eq, BorshSerialize, BorshDeserialize)]
+pub struct CreateTokenInfo {
+    pub discriminator: u64,
+    pub salt: u64,
+    pub name: String,
+    pub symbol: String,
+    pub uri: String,
+}
+
+impl CreateTokenInfo {
+    pub fn from_create(create: &Create, salt: u64) -> Self {
+        Self {
+            discriminator: BOOPFUN_CREATE_TOKEN_DISCRIMINATOR,
+            salt,
+            name: create.name.to_string(),
+            symbol: create.symbol.to_string(),
+            uri: create.uri.to_string(),
+        }
+    }
+
+    pub fn to_buffer(&self) -> anyhow::Result<Vec<u8>> {
+        let mut buffer = Vec::new();
+        self.serialize(&mut buffer)?;
+        Ok(buffer)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, BorshSerialize, BorshDeserialize)]
+pub struct DeployBondingCurveInfo {
+    pub discriminator: u64,
+    pub creator: Pubkey,
+    pub salt: u64,
+}
+
+impl DeployBondingCurveInfo {
+    pub fn to_buffer(&self) -> anyhow::Result<Vec<u8>> {
+        let mut buffer = Vec::new();
+        self.serialize(&mut buffer)?;
+        Ok(buffer)
+    }
+}
@@ -1,16 +1,23 @@
 use super::{
-    amm_calc::{amm_buy_get_token_out, amm_sell_get_sol_out, calculate_with_slippage_buy, calculate_with_slippage_sell},
-    types::{BatchBuyParam, BatchSellParam, Create, CreateATA, PoolInfo, SwapInfo, TokenAmountType},
+    amm_calc::{calculate_with_slippage_buy, calculate_with_slippage_sell},
+    types::{
+        BatchBuyParam, BatchSellParam, BuySwapInfo, BuyVariant, Create, CreateATA, PoolInfo, Quote, SellTokenAccount, SlippageBuySwapInfo, SlippageSwapInfo,
+        SwapInfo, TokenAmountType,
+    },
 };
-use crate::common::trading_endpoint::TransactionType;
+use crate::common::trading_endpoint::{AdditionalFees, TransactionType, TxSettings};
 use crate::{
-    common::trading_endpoint::{BatchTxItem, TradingEndpoint},
+    common::{
+        accounts::PUBKEY_WSOL,
+        trading_endpoint::{BatchTxItem, TradingEndpoint},
+    },
     errors::trading_endpoint_error::TradingEndpointError,
-    instruction::builder::{build_sol_sell_instructions, build_token_account_instructions, build_wsol_sell_instructions, PriorityFee},
+    instruction::builder::{
+        build_quote_buy_instructions, build_quote_sell_instructions, build_sol_sell_instructions, build_token_account_instructions, PriorityFee, QuotePayment,
+    },
 };
 use solana_sdk::{
-    hash::Hash,
-    instruction::Instruction,
+    instruction::{AccountMeta, Instruction},
     pubkey::Pubkey,
     signature::{Keypair, Signature},
     signer::Signer,
@@ -21,29 +28,168 @@ use std::{any::Any, sync::Arc};
 pub trait DexTrait: Send + Sync + Any {
     async fn initialize(&self) -> Result<(), TradingEndpointError>;
     fn initialized(&self) -> Result<(), TradingEndpointError>;
-    fn get_swqos_quantity(&self) ->usize{
+    fn get_swqos_quantity(&self) -> usize {
         self.get_trading_endpoint().swqos.len()
     }
     fn use_wsol(&self) -> bool;
     fn get_trading_endpoint(&self) -> Arc<TradingEndpoint>;
     async fn get_pool(&self, mint: &Pubkey) -> Result<PoolInfo, TradingEndpointError>;
+    /// Pool-specific accounts the buy and sell builders splice into the swap instruction, none by default
+    fn get_extra_accounts(&self, _mint: &Pubkey, _pool_info: &PoolInfo) -> Result<Vec<AccountMeta>, TradingEndpointError> {
+        Ok(vec![])
+    }
+    /// Quote mint and its token program that `mint` trades against when `use_wsol` is set, WSOL by default
+    fn get_quote_mint(&self, _mint: &Pubkey) -> (Pubkey, Pubkey) {
+        (PUBKEY_WSOL, spl_token::ID)
+    }
+    /// Whether `build_buy_instruction` spends exactly `sol_amount` for at least `token_amount`, instead of buying exactly `token_amount` for at most `sol_amount`
+    fn buy_is_exact_in(&self) -> bool {
+        false
+    }
+    /// Buy amounts for spending `amount_in` on `token_out` tokens, slippage applied to the side the program does not fix
+    fn get_buy_swap_info(&self, amount_in: u64, token_out: u64, slippage_basis_points: u64) -> SwapInfo {
+        match self.buy_is_exact_in() {
+            true => SwapInfo {
+                token_amount: calculate_with_slippage_sell(token_out, slippage_basis_points),
+                sol_amount: amount_in,
+            },
+            false => SwapInfo {
+                token_amount: token_out,
+                sol_amount: calculate_with_slippage_buy(amount_in, slippage_basis_points),
+            },
+        }
+    }
     async fn create(&self, payer: Keypair, create: Create, fee: Option<PriorityFee>, tip: Option<u64>) -> Result<Vec<Signature>, TradingEndpointError>;
     fn build_buy_instruction(
         &self,
         payer: &Keypair,
         mint: &Pubkey,
-        creator_vault: Option<&Pubkey>,
-        token_program_account: &Pubkey,
+        custom_ata: Option<&Pubkey>,
+        extra_accounts: &[AccountMeta],
+        token_program: &Pubkey,
         buy: SwapInfo,
     ) -> Result<Instruction, TradingEndpointError>;
+    fn build_buy_instruction_with_variant(
+        &self,
+        payer: &Keypair,
+        mint: &Pubkey,
+        custom_ata: Option<&Pubkey>,
+        extra_accounts: &[AccountMeta],
+        token_program: &Pubkey,
+        buy: BuySwapInfo,
+    ) -> Result<Instruction, TradingEndpointError> {
+        let BuySwapInfo { swap: buy, variant } = buy;
+        match variant {
+            BuyVariant::ExactTokensOut => self.build_buy_instruction(payer, mint, custom_ata, extra_accounts, token_program, buy),
+            BuyVariant::ExactSolIn => Err(TradingEndpointError::CustomError("Exact SOL in buys not supported".to_string())),
+        }
+    }
     fn build_sell_instruction(
         &self,
         payer: &Keypair,
         mint: &Pubkey,
         custom_ata: Option<&Pubkey>,
-        creator_vault: Option<&Pubkey>,
+        extra_accounts: &[AccountMeta],
+        token_program: &Pubkey,
         sell: SwapInfo,
     ) -> Result<Instruction, TradingEndpointError>;
+    /// Buy of the expected amounts, bounded by default by the slippage on the side `variant` does not fix.
+    /// Programs that check the slippage themselves override it to pass the slippage through.
+    fn build_buy_instruction_with_slippage(
+        &self,
+        payer: &Keypair,
+        mint: &Pubkey,
+        custom_ata: Option<&Pubkey>,
+        extra_accounts: &[AccountMeta],
+        token_program: &Pubkey,
+        buy: SlippageBuySwapInfo,
+    ) -> Result<Instruction, TradingEndpointError> {
+        let SlippageBuySwapInfo { swap, variant } = buy;
+        let bounds = swap.get_buy_bounds(variant);
+        match variant == BuyVariant::ExactSolIn && self.buy_is_exact_in() {
+            true => self.build_buy_instruction(payer, mint, custom_ata, extra_accounts, token_program, bounds),
+            false => self.build_buy_instruction_with_variant(payer, mint, custom_ata, extra_accounts, token_program, bounds.with_variant(variant)),
+        }
+    }
+    /// Sell of the expected amounts, bounded by default by the SOL out less the slippage.
+    /// Programs that check the slippage themselves override it to pass the slippage through.
+    fn build_sell_instruction_with_slippage(
+        &self,
+        payer: &Keypair,
+        mint: &Pubkey,
+        custom_ata: Option<&Pubkey>,
+        extra_accounts: &[AccountMeta],
+        token_program: &Pubkey,
+        sell: SlippageSwapInfo,
+    ) -> Result<Instruction, TradingEndpointError> {
+        self.build_sell_instruction(payer, mint, custom_ata, extra_accounts, token_program, sell.get_sell_bounds())
+    }
+    /// Buy instruction preceded by wrapping `sol_amount` into the quote mint when `use_wsol` is set, the token account being created separately
+    fn build_buy_instructions(
+        &self,
+        payer: &Keypair,
+        mint: &Pubkey,
+        token_program: &Pubkey,
+        sol_amount: u64,
+        instruction: Instruction,
+    ) -> Result<Vec<Instruction>, TradingEndpointError> {
+        if !self.use_wsol() {
+            return Ok(vec![instruction]);
+        }
+
+        let (quote_mint, quote_token_program) = self.get_quote_mint(mint);
+        let quote = QuotePayment {
+            mint: quote_mint,
+            token_program: quote_token_program,
+            amount: sol_amount,
+        };
+        build_quote_buy_instructions(payer, mint, token_program, quote, instruction, CreateATA::None)
+            .map_err(|e| TradingEndpointError::CustomError(e.to_string()))
+    }
+    async fn quote_buy(&self, mint: &Pubkey, sol_amount: u64, slippage_basis_points: u64) -> Result<Quote, TradingEndpointError> {
+        let trading_endpoint = self.get_trading_endpoint();
+        let (pool_info, mint_info) = tokio::try_join!(self.get_pool(mint), trading_endpoint.get_mint_info(mint))?;
+        Ok(Quote::buy(pool_info, mint_info, sol_amount, slippage_basis_points))
+    }
+    async fn quote_buy_exact_tokens(&self, mint: &Pubkey, token_amount: u64, slippage_basis_points: u64) -> Result<Quote, TradingEndpointError> {
+        let trading_endpoint = self.get_trading_endpoint();
+        let (pool_info, mint_info) = tokio::try_join!(self.get_pool(mint), trading_endpoint.get_mint_info(mint))?;
+        Ok(Quote::buy_exact_tokens(pool_info, mint_info, token_amount, slippage_basis_points))
+    }
+    async fn quote_sell(&self, mint: &Pubkey, token_amount: u64, slippage_basis_points: u64) -> Result<Quote, TradingEndpointError> {
+        let trading_endpoint = self.get_trading_endpoint();
+        let (pool_info, mint_info) = tokio::try_join!(self.get_pool(mint), trading_endpoint.get_mint_info(mint))?;
+        Ok(Quote::sell(pool_info, mint_info, token_amount, slippage_basis_points))
+    }
+    /// Quote buying each of `sol_amounts`, all priced from one pool snapshot
+    async fn quote_buys(&self, mint: &Pubkey, sol_amounts: &[u64], slippage_basis_points: u64) -> Result<Vec<Quote>, TradingEndpointError> {
+        let trading_endpoint = self.get_trading_endpoint();
+        let (pool_info, mint_info) = tokio::try_join!(self.get_pool(mint), trading_endpoint.get_mint_info(mint))?;
+        Ok(sol_amounts
+            .iter()
+            .map(|sol_amount| Quote::buy(pool_info.clone(), mint_info, *sol_amount, slippage_basis_points))
+            .collect())
+    }
+    /// Quote selling each of `token_amounts`, all priced from one pool snapshot
+    async fn quote_sells(&self, mint: &Pubkey, token_amounts: &[u64], slippage_basis_points: u64) -> Result<Vec<Quote>, TradingEndpointError> {
+        let trading_endpoint = self.get_trading_endpoint();
+        let (pool_info, mint_info) = tokio::try_join!(self.get_pool(mint), trading_endpoint.get_mint_info(mint))?;
+        Ok(token_amounts
+            .iter()
+            .map(|token_amount| Quote::sell(pool_info.clone(), mint_info, *token_amount, slippage_basis_points))
+            .collect())
+    }
+    /// Setup instructions and swap instruction of a buy of `sol_amount` when the DEX buys through its own flow, `None` for the generic one.
+    /// `buy` sends them, so wrappers such as `RoutedDex` only need to rewrite the swap instruction.
+    async fn build_custom_buy_instructions(
+        &self,
+        _payer: &Keypair,
+        _mint: &Pubkey,
+        _sol_amount: u64,
+        _slippage_basis_points: u64,
+    ) -> Result<Option<(Vec<Instruction>, Instruction)>, TradingEndpointError> {
+        Ok(None)
+    }
     async fn buy(
         &self,
         payer: &Keypair,
@@ -54,55 +200,100 @@ pub trait DexTrait: Send + Sync + Any {
         tip: Option<u64>,
     ) -> Result<Vec<Signature>, TradingEndpointError> {
         let trading_endpoint = self.get_trading_endpoint();
-        let (pool_info, blockhash) = tokio::try_join!(self.get_pool(mint), trading_endpoint.get_latest_blockhash(),)?;
-        let buy_token_amount = amm_buy_get_token_out(pool_info.sol_reserves, pool_info.token_reserves, sol_amount);
-        let sol_lamports_with_slippage = calculate_with_slippage_buy(sol_amount, slippage_basis_points);
+        if let Some((mut instructions, swap)) = self.build_custom_buy_instructions(payer, mint, sol_amount, slippage_basis_points).await? {
+            instructions.push(swap);
+            let blockhash = trading_endpoint.get_latest_blockhash().await?;
+            return trading_endpoint
+                .build_and_broadcast_tx(
+                    TransactionType::Buy,
+                    payer,
+                    instructions,
+                    TxSettings::new(blockhash, fee, tip.unwrap_or_default()),
+                    None,
+                )
+                .await;
+        }
+
+        let (quote, blockhash) = tokio::try_join!(self.quote_buy(mint, sol_amount, slippage_basis_points), trading_endpoint.get_latest_blockhash(),)?;
+        let swap = self.get_buy_swap_info(quote.amount_in, quote.gross_token_out(), slippage_basis_points);
 
         self.buy_immediately(
             payer,
             mint,
-            pool_info.creator_vault.as_ref(),
-            sol_lamports_with_slippage,
-            buy_token_amount,
-            vec![blockhash],
-            None,
+            &self.get_extra_accounts(mint, &quote.pool_info)?,
+            swap,
             CreateATA::Create,
-            fee,
-            tip.unwrap_or_default(),
+            TxSettings::new(blockhash, fee, tip.unwrap_or_default()),
         )
         .await
     }
-    async fn buy_immediately(
+    async fn buy_exact_tokens(
         &self,
         payer: &Keypair,
         mint: &Pubkey,
-        extra_address: Option<&Pubkey>,
-        sol_amount: u64,
         token_amount: u64,
-        blockhashes: Vec<Hash>,
-        nonce_ix: Option<Instruction>,
+        slippage_basis_points: u64,
+        variant: BuyVariant,
+        fees: AdditionalFees,
+    ) -> Result<Vec<Signature>, TradingEndpointError> {
+        let trading_endpoint = self.get_trading_endpoint();
+        let (quote, blockhash) = tokio::try_join!(
+            self.quote_buy_exact_tokens(mint, token_amount, slippage_basis_points),
+            trading_endpoint.get_latest_blockhash(),
+        )?;
+        if quote.amount_in == 0 {
+            return Err(TradingEndpointError::CustomError(format!(
+                "Cannot buy {} tokens from pool {}",
+                token_amount, quote.pool_info.pool
+            )));
+        }
+
+        let swap = SlippageSwapInfo {
+            expected: SwapInfo {
+                token_amount: quote.gross_token_out(),
+                sol_amount: quote.amount_in,
+            },
+            slippage_basis_points,
+        };
+
+        let sol_amount = swap.get_buy_bounds(variant).sol_amount;
+        let token_program = quote.mint_info.token_program;
+        let (token_account, mut instructions) =
+            build_token_account_instructions(payer, mint, &token_program, CreateATA::Create).map_err(|e| TradingEndpointError::CustomError(e.to_string()))?;
+        let instruction = self.build_buy_instruction_with_slippage(
+            payer,
+            mint,
+            Some(&token_account),
+            &self.get_extra_accounts(mint, &quote.pool_info)?,
+            &token_program,
+            swap.with_variant(variant),
+        )?;
+        instructions.extend(self.build_buy_instructions(payer, mint, &token_program, sol_amount, instruction)?);
+
+        trading_endpoint
+            .build_and_broadcast_tx(TransactionType::Buy, payer, instructions, TxSettings::new(blockhash, fees.fee, fees.tip), None)
+            .await
+    }
+    async fn buy_immediately(
+        &self,
+        payer: &Keypair,
+        mint: &Pubkey,
+        extra_accounts: &[AccountMeta],
+        swap: SwapInfo,
         create_ata: CreateATA,
-        additional_fee: Option<PriorityFee>,
-        additional_tip: u64,
+        settings: TxSettings,
     ) -> Result<Vec<Signature>, TradingEndpointError> {
+        let token_program = self.get_trading_endpoint().get_mint_info(mint).await?.token_program;
         let (token_account, mut instructions) =
-            build_token_account_instructions(payer, mint, create_ata).map_err(|e| TradingEndpointError::CustomError(e.to_string()))?;
+            build_token_account_instructions(payer, mint, &token_program, create_ata).map_err(|e| TradingEndpointError::CustomError(e.to_string()))?;
 
-        let instruction = self.build_buy_instruction(payer, mint, extra_address, &token_account, SwapInfo { token_amount, sol_amount })?;
+        let sol_amount = swap.sol_amount;
+        let instruction = self.build_buy_instruction(payer, mint, Some(&token_account), extra_accounts, &token_program, swap)?;
 
-        instructions.push(instruction);
+        instructions.extend(self.build_buy_instructions(payer, mint, &token_program, sol_amount, instruction)?);
         let signatures = self
             .get_trading_endpoint()
-            .build_and_broadcast_tx(
-                TransactionType::Buy,
-                payer,
-                instructions,
-                nonce_ix,
-                blockhashes,
-                additional_fee,
-                additional_tip,
-                None,
-            )
+            .build_and_broadcast_tx(TransactionType::Buy, payer, instructions, settings, None)
             .await?;
 
         Ok(signatures)
@@ -113,35 +304,44 @@ pub trait DexTrait: Send + Sync + Any {
         mint: &Pubkey,
         token_amount: TokenAmountType,
         slippage_basis_points: u64,
-        custom_ata: Option<&Pubkey>,
-        close_mint_ata: bool,
-        additional_fee: Option<PriorityFee>,
-        additional_tip: u64,
+        token_account: SellTokenAccount,
+        fees: AdditionalFees,
     ) -> Result<Vec<Signature>, TradingEndpointError> {
         let trading_endpoint = self.get_trading_endpoint();
         let payer_pubkey = payer.pubkey();
-        let get_amount = async || {
-            token_amount
-                .to_amount(trading_endpoint.rpc.clone(), &payer_pubkey, mint)
-                .await
-                .map_err(|w| TradingEndpointError::SolanaClientError(w))
-        };
-        let (pool_info, blockhash, token_amount) = tokio::try_join!(self.get_pool(&mint), trading_endpoint.get_latest_blockhash(), get_amount())?;
-        let sol_lamports = amm_sell_get_sol_out(pool_info.sol_reserves, pool_info.token_reserves, token_amount);
-        let sol_lamports_with_slippage = calculate_with_slippage_sell(sol_lamports, slippage_basis_points);
+        let mint_info = trading_endpoint.get_mint_info(mint).await?;
+        let token_amount = token_amount
+            .to_amount(trading_endpoint.rpc.clone(), &payer_pubkey, mint, &mint_info.token_program)
+            .await
+            .map_err(TradingEndpointError::from)?;
+        let (quote, blockhash) = tokio::try_join!(
+            self.quote_sell(mint, token_amount, slippage_basis_points),
+            trading_endpoint.get_latest_blockhash()
+        )?;
+
+        let SellTokenAccount { custom_ata, close_mint_ata } = token_account;
+        let instruction = self.build_sell_instruction_with_slippage(
+            payer,
+            mint,
+            custom_ata.as_ref(),
+            &self.get_extra_accounts(mint, &quote.pool_info)?,
+            &mint_info.token_program,
+            SlippageSwapInfo {
+                expected: SwapInfo {
+                    token_amount: quote.amount_in,
+                    sol_amount: quote.expected_out,
+                },
+                slippage_basis_points,
+            },
+        )?;
 
-        self.sell_immediately(
+        self.send_sell_instruction(
             payer,
             mint,
-            custom_ata,
-            pool_info.creator_vault.as_ref(),
-            token_amount,
-            sol_lamports_with_slippage,
+            &mint_info.token_program,
+            instruction,
             close_mint_ata,
-            vec![blockhash],
-            None,
-            additional_fee,
-            additional_tip,
+            TxSettings::new(blockhash, fees.fee, fees.tip),
         )
         .await
     }
@@ -149,34 +349,38 @@ pub trait DexTrait: Send + Sync + Any {
         &self,
         payer: &Keypair,
         mint: &Pubkey,
-        custom_ata: Option<&Pubkey>,
-        extra_address: Option<&Pubkey>,
-        token_amount: u64,
-        sol_amount: u64,
+        extra_accounts: &[AccountMeta],
+        swap: SwapInfo,
+        token_account: SellTokenAccount,
+        settings: TxSettings,
+    ) -> Result<Vec<Signature>, TradingEndpointError> {
+        let token_program = self.get_trading_endpoint().get_mint_info(mint).await?.token_program;
+        let SellTokenAccount { custom_ata, close_mint_ata } = token_account;
+        let instruction = self.build_sell_instruction(payer, mint, custom_ata.as_ref(), extra_accounts, &token_program, swap)?;
+        self.send_sell_instruction(payer, mint, &token_program, instruction, close_mint_ata, settings)
+            .await
+    }
+    /// Send a sell instruction, unwrapping the quote mint when `use_wsol` is set
+    async fn send_sell_instruction(
+        &self,
+        payer: &Keypair,
+        mint: &Pubkey,
+        token_program: &Pubkey,
+        instruction: Instruction,
         close_mint_ata: bool,
-        blockhashes: Vec<Hash>,
-        nonce_ix: Option<Instruction>,
-        additional_fee: Option<PriorityFee>,
-        additional_tip: u64,
+        settings: TxSettings,
     ) -> Result<Vec<Signature>, TradingEndpointError> {
-        let instruction = self.build_sell_instruction(payer, mint, custom_ata, extra_address, SwapInfo { token_amount, sol_amount })?;
         let instructions = if self.use_wsol() {
-            build_wsol_sell_instructions(payer, mint, instruction, close_mint_ata).map_err(|e| TradingEndpointError::CustomError(e.to_string()))?
+            let (quote_mint, quote_token_program) = self.get_quote_mint(mint);
+            build_quote_sell_instructions(payer, mint, token_program, &quote_mint, &quote_token_program, instruction, close_mint_ata)
+                .map_err(|e| TradingEndpointError::CustomError(e.to_string()))?
         } else {
-            build_sol_sell_instructions(payer, mint, instruction, close_mint_ata).map_err(|e| TradingEndpointError::CustomError(e.to_string()))?
+            build_sol_sell_instructions(payer, mint, token_program, instruction, close_mint_ata)
+                .map_err(|e| TradingEndpointError::CustomError(e.to_string()))?
         };
         let signatures = self
             .get_trading_endpoint()
-            .build_and_broadcast_tx(
-                TransactionType::Sell,
-                payer,
-                instructions,
-                nonce_ix,
-                blockhashes,
-                additional_fee,
-                additional_tip,
-                None,
-            )
+            .build_and_broadcast_tx(TransactionType::Sell, payer, instructions, settings, None)
             .await?;
 
         Ok(signatures)
@@ -190,34 +394,56 @@ pub trait DexTrait: Send + Sync + Any {
         items: Vec<BatchBuyParam>,
     ) -> Result<Vec<Signature>, TradingEndpointError> {
         let trading_endpoint = self.get_trading_endpoint();
-        let (pool_info, blockhash) = tokio::try_join!(self.get_pool(&mint), trading_endpoint.get_latest_blockhash(),)?;
-        let mut pool_token_amount = pool_info.token_reserves;
-        let mut pool_sol_amount = pool_info.sol_reserves;
+        // Each item is priced as the next slice of one cumulative buy, so it accounts for the items before it moving the pool
+        let sol_amounts = items
+            .iter()
+            .scan(0, |total, item| {
+                *total += item.sol_amount;
+                Some(*total)
+            })
+            .collect::<Vec<_>>();
+        let (quotes, mint_info, blockhash) = tokio::try_join!(
+            self.quote_buys(mint, &sol_amounts, slippage_basis_points),
+            trading_endpoint.get_mint_info(mint),
+            trading_endpoint.get_latest_blockhash()
+        )?;
+        let extra_accounts = match quotes.first() {
+            Some(quote) => self.get_extra_accounts(mint, &quote.pool_info)?,
+            None => vec![],
+        };
+        let variant = match self.buy_is_exact_in() {
+            true => BuyVariant::ExactSolIn,
+            false => BuyVariant::ExactTokensOut,
+        };
+        let mut token_out_before = 0;
         let mut batch_items = vec![];
 
-        for item in items {
-            let sol_lamports_with_slippage = calculate_with_slippage_buy(item.sol_amount, slippage_basis_points);
-            let buy_token_amount = amm_buy_get_token_out(pool_sol_amount, pool_token_amount, item.sol_amount);
-            let instruction = self.build_buy_instruction(
-                &item.payer,
-                &mint,
-                pool_info.creator_vault.as_ref(),
-                &spl_token::ID,
-                SwapInfo {
-                    token_amount: buy_token_amount,
-                    sol_amount: sol_lamports_with_slippage,
+        for (item, quote) in items.into_iter().zip(quotes) {
+            let token_out = quote.gross_token_out().saturating_sub(token_out_before);
+            token_out_before = quote.gross_token_out();
+            let swap = SlippageSwapInfo {
+                expected: SwapInfo {
+                    token_amount: token_out,
+                    sol_amount: item.sol_amount,
                 },
+                slippage_basis_points,
+            };
+            let sol_amount = swap.get_buy_bounds(variant).sol_amount;
+            let (token_account, mut instructions) = build_token_account_instructions(&item.payer, mint, &mint_info.token_program, CreateATA::Idempotent)
+                .map_err(|e| TradingEndpointError::CustomError(e.to_string()))?;
+            let instruction = self.build_buy_instruction_with_slippage(
+                &item.payer,
+                mint,
+                Some(&token_account),
+                &extra_accounts,
+                &mint_info.token_program,
+                swap.with_variant(variant),
             )?;
-
-            let (_, mut instructions) =
-                build_token_account_instructions(&item.payer, mint, CreateATA::Idempotent).map_err(|e| TradingEndpointError::CustomError(e.to_string()))?;
-            instructions.push(instruction);
+            instructions.extend(self.build_buy_instructions(&item.payer, mint, &mint_info.token_program, sol_amount, instruction)?);
             batch_items.push(BatchTxItem {
                 payer: item.payer,
                 instructions,
             });
-            pool_sol_amount += item.sol_amount;
-            pool_token_amount -= buy_token_amount;
         }
 
         let signatures = trading_endpoint
@@ -235,37 +461,63 @@ pub trait DexTrait: Send + Sync + Any {
         items: Vec<BatchSellParam>,
     ) -> Result<Vec<Signature>, TradingEndpointError> {
         let trading_endpoint = self.get_trading_endpoint();
-        let (pool_info, blockhash) = tokio::try_join!(self.get_pool(&mint), trading_endpoint.get_latest_blockhash(),)?;
-        let mut pool_token_amount = pool_info.token_reserves;
-        let mut pool_sol_amount = pool_info.sol_reserves;
+        // Each item is priced as the next slice of one cumulative sell, so it accounts for the items before it moving the pool
+        let token_amounts = items
+            .iter()
+            .scan(0, |total, item| {
+                *total += item.token_amount;
+                Some(*total)
+            })
+            .collect::<Vec<_>>();
+        let (quotes, mint_info, blockhash) = tokio::try_join!(
+            self.quote_sells(mint, &token_amounts, slippage_basis_points),
+            trading_endpoint.get_mint_info(mint),
+            trading_endpoint.get_latest_blockhash()
+        )?;
+        let extra_accounts = match quotes.first() {
+            Some(quote) => self.get_extra_accounts(mint, &quote.pool_info)?,
+            None => vec![],
+        };
+        let (quote_mint, quote_token_program) = self.get_quote_mint(mint);
+        let mut sol_out_before = 0;
         let mut batch_items = vec![];
 
-        for item in items {
-            let sol_amount = amm_sell_get_sol_out(pool_sol_amount, pool_token_amount, item.token_amount);
-            let sol_lamports_with_slippage = calculate_with_slippage_sell(sol_amount, slippage_basis_points);
-            let instruction = self.build_sell_instruction(
+        for (item, quote) in items.into_iter().zip(quotes) {
+            let sol_out = quote.expected_out.saturating_sub(sol_out_before);
+            sol_out_before = quote.expected_out;
+            let instruction = self.build_sell_instruction_with_slippage(
                 &item.payer,
-                &mint,
+                mint,
                 item.custom_ata.as_ref(),
-                pool_info.creator_vault.as_ref(),
-                SwapInfo {
-                    token_amount: sol_amount,
-                    sol_amount: sol_lamports_with_slippage,
+                &extra_accounts,
+                &mint_info.token_program,
+                SlippageSwapInfo {
+                    expected: SwapInfo {
+                        token_amount: item.token_amount,
+                        sol_amount: sol_out,
+                    },
+                    slippage_basis_points,
                 },
             )?;
             let instructions = if self.use_wsol() {
-                build_wsol_sell_instructions(&item.payer, mint, instruction, item.close_mint_ata)
-                    .map_err(|e| TradingEndpointError::CustomError(e.to_string()))?
+                build_quote_sell_instructions(
+                    &item.payer,
+                    mint,
+                    &mint_info.token_program,
+                    &quote_mint,
+                    &quote_token_program,
+                    instruction,
+                    item.close_mint_ata,
+                )
+                .map_err(|e| TradingEndpointError::CustomError(e.to_string()))?
             } else {
-                build_sol_sell_instructions(&item.payer, mint, instruction, item.close_mint_ata)
+                build_sol_sell_instructions(&item.payer, mint, &mint_info.token_program, instruction, item.close_mint_ata)
                     .map_err(|e| TradingEndpointError::CustomError(e.to_string()))?
             };
             batch_items.push(BatchTxItem {
                 payer: item.payer,
                 instructions,
             });
-            pool_sol_amount -= sol_amount;
-            pool_token_amount += item.token_amount;
         }
 
         let signatures = trading_endpoint
@@ -0,0 +1,420 @@
+use super::{
+    amm_calc::{calculate_price_impact_bps, calculate_with_slippage_buy, calculate_with_slippage_sell, FeeAmounts, FeeModel, FEE_RATE_DENOMINATOR},
+    dex_traits::DexTrait,
+    meteora_damm_v2_math::{get_swap_result, get_total_fee_numerator, get_virtual_reserves, TradeDirection},
+    meteora_damm_v2_types::*,
+    meteora_dbc_math::{get_amount_in_for_exact_out, sqrt_price_to_price},
+    meteora_dbc_types::FEE_DENOMINATOR,
+    types::{Create, PoolInfo, Quote, SwapInfo},
+};
+use crate::{
+    common::{accounts::PUBKEY_WSOL, mint::MintInfo, trading_endpoint::TradingEndpoint},
+    errors::trading_endpoint_error::TradingEndpointError,
+    instruction::builder::PriorityFee,
+};
+use parking_lot::RwLock;
+use solana_sdk::{
+    instruction::{AccountMeta, Instruction},
+    pubkey::Pubkey,
+    signature::{Keypair, Signature},
+    signer::Signer,
+};
+use spl_associated_token_account::get_associated_token_address_with_program_id;
+use std::{
+    collections::HashMap,
+    sync::Arc,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+pub struct MeteoraDammV2 {
+    pub endpoint: Arc<TradingEndpoint>,
+    pools: RwLock<HashMap<Pubkey, PoolKeys>>,
+}
+
+/// Pool of a mint and which side of it the mint is on
+#[derive(Clone, Debug)]
+pub struct PoolState {
+    pub pool_info: PoolInfo,
+    pub pool: Pool,
+    pub mint_is_a: bool,
+}
+
+#[async_trait::async_trait]
+impl DexTrait for MeteoraDammV2 {
+    async fn initialize(&self) -> Result<(), TradingEndpointError> {
+        Ok(())
+    }
+
+    fn initialized(&self) -> Result<(), TradingEndpointError> {
+        Ok(())
+    }
+
+    fn get_trading_endpoint(&self) -> Arc<TradingEndpoint> {
+        self.endpoint.clone()
+    }
+
+    fn use_wsol(&self) -> bool {
+        true
+    }
+
+    fn buy_is_exact_in(&self) -> bool {
+        true
+    }
+
+    async fn get_pool(&self, mint: &Pubkey) -> Result<PoolInfo, TradingEndpointError> {
+        Ok(self.get_pool_state(mint).await?.pool_info)
+    }
+
+    fn get_quote_mint(&self, mint: &Pubkey) -> (Pubkey, Pubkey) {
+        match self.pools.read().get(mint) {
+            Some(keys) => keys.get_quote_mint(mint),
+            None => (PUBKEY_WSOL, spl_token::ID),
+        }
+    }
+
+    async fn quote_buy(&self, mint: &Pubkey, sol_amount: u64, slippage_basis_points: u64) -> Result<Quote, TradingEndpointError> {
+        let (state, mint_info) = tokio::try_join!(self.get_pool_state(mint), self.endpoint.get_mint_info(mint))?;
+        let trade_direction = TradeDirection::buying(state.mint_is_a);
+        Self::quote_swap(state, mint_info, sol_amount, trade_direction, slippage_basis_points)
+    }
+
+    async fn quote_buy_exact_tokens(&self, mint: &Pubkey, token_amount: u64, slippage_basis_points: u64) -> Result<Quote, TradingEndpointError> {
+        let (state, mint_info) = tokio::try_join!(self.get_pool_state(mint), self.endpoint.get_mint_info(mint))?;
+        let trade_direction = TradeDirection::buying(state.mint_is_a);
+        let slot = state.pool_info.slot;
+        let token_out = token_amount + mint_info.get_inverse_transfer_fee(slot, token_amount);
+        let current_point = Self::get_current_point(&state.pool, slot);
+        let sol_amount = get_amount_in_for_exact_out(token_out, |sol_amount| {
+            match get_swap_result(&state.pool, sol_amount, trade_direction, current_point) {
+                Ok(result) => Ok(result.output_amount),
+                Err(MeteoraDammV2Error::PriceRangeViolation) => Err(MeteoraDammV2Error::NotEnoughLiquidity),
+                Err(e) => Err(e),
+            }
+        })?;
+
+        let mut quote = Self::quote_swap(state, mint_info, sol_amount, trade_direction, slippage_basis_points)?;
+        quote.max_amount_in = calculate_with_slippage_buy(quote.amount_in, slippage_basis_points);
+        quote.min_out = token_amount;
+        Ok(quote)
+    }
+
+    async fn quote_sell(&self, mint: &Pubkey, token_amount: u64, slippage_basis_points: u64) -> Result<Quote, TradingEndpointError> {
+        let (state, mint_info) = tokio::try_join!(self.get_pool_state(mint), self.endpoint.get_mint_info(mint))?;
+        let trade_direction = TradeDirection::selling(state.mint_is_a);
+        Self::quote_swap(state, mint_info, token_amount, trade_direction, slippage_basis_points)
+    }
+
+    async fn quote_buys(&self, mint: &Pubkey, sol_amounts: &[u64], slippage_basis_points: u64) -> Result<Vec<Quote>, TradingEndpointError> {
+        let (state, mint_info) = tokio::try_join!(self.get_pool_state(mint), self.endpoint.get_mint_info(mint))?;
+        let trade_direction = TradeDirection::buying(state.mint_is_a);
+        sol_amounts
+            .iter()
+            .map(|sol_amount| Self::quote_swap(state.clone(), mint_info, *sol_amount, trade_direction, slippage_basis_points))
+            .collect()
+    }
+
+    async fn quote_sells(&self, mint: &Pubkey, token_amounts: &[u64], slippage_basis_points: u64) -> Result<Vec<Quote>, TradingEndpointError> {
+        let (state, mint_info) = tokio::try_join!(self.get_pool_state(mint), self.endpoint.get_mint_info(mint))?;
+        let trade_direction = TradeDirection::selling(state.mint_is_a);
+        token_amounts
+            .iter()
+            .map(|token_amount| Self::quote_swap(state.clone(), mint_info, *token_amount, trade_direction, slippage_basis_points))
+            .collect()
+    }
+
+    async fn create(&self, _: Keypair, _: Create, _: Option<PriorityFee>, _: Option<u64>) -> Result<Vec<Signature>, TradingEndpointError> {
+        Err(TradingEndpointError::CustomError("Not supported".to_string()))
+    }
+
+    fn build_buy_instruction(
+        &self,
+        payer: &Keypair,
+        mint: &Pubkey,
+        custom_ata: Option<&Pubkey>,
+        _: &[AccountMeta],
+        token_program: &Pubkey,
+        buy: SwapInfo,
+    ) -> Result<Instruction, TradingEndpointError> {
+        let buffer = SwapInstruction::from_swap_info(&buy, true)
+            .to_buffer()
+            .map_err(|e| TradingEndpointError::CustomError(e.to_string()))?;
+        self.build_swap_instruction(payer, mint, custom_ata, token_program, true, buffer)
+    }
+
+    fn build_sell_instruction(
+        &self,
+        payer: &Keypair,
+        mint: &Pubkey,
+        custom_ata: Option<&Pubkey>,
+        _: &[AccountMeta],
+        token_program: &Pubkey,
+        sell: SwapInfo,
+    ) -> Result<Instruction, TradingEndpointError> {
+        let buffer = SwapInstruction::from_swap_info(&sell, false)
+            .to_buffer()
+            .map_err(|e| TradingEndpointError::CustomError(e.to_string()))?;
+        self.build_swap_instruction(payer, mint, custom_ata, token_program, false, buffer)
+    }
+}
+
+impl MeteoraDammV2 {
+    pub fn new(endpoint: Arc<TradingEndpoint>) -> Self {
+        Self {
+            endpoint,
+            pools: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// Every DAMM v2 pool with `mint` on either side
+    pub async fn get_pools(&self, mint: &Pubkey) -> Result<Vec<(Pubkey, Pool)>, TradingEndpointError> {
+        let (token_a_pools, token_b_pools) = tokio::try_join!(
+            self.get_pool_accounts(POOL_TOKEN_A_MINT_OFFSET, mint),
+            self.get_pool_accounts(POOL_TOKEN_B_MINT_OFFSET, mint)
+        )?;
+        Ok(token_a_pools.into_iter().chain(token_b_pools).collect())
+    }
+
+    async fn get_pool_accounts(&self, offset: usize, mint: &Pubkey) -> Result<Vec<(Pubkey, Pool)>, TradingEndpointError> {
+        let accounts = self
+            .endpoint
+            .rpc
+            .get_program_accounts_with_config(
+                &PUBKEY_METEORA_DAMM_V2,
+                solana_client::rpc_config::RpcProgramAccountsConfig {
+                    filters: Some(vec![
+                        solana_client::rpc_filter::RpcFilterType::Memcmp(solana_client::rpc_filter::Memcmp::new_raw_bytes(0, POOL_DISCRIMINATOR.to_vec())),
+                        solana_client::rpc_filter::RpcFilterType::Memcmp(solana_client::rpc_filter::Memcmp::new_raw_bytes(offset, mint.to_bytes().to_vec())),
+                    ]),
+                    account_config: solana_client::rpc_config::RpcAccountInfoConfig {
+                        encoding: Some(solana_account_decoder::UiAccountEncoding::Base64),
+                        commitment: None,
+                        data_slice: None,
+                        min_context_slot: None,
+                    },
+                    with_context: None,
+                    sort_results: None,
+                },
+            )
+            .await?;
+
+        accounts.iter().map(|(pool, account)| Ok((*pool, Self::decode_pool(&account.data)?))).collect()
+    }
+
+    /// Pool with the most liquidity pairing `mint` with `quote_mint`, or with WSOL when any does and no quote mint is given.
+    /// The pool is remembered for `mint`, so later quotes and trades use it.
+    /// Mints migrated from a DBC curve skip this, their pool being derived with `get_migrated_pool_pda`.
+    pub async fn select_pool(&self, mint: &Pubkey, quote_mint: Option<&Pubkey>) -> Result<(Pubkey, Pool), TradingEndpointError> {
+        let pools = self.get_pools(mint).await?;
+        let other_mint = |pool: &Pool| match pool.token_a_mint == *mint {
+            true => pool.token_b_mint,
+            false => pool.token_a_mint,
+        };
+        let quote_mint = match quote_mint {
+            Some(quote_mint) => Some(*quote_mint),
+            None => pools.iter().any(|(_, pool)| other_mint(pool) == PUBKEY_WSOL).then_some(PUBKEY_WSOL),
+        };
+        let (pool_address, pool) = pools
+            .into_iter()
+            .filter(|(_, pool)| quote_mint.is_none_or(|quote_mint| other_mint(pool) == quote_mint))
+            .max_by_key(|(_, pool)| pool.liquidity)
+            .ok_or_else(|| TradingEndpointError::CustomError(format!("No Meteora DAMM v2 pool found for mint: {}", mint)))?;
+
+        self.pools.write().insert(*mint, PoolKeys::new(pool_address, &pool));
+        Ok((pool_address, pool))
+    }
+
+    /// Pool a DBC curve of `mint` against `quote_mint` migrated to, derived from the DAMM v2 config its migration fee option uses.
+    /// Customizable fee options have no shared config, so they fail with `UnsupportedMigrationFeeOption`.
+    pub fn get_migrated_pool_pda(mint: &Pubkey, quote_mint: &Pubkey, migration_fee_option: u8) -> Result<Pubkey, TradingEndpointError> {
+        let config = DAMM_V2_MIGRATION_CONFIGS
+            .get(migration_fee_option as usize)
+            .ok_or(MeteoraDammV2Error::UnsupportedMigrationFeeOption(migration_fee_option))?;
+        Self::get_pool_pda(config, mint, quote_mint)
+    }
+
+    /// Pool created from `config`, its seeds ordering the mints by key
+    pub fn get_pool_pda(config: &Pubkey, token_a_mint: &Pubkey, token_b_mint: &Pubkey) -> Result<Pubkey, TradingEndpointError> {
+        let (first, second) = match token_a_mint > token_b_mint {
+            true => (token_a_mint, token_b_mint),
+            false => (token_b_mint, token_a_mint),
+        };
+        let seeds: &[&[u8]; 4] = &[POOL_SEED, config.as_ref(), first.as_ref(), second.as_ref()];
+        let pda = Pubkey::try_find_program_address(seeds, &PUBKEY_METEORA_DAMM_V2)
+            .ok_or_else(|| TradingEndpointError::CustomError("Failed to find pool PDA".to_string()))?;
+        Ok(pda.0)
+    }
+
+    /// Select `pool_address` for later quotes and trades of `mint`, checking `mint` is one of its tokens
+    pub async fn use_pool(&self, mint: &Pubkey, pool_address: &Pubkey) -> Result<(Pubkey, Pool), TradingEndpointError> {
+        let account = self.endpoint.rpc.get_account(pool_address).await?;
+        let pool = Self::decode_pool(&account.data)?;
+        if pool.token_a_mint != *mint && pool.token_b_mint != *mint {
+            return Err(TradingEndpointError::CustomError(format!("Pool {pool_address} does not trade {mint}")));
+        }
+
+        self.pools.write().insert(*mint, PoolKeys::new(*pool_address, &pool));
+        Ok((*pool_address, pool))
+    }
+
+    /// Pool selected for `mint` with its virtual reserves and current fee, selecting one first if none was
+    pub async fn get_pool_state(&self, mint: &Pubkey) -> Result<PoolState, TradingEndpointError> {
+        let cached = self.pools.read().get(mint).copied();
+        let pool_address = match cached {
+            Some(keys) => keys.pool,
+            None => self.select_pool(mint, None).await?.0,
+        };
+        let (account, slot) = self.endpoint.get_account_with_slot(&pool_address).await?;
+        let pool = Self::decode_pool(&account.data)?;
+        if !pool.swap_enabled() {
+            return Err(MeteoraDammV2Error::PoolDisabled { pool: pool_address }.into());
+        }
+
+        let mint_is_a = pool.token_a_mint == *mint;
+        let (reserve_a, reserve_b) = get_virtual_reserves(&pool);
+        let (token_reserves, sol_reserves) = match mint_is_a {
+            true => (reserve_a, reserve_b),
+            false => (reserve_b, reserve_a),
+        };
+        let fee_numerator = get_total_fee_numerator(&pool, Self::get_current_point(&pool, slot), TradeDirection::buying(mint_is_a), 0)?;
+
+        let pool_info = PoolInfo {
+            pool: pool_address,
+            creator: Some(pool.creator),
+            creator_vault: None,
+            config: None,
+            token_reserves,
+            sol_reserves,
+            fees: FeeModel {
+                lp_fee_rate: fee_numerator.div_ceil(FEE_DENOMINATOR / FEE_RATE_DENOMINATOR),
+                charged_on_top: false,
+                ..Default::default()
+            },
+            slot,
+        };
+        Ok(PoolState { pool_info, pool, mint_is_a })
+    }
+
+    pub fn decode_pool(data: &[u8]) -> Result<Pool, TradingEndpointError> {
+        bincode::deserialize::<Pool>(data).map_err(|e| TradingEndpointError::CustomError(e.to_string()))
+    }
+
+    /// Slot or unix timestamp, depending on what the pool measures activation in
+    fn get_current_point(pool: &Pool, slot: u64) -> u64 {
+        match pool.activation_type {
+            0 => slot,
+            _ => SystemTime::now().duration_since(UNIX_EPOCH).map(|w| w.as_secs()).unwrap_or_default(),
+        }
+    }
+
+    /// Quote for an exact amount in, using the program's sqrt price and fee math
+    pub fn quote_swap(
+        state: PoolState,
+        mint_info: MintInfo,
+        amount_in: u64,
+        trade_direction: TradeDirection,
+        slippage_basis_points: u64,
+    ) -> Result<Quote, TradingEndpointError> {
+        let PoolState { pool_info, pool, mint_is_a } = state;
+        let slot = pool_info.slot;
+        let is_buy = trade_direction == TradeDirection::buying(mint_is_a);
+        let current_point = Self::get_current_point(&pool, slot);
+
+        let (pool_amount_in, sell_transfer_fee) = match is_buy {
+            true => (amount_in, 0),
+            false => {
+                let transfer_fee = mint_info.get_transfer_fee(slot, amount_in);
+                (amount_in - transfer_fee, transfer_fee)
+            }
+        };
+        let result = get_swap_result(&pool, pool_amount_in, trade_direction, current_point)?;
+        let (expected_out, transfer_fee) = match is_buy {
+            true => {
+                let transfer_fee = mint_info.get_transfer_fee(slot, result.output_amount);
+                (result.output_amount - transfer_fee, transfer_fee)
+            }
+            false => (result.output_amount, sell_transfer_fee),
+        };
+
+        // Prices are quoted in token B per token A, so invert them when the mint is token B
+        let to_price = |sqrt_price: u128| match mint_is_a {
+            true => sqrt_price_to_price(sqrt_price),
+            false => 1.0 / sqrt_price_to_price(sqrt_price),
+        };
+        let price_before = to_price(pool.sqrt_price);
+        let price_after = to_price(result.next_sqrt_price);
+
+        Ok(Quote {
+            amount_in,
+            max_amount_in: amount_in,
+            expected_out,
+            min_out: calculate_with_slippage_sell(expected_out, slippage_basis_points),
+            fees: FeeAmounts {
+                protocol: result.protocol_fee,
+                lp: result.trading_fee,
+                creator: 0,
+                platform: 0,
+                share: 0,
+            },
+            transfer_fee,
+            price_before,
+            price_after,
+            price_impact_bps: calculate_price_impact_bps(price_before, price_after),
+            pool_info,
+            mint_info,
+            slot,
+        })
+    }
+
+    /// Accounts of the pool selected for `mint`, which must have been looked up first
+    pub fn get_pool_keys(&self, mint: &Pubkey) -> Result<PoolKeys, TradingEndpointError> {
+        self.pools
+            .read()
+            .get(mint)
+            .copied()
+            .ok_or_else(|| TradingEndpointError::CustomError(format!("No Meteora DAMM v2 pool selected for mint: {}", mint)))
+    }
+
+    fn build_swap_instruction(
+        &self,
+        payer: &Keypair,
+        mint: &Pubkey,
+        custom_ata: Option<&Pubkey>,
+        token_program: &Pubkey,
+        is_buy: bool,
+        buffer: Vec<u8>,
+    ) -> Result<Instruction, TradingEndpointError> {
+        let keys = self.get_pool_keys(mint)?;
+        let (quote_mint, quote_token_program) = keys.get_quote_mint(mint);
+        let ata = match custom_ata {
+            None => get_associated_token_address_with_program_id(&payer.pubkey(), mint, token_program),
+            Some(t) => *t,
+        };
+        let quote_ata = get_associated_token_address_with_program_id(&payer.pubkey(), &quote_mint, &quote_token_program);
+        let (input_account, output_account) = match is_buy {
+            true => (quote_ata, ata),
+            false => (ata, quote_ata),
+        };
+
+        Ok(Instruction::new_with_bytes(
+            PUBKEY_METEORA_DAMM_V2,
+            &buffer,
+            vec![
+                AccountMeta::new_readonly(PUBKEY_METEORA_DAMM_V2_POOL_AUTHORITY, false),
+                AccountMeta::new(keys.pool, false),
+                AccountMeta::new(input_account, false),
+                AccountMeta::new(output_account, false),
+                AccountMeta::new(keys.token_a_vault, false),
+                AccountMeta::new(keys.token_b_vault, false),
+                AccountMeta::new_readonly(keys.token_a_mint, false),
+                AccountMeta::new_readonly(keys.token_b_mint, false),
+                AccountMeta::new(payer.pubkey(), true),
+                AccountMeta::new_readonly(keys.token_a_program, false),
+                AccountMeta::new_readonly(keys.token_b_program, false),
+                // No referral token account
+                AccountMeta::new_readonly(PUBKEY_METEORA_DAMM_V2, false),
+                AccountMeta::new_readonly(PUBKEY_METEORA_DAMM_V2_EVENT_AUTHORITY, false),
+                AccountMeta::new_readonly(PUBKEY_METEORA_DAMM_V2, false),
+            ],
+        ))
+    }
+}
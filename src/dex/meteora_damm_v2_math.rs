@@ -0,0 +1,215 @@
+use super::{
+    meteora_damm_v2_types::*,
+    meteora_dbc_math::{
+        get_base_fee_numerator, get_delta_amount_base_unsigned, get_delta_amount_quote_unsigned, get_fee_on_amount,
+        get_next_sqrt_price_from_amount_base_rounding_up, get_next_sqrt_price_from_amount_quote_rounding_down, get_variable_fee_numerator, Rounding,
+        SwapResult,
+    },
+    meteora_dbc_types::{MAX_FEE_NUMERATOR, RESOLUTION},
+};
+use ruint::aliases::U256;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TradeDirection {
+    AtoB,
+    BtoA,
+}
+
+impl TradeDirection {
+    /// Direction of a buy of `mint` when it is token A of the pool, of a sell otherwise
+    pub fn buying(mint_is_a: bool) -> Self {
+        match mint_is_a {
+            true => TradeDirection::BtoA,
+            false => TradeDirection::AtoB,
+        }
+    }
+
+    pub fn selling(mint_is_a: bool) -> Self {
+        match mint_is_a {
+            true => TradeDirection::AtoB,
+            false => TradeDirection::BtoA,
+        }
+    }
+}
+
+/// Base fee at `current_point` plus the dynamic fee, the rate limiter only applying to token B in
+pub fn get_total_fee_numerator(pool: &Pool, current_point: u64, trade_direction: TradeDirection, amount: u64) -> Result<u64, MeteoraDammV2Error> {
+    let direction = match trade_direction {
+        TradeDirection::AtoB => super::meteora_dbc_math::TradeDirection::BaseToQuote,
+        TradeDirection::BtoA => super::meteora_dbc_math::TradeDirection::QuoteToBase,
+    };
+    let base_fee = get_base_fee_numerator(
+        &pool.pool_fees.base_fee.to_base_fee_config(),
+        current_point,
+        pool.activation_point,
+        direction,
+        amount,
+    )?;
+    let variable_fee = get_variable_fee_numerator(
+        &pool.pool_fees.dynamic_fee.to_dynamic_fee_config(),
+        pool.pool_fees.dynamic_fee.volatility_accumulator,
+    )?;
+    Ok(base_fee.saturating_add(variable_fee).min(MAX_FEE_NUMERATOR))
+}
+
+/// Token A and token B amounts the pool behaves like around its current price, `L / √P` and `L * √P`
+pub fn get_virtual_reserves(pool: &Pool) -> (u64, u64) {
+    if pool.sqrt_price == 0 {
+        return (0, 0);
+    }
+
+    let liquidity = U256::from(pool.liquidity);
+    let reserve_a = liquidity / U256::from(pool.sqrt_price);
+    let reserve_b = (liquidity * U256::from(pool.sqrt_price)) >> (RESOLUTION * 2);
+    (u64::try_from(reserve_a).unwrap_or(u64::MAX), u64::try_from(reserve_b).unwrap_or(u64::MAX))
+}
+
+/// Token out of swapping `amount_in` inside the pool's price range, and the price reached
+fn get_swap_amount(pool: &Pool, amount_in: u64, trade_direction: TradeDirection) -> Result<(u64, u128), MeteoraDammV2Error> {
+    if pool.liquidity == 0 {
+        return Err(MeteoraDammV2Error::NotEnoughLiquidity);
+    }
+
+    match trade_direction {
+        TradeDirection::AtoB => {
+            let next_sqrt_price = get_next_sqrt_price_from_amount_base_rounding_up(pool.sqrt_price, pool.liquidity, amount_in)?;
+            if next_sqrt_price < pool.sqrt_min_price {
+                return Err(MeteoraDammV2Error::PriceRangeViolation);
+            }
+            let output_amount = get_delta_amount_quote_unsigned(next_sqrt_price, pool.sqrt_price, pool.liquidity, Rounding::Down)?;
+            Ok((output_amount, next_sqrt_price))
+        }
+        TradeDirection::BtoA => {
+            let next_sqrt_price = get_next_sqrt_price_from_amount_quote_rounding_down(pool.sqrt_price, pool.liquidity, amount_in)?;
+            if next_sqrt_price > pool.sqrt_max_price {
+                return Err(MeteoraDammV2Error::PriceRangeViolation);
+            }
+            let output_amount = get_delta_amount_base_unsigned(pool.sqrt_price, next_sqrt_price, pool.liquidity, Rounding::Down)?;
+            Ok((output_amount, next_sqrt_price))
+        }
+    }
+}
+
+/// Simulates the program's swap of `amount_in` against `pool`, `fees_on_base` meaning fees taken in token A
+pub fn get_swap_result(pool: &Pool, amount_in: u64, trade_direction: TradeDirection, current_point: u64) -> Result<SwapResult, MeteoraDammV2Error> {
+    // Fees come out of the output unless the pool only collects token B and B is the input
+    let (fees_on_input, fees_on_base) = match (pool.collect_fee_mode, trade_direction) {
+        (0, TradeDirection::BtoA) => (false, true),
+        (_, TradeDirection::AtoB) => (false, false),
+        (_, TradeDirection::BtoA) => (true, false),
+    };
+    let protocol_fee_percent = pool.pool_fees.protocol_fee_percent;
+
+    let (actual_input_amount, mut trading_fee, mut protocol_fee) = match fees_on_input {
+        true => {
+            let fee_numerator = get_total_fee_numerator(pool, current_point, trade_direction, amount_in)?;
+            get_fee_on_amount(amount_in, fee_numerator, protocol_fee_percent)?
+        }
+        false => (amount_in, 0, 0),
+    };
+
+    let (output_amount, next_sqrt_price) = get_swap_amount(pool, actual_input_amount, trade_direction)?;
+
+    let output_amount = match fees_on_input {
+        true => output_amount,
+        false => {
+            let fee_numerator = get_total_fee_numerator(pool, current_point, trade_direction, output_amount)?;
+            let (amount, fee, protocol) = get_fee_on_amount(output_amount, fee_numerator, protocol_fee_percent)?;
+            trading_fee = fee;
+            protocol_fee = protocol;
+            amount
+        }
+    };
+
+    Ok(SwapResult {
+        actual_input_amount,
+        output_amount,
+        next_sqrt_price,
+        trading_fee,
+        protocol_fee,
+        fees_on_base,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dex::meteora_damm_v2::MeteoraDammV2;
+
+    const SQRT_PRICE: u128 = 373_909_000_000_000_000;
+    const LIQUIDITY: u128 = 77_350_000_000_000_000_000_000_000_000_000;
+
+    /// Token A / SOL pool at `SQRT_PRICE` over the full price range with a 0.25% fee, 20% of it to the protocol
+    fn pool(collect_fee_mode: u8) -> Pool {
+        let mut pool = MeteoraDammV2::decode_pool(&[0u8; 1112]).unwrap();
+        pool.pool_fees.base_fee.cliff_fee_numerator = 2_500_000;
+        pool.pool_fees.protocol_fee_percent = 20;
+        pool.liquidity = LIQUIDITY;
+        pool.sqrt_min_price = 4_295_048_016;
+        pool.sqrt_max_price = 79_226_673_521_066_979_257_578_248_091;
+        pool.sqrt_price = SQRT_PRICE;
+        pool.collect_fee_mode = collect_fee_mode;
+        pool
+    }
+
+    #[test]
+    fn virtual_reserves() {
+        assert_eq!(get_virtual_reserves(&pool(1)), (206_868_516_136_279, 84_993_710_992));
+    }
+
+    #[test]
+    fn buy_with_fees_on_input() {
+        let result = get_swap_result(&pool(1), 1_000_000_000, TradeDirection::BtoA, 0).unwrap();
+        assert_eq!(result.actual_input_amount, 997_500_000);
+        assert_eq!(result.output_amount, 2_399_679_484_260);
+        assert_eq!(result.next_sqrt_price, 378_297_256_767_984_953);
+        assert_eq!((result.trading_fee, result.protocol_fee), (2_000_000, 500_000));
+        assert!(!result.fees_on_base);
+    }
+
+    #[test]
+    fn buy_with_fees_on_output() {
+        let result = get_swap_result(&pool(0), 1_000_000_000, TradeDirection::BtoA, 0).unwrap();
+        assert_eq!(result.actual_input_amount, 1_000_000_000);
+        assert_eq!(result.output_amount, 2_399_609_721_033);
+        assert_eq!(result.next_sqrt_price, 378_308_254_905_248_073);
+        assert_eq!((result.trading_fee, result.protocol_fee), (4_811_247_562, 1_202_811_890));
+        assert!(result.fees_on_base);
+    }
+
+    #[test]
+    fn sell_takes_fees_from_output() {
+        for collect_fee_mode in [0, 1] {
+            let result = get_swap_result(&pool(collect_fee_mode), 1_000_000_000_000, TradeDirection::AtoB, 0).unwrap();
+            assert_eq!(result.actual_input_amount, 1_000_000_000_000);
+            assert_eq!(result.output_amount, 407_859_872);
+            assert_eq!(result.next_sqrt_price, 372_110_223_509_216_450);
+            assert_eq!((result.trading_fee, result.protocol_fee), (817_765, 204_441));
+            assert!(!result.fees_on_base);
+        }
+    }
+
+    #[test]
+    fn swap_out_of_range_fails() {
+        let mut pool = pool(1);
+        pool.sqrt_max_price = SQRT_PRICE + 1_000_000_000_000_000;
+        assert!(matches!(
+            get_swap_result(&pool, 1_000_000_000, TradeDirection::BtoA, 0),
+            Err(MeteoraDammV2Error::PriceRangeViolation)
+        ));
+
+        pool.liquidity = 0;
+        assert!(matches!(
+            get_swap_result(&pool, 1_000_000_000, TradeDirection::BtoA, 0),
+            Err(MeteoraDammV2Error::NotEnoughLiquidity)
+        ));
+    }
+
+    #[test]
+    fn direction_follows_mint_side() {
+        assert_eq!(TradeDirection::buying(true), TradeDirection::BtoA);
+        assert_eq!(TradeDirection::buying(false), TradeDirection::AtoB);
+        assert_eq!(TradeDirection::selling(true), TradeDirection::AtoB);
+        assert_eq!(TradeDirection::selling(false), TradeDirection::BtoA);
+    }
+}
@@ -0,0 +1,252 @@
+use super::{
+    meteora_dbc_types::{BaseFeeConfig, DynamicFeeConfig, MeteoraDbcError},
+    types::SwapInfo,
+};
+use borsh::{BorshDeserialize, BorshSerialize};
+use serde::Deserialize;
+use solana_sdk::{pubkey, pubkey::Pubkey};
+
+pub const PUBKEY_METEORA_DAMM_V2: Pubkey = pubkey!("cpamdpZCGKUy5JxQXB4dcpGPiikHawvSWAd6mEn1sGG");
+pub const PUBKEY_METEORA_DAMM_V2_POOL_AUTHORITY: Pubkey = pubkey!("HLnpSz9h2S4hiLQ43rnSD9XkcUThA7B8hQMKmDaiTLcC");
+pub const PUBKEY_METEORA_DAMM_V2_EVENT_AUTHORITY: Pubkey = pubkey!("3rmHSu74h1ZcmAisVcWerTCiRDQbUrBKmcwptYGjHfet");
+
+/// Anchor discriminator of `Pool` accounts
+pub const POOL_DISCRIMINATOR: [u8; 8] = [241, 154, 109, 4, 17, 177, 109, 188];
+pub const METEORA_DAMM_V2_SWAP_DISCRIMINATOR: u64 = 14449647541112719096;
+
+/// Offsets of `Pool.token_a_mint` and `Pool.token_b_mint`
+pub const POOL_TOKEN_A_MINT_OFFSET: usize = 168;
+pub const POOL_TOKEN_B_MINT_OFFSET: usize = 200;
+
+/// `PoolConfig.migration_option` of DBC curves migrating to DAMM v2
+pub const MIGRATION_OPTION_DAMM_V2: u8 = 1;
+/// DAMM v2 configs DBC migrates into, indexed by `PoolConfig.migration_fee_option`: 0.25%, 0.3%, 1%, 2%, 4% and 6%
+pub const DAMM_V2_MIGRATION_CONFIGS: [Pubkey; 6] = [
+    pubkey!("7F6dnUcRuyM2TwR8myT1dYypFXpPSxqwKNSFNkxyNESd"),
+    pubkey!("2nHK1kju6XjphBLbNxpM5XRGFj7p9U8vvNzyZiha1z6k"),
+    pubkey!("Hv8Lmzmnju6m7kcokVKvwqz7QPmdX9XfKjJsXz8RXcjp"),
+    pubkey!("2c4cYd4reUYVRAB9kUUkrq55VPyy2FNQ3FDL4o12JXmq"),
+    pubkey!("AkmQWebAwFvWk55wBoCr5D62C6VVDTzi84NJuD9H7cFD"),
+    pubkey!("DbCRBj8McvPYHJG1ukj8RE15h2dCNUdTAESG49XpQ44u"),
+];
+/// `PoolConfig.migration_fee_option` whose fees are set per curve in its migration metadata rather than by a shared config
+pub const MIGRATION_FEE_OPTION_CUSTOMIZABLE: u8 = 6;
+pub const POOL_SEED: &[u8] = b"pool";
+
+#[derive(Debug, thiserror::Error)]
+pub enum MeteoraDammV2Error {
+    #[error("Swaps disabled on pool {pool}")]
+    PoolDisabled { pool: Pubkey },
+    #[error("Swap would move the price out of the pool's range")]
+    PriceRangeViolation,
+    #[error("Not enough liquidity in the pool")]
+    NotEnoughLiquidity,
+    /// Fixed-fee configs only, customizable migrations not being derivable from the DBC config alone
+    #[error("Unsupported DBC migration fee option: {0}")]
+    UnsupportedMigrationFeeOption(u8),
+    #[error(transparent)]
+    Math(#[from] MeteoraDbcError),
+}
+
+/// Fee scheduler when `base_fee_mode` is linear or exponential, rate limiter otherwise
+#[derive(Clone, Copy, Debug, Deserialize)]
+pub struct BaseFeeStruct {
+    pub cliff_fee_numerator: u64,
+    pub base_fee_mode: u8,
+    pub padding_0: [u8; 5],
+    pub first_factor: u16,
+    pub second_factor: u64,
+    pub third_factor: u64,
+    pub padding_1: u64,
+}
+
+impl BaseFeeStruct {
+    /// Same parameters as a DBC base fee, laid out differently
+    pub fn to_base_fee_config(&self) -> BaseFeeConfig {
+        BaseFeeConfig {
+            cliff_fee_numerator: self.cliff_fee_numerator,
+            second_factor: self.second_factor,
+            third_factor: self.third_factor,
+            first_factor: self.first_factor,
+            base_fee_mode: self.base_fee_mode,
+            padding_0: [0; 5],
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Deserialize)]
+pub struct DynamicFeeStruct {
+    pub initialized: u8,
+    pub padding: [u8; 7],
+    pub max_volatility_accumulator: u32,
+    pub variable_fee_control: u32,
+    pub bin_step: u16,
+    pub filter_period: u16,
+    pub decay_period: u16,
+    pub reduction_factor: u16,
+    pub last_update_timestamp: u64,
+    pub bin_step_u128: u128,
+    pub sqrt_price_reference: u128,
+    pub volatility_accumulator: u128,
+    pub volatility_reference: u128,
+}
+
+impl DynamicFeeStruct {
+    pub fn to_dynamic_fee_config(&self) -> DynamicFeeConfig {
+        DynamicFeeConfig {
+            initialized: self.initialized,
+            padding: [0; 7],
+            max_volatility_accumulator: self.max_volatility_accumulator,
+            variable_fee_control: self.variable_fee_control,
+            bin_step: self.bin_step,
+            filter_period: self.filter_period,
+            decay_period: self.decay_period,
+            reduction_factor: self.reduction_factor,
+            padding2: [0; 8],
+            bin_step_u128: self.bin_step_u128,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Deserialize)]
+pub struct PoolFeesStruct {
+    pub base_fee: BaseFeeStruct,
+    pub protocol_fee_percent: u8,
+    pub partner_fee_percent: u8,
+    pub referral_fee_percent: u8,
+    pub padding_0: [u8; 5],
+    pub dynamic_fee: DynamicFeeStruct,
+    pub padding_1: [u64; 2],
+}
+
+#[derive(Clone, Copy, Debug, Deserialize)]
+pub struct PoolMetrics {
+    pub total_lp_a_fee: u128,
+    pub total_lp_b_fee: u128,
+    pub total_protocol_a_fee: u64,
+    pub total_protocol_b_fee: u64,
+    pub total_partner_a_fee: u64,
+    pub total_partner_b_fee: u64,
+    pub total_position: u64,
+    pub padding: u64,
+}
+
+/// Single price range pool, reward infos left undecoded
+#[derive(Clone, Debug, Deserialize)]
+pub struct Pool {
+    pub discriminator: [u8; 8],
+    pub pool_fees: PoolFeesStruct,
+    pub token_a_mint: Pubkey,
+    pub token_b_mint: Pubkey,
+    pub token_a_vault: Pubkey,
+    pub token_b_vault: Pubkey,
+    pub whitelisted_vault: Pubkey,
+    pub partner: Pubkey,
+    pub liquidity: u128,
+    pub _padding: u128,
+    pub protocol_a_fee: u64,
+    pub protocol_b_fee: u64,
+    pub partner_a_fee: u64,
+    pub partner_b_fee: u64,
+    pub sqrt_min_price: u128,
+    pub sqrt_max_price: u128,
+    pub sqrt_price: u128,
+    pub activation_point: u64,
+    pub activation_type: u8,
+    pub pool_status: u8,
+    pub token_a_flag: u8,
+    pub token_b_flag: u8,
+    /// Fees in both tokens when 0, only in token B otherwise
+    pub collect_fee_mode: u8,
+    pub pool_type: u8,
+    pub _padding_0: [u8; 2],
+    pub fee_a_per_liquidity: [u8; 32],
+    pub fee_b_per_liquidity: [u8; 32],
+    pub permanent_lock_liquidity: u128,
+    pub metrics: PoolMetrics,
+    pub creator: Pubkey,
+}
+
+impl Pool {
+    pub fn swap_enabled(&self) -> bool {
+        self.pool_status == 0
+    }
+
+    pub fn get_token_a_program(&self) -> Pubkey {
+        match self.token_a_flag {
+            0 => spl_token::ID,
+            _ => spl_token_2022::ID,
+        }
+    }
+
+    pub fn get_token_b_program(&self) -> Pubkey {
+        match self.token_b_flag {
+            0 => spl_token::ID,
+            _ => spl_token_2022::ID,
+        }
+    }
+}
+
+/// Accounts a swap needs from a pool, resolved once per mint
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PoolKeys {
+    pub pool: Pubkey,
+    pub token_a_mint: Pubkey,
+    pub token_b_mint: Pubkey,
+    pub token_a_vault: Pubkey,
+    pub token_b_vault: Pubkey,
+    pub token_a_program: Pubkey,
+    pub token_b_program: Pubkey,
+}
+
+impl PoolKeys {
+    pub fn new(pool_address: Pubkey, pool: &Pool) -> Self {
+        Self {
+            pool: pool_address,
+            token_a_mint: pool.token_a_mint,
+            token_b_mint: pool.token_b_mint,
+            token_a_vault: pool.token_a_vault,
+            token_b_vault: pool.token_b_vault,
+            token_a_program: pool.get_token_a_program(),
+            token_b_program: pool.get_token_b_program(),
+        }
+    }
+
+    /// Mint `mint` trades against and its token program
+    pub fn get_quote_mint(&self, mint: &Pubkey) -> (Pubkey, Pubkey) {
+        match self.token_a_mint == *mint {
+            true => (self.token_b_mint, self.token_b_program),
+            false => (self.token_a_mint, self.token_a_program),
+        }
+    }
+}
+
+#[derive(Clone, Debug, BorshSerialize, BorshDeserialize)]
+pub struct SwapInstruction {
+    pub discriminator: u64,
+    pub amount_in: u64,
+    pub minimum_amount_out: u64,
+}
+
+impl SwapInstruction {
+    pub fn from_swap_info(swap_info: &SwapInfo, is_buy: bool) -> Self {
+        match is_buy {
+            true => Self {
+                discriminator: METEORA_DAMM_V2_SWAP_DISCRIMINATOR,
+                amount_in: swap_info.sol_amount,
+                minimum_amount_out: swap_info.token_amount,
+            },
+            false => Self {
+                discriminator: METEORA_DAMM_V2_SWAP_DISCRIMINATOR,
+                amount_in: swap_info.token_amount,
+                minimum_amount_out: swap_info.sol_amount,
+            },
+        }
+    }
+
+    pub fn to_buffer(&self) -> anyhow::Result<Vec<u8>> {
+        let mut buffer = Vec::new();
+        self.serialize(&mut buffer)?;
+        Ok(buffer)
+    }
+}
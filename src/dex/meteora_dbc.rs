@@ -1,25 +1,54 @@
-use super::{dex_traits::DexTrait, meteora_dbc_types::*, types::Create};
+use super::{
+    amm_calc::{calculate_price_impact_bps, calculate_with_slippage_buy, calculate_with_slippage_sell, FeeAmounts},
+    dex_traits::DexTrait,
+    meteora_damm_v2::MeteoraDammV2,
+    meteora_damm_v2_types::MIGRATION_OPTION_DAMM_V2,
+    meteora_dbc_math::{get_amount_in_for_exact_out, get_swap_result, sqrt_price_to_price, TradeDirection},
+    meteora_dbc_types::*,
+    types::{Create, Quote},
+};
 use crate::{
-    common::{accounts::PUBKEY_WSOL, trading_endpoint::TradingEndpoint},
+    common::{accounts::PUBKEY_WSOL, mint::MintInfo, trading_endpoint::TradingEndpoint},
     dex::types::{PoolInfo, SwapInfo},
     errors::trading_endpoint_error::TradingEndpointError,
     instruction::builder::PriorityFee,
 };
+use parking_lot::RwLock;
 use solana_sdk::{
     instruction::{AccountMeta, Instruction},
     pubkey::Pubkey,
     signature::{Keypair, Signature},
     signer::Signer,
 };
-use spl_associated_token_account::get_associated_token_address;
-use std::sync::Arc;
+use spl_associated_token_account::get_associated_token_address_with_program_id;
+use std::{
+    collections::{HashMap, HashSet},
+    sync::Arc,
+    time::{SystemTime, UNIX_EPOCH},
+};
 
-pub struct MemeoraDBC {
+pub struct MeteoraDBC {
     pub endpoint: Arc<TradingEndpoint>,
+    configs: RwLock<HashMap<Pubkey, Arc<PoolConfig>>>,
+    pools: RwLock<HashMap<Pubkey, PoolKeys>>,
+    /// Quotes and trades of mints that migrated to DAMM v2 go through it
+    pub damm_v2: MeteoraDammV2,
+    migrated: RwLock<HashSet<Pubkey>>,
+}
+
+/// Virtual pool of a mint together with the config its curve and fees come from
+#[derive(Clone, Debug)]
+pub struct PoolState {
+    pub pool_info: PoolInfo,
+    pub pool: VirtualPool,
+    pub config: Arc<PoolConfig>,
 }
 
+#[deprecated(note = "Renamed to `MeteoraDBC`")]
+pub type MemeoraDBC = MeteoraDBC;
+
 #[async_trait::async_trait]
-impl DexTrait for MemeoraDBC {
+impl DexTrait for MeteoraDBC {
     async fn initialize(&self) -> Result<(), TradingEndpointError> {
         Ok(())
     }
@@ -36,23 +65,88 @@ impl DexTrait for MemeoraDBC {
         true
     }
 
+    fn buy_is_exact_in(&self) -> bool {
+        true
+    }
+
     async fn get_pool(&self, mint: &Pubkey) -> Result<PoolInfo, TradingEndpointError> {
-        let pool = self.get_pool_by_base_mint(mint).await?;
-        let account = self.endpoint.rpc.get_account(&pool).await?;
-        if account.data.is_empty() {
-            return Err(TradingEndpointError::CustomError(format!("Bonding curve not found: {}", pool.to_string())));
+        match self.get_curve_state(mint).await? {
+            Some(state) => Ok(state.pool_info),
+            None => self.damm_v2.get_pool(mint).await,
         }
+    }
 
-        let bonding_curve = bincode::deserialize::<VirtualPool>(&account.data).map_err(|e| TradingEndpointError::CustomError(e.to_string()))?;
+    fn get_quote_mint(&self, mint: &Pubkey) -> (Pubkey, Pubkey) {
+        if self.is_migrated(mint) {
+            return self.damm_v2.get_quote_mint(mint);
+        }
+        match self.pools.read().get(mint) {
+            Some(keys) => (keys.quote_mint, keys.quote_token_program),
+            None => (PUBKEY_WSOL, spl_token::ID),
+        }
+    }
 
-        Ok(PoolInfo {
-            pool,
-            creator: Some(bonding_curve.creator),
-            creator_vault: None,
-            config: Some(bonding_curve.config),
-            token_reserves: bonding_curve.base_reserve,
-            sol_reserves: bonding_curve.quote_reserve,
-        })
+    async fn quote_buy(&self, mint: &Pubkey, sol_amount: u64, slippage_basis_points: u64) -> Result<Quote, TradingEndpointError> {
+        let (state, mint_info) = tokio::try_join!(self.get_curve_state(mint), self.endpoint.get_mint_info(mint))?;
+        let Some(state) = state else {
+            return self.damm_v2.quote_buy(mint, sol_amount, slippage_basis_points).await;
+        };
+        Self::quote_swap(state, mint_info, sol_amount, TradeDirection::QuoteToBase, slippage_basis_points)
+    }
+
+    async fn quote_buy_exact_tokens(&self, mint: &Pubkey, token_amount: u64, slippage_basis_points: u64) -> Result<Quote, TradingEndpointError> {
+        let (state, mint_info) = tokio::try_join!(self.get_curve_state(mint), self.endpoint.get_mint_info(mint))?;
+        let Some(state) = state else {
+            return self.damm_v2.quote_buy_exact_tokens(mint, token_amount, slippage_basis_points).await;
+        };
+        let slot = state.pool_info.slot;
+        let token_out = token_amount + mint_info.get_inverse_transfer_fee(slot, token_amount);
+        let current_point = Self::get_current_point(&state.config, slot);
+        let sol_amount = get_amount_in_for_exact_out(token_out, |sol_amount| -> Result<u64, MeteoraDbcError> {
+            Ok(get_swap_result(&state.pool, &state.config, sol_amount, TradeDirection::QuoteToBase, current_point)?.output_amount)
+        })?;
+
+        let mut quote = Self::quote_swap(state, mint_info, sol_amount, TradeDirection::QuoteToBase, slippage_basis_points)?;
+        quote.max_amount_in = calculate_with_slippage_buy(quote.amount_in, slippage_basis_points);
+        quote.min_out = token_amount;
+        Ok(quote)
+    }
+
+    async fn quote_sell(&self, mint: &Pubkey, token_amount: u64, slippage_basis_points: u64) -> Result<Quote, TradingEndpointError> {
+        let (state, mint_info) = tokio::try_join!(self.get_curve_state(mint), self.endpoint.get_mint_info(mint))?;
+        let Some(state) = state else {
+            return self.damm_v2.quote_sell(mint, token_amount, slippage_basis_points).await;
+        };
+        Self::quote_swap(state, mint_info, token_amount, TradeDirection::BaseToQuote, slippage_basis_points)
+    }
+
+    async fn quote_buys(&self, mint: &Pubkey, sol_amounts: &[u64], slippage_basis_points: u64) -> Result<Vec<Quote>, TradingEndpointError> {
+        let (state, mint_info) = tokio::try_join!(self.get_curve_state(mint), self.endpoint.get_mint_info(mint))?;
+        let Some(state) = state else {
+            return self.damm_v2.quote_buys(mint, sol_amounts, slippage_basis_points).await;
+        };
+        sol_amounts
+            .iter()
+            .map(|sol_amount| Self::quote_swap(state.clone(), mint_info, *sol_amount, TradeDirection::QuoteToBase, slippage_basis_points))
+            .collect()
+    }
+
+    async fn quote_sells(&self, mint: &Pubkey, token_amounts: &[u64], slippage_basis_points: u64) -> Result<Vec<Quote>, TradingEndpointError> {
+        let (state, mint_info) = tokio::try_join!(self.get_curve_state(mint), self.endpoint.get_mint_info(mint))?;
+        let Some(state) = state else {
+            return self.damm_v2.quote_sells(mint, token_amounts, slippage_basis_points).await;
+        };
+        token_amounts
+            .iter()
+            .map(|token_amount| Self::quote_swap(state.clone(), mint_info, *token_amount, TradeDirection::BaseToQuote, slippage_basis_points))
+            .collect()
+    }
+
+    fn get_extra_accounts(&self, mint: &Pubkey, pool_info: &PoolInfo) -> Result<Vec<AccountMeta>, TradingEndpointError> {
+        match pool_info.config {
+            Some(config) => Ok(vec![AccountMeta::new_readonly(config, false)]),
+            None => self.damm_v2.get_extra_accounts(mint, pool_info),
+        }
     }
 
     async fn create(&self, _: Keypair, _: Create, _: Option<PriorityFee>, _: Option<u64>) -> Result<Vec<Signature>, TradingEndpointError> {
@@ -63,35 +157,45 @@ impl DexTrait for MemeoraDBC {
         &self,
         payer: &Keypair,
         mint: &Pubkey,
-        config: Option<&Pubkey>,
-        token_program_account: &Pubkey,
+        custom_ata: Option<&Pubkey>,
+        extra_accounts: &[AccountMeta],
+        token_program: &Pubkey,
         buy: SwapInfo,
     ) -> Result<Instruction, TradingEndpointError> {
         self.initialized()?;
+        if self.is_migrated(mint) {
+            return self.damm_v2.build_buy_instruction(payer, mint, custom_ata, extra_accounts, token_program, buy);
+        }
+        let ata = match custom_ata {
+            None => get_associated_token_address_with_program_id(&payer.pubkey(), mint, token_program),
+            Some(t) => *t,
+        };
 
         let buy_info = SwapInstruction::from_swap_info(&buy, true);
         let buffer = buy_info.to_buffer().map_err(|e| TradingEndpointError::CustomError(e.to_string()))?;
-        let config = config.ok_or_else(|| TradingEndpointError::CustomError("Config must be provided for buy instruction".to_string()))?;
-        let bonding_curve = Self::get_virtual_pool_pda(mint, config)?;
-        let bonding_curve_vault = Self::get_bonding_curve_vault(mint)?;
-        let bonding_curve_sol_vault = Self::get_bonding_curve_sol_vault(mint)?;
+        let config = extra_accounts
+            .first()
+            .cloned()
+            .ok_or_else(|| TradingEndpointError::CustomError("Config must be provided for buy instruction".to_string()))?;
+        let keys = self.get_pool_keys(mint, &config.pubkey)?;
+        let quote_ata = get_associated_token_address_with_program_id(&payer.pubkey(), &keys.quote_mint, &keys.quote_token_program);
 
         Ok(Instruction::new_with_bytes(
             PUBKEY_METEORA_DBC,
             &buffer,
             vec![
                 AccountMeta::new_readonly(PUBKEY_METEORA_DBC_POOL_AUTHORITY, false),
-                AccountMeta::new_readonly(*config, false),
-                AccountMeta::new(bonding_curve, false),
-                AccountMeta::new(get_associated_token_address(&payer.pubkey(), &PUBKEY_WSOL), false),
-                AccountMeta::new(get_associated_token_address(&payer.pubkey(), mint), false),
-                AccountMeta::new(bonding_curve_vault, false),
-                AccountMeta::new(bonding_curve_sol_vault, false),
+                config,
+                AccountMeta::new(keys.pool, false),
+                AccountMeta::new(quote_ata, false),
+                AccountMeta::new(ata, false),
+                AccountMeta::new(keys.base_vault, false),
+                AccountMeta::new(keys.quote_vault, false),
                 AccountMeta::new_readonly(*mint, false),
-                AccountMeta::new_readonly(PUBKEY_WSOL, false),
+                AccountMeta::new_readonly(keys.quote_mint, false),
                 AccountMeta::new(payer.pubkey(), true),
-                AccountMeta::new_readonly(*token_program_account, false),
-                AccountMeta::new_readonly(spl_token::ID, false),
+                AccountMeta::new_readonly(*token_program, false),
+                AccountMeta::new_readonly(keys.quote_token_program, false),
                 AccountMeta::new_readonly(PUBKEY_METEORA_DBC, false),
                 AccountMeta::new_readonly(PUBKEY_METEORA_DBC_EVENT_AUTHORITY, false),
                 AccountMeta::new_readonly(PUBKEY_METEORA_DBC, false),
@@ -104,37 +208,45 @@ impl DexTrait for MemeoraDBC {
         payer: &Keypair,
         mint: &Pubkey,
         custom_ata: Option<&Pubkey>,
-        config: Option<&Pubkey>,
+        extra_accounts: &[AccountMeta],
+        token_program: &Pubkey,
         sell: SwapInfo,
     ) -> Result<Instruction, TradingEndpointError> {
         self.initialized()?;
+        if self.is_migrated(mint) {
+            return self
+                .damm_v2
+                .build_sell_instruction(payer, mint, custom_ata, extra_accounts, token_program, sell);
+        }
         let ata = match custom_ata {
-            None => get_associated_token_address(&payer.pubkey(), mint),
+            None => get_associated_token_address_with_program_id(&payer.pubkey(), mint, token_program),
             Some(t) => *t,
         };
         let sell_info = SwapInstruction::from_swap_info(&sell, false);
         let buffer = sell_info.to_buffer().map_err(|e| TradingEndpointError::CustomError(e.to_string()))?;
-        let config = config.ok_or_else(|| TradingEndpointError::CustomError("Config must be provided for sell instruction".to_string()))?;
-        let bonding_curve = Self::get_virtual_pool_pda(mint, config)?;
-        let bonding_curve_vault = Self::get_bonding_curve_vault(mint)?;
-        let bonding_curve_sol_vault = Self::get_bonding_curve_sol_vault(mint)?;
+        let config = extra_accounts
+            .first()
+            .cloned()
+            .ok_or_else(|| TradingEndpointError::CustomError("Config must be provided for sell instruction".to_string()))?;
+        let keys = self.get_pool_keys(mint, &config.pubkey)?;
+        let quote_ata = get_associated_token_address_with_program_id(&payer.pubkey(), &keys.quote_mint, &keys.quote_token_program);
 
         Ok(Instruction::new_with_bytes(
             PUBKEY_METEORA_DBC,
             &buffer,
             vec![
                 AccountMeta::new_readonly(PUBKEY_METEORA_DBC_POOL_AUTHORITY, false),
-                AccountMeta::new_readonly(*config, false),
-                AccountMeta::new(bonding_curve, false),
+                config,
+                AccountMeta::new(keys.pool, false),
                 AccountMeta::new(ata, false),
-                AccountMeta::new(get_associated_token_address(&payer.pubkey(), &PUBKEY_WSOL), false),
-                AccountMeta::new(bonding_curve_vault, false),
-                AccountMeta::new(bonding_curve_sol_vault, false),
+                AccountMeta::new(quote_ata, false),
+                AccountMeta::new(keys.base_vault, false),
+                AccountMeta::new(keys.quote_vault, false),
                 AccountMeta::new_readonly(*mint, false),
-                AccountMeta::new_readonly(PUBKEY_WSOL, false),
+                AccountMeta::new_readonly(keys.quote_mint, false),
                 AccountMeta::new(payer.pubkey(), true),
-                AccountMeta::new_readonly(spl_token::ID, false),
-                AccountMeta::new_readonly(spl_token::ID, false),
+                AccountMeta::new_readonly(*token_program, false),
+                AccountMeta::new_readonly(keys.quote_token_program, false),
                 AccountMeta::new_readonly(PUBKEY_METEORA_DBC, false),
                 AccountMeta::new_readonly(PUBKEY_METEORA_DBC_EVENT_AUTHORITY, false),
                 AccountMeta::new_readonly(PUBKEY_METEORA_DBC, false),
@@ -143,13 +255,155 @@ impl DexTrait for MemeoraDBC {
     }
 }
 
-impl MemeoraDBC {
+impl MeteoraDBC {
     pub fn new(endpoint: Arc<TradingEndpoint>) -> Self {
-        Self { endpoint }
+        Self {
+            damm_v2: MeteoraDammV2::new(endpoint.clone()),
+            endpoint,
+            configs: RwLock::new(HashMap::new()),
+            pools: RwLock::new(HashMap::new()),
+            migrated: RwLock::new(HashSet::new()),
+        }
+    }
+
+    /// Whether `mint` was found migrated to DAMM v2, its trades going there
+    pub fn is_migrated(&self, mint: &Pubkey) -> bool {
+        self.migrated.read().contains(mint)
+    }
+
+    pub async fn get_pool_state(&self, mint: &Pubkey) -> Result<PoolState, TradingEndpointError> {
+        self.get_curve_state(mint)
+            .await?
+            .ok_or_else(|| MeteoraDbcError::Migrated { mint: *mint }.into())
+    }
+
+    /// Curve of `mint` while it trades, `None` once it migrated to DAMM v2, whose pool is then selected from the migration config.
+    /// Curves migrating to DAMM v1 (`migration_option` 0) are out of scope and fail with `UnsupportedMigration` once migrated,
+    /// as do customizable DAMM v2 fee options, whose pools can still be traded through `MeteoraDammV2` by mint.
+    pub async fn get_curve_state(&self, mint: &Pubkey) -> Result<Option<PoolState>, TradingEndpointError> {
+        if self.is_migrated(mint) {
+            return Ok(None);
+        }
+
+        let (pool_address, pool, slot) = self.get_virtual_pool_with_slot(mint).await?;
+        let config = self.get_pool_config(&pool.config).await?;
+        if pool.is_migrated != 0 {
+            if config.migration_option != MIGRATION_OPTION_DAMM_V2 {
+                return Err(MeteoraDbcError::UnsupportedMigration {
+                    mint: *mint,
+                    migration_option: config.migration_option,
+                }
+                .into());
+            }
+
+            let damm_v2_pool = MeteoraDammV2::get_migrated_pool_pda(mint, &config.quote_mint, config.migration_fee_option)?;
+            self.damm_v2.use_pool(mint, &damm_v2_pool).await?;
+            self.migrated.write().insert(*mint);
+            return Ok(None);
+        }
+        if pool.is_curve_complete(&config) {
+            return Err(MeteoraDbcError::CurveComplete { mint: *mint }.into());
+        }
+
+        let pool_info = PoolInfo {
+            pool: pool_address,
+            creator: Some(pool.creator),
+            creator_vault: None,
+            config: Some(pool.config),
+            token_reserves: pool.base_reserve,
+            sol_reserves: pool.quote_reserve,
+            fees: Default::default(),
+            slot,
+        };
+
+        Ok(Some(PoolState { pool_info, pool, config }))
+    }
+
+    /// Pool configs never change once created, so they are fetched once and cached
+    pub async fn get_pool_config(&self, config: &Pubkey) -> Result<Arc<PoolConfig>, TradingEndpointError> {
+        if let Some(pool_config) = self.configs.read().get(config) {
+            return Ok(pool_config.clone());
+        }
+
+        let account = self.endpoint.rpc.get_account(config).await?;
+        let pool_config = Arc::new(Self::decode_pool_config(&account.data)?);
+        self.configs.write().insert(*config, pool_config.clone());
+        Ok(pool_config)
+    }
+
+    pub fn decode_pool_config(data: &[u8]) -> Result<PoolConfig, TradingEndpointError> {
+        bincode::deserialize::<PoolConfig>(data).map_err(|e| TradingEndpointError::CustomError(e.to_string()))
+    }
+
+    pub fn decode_virtual_pool(data: &[u8]) -> Result<VirtualPool, TradingEndpointError> {
+        bincode::deserialize::<VirtualPool>(data).map_err(|e| TradingEndpointError::CustomError(e.to_string()))
+    }
+
+    /// Slot or unix timestamp, depending on what the config measures activation in
+    fn get_current_point(config: &PoolConfig, slot: u64) -> u64 {
+        match config.get_activation_type() {
+            ActivationType::Slot => slot,
+            ActivationType::Timestamp => SystemTime::now().duration_since(UNIX_EPOCH).map(|w| w.as_secs()).unwrap_or_default(),
+        }
     }
 
-    pub fn get_virtual_pool_pda(mint: &Pubkey, config: &Pubkey) -> Result<Pubkey, TradingEndpointError> {
-        let seeds: &[&[u8]; 4] = &[VIRTUAL_POOL_SEED, config.as_ref(), mint.as_ref(), PUBKEY_WSOL.as_ref()];
+    /// Quote for an exact amount in, using the program's curve and fee math
+    pub fn quote_swap(
+        state: PoolState,
+        mint_info: MintInfo,
+        amount_in: u64,
+        trade_direction: TradeDirection,
+        slippage_basis_points: u64,
+    ) -> Result<Quote, TradingEndpointError> {
+        let PoolState { pool_info, pool, config } = state;
+        let slot = pool_info.slot;
+        let current_point = Self::get_current_point(&config, slot);
+
+        let (curve_amount_in, sell_transfer_fee) = match trade_direction {
+            TradeDirection::QuoteToBase => (amount_in, 0),
+            TradeDirection::BaseToQuote => {
+                let transfer_fee = mint_info.get_transfer_fee(slot, amount_in);
+                (amount_in - transfer_fee, transfer_fee)
+            }
+        };
+        let result = get_swap_result(&pool, &config, curve_amount_in, trade_direction, current_point)?;
+        let (expected_out, transfer_fee) = match trade_direction {
+            TradeDirection::QuoteToBase => {
+                let transfer_fee = mint_info.get_transfer_fee(slot, result.output_amount);
+                (result.output_amount - transfer_fee, transfer_fee)
+            }
+            TradeDirection::BaseToQuote => (result.output_amount, sell_transfer_fee),
+        };
+
+        let creator_fee = result.trading_fee * config.creator_trading_fee_percentage as u64 / 100;
+        let price_before = sqrt_price_to_price(pool.sqrt_price);
+        let price_after = sqrt_price_to_price(result.next_sqrt_price);
+
+        Ok(Quote {
+            amount_in,
+            max_amount_in: amount_in,
+            expected_out,
+            min_out: calculate_with_slippage_sell(expected_out, slippage_basis_points),
+            fees: FeeAmounts {
+                protocol: result.protocol_fee,
+                lp: 0,
+                creator: creator_fee,
+                platform: result.trading_fee - creator_fee,
+                share: 0,
+            },
+            transfer_fee,
+            price_before,
+            price_after,
+            price_impact_bps: calculate_price_impact_bps(price_before, price_after),
+            pool_info,
+            mint_info,
+            slot,
+        })
+    }
+
+    /// Virtual pool of `mint` under `config`, seeded by the greater then the lesser of the base and quote mints
+    pub fn get_virtual_pool_pda(mint: &Pubkey, config: &Pubkey, quote_mint: &Pubkey) -> Result<Pubkey, TradingEndpointError> {
+        let seeds: &[&[u8]; 4] = &[VIRTUAL_POOL_SEED, config.as_ref(), mint.max(quote_mint).as_ref(), mint.min(quote_mint).as_ref()];
         let pda = Pubkey::try_find_program_address(seeds, &PUBKEY_METEORA_DBC)
             .ok_or_else(|| TradingEndpointError::CustomError("Failed to find virtual pool PDA".to_string()))?;
         Ok(pda.0)
@@ -170,6 +424,43 @@ impl MemeoraDBC {
     }
 
     pub async fn get_pool_by_base_mint(&self, base_mint: &Pubkey) -> Result<Pubkey, TradingEndpointError> {
+        Ok(self.get_virtual_pool(base_mint).await?.0)
+    }
+
+    /// Virtual pool of `base_mint` with the slot it was read at, its accounts looked up once and cached with its config
+    pub async fn get_virtual_pool_with_slot(&self, base_mint: &Pubkey) -> Result<(Pubkey, VirtualPool, u64), TradingEndpointError> {
+        let cached = self.pools.read().get(base_mint).map(|keys| keys.pool);
+        let pool_address = match cached {
+            Some(pool_address) => pool_address,
+            None => {
+                let (pool_address, pool) = self.get_virtual_pool(base_mint).await?;
+                let config = self.get_pool_config(&pool.config).await?;
+                self.pools.write().insert(*base_mint, PoolKeys::new(pool_address, &pool, &config));
+                pool_address
+            }
+        };
+
+        let (account, slot) = self.endpoint.get_account_with_slot(&pool_address).await?;
+        Ok((pool_address, Self::decode_virtual_pool(&account.data)?, slot))
+    }
+
+    /// Accounts of the pool found for `mint`, or of its WSOL pool under `config` when none was looked up yet
+    pub fn get_pool_keys(&self, mint: &Pubkey, config: &Pubkey) -> Result<PoolKeys, TradingEndpointError> {
+        if let Some(keys) = self.pools.read().get(mint) {
+            return Ok(*keys);
+        }
+
+        Ok(PoolKeys {
+            pool: Self::get_virtual_pool_pda(mint, config, &PUBKEY_WSOL)?,
+            config: *config,
+            base_vault: Self::get_bonding_curve_vault(mint)?,
+            quote_vault: Self::get_bonding_curve_sol_vault(mint)?,
+            quote_mint: PUBKEY_WSOL,
+            quote_token_program: spl_token::ID,
+        })
+    }
+
+    pub async fn get_virtual_pool(&self, base_mint: &Pubkey) -> Result<(Pubkey, VirtualPool), TradingEndpointError> {
         let accounts = self
             .endpoint
             .rpc
@@ -177,8 +468,11 @@ impl MemeoraDBC {
                 &PUBKEY_METEORA_DBC,
                 solana_client::rpc_config::RpcProgramAccountsConfig {
                     filters: Some(vec![
-                        solana_client::rpc_filter::RpcFilterType::DataSize(136),
-                        solana_client::rpc_filter::RpcFilterType::Memcmp(solana_client::rpc_filter::Memcmp::new_raw_bytes(8, base_mint.to_bytes().to_vec())),
+                        solana_client::rpc_filter::RpcFilterType::DataSize(VIRTUAL_POOL_SIZE),
+                        solana_client::rpc_filter::RpcFilterType::Memcmp(solana_client::rpc_filter::Memcmp::new_raw_bytes(
+                            VIRTUAL_POOL_BASE_MINT_OFFSET,
+                            base_mint.to_bytes().to_vec(),
+                        )),
                     ]),
                     account_config: solana_client::rpc_config::RpcAccountInfoConfig {
                         encoding: Some(solana_account_decoder::UiAccountEncoding::Base64),
@@ -195,10 +489,106 @@ impl MemeoraDBC {
         if accounts.is_empty() {
             return Err(TradingEndpointError::CustomError(format!(
                 "No bonding curve found for base mint: {}",
-                base_mint.to_string()
+                base_mint
             )));
         }
 
-        Ok(accounts[0].0)
+        let (pool, account) = &accounts[0];
+        Ok((*pool, Self::decode_virtual_pool(&account.data)?))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        common::accounts::PUBKEY_USDC,
+        dex::meteora_damm_v2_types::{MeteoraDammV2Error, DAMM_V2_MIGRATION_CONFIGS, MIGRATION_FEE_OPTION_CUSTOMIZABLE},
+    };
+    use solana_client::nonblocking::rpc_client::RpcClient;
+
+    fn dbc() -> MeteoraDBC {
+        let rpc = Arc::new(RpcClient::new("http://127.0.0.1:8899".to_string()));
+        MeteoraDBC::new(Arc::new(TradingEndpoint::new(rpc, vec![])))
+    }
+
+    #[test]
+    fn virtual_pool_pda_orders_the_mints() {
+        let config = Pubkey::new_unique();
+        let mint = Pubkey::new_unique();
+        for quote_mint in [PUBKEY_WSOL, PUBKEY_USDC] {
+            let (greater, lesser) = match mint > quote_mint {
+                true => (mint, quote_mint),
+                false => (quote_mint, mint),
+            };
+            let expected = Pubkey::find_program_address(&[VIRTUAL_POOL_SEED, config.as_ref(), greater.as_ref(), lesser.as_ref()], &PUBKEY_METEORA_DBC).0;
+            assert_eq!(MeteoraDBC::get_virtual_pool_pda(&mint, &config, &quote_mint).unwrap(), expected);
+        }
+    }
+
+    #[test]
+    fn migrated_pools_need_a_fixed_fee_option() {
+        let mint = Pubkey::new_unique();
+        for (option, config) in DAMM_V2_MIGRATION_CONFIGS.iter().enumerate() {
+            let pool = MeteoraDammV2::get_migrated_pool_pda(&mint, &PUBKEY_WSOL, option as u8).unwrap();
+            assert_eq!(pool, MeteoraDammV2::get_pool_pda(config, &mint, &PUBKEY_WSOL).unwrap());
+        }
+        assert!(matches!(
+            MeteoraDammV2::get_migrated_pool_pda(&mint, &PUBKEY_WSOL, MIGRATION_FEE_OPTION_CUSTOMIZABLE),
+            Err(TradingEndpointError::MeteoraDammV2(MeteoraDammV2Error::UnsupportedMigrationFeeOption(
+                MIGRATION_FEE_OPTION_CUSTOMIZABLE
+            )))
+        ));
+    }
+
+    #[test]
+    fn swaps_use_the_quote_mint_of_the_pool_config() {
+        let dbc = dbc();
+        let payer = Keypair::new();
+        let mint = Pubkey::new_unique();
+        let config = Pubkey::new_unique();
+        let swap = || SwapInfo {
+            token_amount: 1_000,
+            sol_amount: 1_000_000,
+        };
+        let build_buy = |dbc: &MeteoraDBC| dbc.build_buy_instruction(&payer, &mint, None, &[AccountMeta::new_readonly(config, false)], &spl_token::ID, swap());
+
+        // Before its pool was looked up a mint is assumed to trade against WSOL
+        assert_eq!(dbc.get_quote_mint(&mint), (PUBKEY_WSOL, spl_token::ID));
+        let instruction = build_buy(&dbc).unwrap();
+        assert_eq!(
+            instruction.accounts[2].pubkey,
+            MeteoraDBC::get_virtual_pool_pda(&mint, &config, &PUBKEY_WSOL).unwrap()
+        );
+        assert_eq!(instruction.accounts[8].pubkey, PUBKEY_WSOL);
+
+        let keys = PoolKeys {
+            pool: Pubkey::new_unique(),
+            config,
+            base_vault: Pubkey::new_unique(),
+            quote_vault: Pubkey::new_unique(),
+            quote_mint: PUBKEY_USDC,
+            quote_token_program: spl_token::ID,
+        };
+        dbc.pools.write().insert(mint, keys);
+        assert_eq!(dbc.get_quote_mint(&mint), (PUBKEY_USDC, spl_token::ID));
+
+        let instruction = build_buy(&dbc).unwrap();
+        let accounts = instruction.accounts.iter().map(|account| account.pubkey).collect::<Vec<_>>();
+        assert_eq!(accounts[2], keys.pool);
+        assert_eq!(
+            accounts[3],
+            get_associated_token_address_with_program_id(&payer.pubkey(), &PUBKEY_USDC, &spl_token::ID)
+        );
+        assert_eq!(accounts[5..9], [keys.base_vault, keys.quote_vault, mint, PUBKEY_USDC]);
+
+        let instruction = dbc
+            .build_sell_instruction(&payer, &mint, None, &[AccountMeta::new_readonly(config, false)], &spl_token::ID, swap())
+            .unwrap();
+        assert_eq!(
+            instruction.accounts[4].pubkey,
+            get_associated_token_address_with_program_id(&payer.pubkey(), &PUBKEY_USDC, &spl_token::ID)
+        );
+        assert_eq!(instruction.accounts[8].pubkey, PUBKEY_USDC);
     }
 }
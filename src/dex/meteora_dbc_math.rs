@@ -0,0 +1,666 @@
+use super::meteora_dbc_types::*;
+use ruint::aliases::U256;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Rounding {
+    Up,
+    Down,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TradeDirection {
+    /// Sell
+    BaseToQuote,
+    /// Buy
+    QuoteToBase,
+}
+
+/// Outcome of a swap as computed by the program
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct SwapResult {
+    /// Amount that reached the curve, after fees charged on the input
+    pub actual_input_amount: u64,
+    /// Amount received, after fees charged on the output
+    pub output_amount: u64,
+    pub next_sqrt_price: u128,
+    /// Trading fee left to the partner and creator once the protocol fee is taken
+    pub trading_fee: u64,
+    pub protocol_fee: u64,
+    /// Fees were taken in the base token instead of the quote token
+    pub fees_on_base: bool,
+}
+
+fn mul_div_u256(x: U256, y: U256, denominator: U256, rounding: Rounding) -> Result<U256, MeteoraDbcError> {
+    if denominator.is_zero() {
+        return Err(MeteoraDbcError::MathOverflow);
+    }
+
+    let prod = x.checked_mul(y).ok_or(MeteoraDbcError::MathOverflow)?;
+    Ok(match rounding {
+        Rounding::Up => prod.div_ceil(denominator),
+        Rounding::Down => prod / denominator,
+    })
+}
+
+fn to_u64(value: U256) -> Result<u64, MeteoraDbcError> {
+    u64::try_from(value).map_err(|_| MeteoraDbcError::MathOverflow)
+}
+
+fn to_u128(value: U256) -> Result<u128, MeteoraDbcError> {
+    u128::try_from(value).map_err(|_| MeteoraDbcError::MathOverflow)
+}
+
+/// Δbase = L * (√P_upper - √P_lower) / (√P_upper * √P_lower)
+pub fn get_delta_amount_base_unsigned_256(
+    lower_sqrt_price: u128,
+    upper_sqrt_price: u128,
+    liquidity: u128,
+    rounding: Rounding,
+) -> Result<U256, MeteoraDbcError> {
+    let delta_sqrt_price = upper_sqrt_price.checked_sub(lower_sqrt_price).ok_or(MeteoraDbcError::MathOverflow)?;
+    let denominator = U256::from(lower_sqrt_price) * U256::from(upper_sqrt_price);
+    mul_div_u256(U256::from(liquidity), U256::from(delta_sqrt_price), denominator, rounding)
+}
+
+pub fn get_delta_amount_base_unsigned(lower_sqrt_price: u128, upper_sqrt_price: u128, liquidity: u128, rounding: Rounding) -> Result<u64, MeteoraDbcError> {
+    to_u64(get_delta_amount_base_unsigned_256(lower_sqrt_price, upper_sqrt_price, liquidity, rounding)?)
+}
+
+/// Δquote = L * (√P_upper - √P_lower)
+pub fn get_delta_amount_quote_unsigned_256(
+    lower_sqrt_price: u128,
+    upper_sqrt_price: u128,
+    liquidity: u128,
+    rounding: Rounding,
+) -> Result<U256, MeteoraDbcError> {
+    let delta_sqrt_price = upper_sqrt_price.checked_sub(lower_sqrt_price).ok_or(MeteoraDbcError::MathOverflow)?;
+    let prod = U256::from(liquidity) * U256::from(delta_sqrt_price);
+    Ok(match rounding {
+        Rounding::Up => prod.div_ceil(U256::from(1u8) << (RESOLUTION * 2)),
+        Rounding::Down => prod >> (RESOLUTION * 2),
+    })
+}
+
+pub fn get_delta_amount_quote_unsigned(lower_sqrt_price: u128, upper_sqrt_price: u128, liquidity: u128, rounding: Rounding) -> Result<u64, MeteoraDbcError> {
+    to_u64(get_delta_amount_quote_unsigned_256(lower_sqrt_price, upper_sqrt_price, liquidity, rounding)?)
+}
+
+/// √P' = √P * L / (L + Δbase * √P)
+pub fn get_next_sqrt_price_from_amount_base_rounding_up(sqrt_price: u128, liquidity: u128, amount: u64) -> Result<u128, MeteoraDbcError> {
+    if amount == 0 {
+        return Ok(sqrt_price);
+    }
+
+    let sqrt_price = U256::from(sqrt_price);
+    let liquidity = U256::from(liquidity);
+    let denominator = liquidity + U256::from(amount) * sqrt_price;
+    to_u128(mul_div_u256(liquidity, sqrt_price, denominator, Rounding::Up)?)
+}
+
+/// √P' = √P + Δquote / L
+pub fn get_next_sqrt_price_from_amount_quote_rounding_down(sqrt_price: u128, liquidity: u128, amount: u64) -> Result<u128, MeteoraDbcError> {
+    if liquidity == 0 {
+        return Err(MeteoraDbcError::MathOverflow);
+    }
+
+    let quotient = to_u128((U256::from(amount) << (RESOLUTION * 2)) / U256::from(liquidity))?;
+    sqrt_price.checked_add(quotient).ok_or(MeteoraDbcError::MathOverflow)
+}
+
+pub fn get_next_sqrt_price_from_input(sqrt_price: u128, liquidity: u128, amount_in: u64, base_for_quote: bool) -> Result<u128, MeteoraDbcError> {
+    match base_for_quote {
+        true => get_next_sqrt_price_from_amount_base_rounding_up(sqrt_price, liquidity, amount_in),
+        false => get_next_sqrt_price_from_amount_quote_rounding_down(sqrt_price, liquidity, amount_in),
+    }
+}
+
+/// Walks the curve segments up from `sqrt_price`, returning the base out and the price reached
+pub fn get_swap_amount_from_quote_to_base(sqrt_price: u128, config: &PoolConfig, amount_in: u64) -> Result<(u64, u128), MeteoraDbcError> {
+    let mut total_output_amount = 0u64;
+    let mut current_sqrt_price = sqrt_price;
+    let mut amount_left = amount_in;
+
+    for point in config.curve.iter() {
+        if point.sqrt_price == 0 || point.liquidity == 0 {
+            break;
+        }
+
+        if point.sqrt_price > current_sqrt_price {
+            let max_amount_in = get_delta_amount_quote_unsigned_256(current_sqrt_price, point.sqrt_price, point.liquidity, Rounding::Up)?;
+            if U256::from(amount_left) < max_amount_in {
+                let next_sqrt_price = get_next_sqrt_price_from_input(current_sqrt_price, point.liquidity, amount_left, false)?;
+                let output_amount = get_delta_amount_base_unsigned(current_sqrt_price, next_sqrt_price, point.liquidity, Rounding::Down)?;
+                total_output_amount = total_output_amount.checked_add(output_amount).ok_or(MeteoraDbcError::MathOverflow)?;
+                current_sqrt_price = next_sqrt_price;
+                amount_left = 0;
+                break;
+            }
+
+            let output_amount = get_delta_amount_base_unsigned(current_sqrt_price, point.sqrt_price, point.liquidity, Rounding::Down)?;
+            total_output_amount = total_output_amount.checked_add(output_amount).ok_or(MeteoraDbcError::MathOverflow)?;
+            current_sqrt_price = point.sqrt_price;
+            amount_left -= to_u64(max_amount_in)?;
+        }
+    }
+
+    if amount_left != 0 {
+        return Err(MeteoraDbcError::NotEnoughLiquidity);
+    }
+
+    Ok((total_output_amount, current_sqrt_price))
+}
+
+/// Walks the curve segments down from `sqrt_price`, returning the quote out and the price reached
+pub fn get_swap_amount_from_base_to_quote(sqrt_price: u128, config: &PoolConfig, amount_in: u64) -> Result<(u64, u128), MeteoraDbcError> {
+    let mut total_output_amount = 0u64;
+    let mut current_sqrt_price = sqrt_price;
+    let mut amount_left = amount_in;
+
+    for i in (0..MAX_CURVE_POINT - 1).rev() {
+        let point = &config.curve[i];
+        if point.sqrt_price == 0 || point.liquidity == 0 {
+            continue;
+        }
+
+        // The segment below `curve[i + 1].sqrt_price` trades at the next point's liquidity
+        let liquidity = config.curve[i + 1].liquidity;
+
+        if point.sqrt_price < current_sqrt_price {
+            let max_amount_in = get_delta_amount_base_unsigned_256(point.sqrt_price, current_sqrt_price, liquidity, Rounding::Up)?;
+            if U256::from(amount_left) < max_amount_in {
+                let next_sqrt_price = get_next_sqrt_price_from_input(current_sqrt_price, liquidity, amount_left, true)?;
+                let output_amount = get_delta_amount_quote_unsigned(next_sqrt_price, current_sqrt_price, liquidity, Rounding::Down)?;
+                total_output_amount = total_output_amount.checked_add(output_amount).ok_or(MeteoraDbcError::MathOverflow)?;
+                current_sqrt_price = next_sqrt_price;
+                amount_left = 0;
+                break;
+            }
+
+            let output_amount = get_delta_amount_quote_unsigned(point.sqrt_price, current_sqrt_price, liquidity, Rounding::Down)?;
+            total_output_amount = total_output_amount.checked_add(output_amount).ok_or(MeteoraDbcError::MathOverflow)?;
+            current_sqrt_price = point.sqrt_price;
+            amount_left -= to_u64(max_amount_in)?;
+        }
+    }
+
+    if amount_left != 0 {
+        let liquidity = config.curve[0].liquidity;
+        let next_sqrt_price = get_next_sqrt_price_from_input(current_sqrt_price, liquidity, amount_left, true)?;
+        let output_amount = get_delta_amount_quote_unsigned(next_sqrt_price, current_sqrt_price, liquidity, Rounding::Down)?;
+        total_output_amount = total_output_amount.checked_add(output_amount).ok_or(MeteoraDbcError::MathOverflow)?;
+        current_sqrt_price = next_sqrt_price;
+    }
+
+    if current_sqrt_price < config.sqrt_start_price {
+        return Err(MeteoraDbcError::NotEnoughLiquidity);
+    }
+
+    Ok((total_output_amount, current_sqrt_price))
+}
+
+/// Q64.64 `base^exp` for `base` below one, matching the program's rounding
+fn pow_q64(base: u128, exp: u64) -> Option<u128> {
+    let mut squared_base = base;
+    let mut result = 1u128 << RESOLUTION;
+
+    for bit in 0..20 {
+        if exp & (1 << bit) != 0 {
+            result = result.checked_mul(squared_base)? >> RESOLUTION;
+        }
+        squared_base = squared_base.checked_mul(squared_base)? >> RESOLUTION;
+    }
+
+    Some(result)
+}
+
+/// Base fee at `current_point`, from the fee scheduler or the rate limiter
+pub fn get_base_fee_numerator(
+    base_fee: &BaseFeeConfig,
+    current_point: u64,
+    activation_point: u64,
+    trade_direction: TradeDirection,
+    amount: u64,
+) -> Result<u64, MeteoraDbcError> {
+    match BaseFeeMode::try_from(base_fee.base_fee_mode)? {
+        BaseFeeMode::FeeSchedulerLinear | BaseFeeMode::FeeSchedulerExponential => {
+            let (number_of_period, period_frequency, reduction_factor) = (base_fee.first_factor as u64, base_fee.second_factor, base_fee.third_factor);
+            if period_frequency == 0 {
+                return Ok(base_fee.cliff_fee_numerator);
+            }
+
+            let period = match current_point < activation_point {
+                true => number_of_period,
+                false => ((current_point - activation_point) / period_frequency).min(number_of_period),
+            };
+
+            match base_fee.base_fee_mode {
+                0 => Ok(base_fee.cliff_fee_numerator.saturating_sub(period.saturating_mul(reduction_factor))),
+                _ => {
+                    let bps = ((reduction_factor as u128) << RESOLUTION) / BASIS_POINT_MAX as u128;
+                    let base = (1u128 << RESOLUTION).checked_sub(bps).ok_or(MeteoraDbcError::MathOverflow)?;
+                    let factor = pow_q64(base, period).ok_or(MeteoraDbcError::MathOverflow)?;
+                    let fee = (U256::from(factor) * U256::from(base_fee.cliff_fee_numerator)) >> RESOLUTION;
+                    to_u64(fee)
+                }
+            }
+        }
+        BaseFeeMode::RateLimiter => {
+            let (fee_increment_bps, max_limiter_duration, reference_amount) = (base_fee.first_factor as u64, base_fee.second_factor, base_fee.third_factor);
+            let applied = trade_direction == TradeDirection::QuoteToBase
+                && reference_amount != 0
+                && current_point >= activation_point
+                && current_point <= activation_point.saturating_add(max_limiter_duration);
+            match applied {
+                true => get_rate_limiter_fee_numerator(base_fee.cliff_fee_numerator, fee_increment_bps, reference_amount, amount),
+                false => Ok(base_fee.cliff_fee_numerator),
+            }
+        }
+    }
+}
+
+/// Fee rises by `fee_increment_bps` for every `reference_amount` bought past the first one
+fn get_rate_limiter_fee_numerator(cliff_fee_numerator: u64, fee_increment_bps: u64, reference_amount: u64, amount: u64) -> Result<u64, MeteoraDbcError> {
+    if amount <= reference_amount {
+        return Ok(cliff_fee_numerator);
+    }
+
+    let fee_increment_numerator = fee_increment_bps * FEE_DENOMINATOR / BASIS_POINT_MAX;
+    if fee_increment_numerator == 0 {
+        return Ok(cliff_fee_numerator);
+    }
+
+    let one = U256::from(1u8);
+    let two = U256::from(2u8);
+    let c = U256::from(cliff_fee_numerator);
+    let i = U256::from(fee_increment_numerator);
+    let x0 = U256::from(reference_amount);
+    let diff = amount - reference_amount;
+    let a = U256::from(diff / reference_amount);
+    let b = U256::from(diff % reference_amount);
+    let max_index = U256::from(MAX_FEE_NUMERATOR.saturating_sub(cliff_fee_numerator) / fee_increment_numerator);
+
+    let trading_fee_numerator = match a < max_index {
+        true => {
+            let numerator_1 = c + c * a + i * a * (a + one) / two;
+            let numerator_2 = c + i * (a + one);
+            x0 * numerator_1 + b * numerator_2
+        }
+        false => {
+            let numerator_1 = c + c * max_index + i * max_index * (max_index + one) / two;
+            let left_amount = (a - max_index) * x0 + b;
+            x0 * numerator_1 + left_amount * U256::from(MAX_FEE_NUMERATOR)
+        }
+    };
+
+    let denominator = U256::from(FEE_DENOMINATOR);
+    let trading_fee = trading_fee_numerator.div_ceil(denominator);
+    let fee_numerator = to_u64(trading_fee * denominator / U256::from(amount))?;
+    Ok(fee_numerator.min(MAX_FEE_NUMERATOR))
+}
+
+/// Dynamic fee driven by recent volatility, zero when the config disables it
+pub fn get_variable_fee_numerator(dynamic_fee: &DynamicFeeConfig, volatility_accumulator: u128) -> Result<u64, MeteoraDbcError> {
+    if dynamic_fee.initialized == 0 {
+        return Ok(0);
+    }
+
+    let volatility_bin = volatility_accumulator
+        .checked_mul(dynamic_fee.bin_step as u128)
+        .ok_or(MeteoraDbcError::MathOverflow)?;
+    let square_volatility_bin = volatility_bin.checked_mul(volatility_bin).ok_or(MeteoraDbcError::MathOverflow)?;
+    let variable_fee = square_volatility_bin
+        .checked_mul(dynamic_fee.variable_fee_control as u128)
+        .ok_or(MeteoraDbcError::MathOverflow)?;
+    u64::try_from(variable_fee.div_ceil(DYNAMIC_FEE_SCALE)).map_err(|_| MeteoraDbcError::MathOverflow)
+}
+
+/// Base fee plus the dynamic fee of the stored volatility accumulator.
+/// The program refreshes only the volatility and price references before a swap and reads the same stored accumulator,
+/// so no decay against `last_update_timestamp` is applied here. The accumulator is only raised after the swap, from those references.
+pub fn get_total_fee_numerator(
+    config: &PoolConfig,
+    pool: &VirtualPool,
+    current_point: u64,
+    trade_direction: TradeDirection,
+    amount: u64,
+) -> Result<u64, MeteoraDbcError> {
+    let base_fee = get_base_fee_numerator(&config.pool_fees.base_fee, current_point, pool.activation_point, trade_direction, amount)?;
+    let variable_fee = get_variable_fee_numerator(&config.pool_fees.dynamic_fee, pool.volatility_tracker.volatility_accumulator)?;
+    Ok(base_fee.saturating_add(variable_fee).min(MAX_FEE_NUMERATOR))
+}
+
+/// Splits `amount` into what is left after fees, the trading fee and the protocol's share of it
+pub fn get_fee_on_amount(amount: u64, fee_numerator: u64, protocol_fee_percent: u8) -> Result<(u64, u64, u64), MeteoraDbcError> {
+    let trading_fee = to_u64(mul_div_u256(
+        U256::from(amount),
+        U256::from(fee_numerator),
+        U256::from(FEE_DENOMINATOR),
+        Rounding::Up,
+    )?)?;
+    let amount = amount.checked_sub(trading_fee).ok_or(MeteoraDbcError::MathOverflow)?;
+    let protocol_fee = trading_fee * protocol_fee_percent as u64 / 100;
+    Ok((amount, trading_fee - protocol_fee, protocol_fee))
+}
+
+/// Simulates the program's swap of `amount_in` against `pool`
+pub fn get_swap_result(
+    pool: &VirtualPool,
+    config: &PoolConfig,
+    amount_in: u64,
+    trade_direction: TradeDirection,
+    current_point: u64,
+) -> Result<SwapResult, MeteoraDbcError> {
+    // Buys pay fees in quote on the way in unless fees are collected in the output token, sells always pay them on the quote out
+    let (fees_on_input, fees_on_base) = match (config.get_collect_fee_mode(), trade_direction) {
+        (CollectFeeMode::QuoteToken, TradeDirection::QuoteToBase) => (true, false),
+        (CollectFeeMode::OutputToken, TradeDirection::QuoteToBase) => (false, true),
+        (_, TradeDirection::BaseToQuote) => (false, false),
+    };
+    let protocol_fee_percent = config.pool_fees.protocol_fee_percent;
+
+    let (actual_input_amount, mut trading_fee, mut protocol_fee) = match fees_on_input {
+        true => {
+            let fee_numerator = get_total_fee_numerator(config, pool, current_point, trade_direction, amount_in)?;
+            get_fee_on_amount(amount_in, fee_numerator, protocol_fee_percent)?
+        }
+        false => (amount_in, 0, 0),
+    };
+
+    let (output_amount, next_sqrt_price) = match trade_direction {
+        TradeDirection::QuoteToBase => get_swap_amount_from_quote_to_base(pool.sqrt_price, config, actual_input_amount)?,
+        TradeDirection::BaseToQuote => get_swap_amount_from_base_to_quote(pool.sqrt_price, config, actual_input_amount)?,
+    };
+
+    let output_amount = match fees_on_input {
+        true => output_amount,
+        false => {
+            let fee_numerator = get_total_fee_numerator(config, pool, current_point, trade_direction, output_amount)?;
+            let (amount, fee, protocol) = get_fee_on_amount(output_amount, fee_numerator, protocol_fee_percent)?;
+            trading_fee = fee;
+            protocol_fee = protocol;
+            amount
+        }
+    };
+
+    Ok(SwapResult {
+        actual_input_amount,
+        output_amount,
+        next_sqrt_price,
+        trading_fee,
+        protocol_fee,
+        fees_on_base,
+    })
+}
+
+/// Smallest amount in for which `get_amount_out` reaches `amount_out`, as the programs only swap an exact amount in
+pub fn get_amount_in_for_exact_out<E: From<MeteoraDbcError>>(amount_out: u64, get_amount_out: impl Fn(u64) -> Result<u64, E>) -> Result<u64, E> {
+    let mut high = 1u64;
+    while get_amount_out(high)? < amount_out {
+        high = high.checked_mul(2).ok_or(MeteoraDbcError::NotEnoughLiquidity)?;
+    }
+
+    let mut low = high / 2;
+    while low + 1 < high {
+        let mid = low + (high - low) / 2;
+        match get_amount_out(mid)? >= amount_out {
+            true => high = mid,
+            false => low = mid,
+        }
+    }
+    Ok(high)
+}
+
+/// Quote lamports per base token unit at a Q64.64 sqrt price
+pub fn sqrt_price_to_price(sqrt_price: u128) -> f64 {
+    let sqrt_price = sqrt_price as f64 / (1u128 << RESOLUTION) as f64;
+    sqrt_price * sqrt_price
+}
+
+#[cfg(test)]
+mod tests {
+    // The accounts below are laid out by hand rather than captured on chain, so the expected swap results
+    // pin this implementation's reading of the program's math, not swaps observed on mainnet
+    use super::*;
+    use crate::dex::meteora_dbc::MeteoraDBC;
+    use solana_sdk::pubkey::Pubkey;
+
+    const SQRT_START_PRICE: u128 = 95_072_344_172_433_750;
+    const ACTIVATION_POINT: u64 = 1_000;
+
+    /// Little-endian account writer laid out field by field like the program's zero-copy structs
+    #[derive(Default)]
+    struct AccountBytes(Vec<u8>);
+
+    impl AccountBytes {
+        fn u8(mut self, value: u8) -> Self {
+            self.0.push(value);
+            self
+        }
+
+        fn u16(mut self, value: u16) -> Self {
+            self.0.extend_from_slice(&value.to_le_bytes());
+            self
+        }
+
+        fn u64(mut self, value: u64) -> Self {
+            self.0.extend_from_slice(&value.to_le_bytes());
+            self
+        }
+
+        fn u128(mut self, value: u128) -> Self {
+            self.0.extend_from_slice(&value.to_le_bytes());
+            self
+        }
+
+        fn pubkey(mut self, value: Pubkey) -> Self {
+            self.0.extend_from_slice(value.as_ref());
+            self
+        }
+
+        fn zeros(mut self, len: usize) -> Self {
+            self.0.resize(self.0.len() + len, 0);
+            self
+        }
+    }
+
+    /// Synthetic config, not a dump of a mainnet account: two segment curve starting at `SQRT_START_PRICE`,
+    /// 20% protocol fee, quote-token fees, slot activation
+    fn config_bytes(base_fee_mode: u8, first_factor: u16, second_factor: u64, third_factor: u64) -> Vec<u8> {
+        let bytes = AccountBytes::default()
+            .zeros(8)
+            .pubkey(Pubkey::new_unique())
+            .pubkey(Pubkey::new_unique())
+            .pubkey(Pubkey::new_unique())
+            // pool_fees.base_fee
+            .u64(10_000_000)
+            .u64(second_factor)
+            .u64(third_factor)
+            .u16(first_factor)
+            .u8(base_fee_mode)
+            .zeros(5)
+            // pool_fees.dynamic_fee, uninitialized
+            .zeros(48)
+            .zeros(40 + 6)
+            .u8(20)
+            .u8(0)
+            // collect_fee_mode, migration_option, activation_type, token_decimal and the remaining flags
+            .u8(0)
+            .u8(1)
+            .u8(0)
+            .u8(6)
+            .zeros(13 + 7)
+            .u64(0)
+            .u64(85_000_000_000)
+            .u64(0)
+            .u128(12 * SQRT_START_PRICE)
+            .zeros(48)
+            .u64(1_000_000_000_000_000)
+            .u64(1_000_000_000_000_000)
+            .zeros(16 + 16)
+            .u128(SQRT_START_PRICE)
+            .u128(4 * SQRT_START_PRICE)
+            .u128(100_000_000_000_000_000_000_000_000_000_000)
+            .u128(12 * SQRT_START_PRICE)
+            .u128(300_000_000_000_000_000_000_000_000_000_000)
+            .zeros(18 * 32);
+        assert_eq!(bytes.0.len(), 1048);
+        bytes.0
+    }
+
+    /// Synthetic virtual pool at `sqrt_price`, with no volatility recorded
+    fn pool_bytes(sqrt_price: u128) -> Vec<u8> {
+        let bytes = AccountBytes::default()
+            .zeros(8)
+            .zeros(64)
+            .pubkey(Pubkey::new_unique())
+            .pubkey(Pubkey::new_unique())
+            .pubkey(Pubkey::new_unique())
+            .pubkey(Pubkey::new_unique())
+            .pubkey(Pubkey::new_unique())
+            .zeros(6 * 8)
+            .u128(sqrt_price)
+            .u64(ACTIVATION_POINT)
+            .zeros(8 + 32 + 8 + 16 + 56);
+        assert_eq!(bytes.0.len() as u64, VIRTUAL_POOL_SIZE);
+        bytes.0
+    }
+
+    fn linear_scheduler_config() -> PoolConfig {
+        // 1% cliff fee, lowered by 0.05% every 100 slots for 10 periods
+        MeteoraDBC::decode_pool_config(&config_bytes(0, 10, 100, 500_000)).unwrap()
+    }
+
+    #[test]
+    fn decodes_config_and_pool() {
+        let config = linear_scheduler_config();
+        assert_eq!(config.pool_fees.base_fee.cliff_fee_numerator, 10_000_000);
+        assert_eq!(config.pool_fees.protocol_fee_percent, 20);
+        assert_eq!(config.migration_quote_threshold, 85_000_000_000);
+        assert_eq!(config.sqrt_start_price, SQRT_START_PRICE);
+        assert_eq!(config.curve[1].liquidity, 300_000_000_000_000_000_000_000_000_000_000);
+        assert_eq!(config.get_collect_fee_mode(), CollectFeeMode::QuoteToken);
+        assert_eq!(config.get_activation_type(), ActivationType::Slot);
+
+        let pool = MeteoraDBC::decode_virtual_pool(&pool_bytes(6 * SQRT_START_PRICE)).unwrap();
+        assert_eq!(pool.sqrt_price, 6 * SQRT_START_PRICE);
+        assert_eq!(pool.activation_point, ACTIVATION_POINT);
+    }
+
+    #[test]
+    fn quote_to_base_crosses_into_the_next_segment() {
+        let config = linear_scheduler_config();
+        let pool = MeteoraDBC::decode_virtual_pool(&pool_bytes(SQRT_START_PRICE)).unwrap();
+
+        // Period 3 of the scheduler, 0.85% taken on the way in
+        let result = get_swap_result(&pool, &config, 100_000_000_000, TradeDirection::QuoteToBase, 1_350).unwrap();
+        assert_eq!(
+            result,
+            SwapResult {
+                actual_input_amount: 99_150_000_000,
+                output_amount: 823_371_182_968_778,
+                next_sqrt_price: 397_680_354_784_213_250,
+                trading_fee: 680_000_000,
+                protocol_fee: 170_000_000,
+                fees_on_base: false,
+            }
+        );
+        assert!(result.next_sqrt_price > config.curve[0].sqrt_price);
+    }
+
+    #[test]
+    fn base_to_quote_walks_down_into_the_first_segment() {
+        let config = linear_scheduler_config();
+        let pool = MeteoraDBC::decode_virtual_pool(&pool_bytes(6 * SQRT_START_PRICE)).unwrap();
+
+        // Sells pay the fee on the quote that comes out
+        let result = get_swap_result(&pool, &config, 300_000_000_000_000, TradeDirection::BaseToQuote, 1_350).unwrap();
+        assert_eq!(
+            result,
+            SwapResult {
+                actual_input_amount: 300_000_000_000_000,
+                output_amount: 179_892_451_673,
+                next_sqrt_price: 333_333_333_333_333_932,
+                trading_fee: 1_233_755_595,
+                protocol_fee: 308_438_898,
+                fees_on_base: false,
+            }
+        );
+        assert!(result.next_sqrt_price < config.curve[0].sqrt_price);
+    }
+
+    #[test]
+    fn selling_below_the_start_price_runs_out_of_liquidity() {
+        let config = linear_scheduler_config();
+        let pool = MeteoraDBC::decode_virtual_pool(&pool_bytes(SQRT_START_PRICE)).unwrap();
+        assert!(matches!(
+            get_swap_amount_from_base_to_quote(pool.sqrt_price, &config, 1_000_000),
+            Err(MeteoraDbcError::NotEnoughLiquidity)
+        ));
+    }
+
+    #[test]
+    fn exact_out_search_finds_the_smallest_amount_in() {
+        let config = linear_scheduler_config();
+        let pool = MeteoraDBC::decode_virtual_pool(&pool_bytes(SQRT_START_PRICE)).unwrap();
+        let get_amount_out =
+            |amount_in| -> Result<u64, MeteoraDbcError> { Ok(get_swap_result(&pool, &config, amount_in, TradeDirection::QuoteToBase, 1350)?.output_amount) };
+
+        let amount_in = get_amount_in_for_exact_out(823_371_182_968_778, get_amount_out).unwrap();
+        assert!(get_amount_out(amount_in).unwrap() >= 823_371_182_968_778);
+        assert!(get_amount_out(amount_in - 1).unwrap() < 823_371_182_968_778);
+        assert!(amount_in <= 100_000_000_000);
+    }
+
+    #[test]
+    fn fee_scheduler_reduces_the_cliff_fee_per_period() {
+        let linear = linear_scheduler_config().pool_fees.base_fee;
+        let fee = |current_point| get_base_fee_numerator(&linear, current_point, ACTIVATION_POINT, TradeDirection::QuoteToBase, 1).unwrap();
+        assert_eq!(fee(1_000), 10_000_000);
+        assert_eq!(fee(1_350), 8_500_000);
+        assert_eq!(fee(5_000), 5_000_000);
+        // Before activation the fee already sits at its last period
+        assert_eq!(fee(900), 5_000_000);
+
+        // 10% off per period, compounding
+        let exponential = MeteoraDBC::decode_pool_config(&config_bytes(1, 10, 100, 1_000)).unwrap().pool_fees.base_fee;
+        let fee = |current_point| get_base_fee_numerator(&exponential, current_point, ACTIVATION_POINT, TradeDirection::QuoteToBase, 1).unwrap();
+        assert_eq!(fee(1_000), 10_000_000);
+        assert_eq!(fee(1_350), 7_290_000);
+        assert_eq!(fee(900), 3_486_784);
+    }
+
+    #[test]
+    fn dynamic_fee_reads_the_stored_accumulator() {
+        let mut config = linear_scheduler_config();
+        config.pool_fees.dynamic_fee.initialized = 1;
+        config.pool_fees.dynamic_fee.bin_step = 1;
+        config.pool_fees.dynamic_fee.variable_fee_control = 4_000_000;
+        config.pool_fees.dynamic_fee.decay_period = 120;
+        let mut pool = MeteoraDBC::decode_virtual_pool(&pool_bytes(SQRT_START_PRICE)).unwrap();
+        pool.volatility_tracker.volatility_accumulator = 50_000;
+        // Last updated long past the decay period, which only resets the reference before a swap
+        pool.volatility_tracker.last_update_timestamp = 1;
+
+        // (50_000 * 1)^2 * 4_000_000 / 1e11 on top of the 0.85% base fee of period 3
+        let fee = get_total_fee_numerator(&config, &pool, 1_350, TradeDirection::QuoteToBase, 1).unwrap();
+        assert_eq!(fee, 8_500_000 + 100_000);
+    }
+
+    #[test]
+    fn rate_limiter_charges_more_on_larger_buys() {
+        // 1% more for every SOL past the first, for the first 100 slots
+        let rate_limiter = MeteoraDBC::decode_pool_config(&config_bytes(2, 100, 100, 1_000_000_000))
+            .unwrap()
+            .pool_fees
+            .base_fee;
+        let fee =
+            |current_point, trade_direction, amount| get_base_fee_numerator(&rate_limiter, current_point, ACTIVATION_POINT, trade_direction, amount).unwrap();
+
+        assert_eq!(fee(1_050, TradeDirection::QuoteToBase, 1_000_000_000), 10_000_000);
+        assert_eq!(fee(1_050, TradeDirection::QuoteToBase, 3_500_000_000), 22_857_142);
+        assert_eq!(fee(1_050, TradeDirection::QuoteToBase, 500_000_000_000), 892_980_000);
+        // Sells and trades outside the limiter window pay the cliff fee
+        assert_eq!(fee(1_050, TradeDirection::BaseToQuote, 3_500_000_000), 10_000_000);
+        assert_eq!(fee(1_101, TradeDirection::QuoteToBase, 3_500_000_000), 10_000_000);
+    }
+}
@@ -11,7 +11,35 @@ pub const VIRTUAL_POOL_SEED: &[u8] = b"pool";
 pub const VIRTUAL_POOL_BASE_VAULT: &[u8] = b"base_vault";
 pub const VIRTUAL_POOL_QUOTE_VAULT: &[u8] = b"quote_vault";
 
-#[derive(Clone, Debug, Deserialize)]
+pub const VIRTUAL_POOL_SIZE: u64 = 424;
+pub const VIRTUAL_POOL_BASE_MINT_OFFSET: usize = 136;
+
+pub const MAX_CURVE_POINT: usize = 20;
+/// Sqrt prices and liquidity are Q64.64 fixed point
+pub const RESOLUTION: usize = 64;
+pub const FEE_DENOMINATOR: u64 = 1_000_000_000;
+pub const MAX_FEE_NUMERATOR: u64 = 990_000_000;
+pub const BASIS_POINT_MAX: u64 = 10_000;
+/// Scale of the dynamic fee, `(volatility_accumulator * bin_step)^2 * variable_fee_control`
+pub const DYNAMIC_FEE_SCALE: u128 = 100_000_000_000;
+
+#[derive(Debug, thiserror::Error)]
+pub enum MeteoraDbcError {
+    #[error("Bonding curve of {mint} is complete, the pool is migrating")]
+    CurveComplete { mint: Pubkey },
+    #[error("Bonding curve of {mint} migrated to DAMM v2")]
+    Migrated { mint: Pubkey },
+    #[error("Bonding curve of {mint} migrated with unsupported option {migration_option}")]
+    UnsupportedMigration { mint: Pubkey, migration_option: u8 },
+    #[error("Not enough liquidity on the bonding curve")]
+    NotEnoughLiquidity,
+    #[error("Unsupported base fee mode: {0}")]
+    UnsupportedBaseFeeMode(u8),
+    #[error("Math overflow")]
+    MathOverflow,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize)]
 pub struct VolatilityTracker {
     pub last_update_timestamp: u64,
     pub padding: [u8; 8],
@@ -87,6 +115,187 @@ pub struct VirtualPool {
     pub _padding_1: [u64; 7],
 }
 
+/// Fee scheduler when `base_fee_mode` is linear or exponential, rate limiter otherwise
+#[derive(Clone, Copy, Debug, Deserialize)]
+pub struct BaseFeeConfig {
+    pub cliff_fee_numerator: u64,
+    /// Period frequency, or max limiter duration for the rate limiter
+    pub second_factor: u64,
+    /// Reduction factor, or reference amount for the rate limiter
+    pub third_factor: u64,
+    /// Number of periods, or fee increment in basis points for the rate limiter
+    pub first_factor: u16,
+    pub base_fee_mode: u8,
+    pub padding_0: [u8; 5],
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum BaseFeeMode {
+    FeeSchedulerLinear,
+    FeeSchedulerExponential,
+    RateLimiter,
+}
+
+impl TryFrom<u8> for BaseFeeMode {
+    type Error = MeteoraDbcError;
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        match value {
+            0 => Ok(BaseFeeMode::FeeSchedulerLinear),
+            1 => Ok(BaseFeeMode::FeeSchedulerExponential),
+            2 => Ok(BaseFeeMode::RateLimiter),
+            _ => Err(MeteoraDbcError::UnsupportedBaseFeeMode(value)),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Deserialize)]
+pub struct DynamicFeeConfig {
+    pub initialized: u8,
+    pub padding: [u8; 7],
+    pub max_volatility_accumulator: u32,
+    pub variable_fee_control: u32,
+    pub bin_step: u16,
+    pub filter_period: u16,
+    pub decay_period: u16,
+    pub reduction_factor: u16,
+    pub padding2: [u8; 8],
+    pub bin_step_u128: u128,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize)]
+pub struct PoolFeesConfig {
+    pub base_fee: BaseFeeConfig,
+    pub dynamic_fee: DynamicFeeConfig,
+    pub padding_0: [u64; 5],
+    pub padding_1: [u8; 6],
+    pub protocol_fee_percent: u8,
+    pub referral_fee_percent: u8,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize)]
+pub struct LockedVestingConfig {
+    pub amount_per_period: u64,
+    pub cliff_duration_from_migration_time: u64,
+    pub frequency: u64,
+    pub number_of_period: u64,
+    pub cliff_unlock_amount: u64,
+    pub _padding: u64,
+}
+
+/// Curve segment ending at `sqrt_price`, starting at the previous point or `sqrt_start_price`
+#[derive(Clone, Copy, Debug, Deserialize)]
+pub struct LiquidityDistributionConfig {
+    pub sqrt_price: u128,
+    pub liquidity: u128,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CollectFeeMode {
+    /// Fees are always taken in the quote token
+    QuoteToken,
+    /// Fees are taken from whichever token the swap outputs
+    OutputToken,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ActivationType {
+    Slot,
+    Timestamp,
+}
+
+#[derive(Clone, Debug, Deserialize)]
+pub struct PoolConfig {
+    pub discriminator: [u8; 8],
+    pub quote_mint: Pubkey,
+    pub fee_claimer: Pubkey,
+    pub leftover_receiver: Pubkey,
+    pub pool_fees: PoolFeesConfig,
+    pub collect_fee_mode: u8,
+    pub migration_option: u8,
+    pub activation_type: u8,
+    pub token_decimal: u8,
+    pub version: u8,
+    pub token_type: u8,
+    pub quote_token_flag: u8,
+    pub partner_locked_lp_percentage: u8,
+    pub partner_lp_percentage: u8,
+    pub creator_locked_lp_percentage: u8,
+    pub creator_lp_percentage: u8,
+    pub migration_fee_option: u8,
+    pub fixed_token_supply_flag: u8,
+    pub creator_trading_fee_percentage: u8,
+    pub token_update_authority: u8,
+    pub migration_fee_percentage: u8,
+    pub creator_migration_fee_percentage: u8,
+    pub _padding_0: [u8; 7],
+    pub swap_base_amount: u64,
+    /// Quote reserve at which the curve completes and the pool migrates
+    pub migration_quote_threshold: u64,
+    pub migration_base_threshold: u64,
+    pub migration_sqrt_price: u128,
+    pub locked_vesting_config: LockedVestingConfig,
+    pub pre_migration_token_supply: u64,
+    pub post_migration_token_supply: u64,
+    pub _padding_1: [u8; 16],
+    pub _padding_2: u128,
+    pub sqrt_start_price: u128,
+    pub curve: [LiquidityDistributionConfig; MAX_CURVE_POINT],
+}
+
+impl PoolConfig {
+    pub fn get_collect_fee_mode(&self) -> CollectFeeMode {
+        match self.collect_fee_mode {
+            0 => CollectFeeMode::QuoteToken,
+            _ => CollectFeeMode::OutputToken,
+        }
+    }
+
+    pub fn get_activation_type(&self) -> ActivationType {
+        match self.activation_type {
+            0 => ActivationType::Slot,
+            _ => ActivationType::Timestamp,
+        }
+    }
+
+    pub fn get_quote_token_program(&self) -> Pubkey {
+        match self.quote_token_flag {
+            0 => spl_token::ID,
+            _ => spl_token_2022::ID,
+        }
+    }
+}
+
+/// Accounts a swap needs from a virtual pool, resolved once per mint
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PoolKeys {
+    pub pool: Pubkey,
+    pub config: Pubkey,
+    pub base_vault: Pubkey,
+    pub quote_vault: Pubkey,
+    pub quote_mint: Pubkey,
+    pub quote_token_program: Pubkey,
+}
+
+impl PoolKeys {
+    pub fn new(pool_address: Pubkey, pool: &VirtualPool, config: &PoolConfig) -> Self {
+        Self {
+            pool: pool_address,
+            config: pool.config,
+            base_vault: pool.base_vault,
+            quote_vault: pool.quote_vault,
+            quote_mint: config.quote_mint,
+            quote_token_program: config.get_quote_token_program(),
+        }
+    }
+}
+
+impl VirtualPool {
+    pub fn is_curve_complete(&self, config: &PoolConfig) -> bool {
+        self.is_migrated != 0 || self.quote_reserve >= config.migration_quote_threshold
+    }
+}
+
 #[derive(Clone, Debug, BorshSerialize, BorshDeserialize)]
 pub struct SwapInstruction {
     pub discriminator: u64,
@@ -3,15 +3,27 @@ pub mod believe;
 pub mod boopfun;
 pub mod boopfun_types;
 pub mod dex_traits;
+pub mod meteora_damm_v2;
+pub mod meteora_damm_v2_math;
+pub mod meteora_damm_v2_types;
 pub mod meteora_dbc;
+pub mod meteora_dbc_math;
 pub mod meteora_dbc_types;
 pub mod moonit;
+pub mod moonit_math;
 pub mod moonit_types;
 pub mod pumpfun;
 pub mod pumpfun_common_types;
+pub mod pumpfun_incentives;
 pub mod pumpfun_types;
 pub mod pumpswap;
+pub mod pumpswap_math;
 pub mod pumpswap_types;
+pub mod raydium_amm;
+pub mod raydium_amm_types;
 pub mod raydium_bonk;
 pub mod raydium_bonk_types;
+pub mod raydium_cpmm;
+pub mod raydium_cpmm_types;
+pub mod router;
 pub mod types;
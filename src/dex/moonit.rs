@@ -1,22 +1,33 @@
-use super::{dex_traits::DexTrait, moonit_types::*, types::Create};
+use super::{
+    amm_calc::{calculate_with_slippage_buy, calculate_with_slippage_sell, FeeModel},
+    dex_traits::DexTrait,
+    moonit_math::{get_curve_collateral, get_trade_amounts, CurveState},
+    moonit_types::*,
+    types::{BuySwapInfo, BuyVariant, Create, CreateATA, Quote, SlippageBuySwapInfo, SlippageSwapInfo},
+};
 use crate::{
-    common::trading_endpoint::TradingEndpoint,
+    common::{
+        mint::MintInfo,
+        trading_endpoint::{TradingEndpoint, TransactionType, TxSettings},
+    },
     dex::types::{PoolInfo, SwapInfo},
     errors::trading_endpoint_error::TradingEndpointError,
-    instruction::builder::PriorityFee,
+    instruction::builder::{build_sol_sell_instructions, build_token_account_instructions, PriorityFee},
 };
 use borsh::BorshDeserialize;
+use once_cell::sync::OnceCell;
 use solana_sdk::{
     instruction::{AccountMeta, Instruction},
     pubkey::Pubkey,
     signature::{Keypair, Signature},
     signer::Signer,
 };
-use spl_associated_token_account::get_associated_token_address;
+use spl_associated_token_account::get_associated_token_address_with_program_id;
 use std::sync::Arc;
 
 pub struct Moonit {
     pub endpoint: Arc<TradingEndpoint>,
+    pub config: OnceCell<Arc<ConfigAccount>>,
 }
 
 #[async_trait::async_trait]
@@ -38,22 +49,65 @@ impl DexTrait for Moonit {
     }
 
     async fn get_pool(&self, mint: &Pubkey) -> Result<PoolInfo, TradingEndpointError> {
-        let bonding_curve_pda = Self::get_bonding_curve_pda(mint)?;
-        let account = self.endpoint.rpc.get_account(&bonding_curve_pda).await?;
-        if account.data.is_empty() {
-            return Err(TradingEndpointError::CustomError(format!("Bonding curve not found: {}", mint.to_string())));
-        }
+        Ok(self.get_curve_state(mint).await?.0)
+    }
 
-        let bonding_curve = CurveAccount::deserialize(&mut account.data.as_slice()).map_err(|e| TradingEndpointError::CustomError(e.to_string()))?;
+    async fn quote_buy(&self, mint: &Pubkey, sol_amount: u64, slippage_basis_points: u64) -> Result<Quote, TradingEndpointError> {
+        let order = TradeOrder {
+            side: TradeSide::Buy,
+            fixed_side: FixedSide::ExactIn,
+            amount: sol_amount,
+            slippage_basis_points,
+        };
+        self.quote_trade(mint, order).await
+    }
 
-        Ok(PoolInfo {
-            pool: bonding_curve_pda,
-            creator: None,
-            creator_vault: None,
-            config: None,
-            token_reserves: bonding_curve.curve_amount,
-            sol_reserves: INITIAL_VIRTUAL_SOL_RESERVES + account.lamports,
-        })
+    async fn quote_buy_exact_tokens(&self, mint: &Pubkey, token_amount: u64, slippage_basis_points: u64) -> Result<Quote, TradingEndpointError> {
+        let order = TradeOrder {
+            side: TradeSide::Buy,
+            fixed_side: FixedSide::ExactOut,
+            amount: token_amount,
+            slippage_basis_points,
+        };
+        self.quote_trade(mint, order).await
+    }
+
+    async fn quote_sell(&self, mint: &Pubkey, token_amount: u64, slippage_basis_points: u64) -> Result<Quote, TradingEndpointError> {
+        let order = TradeOrder {
+            side: TradeSide::Sell,
+            fixed_side: FixedSide::ExactIn,
+            amount: token_amount,
+            slippage_basis_points,
+        };
+        self.quote_trade(mint, order).await
+    }
+
+    async fn quote_buys(&self, mint: &Pubkey, sol_amounts: &[u64], slippage_basis_points: u64) -> Result<Vec<Quote>, TradingEndpointError> {
+        self.quote_trades(mint, TradeSide::Buy, sol_amounts, slippage_basis_points).await
+    }
+
+    async fn quote_sells(&self, mint: &Pubkey, token_amounts: &[u64], slippage_basis_points: u64) -> Result<Vec<Quote>, TradingEndpointError> {
+        self.quote_trades(mint, TradeSide::Sell, token_amounts, slippage_basis_points).await
+    }
+
+    /// Buys spend exactly `sol_amount`, the program checking the slippage itself
+    async fn build_custom_buy_instructions(
+        &self,
+        payer: &Keypair,
+        mint: &Pubkey,
+        sol_amount: u64,
+        slippage_basis_points: u64,
+    ) -> Result<Option<(Vec<Instruction>, Instruction)>, TradingEndpointError> {
+        let order = TradeOrder {
+            side: TradeSide::Buy,
+            fixed_side: FixedSide::ExactIn,
+            amount: sol_amount,
+            slippage_basis_points,
+        };
+        let (instruction, mint_info) = self.build_order_instruction(payer, mint, order).await?;
+        let (_, instructions) = build_token_account_instructions(payer, mint, &mint_info.token_program, CreateATA::Idempotent)
+            .map_err(|e| TradingEndpointError::CustomError(e.to_string()))?;
+        Ok(Some((instructions, instruction)))
     }
 
     async fn create(&self, _: Keypair, _: Create, _: Option<PriorityFee>, _: Option<u64>) -> Result<Vec<Signature>, TradingEndpointError> {
@@ -64,40 +118,49 @@ impl DexTrait for Moonit {
         &self,
         payer: &Keypair,
         mint: &Pubkey,
-        _: Option<&Pubkey>,
-        token_program_account: &Pubkey,
+        custom_ata: Option<&Pubkey>,
+        extra_accounts: &[AccountMeta],
+        token_program: &Pubkey,
         buy: SwapInfo,
     ) -> Result<Instruction, TradingEndpointError> {
-        self.initialized()?;
+        self.build_buy_instruction_with_variant(
+            payer,
+            mint,
+            custom_ata,
+            extra_accounts,
+            token_program,
+            buy.with_variant(BuyVariant::ExactTokensOut),
+        )
+    }
 
-        let trade_info: TradeParams = TradeParams {
-            discriminator: 16927863322537952870,
-            token_amount: buy.token_amount,
-            collateral_amount: buy.sol_amount,
-            fixed_side: FixedSide::ExactIn,
-            slippage_bps: 0,
+    fn build_buy_instruction_with_variant(
+        &self,
+        payer: &Keypair,
+        mint: &Pubkey,
+        custom_ata: Option<&Pubkey>,
+        _: &[AccountMeta],
+        token_program: &Pubkey,
+        buy: BuySwapInfo,
+    ) -> Result<Instruction, TradingEndpointError> {
+        let BuySwapInfo { swap: buy, variant } = buy;
+        // Amounts are bounds already, so the program is given no slippage on top of them
+        let fixed_side = match variant {
+            BuyVariant::ExactTokensOut => FixedSide::ExactOut,
+            BuyVariant::ExactSolIn => FixedSide::ExactIn,
         };
-
-        let buffer = trade_info.to_buffer().map_err(|e| TradingEndpointError::CustomError(e.to_string()))?;
-        let bonding_curve = Self::get_bonding_curve_pda(mint)?;
-
-        Ok(Instruction::new_with_bytes(
-            PUBKEY_MOONIT,
-            &buffer,
-            vec![
-                AccountMeta::new(payer.pubkey(), true),
-                AccountMeta::new(get_associated_token_address(&payer.pubkey(), mint), false),
-                AccountMeta::new(bonding_curve, false),
-                AccountMeta::new(get_associated_token_address(&bonding_curve, mint), false),
-                AccountMeta::new(PUBKEY_MOONIT_DEX_FEE, false),
-                AccountMeta::new(PUBKEY_MOONIT_HELIO_FEE, false),
-                AccountMeta::new_readonly(*mint, false),
-                AccountMeta::new_readonly(PUBKEY_MOONIT_CONFIG, false),
-                AccountMeta::new_readonly(*token_program_account, false),
-                AccountMeta::new_readonly(spl_associated_token_account::ID, false),
-                AccountMeta::new_readonly(solana_program::system_program::ID, false),
-            ],
-        ))
+        self.build_trade_instruction(
+            payer,
+            mint,
+            custom_ata,
+            token_program,
+            TradeParams {
+                discriminator: MOONIT_BUY_DISCRIMINATOR,
+                token_amount: buy.token_amount,
+                collateral_amount: buy.sol_amount,
+                fixed_side,
+                slippage_bps: 0,
+            },
+        )
     }
 
     fn build_sell_instruction(
@@ -105,24 +168,305 @@ impl DexTrait for Moonit {
         payer: &Keypair,
         mint: &Pubkey,
         custom_ata: Option<&Pubkey>,
-        _: Option<&Pubkey>,
+        _: &[AccountMeta],
+        token_program: &Pubkey,
         sell: SwapInfo,
     ) -> Result<Instruction, TradingEndpointError> {
-        self.initialized()?;
+        self.build_trade_instruction(
+            payer,
+            mint,
+            custom_ata,
+            token_program,
+            TradeParams {
+                discriminator: MOONIT_SELL_DISCRIMINATOR,
+                token_amount: sell.token_amount,
+                collateral_amount: sell.sol_amount,
+                fixed_side: FixedSide::ExactIn,
+                slippage_bps: 0,
+            },
+        )
+    }
 
-        let trade_info: TradeParams = TradeParams {
-            discriminator: 12502976635542562355,
-            token_amount: sell.token_amount,
-            collateral_amount: sell.sol_amount,
-            fixed_side: FixedSide::ExactIn,
-            slippage_bps: 0,
+    fn build_buy_instruction_with_slippage(
+        &self,
+        payer: &Keypair,
+        mint: &Pubkey,
+        custom_ata: Option<&Pubkey>,
+        _: &[AccountMeta],
+        token_program: &Pubkey,
+        buy: SlippageBuySwapInfo,
+    ) -> Result<Instruction, TradingEndpointError> {
+        let SlippageBuySwapInfo { swap, variant } = buy;
+        let fixed_side = match variant {
+            BuyVariant::ExactTokensOut => FixedSide::ExactOut,
+            BuyVariant::ExactSolIn => FixedSide::ExactIn,
+        };
+        let params = self.get_trade_params(TradeSide::Buy, fixed_side, swap)?;
+        self.build_trade_instruction(payer, mint, custom_ata, token_program, params)
+    }
+
+    fn build_sell_instruction_with_slippage(
+        &self,
+        payer: &Keypair,
+        mint: &Pubkey,
+        custom_ata: Option<&Pubkey>,
+        _: &[AccountMeta],
+        token_program: &Pubkey,
+        sell: SlippageSwapInfo,
+    ) -> Result<Instruction, TradingEndpointError> {
+        let params = self.get_trade_params(TradeSide::Sell, FixedSide::ExactIn, sell)?;
+        self.build_trade_instruction(payer, mint, custom_ata, token_program, params)
+    }
+}
+
+impl Moonit {
+    pub fn new(endpoint: Arc<TradingEndpoint>) -> Self {
+        Self {
+            endpoint,
+            config: OnceCell::new(),
+        }
+    }
+
+    pub fn get_bonding_curve_pda(mint: &Pubkey) -> Result<Pubkey, TradingEndpointError> {
+        let seeds: &[&[u8]; 2] = &[BONDING_CURVE_SEED, mint.as_ref()];
+        let pda = Pubkey::try_find_program_address(seeds, &PUBKEY_MOONIT)
+            .ok_or_else(|| TradingEndpointError::CustomError("Failed to find bonding curve PDA".to_string()))?;
+        Ok(pda.0)
+    }
+
+    /// Launchpad config, fetched on first use
+    pub async fn get_config(&self) -> Result<Arc<ConfigAccount>, TradingEndpointError> {
+        if let Some(config) = self.config.get() {
+            return Ok(config.clone());
+        }
+
+        let account = self.endpoint.rpc.get_account(&PUBKEY_MOONIT_CONFIG).await?;
+        let config = Self::decode_config(&account.data)?;
+        Ok(self.config.get_or_init(|| Arc::new(config)).clone())
+    }
+
+    pub fn decode_config(data: &[u8]) -> Result<ConfigAccount, TradingEndpointError> {
+        ConfigAccount::deserialize(&mut &data[..]).map_err(|e| TradingEndpointError::CustomError(e.to_string()))
+    }
+
+    pub async fn get_curve(&self, mint: &Pubkey) -> Result<CurveAccount, TradingEndpointError> {
+        Ok(self.get_curve_with_slot(mint).await?.0)
+    }
+
+    /// Curve with the slot it was read at
+    pub async fn get_curve_with_slot(&self, mint: &Pubkey) -> Result<(CurveAccount, u64), TradingEndpointError> {
+        let (account, slot) = self.endpoint.get_account_with_slot(&Self::get_bonding_curve_pda(mint)?).await?;
+        if account.data.is_empty() {
+            return Err(TradingEndpointError::CustomError(format!("Bonding curve not found: {mint}")));
+        }
+
+        Ok((Self::decode_curve(&account.data)?, slot))
+    }
+
+    pub fn decode_curve(data: &[u8]) -> Result<CurveAccount, TradingEndpointError> {
+        CurveAccount::deserialize(&mut &data[..]).map_err(|e| TradingEndpointError::CustomError(e.to_string()))
+    }
+
+    /// Trade fee, charged on top of the SOL paid for buys and out of the SOL received for sells
+    pub fn get_fee_model(config: &ConfigAccount) -> FeeModel {
+        FeeModel {
+            platform_fee_rate: FeeModel::rate_from_basis_points(config.fee_bps as u64),
+            charged_on_top: true,
+            ..Default::default()
+        }
+    }
+
+    /// Where the token migrates to once its curve reached the market cap threshold, `None` while it still trades
+    pub async fn get_migration_target(&self, mint: &Pubkey) -> Result<Option<MigrationTarget>, TradingEndpointError> {
+        let (curve, config) = tokio::try_join!(self.get_curve(mint), self.get_config())?;
+        let state = CurveState::new(&curve, config.coef_a);
+        Ok((state.get_market_cap() >= curve.marketcap_threshold).then_some(curve.migration_target))
+    }
+
+    /// Pool info, curve position and fees of a curve that is still trading
+    pub async fn get_curve_state(&self, mint: &Pubkey) -> Result<(PoolInfo, CurveState, FeeModel), TradingEndpointError> {
+        let ((curve, slot), config) = tokio::try_join!(self.get_curve_with_slot(mint), self.get_config())?;
+        let state = CurveState::new(&curve, config.coef_a);
+        if state.get_market_cap() >= curve.marketcap_threshold {
+            return Err(MoonitError::Migrated {
+                mint: *mint,
+                target: curve.migration_target,
+            }
+            .into());
+        }
+
+        let fees = Self::get_fee_model(&config);
+        let pool_info = PoolInfo {
+            pool: Self::get_bonding_curve_pda(mint)?,
+            creator: None,
+            creator_vault: None,
+            config: Some(PUBKEY_MOONIT_CONFIG),
+            token_reserves: curve.curve_amount,
+            sol_reserves: state.get_collateral_for_sell(state.tokens_sold).unwrap_or_default(),
+            fees,
+            slot,
+        };
+
+        Ok((pool_info, state, fees))
+    }
+
+    /// Quote a trade whose fixed side is `order.amount`
+    pub async fn quote_trade(&self, mint: &Pubkey, order: TradeOrder) -> Result<Quote, TradingEndpointError> {
+        let ((pool_info, state, fees), mint_info) = tokio::try_join!(self.get_curve_state(mint), self.endpoint.get_mint_info(mint))?;
+        Self::build_quote(pool_info, state, fees, mint_info, order)
+    }
+
+    /// Quote exact-in trades of each of `amounts` from one curve snapshot
+    pub async fn quote_trades(&self, mint: &Pubkey, side: TradeSide, amounts: &[u64], slippage_basis_points: u64) -> Result<Vec<Quote>, TradingEndpointError> {
+        let ((pool_info, state, fees), mint_info) = tokio::try_join!(self.get_curve_state(mint), self.endpoint.get_mint_info(mint))?;
+        amounts
+            .iter()
+            .map(|amount| {
+                let order = TradeOrder {
+                    side,
+                    fixed_side: FixedSide::ExactIn,
+                    amount: *amount,
+                    slippage_basis_points,
+                };
+                Self::build_quote(pool_info.clone(), state, fees, mint_info, order)
+            })
+            .collect()
+    }
+
+    pub fn build_quote(pool_info: PoolInfo, state: CurveState, fees: FeeModel, mint_info: MintInfo, order: TradeOrder) -> Result<Quote, TradingEndpointError> {
+        let TradeOrder {
+            side,
+            fixed_side,
+            amount,
+            slippage_basis_points,
+        } = order;
+        let amounts = get_trade_amounts(&state, &fees, side, fixed_side, amount)?;
+        let (amount_in, expected_out) = match side {
+            TradeSide::Buy => (amounts.collateral_amount, amounts.token_amount),
+            TradeSide::Sell => (amounts.token_amount, amounts.collateral_amount),
+        };
+        let (max_amount_in, min_out) = match fixed_side {
+            FixedSide::ExactIn => (amount_in, calculate_with_slippage_sell(expected_out, slippage_basis_points)),
+            FixedSide::ExactOut => (calculate_with_slippage_buy(amount_in, slippage_basis_points), expected_out),
+        };
+        let price_before = state.get_price();
+        let price_after = state.after_trade(side, amounts.token_amount).get_price();
+
+        Ok(Quote {
+            amount_in,
+            max_amount_in,
+            expected_out,
+            min_out,
+            fees: fees.get_fee_amounts(amounts.curve_collateral),
+            transfer_fee: 0,
+            price_before,
+            price_after,
+            price_impact_bps: super::amm_calc::calculate_price_impact_bps(price_before, price_after),
+            slot: pool_info.slot,
+            pool_info,
+            mint_info,
+        })
+    }
+
+    /// Trade with the program checking `order.slippage_basis_points` itself, `order.amount` being the fixed side
+    pub async fn trade(
+        &self,
+        payer: &Keypair,
+        mint: &Pubkey,
+        order: TradeOrder,
+        fee: Option<PriorityFee>,
+        tip: Option<u64>,
+    ) -> Result<Vec<Signature>, TradingEndpointError> {
+        let ((instruction, mint_info), blockhash) = tokio::try_join!(self.build_order_instruction(payer, mint, order), self.endpoint.get_latest_blockhash())?;
+
+        let (transaction_type, instructions) = match order.side {
+            TradeSide::Buy => {
+                let (_, mut instructions) = build_token_account_instructions(payer, mint, &mint_info.token_program, CreateATA::Idempotent)
+                    .map_err(|e| TradingEndpointError::CustomError(e.to_string()))?;
+                instructions.push(instruction);
+                (TransactionType::Buy, instructions)
+            }
+            TradeSide::Sell => {
+                let instructions = build_sol_sell_instructions(payer, mint, &mint_info.token_program, instruction, false)
+                    .map_err(|e| TradingEndpointError::CustomError(e.to_string()))?;
+                (TransactionType::Sell, instructions)
+            }
         };
 
-        let buffer = trade_info.to_buffer().map_err(|e| TradingEndpointError::CustomError(e.to_string()))?;
+        self.endpoint
+            .build_and_broadcast_tx(
+                transaction_type,
+                payer,
+                instructions,
+                TxSettings::new(blockhash, fee, tip.unwrap_or_default()),
+                None,
+            )
+            .await
+    }
+
+    /// Trade instruction of `order` priced from the current curve, with the mint info it was built for
+    pub async fn build_order_instruction(&self, payer: &Keypair, mint: &Pubkey, order: TradeOrder) -> Result<(Instruction, MintInfo), TradingEndpointError> {
+        let TradeOrder {
+            side,
+            fixed_side,
+            amount,
+            slippage_basis_points,
+        } = order;
+        let ((_, state, fees), mint_info) = tokio::try_join!(self.get_curve_state(mint), self.endpoint.get_mint_info(mint))?;
+        let amounts = get_trade_amounts(&state, &fees, side, fixed_side, amount)?;
+        let params = TradeParams {
+            discriminator: match side {
+                TradeSide::Buy => MOONIT_BUY_DISCRIMINATOR,
+                TradeSide::Sell => MOONIT_SELL_DISCRIMINATOR,
+            },
+            token_amount: amounts.token_amount,
+            collateral_amount: amounts.curve_collateral,
+            fixed_side,
+            slippage_bps: slippage_basis_points,
+        };
+        let instruction = self.build_trade_instruction(payer, mint, None, &mint_info.token_program, params)?;
+        Ok((instruction, mint_info))
+    }
+
+    /// Trade params of a quoted trade, the program checking the slippage against the collateral moving through the curve.
+    /// The collateral is derived from the quoted amount after fees, so the config must have been loaded by quoting first.
+    pub fn get_trade_params(&self, side: TradeSide, fixed_side: FixedSide, swap: SlippageSwapInfo) -> Result<TradeParams, TradingEndpointError> {
+        let config = self
+            .config
+            .get()
+            .ok_or_else(|| TradingEndpointError::CustomError("Moonit config not loaded".to_string()))?;
+        let SlippageSwapInfo {
+            expected,
+            slippage_basis_points,
+        } = swap;
+
+        Ok(TradeParams {
+            discriminator: match side {
+                TradeSide::Buy => MOONIT_BUY_DISCRIMINATOR,
+                TradeSide::Sell => MOONIT_SELL_DISCRIMINATOR,
+            },
+            token_amount: expected.token_amount,
+            collateral_amount: get_curve_collateral(&Self::get_fee_model(config), side, expected.sol_amount),
+            fixed_side,
+            slippage_bps: slippage_basis_points,
+        })
+    }
+
+    pub fn build_trade_instruction(
+        &self,
+        payer: &Keypair,
+        mint: &Pubkey,
+        custom_ata: Option<&Pubkey>,
+        token_program: &Pubkey,
+        params: TradeParams,
+    ) -> Result<Instruction, TradingEndpointError> {
+        self.initialized()?;
+
+        let buffer = params.to_buffer().map_err(|e| TradingEndpointError::CustomError(e.to_string()))?;
         let bonding_curve = Self::get_bonding_curve_pda(mint)?;
 
         let ata = match custom_ata {
-            None => get_associated_token_address(&payer.pubkey(), mint),
+            None => get_associated_token_address_with_program_id(&payer.pubkey(), mint, token_program),
             Some(t) => *t,
         };
 
@@ -133,12 +477,12 @@ impl DexTrait for Moonit {
                 AccountMeta::new(payer.pubkey(), true),
                 AccountMeta::new(ata, false),
                 AccountMeta::new(bonding_curve, false),
-                AccountMeta::new(get_associated_token_address(&bonding_curve, mint), false),
+                AccountMeta::new(get_associated_token_address_with_program_id(&bonding_curve, mint, token_program), false),
                 AccountMeta::new(PUBKEY_MOONIT_DEX_FEE, false),
                 AccountMeta::new(PUBKEY_MOONIT_HELIO_FEE, false),
                 AccountMeta::new_readonly(*mint, false),
                 AccountMeta::new_readonly(PUBKEY_MOONIT_CONFIG, false),
-                AccountMeta::new_readonly(spl_token::ID, false),
+                AccountMeta::new_readonly(*token_program, false),
                 AccountMeta::new_readonly(spl_associated_token_account::ID, false),
                 AccountMeta::new_readonly(solana_program::system_program::ID, false),
             ],
@@ -146,15 +490,134 @@ impl DexTrait for Moonit {
     }
 }
 
-impl Moonit {
-    pub fn new(endpoint: Arc<TradingEndpoint>) -> Self {
-        Self { endpoint }
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_client::nonblocking::rpc_client::RpcClient;
+
+    const TOKEN: u64 = 1_000_000_000;
+
+    fn moonit() -> Moonit {
+        Moonit::new(Arc::new(TradingEndpoint::new(
+            Arc::new(RpcClient::new("http://127.0.0.1:8899".to_string())),
+            vec![],
+        )))
     }
 
-    pub fn get_bonding_curve_pda(mint: &Pubkey) -> Result<Pubkey, TradingEndpointError> {
-        let seeds: &[&[u8]; 2] = &[BONDING_CURVE_SEED, mint.as_ref()];
-        let pda = Pubkey::try_find_program_address(seeds, &PUBKEY_MOONIT)
-            .ok_or_else(|| TradingEndpointError::CustomError("Failed to find bonding curve PDA".to_string()))?;
-        Ok(pda.0)
+    // Synthetic account data laid out field by field as the program stores it, not captured from mainnet
+    fn config_dump() -> Vec<u8> {
+        [
+            &7u64.to_le_bytes()[..],
+            &[1; 32],
+            &[2; 32],
+            &[3; 32],
+            PUBKEY_MOONIT_HELIO_FEE.as_ref(),
+            PUBKEY_MOONIT_DEX_FEE.as_ref(),
+            &100u16.to_le_bytes(),
+            &[90],
+            &1_000_000u64.to_le_bytes(),
+            &500_000_000_000u64.to_le_bytes(),
+            &[0, 6, 9],
+            &(100 * TOKEN).to_le_bytes(),
+            &(10_000_000_000 * TOKEN).to_le_bytes(),
+            &[254],
+            &1_000u32.to_le_bytes(),
+            &25u32.to_le_bytes(),
+        ]
+        .concat()
+    }
+
+    fn curve_dump(mint: &Pubkey) -> Vec<u8> {
+        [
+            &9u64.to_le_bytes()[..],
+            &(1_000_000_000 * TOKEN).to_le_bytes(),
+            &(800_000_000 * TOKEN).to_le_bytes(),
+            mint.as_ref(),
+            &[9, 0, 1],
+            &500_000_000_000u64.to_le_bytes(),
+            &[0],
+            &1_000_000u64.to_le_bytes(),
+            &0u32.to_le_bytes(),
+            &[253, 0],
+        ]
+        .concat()
+    }
+
+    fn decode_params(instruction: &Instruction) -> TradeParams {
+        TradeParams::deserialize(&mut &instruction.data[..]).unwrap()
+    }
+
+    #[test]
+    fn decodes_curve_and_config_dumps() {
+        let mint = Pubkey::new_unique();
+        let config = Moonit::decode_config(&config_dump()).unwrap();
+        assert_eq!(config.dex_fee, PUBKEY_MOONIT_DEX_FEE);
+        assert_eq!((config.fee_bps, config.dex_fee_share, config.bump), (100, 90, 254));
+        assert_eq!((config.coef_a, config.coef_b), (1_000, 25));
+
+        let curve = Moonit::decode_curve(&curve_dump(&mint)).unwrap();
+        assert_eq!(curve.mint, mint);
+        assert_eq!(curve.curve_type, CurveType::ConstantProductV1);
+        assert_eq!(curve.curve_amount, 800_000_000 * TOKEN);
+        assert_eq!(curve.marketcap_threshold, 500_000_000_000);
+        assert_eq!(curve.migration_target, MigrationTarget::Raydium);
+
+        let state = CurveState::new(&curve, config.coef_a);
+        assert_eq!(state.tokens_sold, 200_000_000 * TOKEN);
+        assert!(state.get_market_cap() < curve.marketcap_threshold);
+    }
+
+    #[test]
+    fn trades_pass_the_slippage_to_the_program() {
+        let moonit = moonit();
+        let payer = Keypair::new();
+        let mint = Pubkey::new_unique();
+        let slippage = SlippageSwapInfo {
+            expected: SwapInfo {
+                token_amount: 0,
+                sol_amount: 0,
+            },
+            slippage_basis_points: 250,
+        };
+        assert!(moonit
+            .build_sell_instruction_with_slippage(&payer, &mint, None, &[], &spl_token::ID, slippage)
+            .is_err());
+
+        let config = Moonit::decode_config(&config_dump()).unwrap();
+        let state = CurveState::new(&Moonit::decode_curve(&curve_dump(&mint)).unwrap(), config.coef_a);
+        let fees = Moonit::get_fee_model(&config);
+        moonit.config.set(Arc::new(config)).unwrap();
+
+        let sell = get_trade_amounts(&state, &fees, TradeSide::Sell, FixedSide::ExactIn, 1_000_000 * TOKEN).unwrap();
+        let swap = SlippageSwapInfo {
+            expected: SwapInfo {
+                token_amount: sell.token_amount,
+                sol_amount: sell.collateral_amount,
+            },
+            slippage_basis_points: 250,
+        };
+        let instruction = moonit
+            .build_sell_instruction_with_slippage(&payer, &mint, None, &[], &spl_token::ID, swap)
+            .unwrap();
+        let params = decode_params(&instruction);
+        assert_eq!(params.discriminator, MOONIT_SELL_DISCRIMINATOR);
+        assert_eq!((params.token_amount, params.collateral_amount), (sell.token_amount, sell.curve_collateral));
+        assert_eq!((params.fixed_side, params.slippage_bps), (FixedSide::ExactIn, 250));
+
+        let buy = get_trade_amounts(&state, &fees, TradeSide::Buy, FixedSide::ExactOut, 1_000_000 * TOKEN).unwrap();
+        let swap = SlippageSwapInfo {
+            expected: SwapInfo {
+                token_amount: buy.token_amount,
+                sol_amount: buy.collateral_amount,
+            },
+            slippage_basis_points: 250,
+        };
+        let instruction = moonit
+            .build_buy_instruction_with_slippage(&payer, &mint, None, &[], &spl_token::ID, swap.with_variant(BuyVariant::ExactTokensOut))
+            .unwrap();
+        let params = decode_params(&instruction);
+        assert_eq!(params.discriminator, MOONIT_BUY_DISCRIMINATOR);
+        assert_eq!((params.token_amount, params.collateral_amount), (buy.token_amount, buy.curve_collateral));
+        assert_eq!((params.fixed_side, params.slippage_bps), (FixedSide::ExactOut, 250));
     }
 }
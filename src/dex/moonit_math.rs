@@ -0,0 +1,302 @@
+use super::{amm_calc::FeeModel, moonit_types::*};
+use ruint::aliases::U256;
+
+/// Position of a curve along with everything its pricing depends on
+#[derive(Clone, Copy, Debug)]
+pub struct CurveState {
+    pub curve_type: CurveType,
+    pub tokens_sold: u64,
+    pub curve_amount: u64,
+    pub total_supply: u64,
+    pub decimals: u8,
+    pub coef_a: u32,
+    pub coef_b: u32,
+}
+
+impl CurveState {
+    pub fn new(curve: &CurveAccount, coef_a: u32) -> Self {
+        Self {
+            curve_type: curve.curve_type,
+            tokens_sold: curve.total_supply.saturating_sub(curve.curve_amount),
+            curve_amount: curve.curve_amount,
+            total_supply: curve.total_supply,
+            decimals: curve.decimals,
+            coef_a,
+            coef_b: curve.coef_b,
+        }
+    }
+
+    fn token_unit(&self) -> U256 {
+        U256::from(10u64).pow(U256::from(self.decimals))
+    }
+
+    /// Collateral the curve takes in between `start` and `end` tokens sold
+    fn get_collateral_between(&self, start: u64, end: u64, round_up: bool) -> Option<u64> {
+        let (numerator, denominator) = match self.curve_type {
+            // price(x) = coef_a * x / scale + coef_b, in lamports per whole token
+            CurveType::LinearV1 => {
+                let unit = self.token_unit();
+                let (start, end) = (U256::from(start), U256::from(end));
+                let scale = U256::from(LINEAR_V1_COEF_A_SCALE);
+                let quadratic = U256::from(self.coef_a) * (end * end - start * start);
+                let linear = U256::from(2u8) * scale * unit * U256::from(self.coef_b) * (end - start);
+                (quadratic + linear, U256::from(2u8) * scale * unit * unit)
+            }
+            // virtual collateral * virtual tokens is constant
+            CurveType::ConstantProductV1 => {
+                let virtual_tokens = U256::from(CONSTANT_PRODUCT_V1_VIRTUAL_TOKENS) * self.token_unit();
+                let tokens_before = virtual_tokens.checked_sub(U256::from(start))?;
+                let tokens_after = virtual_tokens.checked_sub(U256::from(end))?;
+                if tokens_after.is_zero() {
+                    return None;
+                }
+                let invariant = U256::from(CONSTANT_PRODUCT_V1_VIRTUAL_COLLATERAL) * virtual_tokens;
+                (invariant * (tokens_before - tokens_after), tokens_before * tokens_after)
+            }
+        };
+
+        let collateral = match round_up {
+            true => numerator.div_ceil(denominator),
+            false => numerator / denominator,
+        };
+        u64::try_from(collateral).ok()
+    }
+
+    /// Collateral, before fees, paid for `token_amount`
+    pub fn get_collateral_for_buy(&self, token_amount: u64) -> Option<u64> {
+        if token_amount > self.curve_amount {
+            return None;
+        }
+        self.get_collateral_between(self.tokens_sold, self.tokens_sold + token_amount, true)
+    }
+
+    /// Collateral, before fees, received for `token_amount`
+    pub fn get_collateral_for_sell(&self, token_amount: u64) -> Option<u64> {
+        let start = self.tokens_sold.checked_sub(token_amount)?;
+        self.get_collateral_between(start, self.tokens_sold, false)
+    }
+
+    /// Most tokens `collateral` buys
+    pub fn get_tokens_for_buy(&self, collateral: u64) -> Option<u64> {
+        let (mut low, mut high) = (0u64, self.curve_amount);
+        if self.get_collateral_for_buy(high)? <= collateral {
+            return Some(high);
+        }
+        while low + 1 < high {
+            let mid = low + (high - low) / 2;
+            match self.get_collateral_for_buy(mid)? <= collateral {
+                true => low = mid,
+                false => high = mid,
+            }
+        }
+        Some(low)
+    }
+
+    /// Fewest tokens selling for at least `collateral`
+    pub fn get_tokens_for_sell(&self, collateral: u64) -> Option<u64> {
+        let (mut low, mut high) = (0u64, self.tokens_sold);
+        if self.get_collateral_for_sell(high)? < collateral {
+            return None;
+        }
+        while low + 1 < high {
+            let mid = low + (high - low) / 2;
+            match self.get_collateral_for_sell(mid)? >= collateral {
+                true => high = mid,
+                false => low = mid,
+            }
+        }
+        Some(high)
+    }
+
+    /// Spot price in lamports per token unit
+    pub fn get_price(&self) -> f64 {
+        let unit = 10f64.powi(self.decimals as i32);
+        match self.curve_type {
+            CurveType::LinearV1 => {
+                let tokens_sold = self.tokens_sold as f64 / unit;
+                (self.coef_a as f64 * tokens_sold / LINEAR_V1_COEF_A_SCALE as f64 + self.coef_b as f64) / unit
+            }
+            CurveType::ConstantProductV1 => {
+                let virtual_tokens = CONSTANT_PRODUCT_V1_VIRTUAL_TOKENS as f64 * unit;
+                let tokens_left = virtual_tokens - self.tokens_sold as f64;
+                CONSTANT_PRODUCT_V1_VIRTUAL_COLLATERAL as f64 * virtual_tokens / (tokens_left * tokens_left)
+            }
+        }
+    }
+
+    /// Market cap in lamports at the spot price
+    pub fn get_market_cap(&self) -> u64 {
+        (self.get_price() * self.total_supply as f64) as u64
+    }
+
+    /// Curve once `token_amount` is bought or sold
+    pub fn after_trade(&self, side: TradeSide, token_amount: u64) -> Self {
+        let tokens_sold = match side {
+            TradeSide::Buy => self.tokens_sold + token_amount,
+            TradeSide::Sell => self.tokens_sold - token_amount,
+        };
+        Self {
+            tokens_sold,
+            curve_amount: self.total_supply - tokens_sold,
+            ..*self
+        }
+    }
+}
+
+/// Token and collateral amounts of a trade, collateral being what the trader pays or receives after fees
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TradeAmounts {
+    pub token_amount: u64,
+    /// Collateral moved along the curve, before fees
+    pub curve_collateral: u64,
+    pub collateral_amount: u64,
+}
+
+/// Resolves the side of a trade that is not fixed, `amount` being tokens or collateral depending on `side` and `fixed_side`
+pub fn get_trade_amounts(curve: &CurveState, fees: &FeeModel, side: TradeSide, fixed_side: FixedSide, amount: u64) -> Result<TradeAmounts, MoonitError> {
+    let amounts = match (side, fixed_side) {
+        (TradeSide::Buy, FixedSide::ExactIn) => {
+            let curve_collateral = get_curve_collateral(fees, side, amount);
+            let token_amount = curve.get_tokens_for_buy(curve_collateral).ok_or(MoonitError::NotEnoughLiquidity)?;
+            TradeAmounts {
+                token_amount,
+                curve_collateral,
+                collateral_amount: amount,
+            }
+        }
+        (TradeSide::Buy, FixedSide::ExactOut) => {
+            let curve_collateral = curve.get_collateral_for_buy(amount).ok_or(MoonitError::NotEnoughLiquidity)?;
+            TradeAmounts {
+                token_amount: amount,
+                curve_collateral,
+                collateral_amount: fees.buy_amount_with_fees(curve_collateral),
+            }
+        }
+        (TradeSide::Sell, FixedSide::ExactIn) => {
+            let curve_collateral = curve.get_collateral_for_sell(amount).ok_or(MoonitError::NotEnoughLiquidity)?;
+            TradeAmounts {
+                token_amount: amount,
+                curve_collateral,
+                collateral_amount: fees.sell_amount_after_fees(curve_collateral),
+            }
+        }
+        (TradeSide::Sell, FixedSide::ExactOut) => {
+            let curve_collateral = get_curve_collateral(fees, side, amount);
+            let token_amount = curve.get_tokens_for_sell(curve_collateral).ok_or(MoonitError::NotEnoughLiquidity)?;
+            TradeAmounts {
+                token_amount,
+                curve_collateral,
+                collateral_amount: amount,
+            }
+        }
+    };
+
+    Ok(amounts)
+}
+
+/// Collateral moving through the curve when the trader pays or receives `collateral_amount` after fees
+pub fn get_curve_collateral(fees: &FeeModel, side: TradeSide, collateral_amount: u64) -> u64 {
+    match side {
+        TradeSide::Buy => fees.buy_amount_after_fees(collateral_amount),
+        TradeSide::Sell => {
+            let mut curve_collateral = collateral_amount + fees.get_fee(collateral_amount);
+            while fees.sell_amount_after_fees(curve_collateral) < collateral_amount {
+                curve_collateral += 1;
+            }
+            curve_collateral
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TOKEN: u64 = 1_000_000_000;
+    const TOTAL_SUPPLY: u64 = 1_000_000_000 * TOKEN;
+
+    fn curve(curve_type: CurveType, tokens_sold: u64, coef_a: u32, coef_b: u32) -> CurveState {
+        CurveState {
+            curve_type,
+            tokens_sold,
+            curve_amount: TOTAL_SUPPLY - tokens_sold,
+            total_supply: TOTAL_SUPPLY,
+            decimals: 9,
+            coef_a,
+            coef_b,
+        }
+    }
+
+    /// 1% charged on top of buys, as the launchpad config sets it
+    fn fees() -> FeeModel {
+        FeeModel {
+            platform_fee_rate: FeeModel::rate_from_basis_points(100),
+            charged_on_top: true,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn constant_product_v1_prices_against_virtual_reserves() {
+        let fresh = curve(CurveType::ConstantProductV1, 0, 0, 0);
+        assert_eq!(fresh.get_collateral_for_buy(1_000_000 * TOKEN), Some(27_985_075));
+        assert_eq!(fresh.get_tokens_for_buy(990_099_009), Some(34_281_150_129_545_234));
+        assert_eq!(fresh.get_collateral_for_sell(1), None);
+
+        let traded = curve(CurveType::ConstantProductV1, 300_000_000 * TOKEN, 0, 0);
+        assert_eq!(traded.get_collateral_for_sell(1_000_000 * TOKEN), Some(53_802_260));
+    }
+
+    #[test]
+    fn linear_v1_prices_along_its_slope() {
+        // price(x) = 0.001 * x + 25 lamports per whole token, 100M tokens in
+        let state = curve(CurveType::LinearV1, 100_000_000 * TOKEN, 1_000_000, 25);
+        assert_eq!(state.get_collateral_for_buy(1_000_000 * TOKEN), Some(100_525_000_000));
+        assert_eq!(state.get_collateral_for_sell(1_000_000 * TOKEN), Some(99_525_000_000));
+        assert_eq!(state.get_tokens_for_buy(990_099_009), Some(9_898_025_729_001));
+        assert_eq!(state.get_price(), 100_025.0 / TOKEN as f64);
+        assert_eq!(state.get_collateral_for_buy(state.curve_amount + 1), None);
+    }
+
+    #[test]
+    fn trade_amounts_charge_fees_on_the_sol_side() {
+        let fresh = curve(CurveType::ConstantProductV1, 0, 0, 0);
+        assert_eq!(
+            get_trade_amounts(&fresh, &fees(), TradeSide::Buy, FixedSide::ExactIn, 1_000_000_000).unwrap(),
+            TradeAmounts {
+                token_amount: 34_281_150_129_545_234,
+                curve_collateral: 990_099_009,
+                collateral_amount: 1_000_000_000,
+            }
+        );
+
+        let linear = curve(CurveType::LinearV1, 100_000_000 * TOKEN, 1_000_000, 25);
+        assert_eq!(
+            get_trade_amounts(&linear, &fees(), TradeSide::Buy, FixedSide::ExactOut, 1_000_000 * TOKEN).unwrap(),
+            TradeAmounts {
+                token_amount: 1_000_000 * TOKEN,
+                curve_collateral: 100_525_000_000,
+                collateral_amount: 101_530_250_000,
+            }
+        );
+
+        let traded = curve(CurveType::ConstantProductV1, 300_000_000 * TOKEN, 0, 0);
+        assert_eq!(
+            get_trade_amounts(&traded, &fees(), TradeSide::Sell, FixedSide::ExactIn, 1_000_000 * TOKEN).unwrap(),
+            TradeAmounts {
+                token_amount: 1_000_000 * TOKEN,
+                curve_collateral: 53_802_260,
+                collateral_amount: 53_264_237,
+            }
+        );
+    }
+
+    #[test]
+    fn exact_out_sell_receives_at_least_the_amount_asked() {
+        let traded = curve(CurveType::ConstantProductV1, 300_000_000 * TOKEN, 0, 0);
+        let amounts = get_trade_amounts(&traded, &fees(), TradeSide::Sell, FixedSide::ExactOut, 53_264_237).unwrap();
+        assert!(amounts.token_amount <= 1_000_000 * TOKEN);
+        let received = get_trade_amounts(&traded, &fees(), TradeSide::Sell, FixedSide::ExactIn, amounts.token_amount).unwrap();
+        assert!(received.collateral_amount >= 53_264_237);
+    }
+}
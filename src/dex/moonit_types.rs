@@ -7,36 +7,90 @@ pub const PUBKEY_MOONIT_DEX_FEE: Pubkey = pubkey!("3udvfL24waJcLhskRAsStNMoNUvty
 pub const PUBKEY_MOONIT_HELIO_FEE: Pubkey = pubkey!("5K5RtTWzzLp4P8Npi84ocf7F1vBsAu29N1irG4iiUnzt");
 pub const PUBKEY_MOONIT_CONFIG: Pubkey = pubkey!("36Eru7v11oU5Pfrojyn5oY3nETA1a1iqsw2WUu6afkM9");
 
-pub const INITIAL_VIRTUAL_TOKEN_RESERVES: u64 = 1_073_000_000_000_000;
-pub const INITIAL_VIRTUAL_SOL_RESERVES: u64 = 30_000_000_000;
+/// Virtual reserves of a `ConstantProductV1` curve before any trade, in whole tokens and lamports
+pub const CONSTANT_PRODUCT_V1_VIRTUAL_TOKENS: u64 = 1_073_000_000;
+pub const CONSTANT_PRODUCT_V1_VIRTUAL_COLLATERAL: u64 = 30_000_000_000;
+/// `coef_a` of a `LinearV1` curve is in billionths of a lamport per whole token squared
+pub const LINEAR_V1_COEF_A_SCALE: u64 = 1_000_000_000;
 pub const BONDING_CURVE_SEED: &[u8] = b"token";
 
-#[derive(Clone, Debug, BorshDeserialize, BorshSerialize)]
+pub const MOONIT_BUY_DISCRIMINATOR: u64 = 16927863322537952870;
+pub const MOONIT_SELL_DISCRIMINATOR: u64 = 12502976635542562355;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, BorshDeserialize, BorshSerialize)]
 pub enum Currency {
     Sol,
 }
 
-#[derive(Clone, Debug, BorshDeserialize, BorshSerialize)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, BorshDeserialize, BorshSerialize)]
 pub enum CurveType {
     LinearV1,
     ConstantProductV1,
 }
 
-#[derive(Clone, Debug, BorshDeserialize, BorshSerialize)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, BorshDeserialize, BorshSerialize)]
 pub enum MigrationTarget {
     Raydium,
     Meteora,
 }
 
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TradeSide {
+    Buy,
+    Sell,
+}
+
+/// Trade with one side fixed, `amount` being tokens or SOL depending on `side` and `fixed_side`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct TradeOrder {
+    pub side: TradeSide,
+    pub fixed_side: FixedSide,
+    pub amount: u64,
+    pub slippage_basis_points: u64,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum MoonitError {
+    #[error("Bonding curve of {mint} reached its market cap threshold, the token migrates to {target:?}")]
+    Migrated { mint: Pubkey, target: MigrationTarget },
+    #[error("Not enough tokens left on the bonding curve")]
+    NotEnoughLiquidity,
+}
+
+/// Global launchpad config, holding the trade fee and the linear curve slope
+#[derive(Clone, Debug, BorshDeserialize, BorshSerialize)]
+pub struct ConfigAccount {
+    pub discriminator: u64,
+    pub migration_authority: Pubkey,
+    pub backend_authority: Pubkey,
+    pub config_authority: Pubkey,
+    pub helio_fee: Pubkey,
+    pub dex_fee: Pubkey,
+    pub fee_bps: u16,
+    pub dex_fee_share: u8,
+    pub migration_fee: u64,
+    pub marketcap_threshold: u64,
+    pub marketcap_currency: Currency,
+    pub min_supported_decimal_places: u8,
+    pub max_supported_decimal_places: u8,
+    pub min_supported_token_supply: u64,
+    pub max_supported_token_supply: u64,
+    pub bump: u8,
+    pub coef_a: u32,
+    pub coef_b: u32,
+}
+
 #[derive(Clone, Debug, BorshDeserialize, BorshSerialize)]
 pub struct CurveAccount {
     pub discriminator: u64,
     pub total_supply: u64,
+    /// Tokens still held by the curve
     pub curve_amount: u64,
     pub mint: Pubkey,
     pub decimals: u8,
     pub collateral_currency: Currency,
     pub curve_type: CurveType,
+    /// Market cap in lamports at which trading stops and the token migrates
     pub marketcap_threshold: u64,
     pub marketcap_currency: Currency,
     pub migration_fee: u64,
@@ -45,6 +99,7 @@ pub struct CurveAccount {
     pub migration_target: MigrationTarget,
 }
 
+/// Amounts of a trade, the side that is not fixed may deviate from its expected amount by `slippage_bps`
 #[derive(Clone, Debug, BorshDeserialize, BorshSerialize)]
 pub struct TradeParams {
     pub discriminator: u64,
@@ -54,8 +109,9 @@ pub struct TradeParams {
     pub slippage_bps: u64,
 }
 
-#[derive(Clone, Debug, BorshDeserialize, BorshSerialize)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Default, BorshDeserialize, BorshSerialize)]
 pub enum FixedSide {
+    #[default]
     ExactIn,
     ExactOut,
 }
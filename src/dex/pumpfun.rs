@@ -1,75 +1,82 @@
 use super::{
-    amm_calc::{amm_buy_get_token_out, calculate_with_slippage_buy},
+    amm_calc::{amm_buy_get_token_out_with_fees, calculate_with_slippage_buy, FeeModel},
     dex_traits::DexTrait,
-    pumpfun_common_types::{BuyInfo, SellInfo},
+    pumpfun_common_types::{BuyExactInInfo, BuyInfo, FeeConfig, SellInfo, PUMPFUN_BUY_EXACT_SOL_IN_DISCRIMINATOR},
+    pumpfun_incentives::VolumeIncentives,
     pumpfun_types::*,
-    types::{Create, PoolInfo, SwapInfo},
+    types::{BuySwapInfo, BuyVariant, Create, PoolInfo, SwapInfo},
 };
-use crate::common::trading_endpoint::TransactionType;
+use crate::common::trading_endpoint::{TransactionType, TxSettings};
 use crate::{common::trading_endpoint::TradingEndpoint, errors::trading_endpoint_error::TradingEndpointError, instruction::builder::PriorityFee};
 use borsh::BorshSerialize;
 use once_cell::sync::OnceCell;
 use solana_sdk::{
+    account::Account,
     instruction::{AccountMeta, Instruction},
     pubkey::Pubkey,
+    rent::Rent,
     signature::{Keypair, Signature},
     signer::Signer,
 };
-use spl_associated_token_account::{get_associated_token_address, instruction::create_associated_token_account};
+use spl_associated_token_account::{get_associated_token_address, get_associated_token_address_with_program_id, instruction::create_associated_token_account};
 use std::sync::Arc;
 
 pub struct Pumpfun {
     pub endpoint: Arc<TradingEndpoint>,
     pub global_account: OnceCell<Arc<GlobalAccount>>,
-    custom_router: Option<Pubkey>,
+    /// Market cap fee tiers, unset while the fee program has no config for Pumpfun
+    pub fee_config: OnceCell<Arc<FeeConfig>>,
 }
 
 #[async_trait::async_trait]
 impl DexTrait for Pumpfun {
     async fn initialize(&self) -> Result<(), TradingEndpointError> {
-        let account = self.endpoint.rpc.get_account(&PUBKEY_GLOBAL_ACCOUNT).await?;
+        let fee_config_pda = Self::get_fee_config_pda().ok_or_else(|| TradingEndpointError::CustomError("Failed to find fee config PDA".to_string()))?;
+        let accounts = self.endpoint.rpc.get_multiple_accounts(&[PUBKEY_GLOBAL_ACCOUNT, fee_config_pda]).await?;
+        let [Some(account), fee_config] =
+            <[_; 2]>::try_from(accounts).map_err(|_| TradingEndpointError::CustomError("Global account not found".to_string()))?
+        else {
+            return Err(TradingEndpointError::CustomError("Global account not found".to_string()));
+        };
         let global_account = bincode::deserialize::<GlobalAccount>(&account.data).map_err(|e| TradingEndpointError::CustomError(e.to_string()))?;
         let global_account = Arc::new(global_account);
 
         self.global_account
             .set(global_account)
             .map_err(|_| TradingEndpointError::CustomError("OnceCell already set".to_string()))?;
+        if let Some(fee_config) = fee_config {
+            let fee_config = FeeConfig::decode(&fee_config.data).map_err(|e| TradingEndpointError::CustomError(e.to_string()))?;
+            self.fee_config
+                .set(Arc::new(fee_config))
+                .map_err(|_| TradingEndpointError::CustomError("OnceCell already set".to_string()))?;
+        }
         Ok(())
     }
 
     fn initialized(&self) -> Result<(), TradingEndpointError> {
-        if self.global_account.get().is_none() {
-            return Err(TradingEndpointError::CustomError("Pumpfun not initialized".to_string()));
-        }
-        Ok(())
+        self.get_global_account().map(|_| ())
     }
 
     fn get_trading_endpoint(&self) -> Arc<TradingEndpoint> {
         self.endpoint.clone()
     }
 
-
     fn use_wsol(&self) -> bool {
         false
     }
 
     async fn get_pool(&self, mint: &Pubkey) -> Result<PoolInfo, TradingEndpointError> {
-        let bonding_curve_pda = Self::get_bonding_curve_pda(mint)?;
-        let account = self.endpoint.rpc.get_account(&bonding_curve_pda).await?;
-        if account.data.is_empty() {
-            return Err(TradingEndpointError::CustomError(format!("Bonding curve not found: {}", mint.to_string())));
-        }
+        self.initialized()?;
 
-        let bonding_curve = bincode::deserialize::<BondingCurveAccount>(&account.data).map_err(|e| TradingEndpointError::CustomError(e.to_string()))?;
+        let (bonding_curve, slot) = self.get_bonding_curve_with_slot(mint).await?;
+        self.get_pool_info(mint, &bonding_curve, slot)
+    }
 
-        Ok(PoolInfo {
-            pool: bonding_curve_pda,
-            creator: Some(bonding_curve.creator),
-            creator_vault: Some(Self::get_creator_vault_pda(&bonding_curve.creator)?),
-            config: None,
-            token_reserves: bonding_curve.virtual_token_reserves,
-            sol_reserves: bonding_curve.virtual_sol_reserves,
-        })
+    fn get_extra_accounts(&self, _mint: &Pubkey, pool_info: &PoolInfo) -> Result<Vec<AccountMeta>, TradingEndpointError> {
+        let creator_vault = pool_info
+            .creator_vault
+            .ok_or(TradingEndpointError::CustomError("Creator vault not provided".to_string()))?;
+        Ok(vec![AccountMeta::new(creator_vault, false)])
     }
 
     async fn create(
@@ -122,14 +129,27 @@ impl DexTrait for Pumpfun {
             let create_ata = create_associated_token_account(&payer.pubkey(), &payer.pubkey(), &mint, &spl_token::ID);
             instructions.push(create_ata);
 
-            let buy_token_amount = amm_buy_get_token_out(INITIAL_VIRTUAL_SOL_RESERVES, INITIAL_VIRTUAL_TOKEN_RESERVES, buy_sol_amount);
+            let buy_token_amount = amm_buy_get_token_out_with_fees(
+                INITIAL_VIRTUAL_SOL_RESERVES,
+                INITIAL_VIRTUAL_TOKEN_RESERVES,
+                buy_sol_amount,
+                &self.get_fee_model(
+                    &payer.pubkey(),
+                    FeeConfig::get_market_cap(
+                        INITIAL_VIRTUAL_SOL_RESERVES,
+                        INITIAL_VIRTUAL_TOKEN_RESERVES,
+                        self.get_global_account()?.token_total_supply,
+                    ),
+                )?,
+            );
             let sol_lamports_with_slippage = calculate_with_slippage_buy(buy_sol_amount, slippage_basis_points);
             let creator_vault = Self::get_creator_vault_pda(&payer.pubkey())?;
             let buy_instruction = self.build_buy_instruction(
                 &payer,
                 &mint,
-                Some(&creator_vault),
-                &get_associated_token_address(&payer.pubkey(), &mint),
+                None,
+                &[AccountMeta::new(creator_vault, false)],
+                &spl_token::ID,
                 SwapInfo {
                     token_amount: buy_token_amount,
                     sol_amount: sol_lamports_with_slippage,
@@ -144,10 +164,7 @@ impl DexTrait for Pumpfun {
                 TransactionType::Create,
                 &payer,
                 instructions,
-                None,
-                vec![blockhash],
-                fee,
-                additional_tip.unwrap_or_default(),
+                TxSettings::new(blockhash, fee, additional_tip.unwrap_or_default()),
                 Some(vec![&create.mint_private_key]),
             )
             .await?;
@@ -159,41 +176,66 @@ impl DexTrait for Pumpfun {
         &self,
         payer: &Keypair,
         mint: &Pubkey,
-        creator_vault: Option<&Pubkey>,
-        token_account: &Pubkey,
+        custom_ata: Option<&Pubkey>,
+        extra_accounts: &[AccountMeta],
+        token_program: &Pubkey,
         buy: SwapInfo,
     ) -> Result<Instruction, TradingEndpointError> {
+        self.build_buy_instruction_with_variant(
+            payer,
+            mint,
+            custom_ata,
+            extra_accounts,
+            token_program,
+            buy.with_variant(BuyVariant::ExactTokensOut),
+        )
+    }
+
+    fn build_buy_instruction_with_variant(
+        &self,
+        payer: &Keypair,
+        mint: &Pubkey,
+        custom_ata: Option<&Pubkey>,
+        extra_accounts: &[AccountMeta],
+        token_program: &Pubkey,
+        buy: BuySwapInfo,
+    ) -> Result<Instruction, TradingEndpointError> {
+        let BuySwapInfo { swap: buy, variant } = buy;
         self.initialized()?;
 
-        let buy_info: BuyInfo = buy.into();
-        let buffer = buy_info.to_buffer().map_err(|e| TradingEndpointError::CustomError(e.to_string()))?;
+        let buffer = match variant {
+            BuyVariant::ExactTokensOut => BuyInfo::from(buy).to_buffer(),
+            BuyVariant::ExactSolIn => BuyExactInInfo::from_swap_info(buy, PUMPFUN_BUY_EXACT_SOL_IN_DISCRIMINATOR).to_buffer(),
+        }
+        .map_err(|e| TradingEndpointError::CustomError(e.to_string()))?;
+        let creator_vault = extra_accounts
+            .first()
+            .cloned()
+            .ok_or(TradingEndpointError::CustomError("Creator vault not provided".to_string()))?;
         let bonding_curve = Self::get_bonding_curve_pda(mint)?;
-
-        let program = match self.custom_router {
-            None => PUMPFUN_PROGRAM,
-            Some(t) => t,
+        let ata = match custom_ata {
+            None => get_associated_token_address_with_program_id(&payer.pubkey(), mint, token_program),
+            Some(t) => *t,
         };
+
         Ok(Instruction::new_with_bytes(
-            program,
+            PUMPFUN_PROGRAM,
             &buffer,
             vec![
                 AccountMeta::new_readonly(PUBKEY_GLOBAL_ACCOUNT, false),
                 AccountMeta::new(PUBKEY_FEE_RECIPIENT, false),
                 AccountMeta::new_readonly(*mint, false),
                 AccountMeta::new(bonding_curve, false),
-                AccountMeta::new(get_associated_token_address(&bonding_curve, mint), false),
-                AccountMeta::new(*token_account, false),
+                AccountMeta::new(get_associated_token_address_with_program_id(&bonding_curve, mint, token_program), false),
+                AccountMeta::new(ata, false),
                 AccountMeta::new(payer.pubkey(), true),
                 AccountMeta::new_readonly(solana_program::system_program::ID, false),
-                AccountMeta::new_readonly(spl_token::ID, false),
-                AccountMeta::new(
-                    *creator_vault.ok_or(TradingEndpointError::CustomError("Creator vault not provided".to_string()))?,
-                    false,
-                ),
+                AccountMeta::new_readonly(*token_program, false),
+                creator_vault,
                 AccountMeta::new_readonly(PUBKEY_EVENT_AUTHORITY, false),
                 AccountMeta::new_readonly(PUMPFUN_PROGRAM, false),
-                AccountMeta::new(Self::get_global_volume_accumulator_pda()?, false),
-                AccountMeta::new(Self::get_user_volume_accumulator_pda(&payer.pubkey())?, false),
+                AccountMeta::new(VolumeIncentives::get_global_volume_accumulator_pda(&PUMPFUN_PROGRAM)?, false),
+                AccountMeta::new(VolumeIncentives::get_user_volume_accumulator_pda(&PUMPFUN_PROGRAM, &payer.pubkey())?, false),
                 AccountMeta::new(Self::get_fee_config_pda().unwrap(), false),
                 AccountMeta::new_readonly(PUMPFUN_FEE_PROGRAM, false),
             ],
@@ -205,17 +247,22 @@ impl DexTrait for Pumpfun {
         payer: &Keypair,
         mint: &Pubkey,
         custom_ata: Option<&Pubkey>,
-        creator_vault: Option<&Pubkey>,
+        extra_accounts: &[AccountMeta],
+        token_program: &Pubkey,
         sell: SwapInfo,
     ) -> Result<Instruction, TradingEndpointError> {
         self.initialized()?;
 
         let sell_info: SellInfo = sell.into();
         let buffer = sell_info.to_buffer().map_err(|e| TradingEndpointError::CustomError(e.to_string()))?;
+        let creator_vault = extra_accounts
+            .first()
+            .cloned()
+            .ok_or(TradingEndpointError::CustomError("Creator vault not provided".to_string()))?;
         let bonding_curve = Self::get_bonding_curve_pda(mint)?;
 
         let ata = match custom_ata {
-            None => get_associated_token_address(&payer.pubkey(), mint),
+            None => get_associated_token_address_with_program_id(&payer.pubkey(), mint, token_program),
             Some(t) => *t,
         };
 
@@ -227,15 +274,12 @@ impl DexTrait for Pumpfun {
                 AccountMeta::new(PUBKEY_FEE_RECIPIENT, false),
                 AccountMeta::new_readonly(*mint, false),
                 AccountMeta::new(bonding_curve, false),
-                AccountMeta::new(get_associated_token_address(&bonding_curve, mint), false),
+                AccountMeta::new(get_associated_token_address_with_program_id(&bonding_curve, mint, token_program), false),
                 AccountMeta::new(ata, false),
                 AccountMeta::new(payer.pubkey(), true),
                 AccountMeta::new_readonly(solana_program::system_program::ID, false),
-                AccountMeta::new(
-                    *creator_vault.ok_or(TradingEndpointError::CustomError("Creator vault not provided".to_string()))?,
-                    false,
-                ),
-                AccountMeta::new_readonly(spl_token::ID, false),
+                creator_vault,
+                AccountMeta::new_readonly(*token_program, false),
                 AccountMeta::new_readonly(PUBKEY_EVENT_AUTHORITY, false),
                 AccountMeta::new_readonly(PUMPFUN_PROGRAM, false),
                 AccountMeta::new(Self::get_fee_config_pda().unwrap(), false),
@@ -246,19 +290,91 @@ impl DexTrait for Pumpfun {
 }
 
 impl Pumpfun {
-    pub fn new(endpoint: Arc<TradingEndpoint>, custom_router: Option<Pubkey>) -> Self {
+    pub fn new(endpoint: Arc<TradingEndpoint>) -> Self {
         Self {
             endpoint,
-            custom_router,
             global_account: OnceCell::new(),
+            fee_config: OnceCell::new(),
         }
     }
 
+    pub async fn get_bonding_curve(&self, mint: &Pubkey) -> Result<BondingCurveAccount, TradingEndpointError> {
+        Ok(self.get_bonding_curve_with_slot(mint).await?.0)
+    }
+
+    /// Bonding curve with the slot it was read at
+    pub async fn get_bonding_curve_with_slot(&self, mint: &Pubkey) -> Result<(BondingCurveAccount, u64), TradingEndpointError> {
+        let (account, slot) = self.endpoint.get_account_with_slot(&Self::get_bonding_curve_pda(mint)?).await?;
+        if account.data.is_empty() {
+            return Err(TradingEndpointError::CustomError(format!("Bonding curve not found: {}", mint)));
+        }
+
+        Ok((Self::decode_bonding_curve(&account.data)?, slot))
+    }
+
+    pub fn decode_bonding_curve(data: &[u8]) -> Result<BondingCurveAccount, TradingEndpointError> {
+        bincode::deserialize::<BondingCurveAccount>(data).map_err(|e| TradingEndpointError::CustomError(e.to_string()))
+    }
+
+    /// Pool info of the live curve of `mint`, `PumpfunError::Migrated` once it completed
+    pub fn get_pool_info(&self, mint: &Pubkey, bonding_curve: &BondingCurveAccount, slot: u64) -> Result<PoolInfo, TradingEndpointError> {
+        if bonding_curve.complete {
+            return Err(PumpfunError::Migrated { mint: *mint }.into());
+        }
+
+        Ok(PoolInfo {
+            pool: Self::get_bonding_curve_pda(mint)?,
+            creator: Some(bonding_curve.creator),
+            creator_vault: Some(Self::get_creator_vault_pda(&bonding_curve.creator)?),
+            config: None,
+            token_reserves: bonding_curve.virtual_token_reserves,
+            sol_reserves: bonding_curve.virtual_sol_reserves,
+            fees: self.get_fee_model(&bonding_curve.creator, Self::get_market_cap(bonding_curve))?,
+            slot,
+        })
+    }
+
+    fn get_global_account(&self) -> Result<&GlobalAccount, TradingEndpointError> {
+        self.global_account
+            .get()
+            .map(|global_account| global_account.as_ref())
+            .ok_or_else(|| TradingEndpointError::CustomError("Pumpfun not initialized".to_string()))
+    }
+
+    /// Protocol fee plus the creator fee when the curve has a creator, from the fee tier of `market_cap` or the global account without fee config.
+    /// Curves have no LP, so a tier's LP fee does not apply.
+    pub fn get_fee_model(&self, creator: &Pubkey, market_cap: u128) -> Result<FeeModel, TradingEndpointError> {
+        let global_account = self.get_global_account()?;
+        let (protocol_fee_bps, creator_fee_bps) = match self.fee_config.get() {
+            Some(fee_config) => {
+                let fees = fee_config.get_fees(market_cap);
+                (fees.protocol_fee_bps, fees.creator_fee_bps)
+            }
+            None => (global_account.fee_basis_points, global_account.creator_fee_basis_points),
+        };
+
+        Ok(FeeModel {
+            protocol_fee_rate: FeeModel::rate_from_basis_points(protocol_fee_bps),
+            creator_fee_rate: match *creator == Pubkey::default() {
+                true => 0,
+                false => FeeModel::rate_from_basis_points(creator_fee_bps),
+            },
+            charged_on_top: true,
+            ..Default::default()
+        })
+    }
+
+    /// Market cap of the curve in lamports, its total supply priced at the virtual reserves
+    pub fn get_market_cap(bonding_curve: &BondingCurveAccount) -> u128 {
+        FeeConfig::get_market_cap(
+            bonding_curve.virtual_sol_reserves,
+            bonding_curve.virtual_token_reserves,
+            bonding_curve.token_total_supply,
+        )
+    }
+
     pub fn get_fee_config_pda() -> Option<Pubkey> {
-        let seeds: &[&[u8]; 2] = &[FEE_CONFIG_SEED, PUMPFUN_PROGRAM.as_ref()];
-        let program_id: &Pubkey = &PUMPFUN_FEE_PROGRAM;
-        let pda: Option<(Pubkey, u8)> = Pubkey::try_find_program_address(seeds, program_id);
-        pda.map(|pubkey| pubkey.0)
+        FeeConfig::get_pda(&PUMPFUN_PROGRAM)
     }
 
     pub fn get_bonding_curve_pda(mint: &Pubkey) -> Result<Pubkey, TradingEndpointError> {
@@ -277,19 +393,174 @@ impl Pumpfun {
         Ok(pda.0)
     }
 
-    pub fn get_user_volume_accumulator_pda(user: &Pubkey) -> Result<Pubkey, TradingEndpointError> {
-        let seeds: &[&[u8]; 2] = &[&USER_VOLUME_ACCUMULATOR_SEED, user.as_ref()];
-        let program_id: &Pubkey = &PUMPFUN_PROGRAM;
-        let pda = Pubkey::try_find_program_address(seeds, program_id)
-            .ok_or_else(|| TradingEndpointError::CustomError("Failed to find user volume accumulator PDA".to_string()))?;
-        Ok(pda.0)
+    /// Lamports `creator` can withdraw from its vault, everything above the rent-exempt minimum
+    pub async fn get_creator_vault_balance(&self, creator: &Pubkey) -> Result<u64, TradingEndpointError> {
+        let vault = Self::get_creator_vault_pda(creator)?;
+        let account = self.endpoint.rpc.get_multiple_accounts(&[vault]).await?.pop().flatten();
+        Ok(Self::decode_creator_vault_balance(account.as_ref()))
     }
 
-    pub fn get_global_volume_accumulator_pda() -> Result<Pubkey, TradingEndpointError> {
-        let seeds: &[&[u8]; 1] = &[&GLOBAL_VOLUME_ACCUMULATOR_SEED];
-        let program_id: &Pubkey = &PUMPFUN_PROGRAM;
-        let pda = Pubkey::try_find_program_address(seeds, program_id)
-            .ok_or_else(|| TradingEndpointError::CustomError("Failed to find global volume accumulator PDA".to_string()))?;
-        Ok(pda.0)
+    pub fn decode_creator_vault_balance(account: Option<&Account>) -> u64 {
+        account.map_or(0, |account| {
+            account.lamports.saturating_sub(Rent::default().minimum_balance(account.data.len()))
+        })
+    }
+
+    /// Withdraw the bonding curve creator fees of `creator` to its wallet
+    pub fn build_collect_creator_fee_instruction(creator: &Pubkey) -> Result<Instruction, TradingEndpointError> {
+        Ok(Instruction::new_with_bytes(
+            PUMPFUN_PROGRAM,
+            &PUMPFUN_COLLECT_CREATOR_FEE_DISCRIMINATOR.to_le_bytes(),
+            vec![
+                AccountMeta::new(*creator, true),
+                AccountMeta::new(Self::get_creator_vault_pda(creator)?, false),
+                AccountMeta::new_readonly(solana_program::system_program::ID, false),
+                AccountMeta::new_readonly(PUBKEY_EVENT_AUTHORITY, false),
+                AccountMeta::new_readonly(PUMPFUN_PROGRAM, false),
+            ],
+        ))
+    }
+
+    pub async fn collect_creator_fee(&self, creator: &Keypair, fee: Option<PriorityFee>, tip: Option<u64>) -> Result<Vec<Signature>, TradingEndpointError> {
+        let blockhash = self.endpoint.get_latest_blockhash().await?;
+        let instruction = Self::build_collect_creator_fee_instruction(&creator.pubkey())?;
+
+        self.endpoint
+            .build_and_broadcast_tx(
+                TransactionType::Sell,
+                creator,
+                vec![instruction],
+                TxSettings::new(blockhash, fee, tip.unwrap_or_default()),
+                None,
+            )
+            .await
+    }
+
+    /// Volume incentives earned by trading on Pumpfun
+    pub fn get_volume_incentives(&self) -> VolumeIncentives {
+        VolumeIncentives::new(self.endpoint.clone(), PUMPFUN_PROGRAM, PUBKEY_EVENT_AUTHORITY)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dex::pumpfun_common_types::{FeeTier, Fees};
+    use solana_client::nonblocking::rpc_client::RpcClient;
+
+    fn pumpfun() -> Pumpfun {
+        let rpc = Arc::new(RpcClient::new("http://127.0.0.1:8899".to_string()));
+        Pumpfun::new(Arc::new(TradingEndpoint::new(rpc, vec![])))
+    }
+
+    /// Global account charging 95 bps protocol and 5 bps creator fees
+    fn global_account() -> Arc<GlobalAccount> {
+        Arc::new(GlobalAccount {
+            discriminator: 0,
+            initialized: true,
+            authority: Pubkey::new_unique(),
+            fee_recipient: PUBKEY_FEE_RECIPIENT,
+            initial_virtual_token_reserves: INITIAL_VIRTUAL_TOKEN_RESERVES,
+            initial_virtual_sol_reserves: INITIAL_VIRTUAL_SOL_RESERVES,
+            initial_real_token_reserves: 793_100_000_000_000,
+            token_total_supply: 1_000_000_000_000_000,
+            fee_basis_points: 95,
+            withdraw_authority: Pubkey::new_unique(),
+            enable_migrate: true,
+            pool_migration_fee: 0,
+            creator_fee_basis_points: 5,
+        })
+    }
+
+    fn bonding_curve(virtual_sol_reserves: u64) -> BondingCurveAccount {
+        BondingCurveAccount {
+            discriminator: 0,
+            virtual_token_reserves: INITIAL_VIRTUAL_TOKEN_RESERVES,
+            virtual_sol_reserves,
+            real_token_reserves: 793_100_000_000_000,
+            real_sol_reserves: virtual_sol_reserves - INITIAL_VIRTUAL_SOL_RESERVES,
+            token_total_supply: 1_000_000_000_000_000,
+            complete: false,
+            creator: Pubkey::new_unique(),
+        }
+    }
+
+    #[test]
+    fn completed_curve_is_migrated() {
+        let pumpfun = pumpfun();
+        pumpfun.global_account.set(global_account()).unwrap();
+        let mint = Pubkey::new_unique();
+
+        // Curve account bytes: discriminator, five reserves, complete flag and creator, padded like the program allocates it
+        let mut curve = bonding_curve(115_005_359_056);
+        curve.real_token_reserves = 0;
+        curve.complete = true;
+        let mut data = bincode::serialize(&curve).unwrap();
+        data.resize(150, 0);
+
+        let decoded = Pumpfun::decode_bonding_curve(&data).unwrap();
+        assert!(decoded.complete);
+        assert_eq!(decoded.creator, curve.creator);
+        assert!(matches!(
+            pumpfun.get_pool_info(&mint, &decoded, 0),
+            Err(TradingEndpointError::Pumpfun(PumpfunError::Migrated { mint: migrated })) if migrated == mint
+        ));
+
+        // Clear the complete flag after the discriminator and five reserves
+        data[48] = 0;
+        let pool_info = pumpfun.get_pool_info(&mint, &Pumpfun::decode_bonding_curve(&data).unwrap(), 0).unwrap();
+        assert_eq!(pool_info.pool, Pumpfun::get_bonding_curve_pda(&mint).unwrap());
+        assert_eq!(pool_info.sol_reserves, 115_005_359_056);
+    }
+
+    #[test]
+    fn fee_model_requires_initialize() {
+        let pumpfun = pumpfun();
+        assert!(pumpfun.get_fee_model(&Pubkey::new_unique(), 0).is_err());
+    }
+
+    #[test]
+    fn fee_model_falls_back_to_global_account() {
+        let pumpfun = pumpfun();
+        pumpfun.global_account.set(global_account()).unwrap();
+
+        let fees = pumpfun.get_fee_model(&Pubkey::new_unique(), 0).unwrap();
+        assert_eq!(fees.protocol_fee_rate, 9_500);
+        assert_eq!(fees.creator_fee_rate, 500);
+        assert_eq!(pumpfun.get_fee_model(&Pubkey::default(), 0).unwrap().creator_fee_rate, 0);
+    }
+
+    #[test]
+    fn fee_model_uses_the_market_cap_tier() {
+        let pumpfun = pumpfun();
+        pumpfun.global_account.set(global_account()).unwrap();
+        let tier = |threshold: u128, protocol_fee_bps: u64, creator_fee_bps: u64| FeeTier {
+            market_cap_lamports_threshold: threshold,
+            fees: Fees {
+                // Never charged on a curve
+                lp_fee_bps: 20,
+                protocol_fee_bps,
+                creator_fee_bps,
+            },
+        };
+        pumpfun
+            .fee_config
+            .set(Arc::new(FeeConfig {
+                discriminator: 0,
+                bump: 255,
+                admin: Pubkey::new_unique(),
+                flat_fees: Fees::default(),
+                fee_tiers: vec![tier(0, 95, 30), tier(100_000_000_000, 90, 20)],
+            }))
+            .unwrap();
+
+        // A fresh curve is worth about 28 SOL, one holding 120 virtual SOL about 112 SOL
+        let curve = bonding_curve(INITIAL_VIRTUAL_SOL_RESERVES);
+        let fees = pumpfun.get_fee_model(&curve.creator, Pumpfun::get_market_cap(&curve)).unwrap();
+        assert_eq!((fees.protocol_fee_rate, fees.creator_fee_rate, fees.lp_fee_rate), (9_500, 3_000, 0));
+
+        let curve = bonding_curve(120_000_000_000);
+        let fees = pumpfun.get_fee_model(&curve.creator, Pumpfun::get_market_cap(&curve)).unwrap();
+        assert_eq!((fees.protocol_fee_rate, fees.creator_fee_rate, fees.lp_fee_rate), (9_000, 2_000, 0));
     }
 }
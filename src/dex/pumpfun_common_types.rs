@@ -1,5 +1,20 @@
-use super::types::SwapInfo;
+use super::{
+    pumpfun_types::{FEE_CONFIG_SEED, PUMPFUN_FEE_PROGRAM},
+    types::SwapInfo,
+};
 use borsh::{BorshDeserialize, BorshSerialize};
+use serde::Deserialize;
+use solana_sdk::{
+    instruction::{AccountMeta, Instruction},
+    pubkey::Pubkey,
+};
+use spl_associated_token_account::get_associated_token_address_with_program_id;
+
+pub const PUMPFUN_BUY_EXACT_SOL_IN_DISCRIMINATOR: u64 = 6903419673668549688;
+pub const PUMPSWAP_BUY_EXACT_QUOTE_IN_DISCRIMINATOR: u64 = 8135992095339261638;
+/// Volume incentive instructions, shared by Pumpfun and PumpSwap
+pub const CLAIM_TOKEN_INCENTIVES_DISCRIMINATOR: u64 = 1956816014251852816;
+pub const INIT_USER_VOLUME_ACCUMULATOR_DISCRIMINATOR: u64 = 13251948553813231198;
 
 #[derive(Debug, Clone, PartialEq, Eq, BorshSerialize, BorshDeserialize)]
 pub struct BuyInfo {
@@ -15,6 +30,30 @@ pub struct SellInfo {
     pub sol_amount: u64,
 }
 
+/// Buy spending exactly `sol_amount`, receiving at least `token_amount`
+#[derive(Debug, Clone, PartialEq, Eq, BorshSerialize, BorshDeserialize)]
+pub struct BuyExactInInfo {
+    pub discriminator: u64,
+    pub spendable_sol_in: u64,
+    pub min_tokens_out: u64,
+}
+
+impl BuyExactInInfo {
+    pub fn from_swap_info(buy: SwapInfo, discriminator: u64) -> Self {
+        Self {
+            discriminator,
+            spendable_sol_in: buy.sol_amount,
+            min_tokens_out: buy.token_amount,
+        }
+    }
+
+    pub fn to_buffer(&self) -> anyhow::Result<Vec<u8>> {
+        let mut buffer = Vec::new();
+        self.serialize(&mut buffer)?;
+        Ok(buffer)
+    }
+}
+
 impl From<SwapInfo> for BuyInfo {
     fn from(buy: SwapInfo) -> Self {
         Self {
@@ -50,3 +89,306 @@ impl SellInfo {
         Ok(buffer)
     }
 }
+
+/// Daily incentive schedule: `total_token_supply[day]` shared pro rata by volume among the `sol_volumes[day]` traded that day
+#[derive(Debug, Clone, Deserialize)]
+pub struct GlobalVolumeAccumulator {
+    pub discriminator: u64,
+    pub start_time: i64,
+    pub end_time: i64,
+    pub seconds_in_a_day: i64,
+    pub mint: Pubkey,
+    pub total_token_supply: [u64; 30],
+    pub sol_volumes: [u64; 30],
+}
+
+impl GlobalVolumeAccumulator {
+    /// Day of the schedule `timestamp` falls in, `None` outside it
+    pub fn get_day_index(&self, timestamp: i64) -> Option<usize> {
+        if self.seconds_in_a_day <= 0 || timestamp < self.start_time {
+            return None;
+        }
+        let index = ((timestamp - self.start_time) / self.seconds_in_a_day) as usize;
+        (index < self.sol_volumes.len()).then_some(index)
+    }
+
+    /// Share of day `index`'s tokens earned by trading `sol_volume` that day
+    pub fn get_daily_incentive(&self, index: usize, sol_volume: u64) -> u64 {
+        match self.sol_volumes[index] {
+            0 => 0,
+            daily_volume => (sol_volume as u128 * self.total_token_supply[index] as u128 / daily_volume as u128) as u64,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct UserVolumeAccumulator {
+    pub discriminator: u64,
+    pub user: Pubkey,
+    pub needs_claim: bool,
+    pub total_unclaimed_tokens: u64,
+    pub total_claimed_tokens: u64,
+    pub current_sol_volume: u64,
+    pub last_update_timestamp: i64,
+}
+
+impl UserVolumeAccumulator {
+    /// Tokens claimable at `timestamp`: those already settled plus the volume of a finished day the program has not settled yet
+    pub fn get_unclaimed_tokens(&self, global: &GlobalVolumeAccumulator, timestamp: i64) -> u64 {
+        let Some(last_day) = global.get_day_index(self.last_update_timestamp) else {
+            return self.total_unclaimed_tokens;
+        };
+        if global.get_day_index(timestamp) == Some(last_day) {
+            return self.total_unclaimed_tokens;
+        }
+        self.total_unclaimed_tokens
+            .saturating_add(global.get_daily_incentive(last_day, self.current_sol_volume))
+    }
+
+    /// Tokens earned so far today, claimable once the day ends
+    pub fn get_current_day_tokens(&self, global: &GlobalVolumeAccumulator, timestamp: i64) -> u64 {
+        match global.get_day_index(timestamp) {
+            Some(day) if global.get_day_index(self.last_update_timestamp) == Some(day) => global.get_daily_incentive(day, self.current_sol_volume),
+            _ => 0,
+        }
+    }
+}
+
+/// Create the volume accumulator of `user` on `program`, paid by `payer`
+pub fn build_init_user_volume_accumulator_instruction(
+    program: &Pubkey,
+    event_authority: &Pubkey,
+    payer: &Pubkey,
+    user: &Pubkey,
+    user_volume_accumulator: &Pubkey,
+) -> Instruction {
+    Instruction::new_with_bytes(
+        *program,
+        &INIT_USER_VOLUME_ACCUMULATOR_DISCRIMINATOR.to_le_bytes(),
+        vec![
+            AccountMeta::new(*payer, true),
+            AccountMeta::new_readonly(*user, false),
+            AccountMeta::new(*user_volume_accumulator, false),
+            AccountMeta::new_readonly(solana_program::system_program::ID, false),
+            AccountMeta::new_readonly(*event_authority, false),
+            AccountMeta::new_readonly(*program, false),
+        ],
+    )
+}
+
+/// Accounts of a token incentives claim, `mint` and `token_program` being those of `GlobalVolumeAccumulator.mint`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TokenIncentivesAccounts {
+    pub user: Pubkey,
+    pub global_volume_accumulator: Pubkey,
+    pub user_volume_accumulator: Pubkey,
+    pub mint: Pubkey,
+    pub token_program: Pubkey,
+}
+
+/// Transfer the unclaimed incentive tokens of `accounts.user` from the global accumulator to its token account
+pub fn build_claim_token_incentives_instruction(program: &Pubkey, event_authority: &Pubkey, payer: &Pubkey, accounts: &TokenIncentivesAccounts) -> Instruction {
+    let TokenIncentivesAccounts {
+        user,
+        global_volume_accumulator,
+        user_volume_accumulator,
+        mint,
+        token_program,
+    } = accounts;
+    Instruction::new_with_bytes(
+        *program,
+        &CLAIM_TOKEN_INCENTIVES_DISCRIMINATOR.to_le_bytes(),
+        vec![
+            AccountMeta::new_readonly(*user, false),
+            AccountMeta::new(get_associated_token_address_with_program_id(user, mint, token_program), false),
+            AccountMeta::new_readonly(*global_volume_accumulator, false),
+            AccountMeta::new(
+                get_associated_token_address_with_program_id(global_volume_accumulator, mint, token_program),
+                false,
+            ),
+            AccountMeta::new(*user_volume_accumulator, false),
+            AccountMeta::new_readonly(*mint, false),
+            AccountMeta::new_readonly(*token_program, false),
+            AccountMeta::new_readonly(solana_program::system_program::ID, false),
+            AccountMeta::new_readonly(spl_associated_token_account::ID, false),
+            AccountMeta::new_readonly(*event_authority, false),
+            AccountMeta::new_readonly(*program, false),
+            AccountMeta::new(*payer, true),
+        ],
+    )
+}
+
+/// Fees of a trade in basis points
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, BorshSerialize, BorshDeserialize)]
+pub struct Fees {
+    pub lp_fee_bps: u64,
+    pub protocol_fee_bps: u64,
+    pub creator_fee_bps: u64,
+}
+
+/// Fees charged from `market_cap_lamports_threshold` up to the threshold of the next tier
+#[derive(Debug, Clone, Copy, PartialEq, Eq, BorshSerialize, BorshDeserialize)]
+pub struct FeeTier {
+    pub market_cap_lamports_threshold: u128,
+    pub fees: Fees,
+}
+
+/// Fee program account of Pumpfun or PumpSwap, with tiers sorted by ascending threshold
+#[derive(Debug, Clone, PartialEq, Eq, BorshSerialize, BorshDeserialize)]
+pub struct FeeConfig {
+    pub discriminator: u64,
+    pub bump: u8,
+    pub admin: Pubkey,
+    pub flat_fees: Fees,
+    pub fee_tiers: Vec<FeeTier>,
+}
+
+impl FeeConfig {
+    /// Fee config of `program`, owned by the fee program
+    pub fn get_pda(program: &Pubkey) -> Option<Pubkey> {
+        Pubkey::try_find_program_address(&[FEE_CONFIG_SEED, program.as_ref()], &PUMPFUN_FEE_PROGRAM).map(|pda| pda.0)
+    }
+
+    pub fn decode(data: &[u8]) -> std::io::Result<Self> {
+        Self::deserialize(&mut &data[..])
+    }
+
+    /// Fees of the highest tier whose threshold `market_cap` reached, those of the first tier below it and the flat fees without tiers
+    pub fn get_fees(&self, market_cap: u128) -> Fees {
+        let Some(first_tier) = self.fee_tiers.first() else {
+            return self.flat_fees;
+        };
+        self.fee_tiers
+            .iter()
+            .rev()
+            .find(|tier| market_cap >= tier.market_cap_lamports_threshold)
+            .unwrap_or(first_tier)
+            .fees
+    }
+
+    /// Market cap in quote lamports the tiers are chosen by, the whole supply priced at the pool's reserves
+    pub fn get_market_cap(quote_reserves: u64, base_reserves: u64, base_supply: u64) -> u128 {
+        match base_reserves {
+            0 => 0,
+            base_reserves => quote_reserves as u128 * base_supply as u128 / base_reserves as u128,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fees(protocol_fee_bps: u64) -> Fees {
+        Fees {
+            lp_fee_bps: 0,
+            protocol_fee_bps,
+            creator_fee_bps: 5,
+        }
+    }
+
+    fn fee_config(thresholds: &[u128]) -> FeeConfig {
+        FeeConfig {
+            discriminator: 0,
+            bump: 255,
+            admin: Pubkey::new_unique(),
+            flat_fees: fees(25),
+            fee_tiers: thresholds
+                .iter()
+                .enumerate()
+                .map(|(index, threshold)| FeeTier {
+                    market_cap_lamports_threshold: *threshold,
+                    fees: fees(100 - index as u64 * 10),
+                })
+                .collect(),
+        }
+    }
+
+    #[test]
+    fn decodes_fee_config_with_spare_space() {
+        let config = fee_config(&[0, 1_000_000_000_000]);
+        let mut data = borsh::to_vec(&config).unwrap();
+        // Accounts are allocated for more tiers than they hold
+        data.extend_from_slice(&[0; 64]);
+        assert_eq!(FeeConfig::decode(&data).unwrap(), config);
+    }
+
+    #[test]
+    fn picks_highest_tier_reached() {
+        let config = fee_config(&[100, 1_000, 10_000]);
+        assert_eq!(config.get_fees(0), fees(100));
+        assert_eq!(config.get_fees(100), fees(100));
+        assert_eq!(config.get_fees(999), fees(100));
+        assert_eq!(config.get_fees(1_000), fees(90));
+        assert_eq!(config.get_fees(u128::MAX), fees(80));
+        assert_eq!(fee_config(&[]).get_fees(1_000), fees(25));
+    }
+
+    #[test]
+    fn market_cap_prices_the_whole_supply() {
+        // Fresh Pumpfun curve: 30 SOL against 1.073B virtual tokens out of a 1B supply
+        assert_eq!(
+            FeeConfig::get_market_cap(30_000_000_000, 1_073_000_000_000_000, 1_000_000_000_000_000),
+            27_958_993_476
+        );
+        assert_eq!(FeeConfig::get_market_cap(30_000_000_000, 0, 1_000_000_000_000_000), 0);
+    }
+
+    const DAY: i64 = 86_400;
+    const START: i64 = 1_750_000_000;
+
+    fn global_accumulator(sol_volumes: [u64; 2]) -> GlobalVolumeAccumulator {
+        let mut global = GlobalVolumeAccumulator {
+            discriminator: 0,
+            start_time: START,
+            end_time: START + 30 * DAY,
+            seconds_in_a_day: DAY,
+            mint: Pubkey::new_unique(),
+            total_token_supply: [1_000_000; 30],
+            sol_volumes: [0; 30],
+        };
+        global.sol_volumes[..2].copy_from_slice(&sol_volumes);
+        global
+    }
+
+    fn user_accumulator(current_sol_volume: u64, last_update_timestamp: i64) -> UserVolumeAccumulator {
+        UserVolumeAccumulator {
+            discriminator: 0,
+            user: Pubkey::new_unique(),
+            needs_claim: false,
+            total_unclaimed_tokens: 500,
+            total_claimed_tokens: 0,
+            current_sol_volume,
+            last_update_timestamp,
+        }
+    }
+
+    #[test]
+    fn settles_the_volume_of_a_finished_day() {
+        let global = global_accumulator([100_000_000_000, 50_000_000_000]);
+        // A tenth of day 0's volume
+        let user = user_accumulator(10_000_000_000, START + 10);
+        assert_eq!(user.get_unclaimed_tokens(&global, START + DAY - 1), 500);
+        assert_eq!(user.get_current_day_tokens(&global, START + DAY - 1), 100_000);
+        assert_eq!(user.get_unclaimed_tokens(&global, START + DAY), 100_500);
+        assert_eq!(user.get_current_day_tokens(&global, START + DAY), 0);
+        // Past the schedule the last day is still owed
+        assert_eq!(user.get_unclaimed_tokens(&global, START + 31 * DAY), 100_500);
+        assert_eq!(user.get_current_day_tokens(&global, START + 31 * DAY), 0);
+    }
+
+    #[test]
+    fn volume_outside_the_schedule_earns_nothing() {
+        let global = global_accumulator([0, 50_000_000_000]);
+        let before_start = user_accumulator(10_000_000_000, START - 1);
+        assert_eq!(before_start.get_unclaimed_tokens(&global, START + DAY), 500);
+        assert_eq!(before_start.get_current_day_tokens(&global, START - 1), 0);
+        // No volume recorded for the day
+        let user = user_accumulator(10_000_000_000, START + 10);
+        assert_eq!(user.get_current_day_tokens(&global, START + 20), 0);
+        assert_eq!(user.get_unclaimed_tokens(&global, START + DAY), 500);
+        let mut no_days = global.clone();
+        no_days.seconds_in_a_day = 0;
+        assert_eq!(no_days.get_day_index(START), None);
+    }
+}
@@ -0,0 +1,129 @@
+use super::{
+    pumpfun_common_types::{
+        build_claim_token_incentives_instruction, build_init_user_volume_accumulator_instruction, GlobalVolumeAccumulator, TokenIncentivesAccounts,
+        UserVolumeAccumulator,
+    },
+    pumpfun_types::{PumpfunError, GLOBAL_VOLUME_ACCUMULATOR_SEED, USER_VOLUME_ACCUMULATOR_SEED},
+};
+use crate::{
+    common::trading_endpoint::{TradingEndpoint, TransactionType, TxSettings},
+    errors::trading_endpoint_error::TradingEndpointError,
+    instruction::builder::PriorityFee,
+};
+use solana_sdk::{
+    instruction::Instruction,
+    pubkey::Pubkey,
+    signature::{Keypair, Signature},
+    signer::Signer,
+};
+use std::{
+    sync::Arc,
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+/// Volume incentives of Pumpfun or PumpSwap, both programs keeping the same accumulators under their own id
+pub struct VolumeIncentives {
+    pub endpoint: Arc<TradingEndpoint>,
+    pub program: Pubkey,
+    pub event_authority: Pubkey,
+}
+
+impl VolumeIncentives {
+    pub fn new(endpoint: Arc<TradingEndpoint>, program: Pubkey, event_authority: Pubkey) -> Self {
+        Self {
+            endpoint,
+            program,
+            event_authority,
+        }
+    }
+
+    pub fn get_user_volume_accumulator_pda(program: &Pubkey, user: &Pubkey) -> Result<Pubkey, TradingEndpointError> {
+        let pda = Pubkey::try_find_program_address(&[USER_VOLUME_ACCUMULATOR_SEED, user.as_ref()], program)
+            .ok_or_else(|| TradingEndpointError::CustomError("Failed to find user volume accumulator PDA".to_string()))?;
+        Ok(pda.0)
+    }
+
+    pub fn get_global_volume_accumulator_pda(program: &Pubkey) -> Result<Pubkey, TradingEndpointError> {
+        let pda = Pubkey::try_find_program_address(&[GLOBAL_VOLUME_ACCUMULATOR_SEED], program)
+            .ok_or_else(|| TradingEndpointError::CustomError("Failed to find global volume accumulator PDA".to_string()))?;
+        Ok(pda.0)
+    }
+
+    pub async fn get_global_volume_accumulator(&self) -> Result<GlobalVolumeAccumulator, TradingEndpointError> {
+        let account = self.endpoint.rpc.get_account(&Self::get_global_volume_accumulator_pda(&self.program)?).await?;
+        Self::decode_volume_accumulator(&account.data)
+    }
+
+    /// Volume accumulator of `user`, `None` until its first buy or `init_user_volume_accumulator`
+    pub async fn get_user_volume_accumulator(&self, user: &Pubkey) -> Result<Option<UserVolumeAccumulator>, TradingEndpointError> {
+        let user_volume_accumulator = Self::get_user_volume_accumulator_pda(&self.program, user)?;
+        match self.endpoint.rpc.get_multiple_accounts(&[user_volume_accumulator]).await?.pop().flatten() {
+            Some(account) => Ok(Some(Self::decode_volume_accumulator(&account.data)?)),
+            None => Ok(None),
+        }
+    }
+
+    pub fn decode_volume_accumulator<T: serde::de::DeserializeOwned>(data: &[u8]) -> Result<T, TradingEndpointError> {
+        bincode::deserialize::<T>(data).map_err(|e| TradingEndpointError::CustomError(e.to_string()))
+    }
+
+    /// Incentive tokens `user` can claim now
+    pub async fn get_unclaimed_incentives(&self, user: &Pubkey) -> Result<u64, TradingEndpointError> {
+        let (global, user) = tokio::try_join!(self.get_global_volume_accumulator(), self.get_user_volume_accumulator(user))?;
+        let now = SystemTime::now().duration_since(UNIX_EPOCH).map(|w| w.as_secs() as i64).unwrap_or_default();
+        Ok(user.map_or(0, |user| user.get_unclaimed_tokens(&global, now)))
+    }
+
+    pub fn build_init_user_volume_accumulator_instruction(&self, payer: &Pubkey, user: &Pubkey) -> Result<Instruction, TradingEndpointError> {
+        Ok(build_init_user_volume_accumulator_instruction(
+            &self.program,
+            &self.event_authority,
+            payer,
+            user,
+            &Self::get_user_volume_accumulator_pda(&self.program, user)?,
+        ))
+    }
+
+    /// `mint` and `token_program` are those of `GlobalVolumeAccumulator.mint`
+    pub fn build_claim_token_incentives_instruction(
+        &self,
+        payer: &Pubkey,
+        user: &Pubkey,
+        mint: &Pubkey,
+        token_program: &Pubkey,
+    ) -> Result<Instruction, TradingEndpointError> {
+        let accounts = TokenIncentivesAccounts {
+            user: *user,
+            global_volume_accumulator: Self::get_global_volume_accumulator_pda(&self.program)?,
+            user_volume_accumulator: Self::get_user_volume_accumulator_pda(&self.program, user)?,
+            mint: *mint,
+            token_program: *token_program,
+        };
+        Ok(build_claim_token_incentives_instruction(&self.program, &self.event_authority, payer, &accounts))
+    }
+
+    /// Claim the incentive tokens `user` earned by trading
+    pub async fn claim_token_incentives(&self, user: &Keypair, fee: Option<PriorityFee>, tip: Option<u64>) -> Result<Vec<Signature>, TradingEndpointError> {
+        let user_pubkey = user.pubkey();
+        let (global, user_volume_accumulator, blockhash) = tokio::try_join!(
+            self.get_global_volume_accumulator(),
+            self.get_user_volume_accumulator(&user_pubkey),
+            self.endpoint.get_latest_blockhash()
+        )?;
+        if user_volume_accumulator.is_none() {
+            return Err(PumpfunError::NoVolumeAccumulator { user: user_pubkey }.into());
+        }
+        let mint_info = self.endpoint.get_mint_info(&global.mint).await?;
+        let instruction = self.build_claim_token_incentives_instruction(&user_pubkey, &user_pubkey, &global.mint, &mint_info.token_program)?;
+
+        self.endpoint
+            .build_and_broadcast_tx(
+                TransactionType::Sell,
+                user,
+                vec![instruction],
+                TxSettings::new(blockhash, fee, tip.unwrap_or_default()),
+                None,
+            )
+            .await
+    }
+}
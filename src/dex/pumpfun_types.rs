@@ -15,6 +15,8 @@ pub const PUBKEY_PUMPFUN_USER_VOLUME_ACCUMULATOR: Pubkey = pubkey!("8BWnAfvqm9BG
 
 pub const PUMPFUN_FEE_PROGRAM: Pubkey = pubkey!("pfeeUxB6jkeY1Hxd7CsFCAjcbHA9rWtchMGdZ6VojVZ");
 
+pub const PUMPFUN_COLLECT_CREATOR_FEE_DISCRIMINATOR: u64 = 9573277071704462868;
+
 pub const GLOBAL_SEED: &[u8] = b"global";
 pub const MINT_AUTHORITY_SEED: &[u8] = b"mint-authority";
 pub const BONDING_CURVE_SEED: &[u8] = b"bonding-curve";
@@ -42,6 +44,19 @@ pub struct GlobalAccount {
     pub initial_real_token_reserves: u64,
     pub token_total_supply: u64,
     pub fee_basis_points: u64,
+    pub withdraw_authority: Pubkey,
+    pub enable_migrate: bool,
+    pub pool_migration_fee: u64,
+    pub creator_fee_basis_points: u64,
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum PumpfunError {
+    #[error("Bonding curve of {mint} is complete, the token migrated to PumpSwap")]
+    Migrated { mint: Pubkey },
+    /// Raised by Pumpfun and PumpSwap alike, both sharing `VolumeIncentives`
+    #[error("No volume accumulator for {user}, nothing to claim")]
+    NoVolumeAccumulator { user: Pubkey },
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
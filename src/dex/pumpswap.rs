@@ -1,41 +1,72 @@
 use super::{
+    amm_calc::{calculate_with_slippage_buy, calculate_with_slippage_sell, FeeModel},
     dex_traits::DexTrait,
-    pumpfun_common_types::{BuyInfo, SellInfo},
+    pumpfun_common_types::{BuyExactInInfo, BuyInfo, FeeConfig, Fees, SellInfo, PUMPSWAP_BUY_EXACT_QUOTE_IN_DISCRIMINATOR},
+    pumpfun_incentives::VolumeIncentives,
     pumpfun_types::PUMPFUN_PROGRAM,
+    pumpswap_math::{get_deposit_amounts, get_initial_lp_amount, get_value_in_quote, get_withdraw_amounts},
     pumpswap_types::*,
-    types::{Create, SwapInfo},
+    types::{BuySwapInfo, BuyVariant, Create, CreateATA, SwapInfo},
 };
 use crate::{
-    common::{accounts::PUBKEY_WSOL, trading_endpoint::TradingEndpoint},
+    common::{
+        accounts::PUBKEY_WSOL,
+        trading_endpoint::{TradingEndpoint, TransactionType, TxSettings},
+    },
     errors::trading_endpoint_error::TradingEndpointError,
-    instruction::builder::PriorityFee,
+    instruction::builder::{build_quote_buy_instructions, build_quote_sell_instructions, build_token_account_instructions, PriorityFee, QuotePayment},
 };
 use once_cell::sync::OnceCell;
+use parking_lot::RwLock;
 use rand::seq::IndexedRandom;
 use solana_sdk::{
+    account::Account,
     instruction::{AccountMeta, Instruction},
     pubkey::Pubkey,
     signature::{Keypair, Signature},
     signer::Signer,
 };
-use spl_associated_token_account::get_associated_token_address;
-use std::{str::FromStr, sync::Arc};
+use spl_associated_token_account::{
+    get_associated_token_address, get_associated_token_address_with_program_id, instruction::create_associated_token_account_idempotent,
+};
+use spl_token_2022::{
+    extension::StateWithExtensions,
+    instruction::close_account,
+    state::{Account as TokenAccount, Mint},
+};
+use std::{collections::HashMap, sync::Arc};
 
 pub struct PumpSwap {
     pub endpoint: Arc<TradingEndpoint>,
     pub global_account: OnceCell<Arc<GlobalAccount>>,
+    /// Market cap fee tiers, unset while the fee program has no config for PumpSwap
+    pub fee_config: OnceCell<Arc<FeeConfig>>,
+    pools: RwLock<HashMap<Pubkey, PoolKeys>>,
 }
 
 #[async_trait::async_trait]
 impl DexTrait for PumpSwap {
     async fn initialize(&self) -> Result<(), TradingEndpointError> {
-        let account = self.endpoint.rpc.get_account(&PUBKEY_GLOBAL_ACCOUNT).await?;
+        let fee_config_pda =
+            FeeConfig::get_pda(&PUBKEY_PUMPSWAP).ok_or_else(|| TradingEndpointError::CustomError("Failed to find fee config PDA".to_string()))?;
+        let accounts = self.endpoint.rpc.get_multiple_accounts(&[PUBKEY_GLOBAL_ACCOUNT, fee_config_pda]).await?;
+        let [Some(account), fee_config] =
+            <[_; 2]>::try_from(accounts).map_err(|_| TradingEndpointError::CustomError("Global account not found".to_string()))?
+        else {
+            return Err(TradingEndpointError::CustomError("Global account not found".to_string()));
+        };
         let global_account = bincode::deserialize::<GlobalAccount>(&account.data).map_err(|e| TradingEndpointError::CustomError(e.to_string()))?;
         let global_account = Arc::new(global_account);
 
         self.global_account
             .set(global_account)
             .map_err(|_| TradingEndpointError::CustomError("OnceCell already set".to_string()))?;
+        if let Some(fee_config) = fee_config {
+            let fee_config = FeeConfig::decode(&fee_config.data).map_err(|e| TradingEndpointError::CustomError(e.to_string()))?;
+            self.fee_config
+                .set(Arc::new(fee_config))
+                .map_err(|_| TradingEndpointError::CustomError("OnceCell already set".to_string()))?;
+        }
         Ok(())
     }
 
@@ -55,38 +86,42 @@ impl DexTrait for PumpSwap {
     }
 
     async fn get_pool(&self, mint: &Pubkey) -> Result<super::types::PoolInfo, TradingEndpointError> {
-        let pool = Self::get_pool_address(mint)?;
-        let pool_base = get_associated_token_address(&pool, &mint);
-        let pool_quote = get_associated_token_address(&pool, &PUBKEY_WSOL);
-        let (pool_account, pool_base_account, pool_quote_account) = tokio::try_join!(
-            self.endpoint.rpc.get_account(&pool),
-            self.endpoint.rpc.get_token_account(&pool_base),
-            self.endpoint.rpc.get_token_account(&pool_quote),
-        )?;
-
-        if pool_account.data.is_empty() {
-            return Err(TradingEndpointError::CustomError(format!("Pool account not found: {}", mint.to_string())));
-        }
-
-        let pool_account = bincode::deserialize::<PoolAccount>(&pool_account.data).map_err(|e| TradingEndpointError::CustomError(e.to_string()))?;
-        let pool_base_account =
-            pool_base_account.ok_or_else(|| TradingEndpointError::CustomError(format!("Pool base account not found: {}", mint.to_string())))?;
-        let pool_quote_account =
-            pool_quote_account.ok_or_else(|| TradingEndpointError::CustomError(format!("Pool quote account not found: {}", mint.to_string())))?;
-
-        let pool_base_reserve = u64::from_str(&pool_base_account.token_amount.amount).map_err(|e| TradingEndpointError::CustomError(e.to_string()))?;
-        let pool_quote_reserve = u64::from_str(&pool_quote_account.token_amount.amount).map_err(|e| TradingEndpointError::CustomError(e.to_string()))?;
+        self.initialized()?;
 
+        let pool = self.get_pool_state(mint).await?;
         Ok(super::types::PoolInfo {
-            pool,
-            creator: Some(pool_account.coin_creator),
-            creator_vault: Some(Self::get_creator_vault(&pool_account.coin_creator)?),
+            pool: pool.pool_address,
+            creator: Some(pool.pool_account.coin_creator),
+            creator_vault: Some(Self::get_creator_vault(&pool.pool_account.coin_creator)?),
             config: None,
-            token_reserves: pool_base_reserve,
-            sol_reserves: pool_quote_reserve,
+            token_reserves: pool.pool_base_reserve,
+            sol_reserves: pool.pool_quote_reserve,
+            fees: self.get_fee_model(&pool)?,
+            slot: pool.slot,
         })
     }
 
+    fn get_extra_accounts(&self, mint: &Pubkey, pool_info: &super::types::PoolInfo) -> Result<Vec<AccountMeta>, TradingEndpointError> {
+        let creator_vault = pool_info
+            .creator_vault
+            .ok_or(TradingEndpointError::CustomError("Creator vault is required for swap instruction".to_string()))?;
+        let (quote_mint, quote_token_program) = self.get_quote_mint(mint);
+        Ok(vec![
+            AccountMeta::new(
+                get_associated_token_address_with_program_id(&creator_vault, &quote_mint, &quote_token_program),
+                false,
+            ),
+            AccountMeta::new_readonly(creator_vault, false),
+        ])
+    }
+
+    fn get_quote_mint(&self, mint: &Pubkey) -> (Pubkey, Pubkey) {
+        match self.pools.read().get(mint) {
+            Some(keys) => (keys.quote_mint, keys.quote_token_program),
+            None => (PUBKEY_WSOL, spl_token::ID),
+        }
+    }
+
     async fn create(&self, _: Keypair, _: Create, _: Option<PriorityFee>, _: Option<u64>) -> Result<Vec<Signature>, TradingEndpointError> {
         Err(TradingEndpointError::CustomError("Not supported".to_string()))
     }
@@ -95,44 +130,37 @@ impl DexTrait for PumpSwap {
         &self,
         payer: &Keypair,
         mint: &Pubkey,
-        creator_vault: Option<&Pubkey>,
-        token_program_account: &Pubkey,
+        custom_ata: Option<&Pubkey>,
+        extra_accounts: &[AccountMeta],
+        token_program: &Pubkey,
         buy: SwapInfo,
     ) -> Result<Instruction, TradingEndpointError> {
-        self.initialized()?;
-
-        let buy_info: BuyInfo = buy.into();
-        let buffer = buy_info.to_buffer().map_err(|e| TradingEndpointError::CustomError(e.to_string()))?;
-        let pool = Self::get_pool_address(&mint)?;
-        let creator_vault = creator_vault.ok_or(TradingEndpointError::CustomError("Creator vault is required for buy instruction".to_string()))?;
-        let creator_vault_ata = get_associated_token_address(creator_vault, &PUBKEY_WSOL);
-        let fee_recipient = self.global_account.get().unwrap().protocol_fee_recipients.choose(&mut rand::rng()).unwrap();
+        self.build_buy_instruction_with_variant(
+            payer,
+            mint,
+            custom_ata,
+            extra_accounts,
+            token_program,
+            buy.with_variant(BuyVariant::ExactTokensOut),
+        )
+    }
 
-        Ok(Instruction::new_with_bytes(
-            PUBKEY_PUMPSWAP,
-            &buffer,
-            vec![
-                AccountMeta::new_readonly(pool, false),
-                AccountMeta::new(payer.pubkey(), true),
-                AccountMeta::new_readonly(PUBKEY_GLOBAL_ACCOUNT, false),
-                AccountMeta::new_readonly(*mint, false),
-                AccountMeta::new_readonly(PUBKEY_WSOL, false),
-                AccountMeta::new(get_associated_token_address(&payer.pubkey(), mint), false),
-                AccountMeta::new(get_associated_token_address(&payer.pubkey(), &PUBKEY_WSOL), false),
-                AccountMeta::new(get_associated_token_address(&pool, mint), false),
-                AccountMeta::new(get_associated_token_address(&pool, &PUBKEY_WSOL), false),
-                AccountMeta::new_readonly(*fee_recipient, false),
-                AccountMeta::new(get_associated_token_address(fee_recipient, &PUBKEY_WSOL), false),
-                AccountMeta::new_readonly(*token_program_account, false),
-                AccountMeta::new_readonly(spl_token::ID, false),
-                AccountMeta::new_readonly(solana_program::system_program::ID, false),
-                AccountMeta::new_readonly(spl_associated_token_account::ID, false),
-                AccountMeta::new_readonly(PUBKEY_EVENT_AUTHORITY, false),
-                AccountMeta::new_readonly(PUBKEY_PUMPSWAP, false),
-                AccountMeta::new(creator_vault_ata, false),
-                AccountMeta::new_readonly(*creator_vault, false),
-            ],
-        ))
+    fn build_buy_instruction_with_variant(
+        &self,
+        payer: &Keypair,
+        mint: &Pubkey,
+        custom_ata: Option<&Pubkey>,
+        extra_accounts: &[AccountMeta],
+        token_program: &Pubkey,
+        buy: BuySwapInfo,
+    ) -> Result<Instruction, TradingEndpointError> {
+        let BuySwapInfo { swap: buy, variant } = buy;
+        let buffer = match variant {
+            BuyVariant::ExactTokensOut => BuyInfo::from(buy).to_buffer(),
+            BuyVariant::ExactSolIn => BuyExactInInfo::from_swap_info(buy, PUMPSWAP_BUY_EXACT_QUOTE_IN_DISCRIMINATOR).to_buffer(),
+        }
+        .map_err(|e| TradingEndpointError::CustomError(e.to_string()))?;
+        self.build_swap_instruction(payer, mint, custom_ata, extra_accounts, token_program, buffer)
     }
 
     fn build_sell_instruction(
@@ -140,55 +168,274 @@ impl DexTrait for PumpSwap {
         payer: &Keypair,
         mint: &Pubkey,
         custom_ata: Option<&Pubkey>,
-        creator_vault: Option<&Pubkey>,
+        extra_accounts: &[AccountMeta],
+        token_program: &Pubkey,
         sell: SwapInfo,
     ) -> Result<Instruction, TradingEndpointError> {
-        self.initialized()?;
-
         let sell_info: SellInfo = sell.into();
         let buffer = sell_info.to_buffer().map_err(|e| TradingEndpointError::CustomError(e.to_string()))?;
-        let pool = Self::get_pool_address(&mint)?;
-        let creator_vault = creator_vault.ok_or(TradingEndpointError::CustomError("Creator vault is required for buy instruction".to_string()))?;
-        let creator_vault_ata = get_associated_token_address(creator_vault, &PUBKEY_WSOL);
+        self.build_swap_instruction(payer, mint, custom_ata, extra_accounts, token_program, buffer)
+    }
+}
+
+impl PumpSwap {
+    pub fn new(endpoint: Arc<TradingEndpoint>) -> Self {
+        Self {
+            endpoint,
+            global_account: OnceCell::new(),
+            fee_config: OnceCell::new(),
+            pools: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// Every pool trading `base_mint`, whatever its creator, index or quote mint
+    pub async fn get_pools(&self, base_mint: &Pubkey) -> Result<Vec<PoolInfo>, TradingEndpointError> {
+        let accounts = self
+            .endpoint
+            .rpc
+            .get_program_accounts_with_config(
+                &PUBKEY_PUMPSWAP,
+                solana_client::rpc_config::RpcProgramAccountsConfig {
+                    filters: Some(vec![
+                        solana_client::rpc_filter::RpcFilterType::Memcmp(solana_client::rpc_filter::Memcmp::new_raw_bytes(
+                            0,
+                            POOL_ACCOUNT_DISCRIMINATOR.to_vec(),
+                        )),
+                        solana_client::rpc_filter::RpcFilterType::Memcmp(solana_client::rpc_filter::Memcmp::new_raw_bytes(
+                            POOL_ACCOUNT_BASE_MINT_OFFSET,
+                            base_mint.to_bytes().to_vec(),
+                        )),
+                    ]),
+                    account_config: solana_client::rpc_config::RpcAccountInfoConfig {
+                        encoding: Some(solana_account_decoder::UiAccountEncoding::Base64),
+                        commitment: None,
+                        data_slice: None,
+                        min_context_slot: None,
+                    },
+                    with_context: None,
+                    sort_results: None,
+                },
+            )
+            .await?;
+
+        let pools = accounts
+            .iter()
+            .map(|(pool, account)| Ok((*pool, Self::decode_pool_account(&account.data)?)))
+            .collect::<Result<Vec<_>, TradingEndpointError>>()?;
+        let vaults = pools
+            .iter()
+            .flat_map(|(_, pool_account)| [pool_account.pool_base_token_account, pool_account.pool_quote_token_account])
+            .collect::<Vec<_>>();
+        let base_mint_supply = match pools.is_empty() {
+            true => 0,
+            false => Self::decode_mint_supply(&self.endpoint.rpc.get_account(base_mint).await?.data)?,
+        };
+        let mut reserves = Vec::with_capacity(vaults.len());
+        let mut slot = 0;
+        for chunk in vaults.chunks(100) {
+            let (accounts, chunk_slot) = self.endpoint.get_multiple_accounts_with_slot(chunk).await?;
+            slot = slot.max(chunk_slot);
+            for account in accounts {
+                reserves.push(match account {
+                    Some(account) => Self::decode_token_amount(&account.data)?,
+                    None => 0,
+                });
+            }
+        }
+
+        Ok(pools
+            .into_iter()
+            .zip(reserves.chunks(2))
+            .map(|((pool_address, pool_account), reserves)| PoolInfo {
+                pool_address,
+                pool_account,
+                pool_base_reserve: reserves[0],
+                pool_quote_reserve: reserves[1],
+                base_mint_supply,
+                slot,
+            })
+            .collect())
+    }
+
+    /// Deepest pool of `base_mint` by quote reserves, among those quoted in `quote_mint`, or in WSOL when any are and no quote mint is given.
+    /// The pool is remembered for `base_mint`, so later quotes and trades use it.
+    pub async fn select_pool(&self, base_mint: &Pubkey, quote_mint: Option<&Pubkey>) -> Result<PoolInfo, TradingEndpointError> {
+        let pools = self.get_pools(base_mint).await?;
+        let quote_mint = match quote_mint {
+            Some(quote_mint) => Some(*quote_mint),
+            None => pools.iter().any(|pool| pool.pool_account.quote_mint == PUBKEY_WSOL).then_some(PUBKEY_WSOL),
+        };
+        let pool = pools
+            .into_iter()
+            .filter(|pool| quote_mint.is_none_or(|quote_mint| pool.pool_account.quote_mint == quote_mint))
+            .max_by_key(|pool| pool.pool_quote_reserve)
+            .ok_or_else(|| TradingEndpointError::CustomError(format!("No PumpSwap pool found for base mint: {}", base_mint)))?;
+
+        let quote_token_program = self.endpoint.get_mint_info(&pool.pool_account.quote_mint).await?.token_program;
+        self.pools
+            .write()
+            .insert(*base_mint, PoolKeys::new(pool.pool_address, &pool.pool_account, quote_token_program));
+        Ok(pool)
+    }
+
+    /// Pool account and reserves of the pool selected for `mint`. Without a selection the canonical pool is loaded by its address,
+    /// every pool of the mint being scanned only when it has none. Call `select_pool` to pick among all pools instead.
+    pub async fn get_pool_state(&self, mint: &Pubkey) -> Result<PoolInfo, TradingEndpointError> {
+        let cached = self.pools.read().get(mint).copied();
+        if let Some(keys) = cached {
+            return self.get_pool_reserves(mint, &keys).await;
+        }
+
+        let pool = Self::get_pool_address(mint)?;
+        let Some(account) = self.endpoint.rpc.get_multiple_accounts(&[pool]).await?.pop().flatten() else {
+            return self.select_pool(mint, None).await;
+        };
+        let keys = PoolKeys::new(pool, &Self::decode_pool_account(&account.data)?, spl_token::ID);
+        self.pools.write().insert(*mint, keys);
+        self.get_pool_reserves(mint, &keys).await
+    }
+
+    /// Accounts of the pool selected for `mint`, or of its canonical WSOL pool when none was selected yet
+    pub fn get_pool_keys(&self, mint: &Pubkey, token_program: &Pubkey) -> Result<PoolKeys, TradingEndpointError> {
+        if let Some(keys) = self.pools.read().get(mint) {
+            return Ok(*keys);
+        }
+
+        let pool = Self::get_pool_address(mint)?;
+        Ok(PoolKeys {
+            pool,
+            quote_mint: PUBKEY_WSOL,
+            quote_token_program: spl_token::ID,
+            pool_base_token_account: get_associated_token_address_with_program_id(&pool, mint, token_program),
+            pool_quote_token_account: get_associated_token_address(&pool, &PUBKEY_WSOL),
+        })
+    }
+
+    async fn get_pool_reserves(&self, mint: &Pubkey, keys: &PoolKeys) -> Result<PoolInfo, TradingEndpointError> {
+        let (accounts, slot) = self
+            .endpoint
+            .get_multiple_accounts_with_slot(&[keys.pool, keys.pool_base_token_account, keys.pool_quote_token_account, *mint])
+            .await?;
+        let [pool_account, pool_base_account, pool_quote_account, mint_account] =
+            <[_; 4]>::try_from(accounts).map_err(|_| TradingEndpointError::CustomError(format!("Pool accounts not found: {mint}")))?;
+
+        let pool_account = pool_account.ok_or_else(|| TradingEndpointError::CustomError(format!("Pool account not found: {mint}")))?;
+        let pool_base_account = pool_base_account.ok_or_else(|| TradingEndpointError::CustomError(format!("Pool base account not found: {mint}")))?;
+        let pool_quote_account = pool_quote_account.ok_or_else(|| TradingEndpointError::CustomError(format!("Pool quote account not found: {mint}")))?;
+        let mint_account = mint_account.ok_or_else(|| TradingEndpointError::CustomError(format!("Mint not found: {mint}")))?;
+
+        Ok(PoolInfo {
+            pool_address: keys.pool,
+            pool_account: Self::decode_pool_account(&pool_account.data)?,
+            pool_base_reserve: Self::decode_token_amount(&pool_base_account.data)?,
+            pool_quote_reserve: Self::decode_token_amount(&pool_quote_account.data)?,
+            base_mint_supply: Self::decode_mint_supply(&mint_account.data)?,
+            slot,
+        })
+    }
+
+    pub fn decode_pool_account(data: &[u8]) -> Result<PoolAccount, TradingEndpointError> {
+        bincode::deserialize::<PoolAccount>(data).map_err(|e| TradingEndpointError::CustomError(e.to_string()))
+    }
+
+    fn decode_token_amount(data: &[u8]) -> Result<u64, TradingEndpointError> {
+        let state = StateWithExtensions::<TokenAccount>::unpack(data).map_err(|e| TradingEndpointError::CustomError(e.to_string()))?;
+        Ok(state.base.amount)
+    }
+
+    fn decode_mint_supply(data: &[u8]) -> Result<u64, TradingEndpointError> {
+        let state = StateWithExtensions::<Mint>::unpack(data).map_err(|e| TradingEndpointError::CustomError(e.to_string()))?;
+        Ok(state.base.supply)
+    }
+
+    fn build_swap_instruction(
+        &self,
+        payer: &Keypair,
+        mint: &Pubkey,
+        custom_ata: Option<&Pubkey>,
+        extra_accounts: &[AccountMeta],
+        token_program: &Pubkey,
+        buffer: Vec<u8>,
+    ) -> Result<Instruction, TradingEndpointError> {
+        self.initialized()?;
+
+        let keys = self.get_pool_keys(mint, token_program)?;
+        let [creator_vault_ata, creator_vault] = extra_accounts else {
+            return Err(TradingEndpointError::CustomError("Creator vault is required for swap instruction".to_string()));
+        };
         let fee_recipient = self.global_account.get().unwrap().protocol_fee_recipients.choose(&mut rand::rng()).unwrap();
         let ata = match custom_ata {
-            None => get_associated_token_address(&payer.pubkey(), mint),
+            None => get_associated_token_address_with_program_id(&payer.pubkey(), mint, token_program),
             Some(t) => *t,
         };
+
         Ok(Instruction::new_with_bytes(
             PUBKEY_PUMPSWAP,
             &buffer,
             vec![
-                AccountMeta::new_readonly(pool, false),
+                AccountMeta::new_readonly(keys.pool, false),
                 AccountMeta::new(payer.pubkey(), true),
                 AccountMeta::new_readonly(PUBKEY_GLOBAL_ACCOUNT, false),
                 AccountMeta::new_readonly(*mint, false),
-                AccountMeta::new_readonly(PUBKEY_WSOL, false),
+                AccountMeta::new_readonly(keys.quote_mint, false),
                 AccountMeta::new(ata, false),
-                AccountMeta::new(get_associated_token_address(&payer.pubkey(), &PUBKEY_WSOL), false),
-                AccountMeta::new(get_associated_token_address(&pool, mint), false),
-                AccountMeta::new(get_associated_token_address(&pool, &PUBKEY_WSOL), false),
+                AccountMeta::new(
+                    get_associated_token_address_with_program_id(&payer.pubkey(), &keys.quote_mint, &keys.quote_token_program),
+                    false,
+                ),
+                AccountMeta::new(keys.pool_base_token_account, false),
+                AccountMeta::new(keys.pool_quote_token_account, false),
                 AccountMeta::new_readonly(*fee_recipient, false),
-                AccountMeta::new(get_associated_token_address(fee_recipient, &PUBKEY_WSOL), false),
-                AccountMeta::new_readonly(spl_token::ID, false),
-                AccountMeta::new_readonly(spl_token::ID, false),
+                AccountMeta::new(
+                    get_associated_token_address_with_program_id(fee_recipient, &keys.quote_mint, &keys.quote_token_program),
+                    false,
+                ),
+                AccountMeta::new_readonly(*token_program, false),
+                AccountMeta::new_readonly(keys.quote_token_program, false),
                 AccountMeta::new_readonly(solana_program::system_program::ID, false),
                 AccountMeta::new_readonly(spl_associated_token_account::ID, false),
                 AccountMeta::new_readonly(PUBKEY_EVENT_AUTHORITY, false),
                 AccountMeta::new_readonly(PUBKEY_PUMPSWAP, false),
-                AccountMeta::new(creator_vault_ata, false),
-                AccountMeta::new_readonly(*creator_vault, false),
+                creator_vault_ata.clone(),
+                creator_vault.clone(),
             ],
         ))
     }
-}
 
-impl PumpSwap {
-    pub fn new(endpoint: Arc<TradingEndpoint>) -> Self {
-        Self {
-            endpoint,
-            global_account: OnceCell::new(),
-        }
+    /// LP and protocol fees plus the coin creator fee when the pool has a coin creator.
+    /// With a fee config, canonical pools pay the fees of their market cap tier and other pools its flat fees, otherwise the global account's.
+    pub fn get_fee_model(&self, pool: &PoolInfo) -> Result<FeeModel, TradingEndpointError> {
+        self.initialized()?;
+        let global_account = self.global_account.get().unwrap();
+        let fees = match self.fee_config.get() {
+            Some(fee_config) if Self::is_canonical_pool(pool) => fee_config.get_fees(FeeConfig::get_market_cap(
+                pool.pool_quote_reserve,
+                pool.pool_base_reserve,
+                pool.base_mint_supply,
+            )),
+            Some(fee_config) => fee_config.flat_fees,
+            None => Fees {
+                lp_fee_bps: global_account.lp_fee_basis_points,
+                protocol_fee_bps: global_account.protocol_fee_basis_points,
+                creator_fee_bps: global_account.coin_creator_fee_basis_points,
+            },
+        };
+
+        Ok(FeeModel {
+            protocol_fee_rate: FeeModel::rate_from_basis_points(fees.protocol_fee_bps),
+            lp_fee_rate: FeeModel::rate_from_basis_points(fees.lp_fee_bps),
+            creator_fee_rate: match pool.pool_account.coin_creator == Pubkey::default() {
+                true => 0,
+                false => FeeModel::rate_from_basis_points(fees.creator_fee_bps),
+            },
+            charged_on_top: true,
+            ..Default::default()
+        })
+    }
+
+    /// Whether `pool` is the WSOL pool a Pumpfun curve migrated to
+    pub fn is_canonical_pool(pool: &PoolInfo) -> bool {
+        Self::get_pool_address(&pool.pool_account.base_mint).is_ok_and(|canonical| canonical == pool.pool_address)
     }
 
     pub fn get_creator_vault(creator: &Pubkey) -> Result<Pubkey, TradingEndpointError> {
@@ -197,24 +444,587 @@ impl PumpSwap {
         Ok(pda.0)
     }
 
+    /// WSOL account of the creator vault, where swaps pay the coin creator fee
+    pub fn get_coin_creator_vault_ata(creator: &Pubkey) -> Result<Pubkey, TradingEndpointError> {
+        Ok(get_associated_token_address(&Self::get_creator_vault(creator)?, &PUBKEY_WSOL))
+    }
+
+    /// WSOL `creator` can withdraw from its vault
+    pub async fn get_coin_creator_vault_balance(&self, creator: &Pubkey) -> Result<u64, TradingEndpointError> {
+        let vault_ata = Self::get_coin_creator_vault_ata(creator)?;
+        let account = self.endpoint.rpc.get_multiple_accounts(&[vault_ata]).await?.pop().flatten();
+        Self::decode_coin_creator_vault_balance(account.as_ref())
+    }
+
+    pub fn decode_coin_creator_vault_balance(account: Option<&Account>) -> Result<u64, TradingEndpointError> {
+        match account {
+            Some(account) => Self::decode_token_amount(&account.data),
+            None => Ok(0),
+        }
+    }
+
+    /// Withdraw the coin creator fees of `creator` and unwrap them to its wallet
+    pub fn build_collect_coin_creator_fee_instructions(creator: &Pubkey) -> Result<Vec<Instruction>, TradingEndpointError> {
+        let creator_ata = get_associated_token_address(creator, &PUBKEY_WSOL);
+        let close = close_account(&spl_token::ID, &creator_ata, creator, creator, &[creator]).map_err(|e| TradingEndpointError::CustomError(e.to_string()))?;
+
+        Ok(vec![
+            create_associated_token_account_idempotent(creator, creator, &PUBKEY_WSOL, &spl_token::ID),
+            Instruction::new_with_bytes(
+                PUBKEY_PUMPSWAP,
+                &PUMPSWAP_COLLECT_COIN_CREATOR_FEE_DISCRIMINATOR.to_le_bytes(),
+                vec![
+                    AccountMeta::new_readonly(PUBKEY_WSOL, false),
+                    AccountMeta::new_readonly(spl_token::ID, false),
+                    AccountMeta::new_readonly(*creator, true),
+                    AccountMeta::new_readonly(Self::get_creator_vault(creator)?, false),
+                    AccountMeta::new(Self::get_coin_creator_vault_ata(creator)?, false),
+                    AccountMeta::new(creator_ata, false),
+                    AccountMeta::new_readonly(PUBKEY_EVENT_AUTHORITY, false),
+                    AccountMeta::new_readonly(PUBKEY_PUMPSWAP, false),
+                ],
+            ),
+            close,
+        ])
+    }
+
+    pub async fn collect_coin_creator_fee(
+        &self,
+        creator: &Keypair,
+        fee: Option<PriorityFee>,
+        tip: Option<u64>,
+    ) -> Result<Vec<Signature>, TradingEndpointError> {
+        let blockhash = self.endpoint.get_latest_blockhash().await?;
+        let instructions = Self::build_collect_coin_creator_fee_instructions(&creator.pubkey())?;
+
+        self.endpoint
+            .build_and_broadcast_tx(
+                TransactionType::Sell,
+                creator,
+                instructions,
+                TxSettings::new(blockhash, fee, tip.unwrap_or_default()),
+                None,
+            )
+            .await
+    }
+
     pub fn get_pool_authority_pda(mint: &Pubkey) -> Result<Pubkey, TradingEndpointError> {
         let pda = Pubkey::try_find_program_address(&[b"pool-authority", mint.as_ref()], &PUMPFUN_PROGRAM)
             .ok_or_else(|| TradingEndpointError::CustomError("Failed to find pool authority PDA".to_string()))?;
         Ok(pda.0)
     }
 
+    /// Canonical pool a Pumpfun curve migrates to, index 0 against WSOL
     pub fn get_pool_address(mint: &Pubkey) -> Result<Pubkey, TradingEndpointError> {
+        Self::get_pool_pda(0, &Self::get_pool_authority_pda(mint)?, mint, &PUBKEY_WSOL)
+    }
+
+    pub fn get_pool_pda(index: u16, creator: &Pubkey, base_mint: &Pubkey, quote_mint: &Pubkey) -> Result<Pubkey, TradingEndpointError> {
         let pda = Pubkey::try_find_program_address(
-            &[
-                b"pool",
-                &0u16.to_le_bytes(),
-                Self::get_pool_authority_pda(mint)?.as_ref(),
-                mint.as_ref(),
-                PUBKEY_WSOL.as_ref(),
-            ],
+            &[b"pool", &index.to_le_bytes(), creator.as_ref(), base_mint.as_ref(), quote_mint.as_ref()],
             &PUBKEY_PUMPSWAP,
         )
         .ok_or_else(|| TradingEndpointError::CustomError("Failed to find pool address PDA".to_string()))?;
         Ok(pda.0)
     }
+
+    pub fn get_lp_mint_pda(pool: &Pubkey) -> Result<Pubkey, TradingEndpointError> {
+        let pda = Pubkey::try_find_program_address(&[b"pool_lp_mint", pool.as_ref()], &PUBKEY_PUMPSWAP)
+            .ok_or_else(|| TradingEndpointError::CustomError("Failed to find LP mint PDA".to_string()))?;
+        Ok(pda.0)
+    }
+
+    /// LP token account of `owner`, LP mints being Token-2022 mints
+    pub fn get_lp_token_account(owner: &Pubkey, pool: &Pubkey) -> Result<Pubkey, TradingEndpointError> {
+        Ok(get_associated_token_address_with_program_id(
+            owner,
+            &Self::get_lp_mint_pda(pool)?,
+            &spl_token_2022::ID,
+        ))
+    }
+
+    /// Pool `index` of `payer` for `base_mint` against `quote_mint`, seeded with both amounts
+    pub fn build_create_pool_instruction(
+        &self,
+        payer: &Keypair,
+        base_mint: &Pubkey,
+        quote_mint: &Pubkey,
+        base_token_program: &Pubkey,
+        quote_token_program: &Pubkey,
+        create_pool: CreatePoolInfo,
+    ) -> Result<Instruction, TradingEndpointError> {
+        let buffer = create_pool.to_buffer().map_err(|e| TradingEndpointError::CustomError(e.to_string()))?;
+        let pool = Self::get_pool_pda(create_pool.index, &payer.pubkey(), base_mint, quote_mint)?;
+
+        Ok(Instruction::new_with_bytes(
+            PUBKEY_PUMPSWAP,
+            &buffer,
+            vec![
+                AccountMeta::new(pool, false),
+                AccountMeta::new_readonly(PUBKEY_GLOBAL_ACCOUNT, false),
+                AccountMeta::new(payer.pubkey(), true),
+                AccountMeta::new_readonly(*base_mint, false),
+                AccountMeta::new_readonly(*quote_mint, false),
+                AccountMeta::new(Self::get_lp_mint_pda(&pool)?, false),
+                AccountMeta::new(
+                    get_associated_token_address_with_program_id(&payer.pubkey(), base_mint, base_token_program),
+                    false,
+                ),
+                AccountMeta::new(
+                    get_associated_token_address_with_program_id(&payer.pubkey(), quote_mint, quote_token_program),
+                    false,
+                ),
+                AccountMeta::new(Self::get_lp_token_account(&payer.pubkey(), &pool)?, false),
+                AccountMeta::new(get_associated_token_address_with_program_id(&pool, base_mint, base_token_program), false),
+                AccountMeta::new(get_associated_token_address_with_program_id(&pool, quote_mint, quote_token_program), false),
+                AccountMeta::new_readonly(solana_program::system_program::ID, false),
+                AccountMeta::new_readonly(spl_token_2022::ID, false),
+                AccountMeta::new_readonly(*base_token_program, false),
+                AccountMeta::new_readonly(*quote_token_program, false),
+                AccountMeta::new_readonly(spl_associated_token_account::ID, false),
+                AccountMeta::new_readonly(PUBKEY_EVENT_AUTHORITY, false),
+                AccountMeta::new_readonly(PUBKEY_PUMPSWAP, false),
+            ],
+        ))
+    }
+
+    pub fn build_deposit_instruction(
+        &self,
+        payer: &Keypair,
+        mint: &Pubkey,
+        token_program: &Pubkey,
+        deposit: DepositInfo,
+    ) -> Result<Instruction, TradingEndpointError> {
+        let buffer = deposit.to_buffer().map_err(|e| TradingEndpointError::CustomError(e.to_string()))?;
+        self.build_liquidity_instruction(payer, mint, token_program, buffer)
+    }
+
+    pub fn build_withdraw_instruction(
+        &self,
+        payer: &Keypair,
+        mint: &Pubkey,
+        token_program: &Pubkey,
+        withdraw: WithdrawInfo,
+    ) -> Result<Instruction, TradingEndpointError> {
+        let buffer = withdraw.to_buffer().map_err(|e| TradingEndpointError::CustomError(e.to_string()))?;
+        self.build_liquidity_instruction(payer, mint, token_program, buffer)
+    }
+
+    /// Deposit or withdrawal on the pool selected for `mint`, the base moving through `token_program` and the LP mint being Token-2022
+    fn build_liquidity_instruction(
+        &self,
+        payer: &Keypair,
+        mint: &Pubkey,
+        token_program: &Pubkey,
+        buffer: Vec<u8>,
+    ) -> Result<Instruction, TradingEndpointError> {
+        let keys = self.get_pool_keys(mint, token_program)?;
+
+        Ok(Instruction::new_with_bytes(
+            PUBKEY_PUMPSWAP,
+            &buffer,
+            vec![
+                AccountMeta::new(keys.pool, false),
+                AccountMeta::new_readonly(PUBKEY_GLOBAL_ACCOUNT, false),
+                AccountMeta::new_readonly(payer.pubkey(), true),
+                AccountMeta::new_readonly(*mint, false),
+                AccountMeta::new_readonly(keys.quote_mint, false),
+                AccountMeta::new(Self::get_lp_mint_pda(&keys.pool)?, false),
+                AccountMeta::new(get_associated_token_address_with_program_id(&payer.pubkey(), mint, token_program), false),
+                AccountMeta::new(
+                    get_associated_token_address_with_program_id(&payer.pubkey(), &keys.quote_mint, &keys.quote_token_program),
+                    false,
+                ),
+                AccountMeta::new(Self::get_lp_token_account(&payer.pubkey(), &keys.pool)?, false),
+                AccountMeta::new(keys.pool_base_token_account, false),
+                AccountMeta::new(keys.pool_quote_token_account, false),
+                AccountMeta::new_readonly(*token_program, false),
+                AccountMeta::new_readonly(spl_token_2022::ID, false),
+                AccountMeta::new_readonly(PUBKEY_EVENT_AUTHORITY, false),
+                AccountMeta::new_readonly(PUBKEY_PUMPSWAP, false),
+            ],
+        ))
+    }
+
+    /// Base and quote a deposit minting `lp_amount` takes now, the limits raised by slippage
+    pub async fn quote_deposit(&self, mint: &Pubkey, lp_amount: u64, slippage_basis_points: u64) -> Result<LiquidityQuote, TradingEndpointError> {
+        let pool = self.get_pool_state(mint).await?;
+        let (base_amount, quote_amount) = get_deposit_amounts(lp_amount, pool.pool_base_reserve, pool.pool_quote_reserve, pool.pool_account.lp_supply)
+            .ok_or_else(|| TradingEndpointError::CustomError(format!("Cannot deposit into pool {}", pool.pool_address)))?;
+
+        Ok(LiquidityQuote {
+            lp_amount,
+            base_amount,
+            quote_amount,
+            base_limit: calculate_with_slippage_buy(base_amount, slippage_basis_points),
+            quote_limit: calculate_with_slippage_buy(quote_amount, slippage_basis_points),
+        })
+    }
+
+    /// Base and quote a withdrawal burning `lp_amount` returns now, the limits lowered by slippage
+    pub async fn quote_withdraw(&self, mint: &Pubkey, lp_amount: u64, slippage_basis_points: u64) -> Result<LiquidityQuote, TradingEndpointError> {
+        let pool = self.get_pool_state(mint).await?;
+        let (base_amount, quote_amount) = get_withdraw_amounts(lp_amount, pool.pool_base_reserve, pool.pool_quote_reserve, pool.pool_account.lp_supply)
+            .ok_or_else(|| TradingEndpointError::CustomError(format!("Cannot withdraw {} LP tokens from pool {}", lp_amount, pool.pool_address)))?;
+
+        Ok(LiquidityQuote {
+            lp_amount,
+            base_amount,
+            quote_amount,
+            base_limit: calculate_with_slippage_sell(base_amount, slippage_basis_points),
+            quote_limit: calculate_with_slippage_sell(quote_amount, slippage_basis_points),
+        })
+    }
+
+    /// LP tokens `owner` holds in the pool selected for `mint` and what they are worth
+    pub async fn get_lp_position(&self, owner: &Pubkey, mint: &Pubkey) -> Result<LpPosition, TradingEndpointError> {
+        let pool = self.get_pool_state(mint).await?;
+        let lp_token_account = Self::get_lp_token_account(owner, &pool.pool_address)?;
+        let lp_amount = match self.endpoint.rpc.get_multiple_accounts(&[lp_token_account]).await?.pop().flatten() {
+            Some(account) => Self::decode_token_amount(&account.data)?,
+            None => 0,
+        };
+        let lp_supply = pool.pool_account.lp_supply;
+        let (base_amount, quote_amount) = get_withdraw_amounts(lp_amount, pool.pool_base_reserve, pool.pool_quote_reserve, lp_supply).unwrap_or_default();
+
+        Ok(LpPosition {
+            pool: pool.pool_address,
+            lp_amount,
+            lp_supply,
+            base_amount,
+            quote_amount,
+            value_in_quote: get_value_in_quote(base_amount, quote_amount, pool.pool_base_reserve, pool.pool_quote_reserve),
+        })
+    }
+
+    /// Create `new_pool` and select it for later trades of its base mint, wrapping SOL when the quote is WSOL
+    pub async fn create_pool(
+        &self,
+        payer: &Keypair,
+        new_pool: NewPool,
+        fee: Option<PriorityFee>,
+        tip: Option<u64>,
+    ) -> Result<Vec<Signature>, TradingEndpointError> {
+        let NewPool {
+            base_mint,
+            quote_mint,
+            index,
+            base_amount,
+            quote_amount,
+            coin_creator,
+        } = new_pool;
+        let (base_mint, quote_mint) = (&base_mint, &quote_mint);
+        if get_initial_lp_amount(base_amount, quote_amount).is_none() {
+            return Err(TradingEndpointError::CustomError("Initial liquidity too small".to_string()));
+        }
+
+        let (base_mint_info, quote_mint_info, blockhash) = tokio::try_join!(
+            self.endpoint.get_mint_info(base_mint),
+            self.endpoint.get_mint_info(quote_mint),
+            self.endpoint.get_latest_blockhash()
+        )?;
+        let instruction = self.build_create_pool_instruction(
+            payer,
+            base_mint,
+            quote_mint,
+            &base_mint_info.token_program,
+            &quote_mint_info.token_program,
+            CreatePoolInfo {
+                discriminator: PUMPSWAP_CREATE_POOL_DISCRIMINATOR,
+                index,
+                base_amount_in: base_amount,
+                quote_amount_in: quote_amount,
+                coin_creator,
+            },
+        )?;
+        let quote = QuotePayment {
+            mint: *quote_mint,
+            token_program: quote_mint_info.token_program,
+            amount: quote_amount,
+        };
+        let instructions = build_quote_buy_instructions(payer, base_mint, &base_mint_info.token_program, quote, instruction, CreateATA::Idempotent)
+            .map_err(|e| TradingEndpointError::CustomError(e.to_string()))?;
+
+        let signatures = self
+            .endpoint
+            .build_and_broadcast_tx(
+                TransactionType::Buy,
+                payer,
+                instructions,
+                TxSettings::new(blockhash, fee, tip.unwrap_or_default()),
+                None,
+            )
+            .await?;
+
+        let pool = Self::get_pool_pda(index, &payer.pubkey(), base_mint, quote_mint)?;
+        self.pools.write().insert(
+            *base_mint,
+            PoolKeys {
+                pool,
+                quote_mint: *quote_mint,
+                quote_token_program: quote_mint_info.token_program,
+                pool_base_token_account: get_associated_token_address_with_program_id(&pool, base_mint, &base_mint_info.token_program),
+                pool_quote_token_account: get_associated_token_address_with_program_id(&pool, quote_mint, &quote_mint_info.token_program),
+            },
+        );
+        Ok(signatures)
+    }
+
+    /// Mint `lp_amount` LP tokens of the pool selected for `mint`, wrapping SOL when the quote is WSOL
+    pub async fn deposit(
+        &self,
+        payer: &Keypair,
+        mint: &Pubkey,
+        lp_amount: u64,
+        slippage_basis_points: u64,
+        fee: Option<PriorityFee>,
+        tip: Option<u64>,
+    ) -> Result<Vec<Signature>, TradingEndpointError> {
+        let (quote, mint_info, blockhash) = tokio::try_join!(
+            self.quote_deposit(mint, lp_amount, slippage_basis_points),
+            self.endpoint.get_mint_info(mint),
+            self.endpoint.get_latest_blockhash()
+        )?;
+        let (quote_mint, quote_token_program) = self.get_quote_mint(mint);
+        let instruction = self.build_deposit_instruction(
+            payer,
+            mint,
+            &mint_info.token_program,
+            DepositInfo {
+                discriminator: PUMPSWAP_DEPOSIT_DISCRIMINATOR,
+                lp_token_amount_out: lp_amount,
+                max_base_amount_in: quote.base_limit,
+                max_quote_amount_in: quote.quote_limit,
+            },
+        )?;
+        let mut instructions = vec![create_associated_token_account_idempotent(
+            &payer.pubkey(),
+            &payer.pubkey(),
+            &Self::get_lp_mint_pda(&self.get_pool_keys(mint, &mint_info.token_program)?.pool)?,
+            &spl_token_2022::ID,
+        )];
+        instructions.extend(
+            build_quote_buy_instructions(
+                payer,
+                mint,
+                &mint_info.token_program,
+                QuotePayment {
+                    mint: quote_mint,
+                    token_program: quote_token_program,
+                    amount: quote.quote_limit,
+                },
+                instruction,
+                CreateATA::Idempotent,
+            )
+            .map_err(|e| TradingEndpointError::CustomError(e.to_string()))?,
+        );
+
+        self.endpoint
+            .build_and_broadcast_tx(
+                TransactionType::Buy,
+                payer,
+                instructions,
+                TxSettings::new(blockhash, fee, tip.unwrap_or_default()),
+                None,
+            )
+            .await
+    }
+
+    /// Burn `lp_amount` LP tokens of the pool selected for `mint`, unwrapping SOL when the quote is WSOL
+    pub async fn withdraw(
+        &self,
+        payer: &Keypair,
+        mint: &Pubkey,
+        lp_amount: u64,
+        slippage_basis_points: u64,
+        fee: Option<PriorityFee>,
+        tip: Option<u64>,
+    ) -> Result<Vec<Signature>, TradingEndpointError> {
+        let (quote, mint_info, blockhash) = tokio::try_join!(
+            self.quote_withdraw(mint, lp_amount, slippage_basis_points),
+            self.endpoint.get_mint_info(mint),
+            self.endpoint.get_latest_blockhash()
+        )?;
+        let (quote_mint, quote_token_program) = self.get_quote_mint(mint);
+        let instruction = self.build_withdraw_instruction(
+            payer,
+            mint,
+            &mint_info.token_program,
+            WithdrawInfo {
+                discriminator: PUMPSWAP_WITHDRAW_DISCRIMINATOR,
+                lp_token_amount_in: lp_amount,
+                min_base_amount_out: quote.base_limit,
+                min_quote_amount_out: quote.quote_limit,
+            },
+        )?;
+        let (_, mut instructions) = build_token_account_instructions(payer, mint, &mint_info.token_program, CreateATA::Idempotent)
+            .map_err(|e| TradingEndpointError::CustomError(e.to_string()))?;
+        instructions.extend(
+            build_quote_sell_instructions(payer, mint, &mint_info.token_program, &quote_mint, &quote_token_program, instruction, false)
+                .map_err(|e| TradingEndpointError::CustomError(e.to_string()))?,
+        );
+
+        self.endpoint
+            .build_and_broadcast_tx(
+                TransactionType::Sell,
+                payer,
+                instructions,
+                TxSettings::new(blockhash, fee, tip.unwrap_or_default()),
+                None,
+            )
+            .await
+    }
+
+    /// Volume incentives earned by trading on PumpSwap
+    pub fn get_volume_incentives(&self) -> VolumeIncentives {
+        VolumeIncentives::new(self.endpoint.clone(), PUBKEY_PUMPSWAP, PUBKEY_EVENT_AUTHORITY)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dex::pumpfun_common_types::FeeTier;
+    use solana_client::nonblocking::rpc_client::RpcClient;
+
+    fn pumpswap() -> PumpSwap {
+        let rpc = Arc::new(RpcClient::new("http://127.0.0.1:8899".to_string()));
+        PumpSwap::new(Arc::new(TradingEndpoint::new(rpc, vec![])))
+    }
+
+    /// Global account charging 20 bps LP, 5 bps protocol and 5 bps coin creator fees
+    fn global_account() -> Arc<GlobalAccount> {
+        Arc::new(GlobalAccount {
+            discriminator: 0,
+            admin: Pubkey::new_unique(),
+            lp_fee_basis_points: 20,
+            protocol_fee_basis_points: 5,
+            disable_flags: 0,
+            protocol_fee_recipients: [Pubkey::new_unique(); 8],
+            coin_creator_fee_basis_points: 5,
+        })
+    }
+
+    fn fees(lp_fee_bps: u64, protocol_fee_bps: u64, creator_fee_bps: u64) -> Fees {
+        Fees {
+            lp_fee_bps,
+            protocol_fee_bps,
+            creator_fee_bps,
+        }
+    }
+
+    /// Flat fees of 25/5/0 bps, tiers of 2/93/30 bps below 500 SOL of market cap and 20/5/5 bps above
+    fn fee_config() -> Arc<FeeConfig> {
+        Arc::new(FeeConfig {
+            discriminator: 0,
+            bump: 255,
+            admin: Pubkey::new_unique(),
+            flat_fees: fees(25, 5, 0),
+            fee_tiers: vec![
+                FeeTier {
+                    market_cap_lamports_threshold: 0,
+                    fees: fees(2, 93, 30),
+                },
+                FeeTier {
+                    market_cap_lamports_threshold: 500_000_000_000,
+                    fees: fees(20, 5, 5),
+                },
+            ],
+        })
+    }
+
+    /// Pool of 1B supply holding `quote_reserve` lamports against 200M tokens
+    fn pool(mint: &Pubkey, pool_address: Pubkey, quote_reserve: u64) -> PoolInfo {
+        PoolInfo {
+            pool_address,
+            pool_account: PoolAccount {
+                discriminator: 0,
+                pool_bump: 255,
+                index: 0,
+                creator: PumpSwap::get_pool_authority_pda(mint).unwrap(),
+                base_mint: *mint,
+                quote_mint: PUBKEY_WSOL,
+                lp_mint: Pubkey::new_unique(),
+                pool_base_token_account: Pubkey::new_unique(),
+                pool_quote_token_account: Pubkey::new_unique(),
+                lp_supply: 0,
+                coin_creator: Pubkey::new_unique(),
+            },
+            pool_base_reserve: 200_000_000_000_000,
+            pool_quote_reserve: quote_reserve,
+            base_mint_supply: 1_000_000_000_000_000,
+            slot: 0,
+        }
+    }
+
+    fn rates(fees: FeeModel) -> (u64, u64, u64) {
+        (fees.lp_fee_rate, fees.protocol_fee_rate, fees.creator_fee_rate)
+    }
+
+    #[test]
+    fn liquidity_instructions_use_the_base_token_program() {
+        let pumpswap = pumpswap();
+        let payer = Keypair::new();
+        let mint = Pubkey::new_unique();
+        let deposit = DepositInfo {
+            discriminator: PUMPSWAP_DEPOSIT_DISCRIMINATOR,
+            lp_token_amount_out: 1,
+            max_base_amount_in: 1,
+            max_quote_amount_in: 1,
+        };
+
+        let instruction = pumpswap.build_deposit_instruction(&payer, &mint, &spl_token_2022::ID, deposit).unwrap();
+        let pool = PumpSwap::get_pool_address(&mint).unwrap();
+        assert_eq!(
+            instruction.accounts[6].pubkey,
+            get_associated_token_address_with_program_id(&payer.pubkey(), &mint, &spl_token_2022::ID)
+        );
+        assert_eq!(
+            instruction.accounts[9].pubkey,
+            get_associated_token_address_with_program_id(&pool, &mint, &spl_token_2022::ID)
+        );
+        assert_eq!(instruction.accounts[11].pubkey, spl_token_2022::ID);
+    }
+
+    #[test]
+    fn fee_model_requires_initialize() {
+        let mint = Pubkey::new_unique();
+        let pool = pool(&mint, PumpSwap::get_pool_address(&mint).unwrap(), 85_000_000_000);
+        assert!(pumpswap().get_fee_model(&pool).is_err());
+    }
+
+    #[test]
+    fn fee_model_falls_back_to_global_account() {
+        let pumpswap = pumpswap();
+        pumpswap.global_account.set(global_account()).unwrap();
+        let mint = Pubkey::new_unique();
+        let mut pool = pool(&mint, PumpSwap::get_pool_address(&mint).unwrap(), 85_000_000_000);
+
+        assert_eq!(rates(pumpswap.get_fee_model(&pool).unwrap()), (2_000, 500, 500));
+        pool.pool_account.coin_creator = Pubkey::default();
+        assert_eq!(rates(pumpswap.get_fee_model(&pool).unwrap()), (2_000, 500, 0));
+    }
+
+    #[test]
+    fn tiers_apply_to_canonical_pools_only() {
+        let pumpswap = pumpswap();
+        pumpswap.global_account.set(global_account()).unwrap();
+        pumpswap.fee_config.set(fee_config()).unwrap();
+        let mint = Pubkey::new_unique();
+        let canonical = PumpSwap::get_pool_address(&mint).unwrap();
+
+        // 85 SOL for a fifth of the supply is a 425 SOL market cap, 110 SOL a 550 SOL one
+        assert_eq!(
+            rates(pumpswap.get_fee_model(&pool(&mint, canonical, 85_000_000_000)).unwrap()),
+            (200, 9_300, 3_000)
+        );
+        assert_eq!(
+            rates(pumpswap.get_fee_model(&pool(&mint, canonical, 110_000_000_000)).unwrap()),
+            (2_000, 500, 500)
+        );
+
+        let other = pool(&mint, Pubkey::new_unique(), 85_000_000_000);
+        assert!(!PumpSwap::is_canonical_pool(&other));
+        assert_eq!(rates(pumpswap.get_fee_model(&other).unwrap()), (2_500, 500, 0));
+    }
 }
@@ -0,0 +1,101 @@
+use super::pumpswap_types::POOL_MINIMUM_LIQUIDITY;
+
+/// LP tokens a new pool seeded with `base_amount` and `quote_amount` mints to its creator
+pub fn get_initial_lp_amount(base_amount: u64, quote_amount: u64) -> Option<u64> {
+    let lp_supply = (base_amount as u128 * quote_amount as u128).isqrt();
+    u64::try_from(lp_supply).ok()?.checked_sub(POOL_MINIMUM_LIQUIDITY)
+}
+
+/// Base and quote a deposit minting `lp_amount` takes, rounded up
+pub fn get_deposit_amounts(lp_amount: u64, base_reserve: u64, quote_reserve: u64, lp_supply: u64) -> Option<(u64, u64)> {
+    if lp_supply == 0 {
+        return None;
+    }
+    let base_amount = (lp_amount as u128 * base_reserve as u128).div_ceil(lp_supply as u128);
+    let quote_amount = (lp_amount as u128 * quote_reserve as u128).div_ceil(lp_supply as u128);
+    Some((u64::try_from(base_amount).ok()?, u64::try_from(quote_amount).ok()?))
+}
+
+/// Most LP tokens a deposit of at most `base_amount` mints, quote being added in proportion
+pub fn get_lp_amount_for_base(base_amount: u64, base_reserve: u64, lp_supply: u64) -> u64 {
+    match base_reserve {
+        0 => 0,
+        base_reserve => (base_amount as u128 * lp_supply as u128 / base_reserve as u128) as u64,
+    }
+}
+
+/// Most LP tokens a deposit of at most `quote_amount` mints, base being added in proportion
+pub fn get_lp_amount_for_quote(quote_amount: u64, quote_reserve: u64, lp_supply: u64) -> u64 {
+    get_lp_amount_for_base(quote_amount, quote_reserve, lp_supply)
+}
+
+/// Base and quote a withdrawal burning `lp_amount` returns, rounded down
+pub fn get_withdraw_amounts(lp_amount: u64, base_reserve: u64, quote_reserve: u64, lp_supply: u64) -> Option<(u64, u64)> {
+    if lp_supply == 0 || lp_amount > lp_supply {
+        return None;
+    }
+    let base_amount = lp_amount as u128 * base_reserve as u128 / lp_supply as u128;
+    let quote_amount = lp_amount as u128 * quote_reserve as u128 / lp_supply as u128;
+    Some((base_amount as u64, quote_amount as u64))
+}
+
+/// Value in quote of `base_amount` plus `quote_amount` at the pool price
+pub fn get_value_in_quote(base_amount: u64, quote_amount: u64, base_reserve: u64, quote_reserve: u64) -> u64 {
+    let base_value = match base_reserve {
+        0 => 0,
+        base_reserve => base_amount as u128 * quote_reserve as u128 / base_reserve as u128,
+    };
+    (base_value + quote_amount as u128).min(u64::MAX as u128) as u64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // 206.9M tokens against 85 SOL, LP supply a little under the pool's initial sqrt(base * quote)
+    const BASE_RESERVE: u64 = 206_900_000_000_000;
+    const QUOTE_RESERVE: u64 = 85_000_000_000;
+    const LP_SUPPLY: u64 = 4_193_581_405_541;
+
+    #[test]
+    fn initial_lp_amount_locks_the_minimum_liquidity() {
+        assert_eq!(get_initial_lp_amount(BASE_RESERVE, QUOTE_RESERVE), Some(4_193_626_115_804));
+        assert_eq!(get_initial_lp_amount(100, 100), Some(0));
+        assert_eq!(get_initial_lp_amount(99, 100), None);
+    }
+
+    #[test]
+    fn deposit_rounds_up_and_withdraw_rounds_down() {
+        let lp_amount = 1_000_000_000;
+        assert_eq!(
+            get_deposit_amounts(lp_amount, BASE_RESERVE, QUOTE_RESERVE, LP_SUPPLY),
+            Some((49_337_303_845, 20_269_072))
+        );
+        assert_eq!(
+            get_withdraw_amounts(lp_amount, BASE_RESERVE, QUOTE_RESERVE, LP_SUPPLY),
+            Some((49_337_303_844, 20_269_071))
+        );
+        assert_eq!(get_deposit_amounts(lp_amount, BASE_RESERVE, QUOTE_RESERVE, 0), None);
+        assert_eq!(get_withdraw_amounts(LP_SUPPLY + 1, BASE_RESERVE, QUOTE_RESERVE, LP_SUPPLY), None);
+    }
+
+    #[test]
+    fn lp_amount_for_one_side_never_overspends_it() {
+        let lp_amount = get_lp_amount_for_base(1_000_000_000_000, BASE_RESERVE, LP_SUPPLY);
+        assert_eq!(lp_amount, 20_268_638_982);
+        assert!(get_deposit_amounts(lp_amount, BASE_RESERVE, QUOTE_RESERVE, LP_SUPPLY).unwrap().0 <= 1_000_000_000_000);
+
+        let lp_amount = get_lp_amount_for_quote(1_000_000_000, QUOTE_RESERVE, LP_SUPPLY);
+        assert_eq!(lp_amount, 49_336_251_829);
+        assert!(get_deposit_amounts(lp_amount, BASE_RESERVE, QUOTE_RESERVE, LP_SUPPLY).unwrap().1 <= 1_000_000_000);
+
+        assert_eq!(get_lp_amount_for_base(1_000, 0, LP_SUPPLY), 0);
+    }
+
+    #[test]
+    fn value_in_quote_prices_base_at_the_pool_price() {
+        let (base_amount, quote_amount) = get_withdraw_amounts(1_000_000_000, BASE_RESERVE, QUOTE_RESERVE, LP_SUPPLY).unwrap();
+        assert_eq!(get_value_in_quote(base_amount, quote_amount, BASE_RESERVE, QUOTE_RESERVE), 40_538_142);
+        assert_eq!(get_value_in_quote(1_000, 5, 0, QUOTE_RESERVE), 5);
+    }
+}
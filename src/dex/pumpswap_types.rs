@@ -6,6 +6,17 @@ pub const PUBKEY_PUMPSWAP: Pubkey = pubkey!("pAMMBay6oceH9fJKBRHGP5D4bD4sWpmSwMn
 pub const PUBKEY_GLOBAL_ACCOUNT: Pubkey = pubkey!("ADyA8hdefvWN2dbGGWFotbzWxrAvLW83WG6QCVXvJKqw");
 pub const PUBKEY_EVENT_AUTHORITY: Pubkey = pubkey!("GS4CU59F31iL7aR2Q8zVS8DRrcRnXX1yjQ66TqNVQnaR");
 
+/// Anchor discriminator of `Pool` accounts
+pub const POOL_ACCOUNT_DISCRIMINATOR: [u8; 8] = [241, 154, 109, 4, 17, 177, 109, 188];
+pub const PUMPSWAP_CREATE_POOL_DISCRIMINATOR: u64 = 13564957318303552233;
+pub const PUMPSWAP_DEPOSIT_DISCRIMINATOR: u64 = 13182846803881894898;
+pub const PUMPSWAP_WITHDRAW_DISCRIMINATOR: u64 = 2495396153584390839;
+pub const PUMPSWAP_COLLECT_COIN_CREATOR_FEE_DISCRIMINATOR: u64 = 4768058240717633952;
+/// LP tokens of a new pool that stay locked in it rather than going to its creator
+pub const POOL_MINIMUM_LIQUIDITY: u64 = 100;
+/// Offset of `PoolAccount.base_mint`: discriminator, bump, index and creator precede it
+pub const POOL_ACCOUNT_BASE_MINT_OFFSET: usize = 43;
+
 #[derive(Debug, Clone, BorshSerialize, BorshDeserialize, Serialize, Deserialize)]
 pub struct GlobalAccount {
     pub discriminator: u64,
@@ -14,6 +25,7 @@ pub struct GlobalAccount {
     pub protocol_fee_basis_points: u64,
     pub disable_flags: u8,
     pub protocol_fee_recipients: [Pubkey; 8],
+    pub coin_creator_fee_basis_points: u64,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -30,9 +42,123 @@ pub struct PoolAccount {
     pub lp_supply: u64,
     pub coin_creator: Pubkey,
 }
+
+/// Accounts a buy or sell needs from a pool, resolved once per mint
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PoolKeys {
+    pub pool: Pubkey,
+    pub quote_mint: Pubkey,
+    pub quote_token_program: Pubkey,
+    pub pool_base_token_account: Pubkey,
+    pub pool_quote_token_account: Pubkey,
+}
+
+impl PoolKeys {
+    pub fn new(pool: Pubkey, pool_account: &PoolAccount, quote_token_program: Pubkey) -> Self {
+        Self {
+            pool,
+            quote_mint: pool_account.quote_mint,
+            quote_token_program,
+            pool_base_token_account: pool_account.pool_base_token_account,
+            pool_quote_token_account: pool_account.pool_quote_token_account,
+        }
+    }
+}
+
 pub struct PoolInfo {
     pub pool_address: Pubkey,
     pub pool_account: PoolAccount,
     pub pool_base_reserve: u64,
     pub pool_quote_reserve: u64,
+    /// Supply of the base mint, which prices the pool's market cap
+    pub base_mint_supply: u64,
+    /// Slot the reserves were read at
+    pub slot: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, BorshSerialize, BorshDeserialize)]
+pub struct CreatePoolInfo {
+    pub discriminator: u64,
+    pub index: u16,
+    pub base_amount_in: u64,
+    pub quote_amount_in: u64,
+    pub coin_creator: Pubkey,
+}
+
+impl CreatePoolInfo {
+    pub fn to_buffer(&self) -> anyhow::Result<Vec<u8>> {
+        let mut buffer = Vec::new();
+        self.serialize(&mut buffer)?;
+        Ok(buffer)
+    }
+}
+
+/// Pool `index` of `base_mint` against `quote_mint`, seeded with the given amounts
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct NewPool {
+    pub base_mint: Pubkey,
+    pub quote_mint: Pubkey,
+    pub index: u16,
+    pub base_amount: u64,
+    pub quote_amount: u64,
+    /// Receives the coin creator fee on every swap, `Pubkey::default()` for no creator fee
+    pub coin_creator: Pubkey,
+}
+
+/// Mint exactly `lp_token_amount_out`, spending at most the max amounts
+#[derive(Debug, Clone, PartialEq, Eq, BorshSerialize, BorshDeserialize)]
+pub struct DepositInfo {
+    pub discriminator: u64,
+    pub lp_token_amount_out: u64,
+    pub max_base_amount_in: u64,
+    pub max_quote_amount_in: u64,
+}
+
+impl DepositInfo {
+    pub fn to_buffer(&self) -> anyhow::Result<Vec<u8>> {
+        let mut buffer = Vec::new();
+        self.serialize(&mut buffer)?;
+        Ok(buffer)
+    }
+}
+
+/// Burn exactly `lp_token_amount_in`, receiving at least the min amounts
+#[derive(Debug, Clone, PartialEq, Eq, BorshSerialize, BorshDeserialize)]
+pub struct WithdrawInfo {
+    pub discriminator: u64,
+    pub lp_token_amount_in: u64,
+    pub min_base_amount_out: u64,
+    pub min_quote_amount_out: u64,
+}
+
+impl WithdrawInfo {
+    pub fn to_buffer(&self) -> anyhow::Result<Vec<u8>> {
+        let mut buffer = Vec::new();
+        self.serialize(&mut buffer)?;
+        Ok(buffer)
+    }
+}
+
+/// Base and quote moved by a deposit or withdrawal of `lp_amount`, with the slippage bounds sent to the program
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LiquidityQuote {
+    pub lp_amount: u64,
+    pub base_amount: u64,
+    pub quote_amount: u64,
+    /// Most base spent by a deposit, least base received by a withdrawal
+    pub base_limit: u64,
+    pub quote_limit: u64,
+}
+
+/// Share of a pool held through its LP tokens
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LpPosition {
+    pub pool: Pubkey,
+    pub lp_amount: u64,
+    pub lp_supply: u64,
+    /// Base and quote the LP tokens withdraw for
+    pub base_amount: u64,
+    pub quote_amount: u64,
+    /// Both sides valued in quote at the pool price
+    pub value_in_quote: u64,
 }
@@ -0,0 +1,456 @@
+use super::{
+    amm_calc::{FeeModel, FEE_RATE_DENOMINATOR},
+    dex_traits::DexTrait,
+    raydium_amm_types::*,
+    types::{BuySwapInfo, BuyVariant, Create, SwapInfo},
+};
+use crate::{
+    common::{accounts::PUBKEY_WSOL, trading_endpoint::TradingEndpoint},
+    errors::trading_endpoint_error::TradingEndpointError,
+    instruction::builder::PriorityFee,
+};
+use parking_lot::RwLock;
+use solana_sdk::{
+    instruction::{AccountMeta, Instruction},
+    pubkey::Pubkey,
+    signature::{Keypair, Signature},
+    signer::Signer,
+};
+use spl_associated_token_account::{get_associated_token_address, get_associated_token_address_with_program_id};
+use spl_token_2022::{extension::StateWithExtensions, state::Account as TokenAccount};
+use std::{collections::HashMap, sync::Arc};
+
+pub struct RaydiumAmm {
+    pub endpoint: Arc<TradingEndpoint>,
+    pools: RwLock<HashMap<Pubkey, PoolKeys>>,
+}
+
+#[async_trait::async_trait]
+impl DexTrait for RaydiumAmm {
+    async fn initialize(&self) -> Result<(), TradingEndpointError> {
+        Ok(())
+    }
+
+    fn initialized(&self) -> Result<(), TradingEndpointError> {
+        Ok(())
+    }
+
+    fn get_trading_endpoint(&self) -> Arc<TradingEndpoint> {
+        self.endpoint.clone()
+    }
+
+    fn use_wsol(&self) -> bool {
+        true
+    }
+
+    async fn get_pool(&self, mint: &Pubkey) -> Result<super::types::PoolInfo, TradingEndpointError> {
+        let pool = self.get_pool_state(mint).await?;
+        let (token_reserves, sol_reserves) = pool.get_reserves(mint);
+        Ok(super::types::PoolInfo {
+            pool: pool.pool_address,
+            creator: None,
+            creator_vault: None,
+            config: None,
+            token_reserves,
+            sol_reserves,
+            fees: Self::get_fee_model(&pool.amm_info),
+            slot: pool.slot,
+        })
+    }
+
+    fn get_quote_mint(&self, mint: &Pubkey) -> (Pubkey, Pubkey) {
+        match self.pools.read().get(mint) {
+            Some(keys) => (keys.quote_mint, spl_token::ID),
+            None => (PUBKEY_WSOL, spl_token::ID),
+        }
+    }
+
+    async fn create(&self, _: Keypair, _: Create, _: Option<PriorityFee>, _: Option<u64>) -> Result<Vec<Signature>, TradingEndpointError> {
+        Err(TradingEndpointError::CustomError("Not supported".to_string()))
+    }
+
+    fn build_buy_instruction(
+        &self,
+        payer: &Keypair,
+        mint: &Pubkey,
+        custom_ata: Option<&Pubkey>,
+        extra_accounts: &[AccountMeta],
+        token_program: &Pubkey,
+        buy: SwapInfo,
+    ) -> Result<Instruction, TradingEndpointError> {
+        self.build_buy_instruction_with_variant(
+            payer,
+            mint,
+            custom_ata,
+            extra_accounts,
+            token_program,
+            buy.with_variant(BuyVariant::ExactTokensOut),
+        )
+    }
+
+    fn build_buy_instruction_with_variant(
+        &self,
+        payer: &Keypair,
+        mint: &Pubkey,
+        custom_ata: Option<&Pubkey>,
+        _: &[AccountMeta],
+        token_program: &Pubkey,
+        buy: BuySwapInfo,
+    ) -> Result<Instruction, TradingEndpointError> {
+        let BuySwapInfo { swap: buy, variant } = buy;
+        let buffer = match variant {
+            BuyVariant::ExactTokensOut => SwapBaseOutInfo {
+                instruction: RAYDIUM_AMM_SWAP_BASE_OUT_V2_INSTRUCTION,
+                max_amount_in: buy.sol_amount,
+                amount_out: buy.token_amount,
+            }
+            .to_buffer(),
+            BuyVariant::ExactSolIn => SwapBaseInInfo {
+                instruction: RAYDIUM_AMM_SWAP_BASE_IN_V2_INSTRUCTION,
+                amount_in: buy.sol_amount,
+                minimum_amount_out: buy.token_amount,
+            }
+            .to_buffer(),
+        }
+        .map_err(|e| TradingEndpointError::CustomError(e.to_string()))?;
+
+        let keys = self.get_pool_keys(mint)?;
+        let ata = match custom_ata {
+            None => get_associated_token_address_with_program_id(&payer.pubkey(), mint, token_program),
+            Some(t) => *t,
+        };
+        Ok(Self::build_swap_instruction(
+            payer,
+            &keys,
+            &get_associated_token_address(&payer.pubkey(), &keys.quote_mint),
+            &ata,
+            buffer,
+        ))
+    }
+
+    fn build_sell_instruction(
+        &self,
+        payer: &Keypair,
+        mint: &Pubkey,
+        custom_ata: Option<&Pubkey>,
+        _: &[AccountMeta],
+        token_program: &Pubkey,
+        sell: SwapInfo,
+    ) -> Result<Instruction, TradingEndpointError> {
+        let buffer = SwapBaseInInfo {
+            instruction: RAYDIUM_AMM_SWAP_BASE_IN_V2_INSTRUCTION,
+            amount_in: sell.token_amount,
+            minimum_amount_out: sell.sol_amount,
+        }
+        .to_buffer()
+        .map_err(|e| TradingEndpointError::CustomError(e.to_string()))?;
+
+        let keys = self.get_pool_keys(mint)?;
+        let ata = match custom_ata {
+            None => get_associated_token_address_with_program_id(&payer.pubkey(), mint, token_program),
+            Some(t) => *t,
+        };
+        Ok(Self::build_swap_instruction(
+            payer,
+            &keys,
+            &ata,
+            &get_associated_token_address(&payer.pubkey(), &keys.quote_mint),
+            buffer,
+        ))
+    }
+}
+
+impl RaydiumAmm {
+    pub fn new(endpoint: Arc<TradingEndpoint>) -> Self {
+        Self {
+            endpoint,
+            pools: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// Every AMM v4 pool with `mint` on either side
+    pub async fn get_pools(&self, mint: &Pubkey) -> Result<Vec<PoolInfo>, TradingEndpointError> {
+        let (coin_pools, pc_pools) = tokio::try_join!(
+            self.get_pool_accounts(AMM_INFO_COIN_MINT_OFFSET, mint),
+            self.get_pool_accounts(AMM_INFO_PC_MINT_OFFSET, mint)
+        )?;
+        let pools = coin_pools.into_iter().chain(pc_pools).collect::<Vec<_>>();
+
+        let vaults = pools
+            .iter()
+            .flat_map(|(_, amm_info)| [amm_info.coin_vault, amm_info.pc_vault])
+            .collect::<Vec<_>>();
+        let mut balances = Vec::with_capacity(vaults.len());
+        let mut slot = 0;
+        for chunk in vaults.chunks(100) {
+            let (accounts, chunk_slot) = self.endpoint.get_multiple_accounts_with_slot(chunk).await?;
+            slot = slot.max(chunk_slot);
+            for account in accounts {
+                balances.push(match account {
+                    Some(account) => Self::decode_token_amount(&account.data)?,
+                    None => 0,
+                });
+            }
+        }
+
+        Ok(pools
+            .into_iter()
+            .zip(balances.chunks(2))
+            .map(|((pool_address, amm_info), balances)| {
+                let (coin_reserve, pc_reserve) = amm_info.get_reserves(balances[0], balances[1]);
+                PoolInfo {
+                    pool_address,
+                    amm_info,
+                    coin_reserve,
+                    pc_reserve,
+                    slot,
+                }
+            })
+            .collect())
+    }
+
+    async fn get_pool_accounts(&self, offset: usize, mint: &Pubkey) -> Result<Vec<(Pubkey, AmmInfo)>, TradingEndpointError> {
+        let accounts = self
+            .endpoint
+            .rpc
+            .get_program_accounts_with_config(
+                &PUBKEY_RAYDIUM_AMM,
+                solana_client::rpc_config::RpcProgramAccountsConfig {
+                    filters: Some(vec![
+                        solana_client::rpc_filter::RpcFilterType::DataSize(AMM_INFO_SIZE),
+                        solana_client::rpc_filter::RpcFilterType::Memcmp(solana_client::rpc_filter::Memcmp::new_raw_bytes(offset, mint.to_bytes().to_vec())),
+                    ]),
+                    account_config: solana_client::rpc_config::RpcAccountInfoConfig {
+                        encoding: Some(solana_account_decoder::UiAccountEncoding::Base64),
+                        commitment: None,
+                        data_slice: None,
+                        min_context_slot: None,
+                    },
+                    with_context: None,
+                    sort_results: None,
+                },
+            )
+            .await?;
+
+        accounts
+            .iter()
+            .map(|(pool, account)| Ok((*pool, Self::decode_amm_info(&account.data)?)))
+            .collect()
+    }
+
+    /// Deepest pool pairing `mint` with `quote_mint`, or with WSOL when any does and no quote mint is given.
+    /// The pool is remembered for `mint`, so later quotes and trades use it.
+    pub async fn select_pool(&self, mint: &Pubkey, quote_mint: Option<&Pubkey>) -> Result<PoolInfo, TradingEndpointError> {
+        let pools = self.get_pools(mint).await?;
+        let quote_mint = match quote_mint {
+            Some(quote_mint) => Some(*quote_mint),
+            None => pools.iter().any(|pool| pool.get_quote_mint(mint) == PUBKEY_WSOL).then_some(PUBKEY_WSOL),
+        };
+        let pool = pools
+            .into_iter()
+            .filter(|pool| quote_mint.is_none_or(|quote_mint| pool.get_quote_mint(mint) == quote_mint))
+            .max_by_key(|pool| pool.get_reserves(mint).1)
+            .ok_or_else(|| TradingEndpointError::CustomError(format!("No Raydium AMM pool found for mint: {}", mint)))?;
+
+        self.pools.write().insert(
+            *mint,
+            PoolKeys {
+                pool: pool.pool_address,
+                quote_mint: pool.get_quote_mint(mint),
+                coin_vault: pool.amm_info.coin_vault,
+                pc_vault: pool.amm_info.pc_vault,
+            },
+        );
+        Ok(pool)
+    }
+
+    /// Pool account and reserves of the pool selected for `mint`, selecting the deepest one first if none was
+    pub async fn get_pool_state(&self, mint: &Pubkey) -> Result<PoolInfo, TradingEndpointError> {
+        let cached = self.pools.read().get(mint).copied();
+        let Some(keys) = cached else {
+            return self.select_pool(mint, None).await;
+        };
+
+        let (accounts, slot) = self
+            .endpoint
+            .get_multiple_accounts_with_slot(&[keys.pool, keys.coin_vault, keys.pc_vault])
+            .await?;
+        let [pool_account, coin_vault_account, pc_vault_account] =
+            <[_; 3]>::try_from(accounts).map_err(|_| TradingEndpointError::CustomError(format!("Pool accounts not found: {}", mint)))?;
+
+        let pool_account = pool_account.ok_or_else(|| TradingEndpointError::CustomError(format!("Pool account not found: {}", mint)))?;
+        let coin_vault_account = coin_vault_account.ok_or_else(|| TradingEndpointError::CustomError(format!("Pool coin vault not found: {}", mint)))?;
+        let pc_vault_account = pc_vault_account.ok_or_else(|| TradingEndpointError::CustomError(format!("Pool pc vault not found: {}", mint)))?;
+
+        let amm_info = Self::decode_amm_info(&pool_account.data)?;
+        let (coin_reserve, pc_reserve) = amm_info.get_reserves(
+            Self::decode_token_amount(&coin_vault_account.data)?,
+            Self::decode_token_amount(&pc_vault_account.data)?,
+        );
+        Ok(PoolInfo {
+            pool_address: keys.pool,
+            amm_info,
+            coin_reserve,
+            pc_reserve,
+            slot,
+        })
+    }
+
+    /// Accounts of the pool selected for `mint`, which must have been looked up first
+    pub fn get_pool_keys(&self, mint: &Pubkey) -> Result<PoolKeys, TradingEndpointError> {
+        self.pools
+            .read()
+            .get(mint)
+            .copied()
+            .ok_or_else(|| TradingEndpointError::CustomError(format!("No Raydium AMM pool selected for mint: {}", mint)))
+    }
+
+    pub fn decode_amm_info(data: &[u8]) -> Result<AmmInfo, TradingEndpointError> {
+        bincode::deserialize::<AmmInfo>(data).map_err(|e| TradingEndpointError::CustomError(e.to_string()))
+    }
+
+    fn decode_token_amount(data: &[u8]) -> Result<u64, TradingEndpointError> {
+        let state = StateWithExtensions::<TokenAccount>::unpack(data).map_err(|e| TradingEndpointError::CustomError(e.to_string()))?;
+        Ok(state.base.amount)
+    }
+
+    /// Swap fee charged on the amount in, all of it counted as the LP fee
+    pub fn get_fee_model(amm_info: &AmmInfo) -> FeeModel {
+        let fees = &amm_info.fees;
+        FeeModel {
+            lp_fee_rate: match fees.swap_fee_denominator {
+                0 => 0,
+                denominator => (fees.swap_fee_numerator * FEE_RATE_DENOMINATOR).div_ceil(denominator),
+            },
+            charged_on_top: false,
+            ..Default::default()
+        }
+    }
+
+    fn build_swap_instruction(payer: &Keypair, keys: &PoolKeys, source: &Pubkey, destination: &Pubkey, buffer: Vec<u8>) -> Instruction {
+        Instruction::new_with_bytes(
+            PUBKEY_RAYDIUM_AMM,
+            &buffer,
+            vec![
+                AccountMeta::new_readonly(spl_token::ID, false),
+                AccountMeta::new(keys.pool, false),
+                AccountMeta::new_readonly(PUBKEY_RAYDIUM_AMM_AUTHORITY, false),
+                AccountMeta::new(keys.coin_vault, false),
+                AccountMeta::new(keys.pc_vault, false),
+                AccountMeta::new(*source, false),
+                AccountMeta::new(*destination, false),
+                AccountMeta::new_readonly(payer.pubkey(), true),
+            ],
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_client::nonblocking::rpc_client::RpcClient;
+
+    const FEES_OFFSET: usize = 16 * 8;
+
+    fn amm_info_bytes(mint: &Pubkey, coin_vault: &Pubkey, pc_vault: &Pubkey) -> Vec<u8> {
+        let mut data = vec![0u8; AMM_INFO_SIZE as usize];
+        // swap_fee_numerator and swap_fee_denominator, the last two of the fees
+        data[FEES_OFFSET + 48..FEES_OFFSET + 56].copy_from_slice(&25u64.to_le_bytes());
+        data[FEES_OFFSET + 56..FEES_OFFSET + 64].copy_from_slice(&10_000u64.to_le_bytes());
+        data[AMM_INFO_COIN_MINT_OFFSET - 64..AMM_INFO_COIN_MINT_OFFSET - 32].copy_from_slice(coin_vault.as_ref());
+        data[AMM_INFO_COIN_MINT_OFFSET - 32..AMM_INFO_COIN_MINT_OFFSET].copy_from_slice(pc_vault.as_ref());
+        data[AMM_INFO_COIN_MINT_OFFSET..AMM_INFO_COIN_MINT_OFFSET + 32].copy_from_slice(mint.as_ref());
+        data[AMM_INFO_PC_MINT_OFFSET..AMM_INFO_PC_MINT_OFFSET + 32].copy_from_slice(PUBKEY_WSOL.as_ref());
+        data
+    }
+
+    /// AMM with a pool of `mint` against WSOL selected, `mint` being the coin side
+    fn amm(mint: &Pubkey) -> (RaydiumAmm, PoolKeys) {
+        let rpc = Arc::new(RpcClient::new("http://127.0.0.1:8899".to_string()));
+        let amm = RaydiumAmm::new(Arc::new(TradingEndpoint::new(rpc, vec![])));
+        let keys = PoolKeys {
+            pool: Pubkey::new_unique(),
+            quote_mint: PUBKEY_WSOL,
+            coin_vault: Pubkey::new_unique(),
+            pc_vault: Pubkey::new_unique(),
+        };
+        amm.pools.write().insert(*mint, keys);
+        (amm, keys)
+    }
+
+    fn swap_accounts(payer: &Keypair, keys: &PoolKeys, source: Pubkey, destination: Pubkey) -> Vec<AccountMeta> {
+        vec![
+            AccountMeta::new_readonly(spl_token::ID, false),
+            AccountMeta::new(keys.pool, false),
+            AccountMeta::new_readonly(PUBKEY_RAYDIUM_AMM_AUTHORITY, false),
+            AccountMeta::new(keys.coin_vault, false),
+            AccountMeta::new(keys.pc_vault, false),
+            AccountMeta::new(source, false),
+            AccountMeta::new(destination, false),
+            AccountMeta::new_readonly(payer.pubkey(), true),
+        ]
+    }
+
+    fn data(instruction: u8, first: u64, second: u64) -> Vec<u8> {
+        let mut data = vec![instruction];
+        data.extend_from_slice(&first.to_le_bytes());
+        data.extend_from_slice(&second.to_le_bytes());
+        data
+    }
+
+    #[test]
+    fn decodes_mints_vaults_and_swap_fee() {
+        let (mint, coin_vault, pc_vault) = (Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique());
+        let amm_info = RaydiumAmm::decode_amm_info(&amm_info_bytes(&mint, &coin_vault, &pc_vault)).unwrap();
+        assert_eq!(amm_info.coin_vault_mint, mint);
+        assert_eq!(amm_info.pc_vault_mint, PUBKEY_WSOL);
+        assert_eq!(amm_info.coin_vault, coin_vault);
+        assert_eq!(amm_info.pc_vault, pc_vault);
+
+        // 25 / 10_000 is 0.25%, all of it to the pool
+        let fees = RaydiumAmm::get_fee_model(&amm_info);
+        assert_eq!(fees.lp_fee_rate, 2_500);
+        assert_eq!(fees.total_fee_rate(), 2_500);
+        assert!(!fees.charged_on_top);
+    }
+
+    #[test]
+    fn buy_and_sell_instruction_layout() {
+        let payer = Keypair::new();
+        let mint = Pubkey::new_unique();
+        let (amm, keys) = amm(&mint);
+        let ata = get_associated_token_address(&payer.pubkey(), &mint);
+        let wsol_ata = get_associated_token_address(&payer.pubkey(), &PUBKEY_WSOL);
+        let swap = || SwapInfo {
+            sol_amount: 1_000_000_000,
+            token_amount: 5_000_000,
+        };
+
+        let exact_out = amm.build_buy_instruction(&payer, &mint, None, &[], &spl_token::ID, swap()).unwrap();
+        assert_eq!(exact_out.program_id, PUBKEY_RAYDIUM_AMM);
+        assert_eq!(exact_out.accounts, swap_accounts(&payer, &keys, wsol_ata, ata));
+        assert_eq!(exact_out.data, data(RAYDIUM_AMM_SWAP_BASE_OUT_V2_INSTRUCTION, 1_000_000_000, 5_000_000));
+
+        let exact_in = amm
+            .build_buy_instruction_with_variant(&payer, &mint, None, &[], &spl_token::ID, swap().with_variant(BuyVariant::ExactSolIn))
+            .unwrap();
+        assert_eq!(exact_in.accounts, swap_accounts(&payer, &keys, wsol_ata, ata));
+        assert_eq!(exact_in.data, data(RAYDIUM_AMM_SWAP_BASE_IN_V2_INSTRUCTION, 1_000_000_000, 5_000_000));
+
+        let sell = amm.build_sell_instruction(&payer, &mint, None, &[], &spl_token::ID, swap()).unwrap();
+        assert_eq!(sell.accounts, swap_accounts(&payer, &keys, ata, wsol_ata));
+        assert_eq!(sell.data, data(RAYDIUM_AMM_SWAP_BASE_IN_V2_INSTRUCTION, 5_000_000, 1_000_000_000));
+    }
+
+    #[test]
+    fn swaps_need_a_selected_pool() {
+        let (amm, _) = amm(&Pubkey::new_unique());
+        let swap = SwapInfo {
+            sol_amount: 1,
+            token_amount: 1,
+        };
+        assert!(amm
+            .build_sell_instruction(&Keypair::new(), &Pubkey::new_unique(), None, &[], &spl_token::ID, swap)
+            .is_err());
+    }
+}
@@ -0,0 +1,160 @@
+use borsh::{BorshDeserialize, BorshSerialize};
+use serde::{Deserialize, Serialize};
+use solana_sdk::{pubkey, pubkey::Pubkey};
+
+pub const PUBKEY_RAYDIUM_AMM: Pubkey = pubkey!("675kPX9MHTjS2zt1qfr1NYHuzeLXfQM9H24wFSUt1Mp8");
+pub const PUBKEY_RAYDIUM_AMM_AUTHORITY: Pubkey = pubkey!("5Q544fKrFoe6tsEbD7S8EmxGTJYAKtTVhAW5Q5pge4j1");
+
+/// Swaps without the OpenBook market accounts
+pub const RAYDIUM_AMM_SWAP_BASE_IN_V2_INSTRUCTION: u8 = 16;
+pub const RAYDIUM_AMM_SWAP_BASE_OUT_V2_INSTRUCTION: u8 = 17;
+
+pub const AMM_INFO_SIZE: u64 = 752;
+/// Offsets of `AmmInfo.coin_vault_mint` and `AmmInfo.pc_vault_mint`
+pub const AMM_INFO_COIN_MINT_OFFSET: usize = 400;
+pub const AMM_INFO_PC_MINT_OFFSET: usize = 432;
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct AmmFees {
+    pub min_separate_numerator: u64,
+    pub min_separate_denominator: u64,
+    pub trade_fee_numerator: u64,
+    pub trade_fee_denominator: u64,
+    pub pnl_numerator: u64,
+    pub pnl_denominator: u64,
+    pub swap_fee_numerator: u64,
+    pub swap_fee_denominator: u64,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct AmmStateData {
+    pub need_take_pnl_coin: u64,
+    pub need_take_pnl_pc: u64,
+    pub total_pnl_pc: u64,
+    pub total_pnl_coin: u64,
+    pub pool_open_time: u64,
+    pub punish_pc_amount: u64,
+    pub punish_coin_amount: u64,
+    pub orderbook_to_init_time: u64,
+    pub swap_coin_in_amount: u128,
+    pub swap_pc_out_amount: u128,
+    pub swap_acc_pc_fee: u64,
+    pub swap_pc_in_amount: u128,
+    pub swap_coin_out_amount: u128,
+    pub swap_acc_coin_fee: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AmmInfo {
+    pub status: u64,
+    pub nonce: u64,
+    pub order_num: u64,
+    pub depth: u64,
+    pub coin_decimals: u64,
+    pub pc_decimals: u64,
+    pub state: u64,
+    pub reset_flag: u64,
+    pub min_size: u64,
+    pub vol_max_cut_ratio: u64,
+    pub amount_wave: u64,
+    pub coin_lot_size: u64,
+    pub pc_lot_size: u64,
+    pub min_price_multiplier: u64,
+    pub max_price_multiplier: u64,
+    pub sys_decimal_value: u64,
+    pub fees: AmmFees,
+    pub state_data: AmmStateData,
+    pub coin_vault: Pubkey,
+    pub pc_vault: Pubkey,
+    pub coin_vault_mint: Pubkey,
+    pub pc_vault_mint: Pubkey,
+    pub lp_mint: Pubkey,
+    pub open_orders: Pubkey,
+    pub market: Pubkey,
+    pub market_program: Pubkey,
+    pub target_orders: Pubkey,
+    pub padding1: [u64; 8],
+    pub amm_owner: Pubkey,
+    pub lp_amount: u64,
+    pub client_order_id: u64,
+    pub recent_epoch: u64,
+    pub padding2: u64,
+}
+
+impl AmmInfo {
+    /// Vault balances minus the PnL the pool owes its owner
+    pub fn get_reserves(&self, coin_vault_amount: u64, pc_vault_amount: u64) -> (u64, u64) {
+        (
+            coin_vault_amount.saturating_sub(self.state_data.need_take_pnl_coin),
+            pc_vault_amount.saturating_sub(self.state_data.need_take_pnl_pc),
+        )
+    }
+}
+
+/// Accounts a swap needs from a pool, resolved once per mint
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PoolKeys {
+    pub pool: Pubkey,
+    pub quote_mint: Pubkey,
+    pub coin_vault: Pubkey,
+    pub pc_vault: Pubkey,
+}
+
+pub struct PoolInfo {
+    pub pool_address: Pubkey,
+    pub amm_info: AmmInfo,
+    pub coin_reserve: u64,
+    pub pc_reserve: u64,
+    /// Slot the reserves were read at
+    pub slot: u64,
+}
+
+impl PoolInfo {
+    /// Reserves of `mint` and of the other side of the pool
+    pub fn get_reserves(&self, mint: &Pubkey) -> (u64, u64) {
+        match self.amm_info.coin_vault_mint == *mint {
+            true => (self.coin_reserve, self.pc_reserve),
+            false => (self.pc_reserve, self.coin_reserve),
+        }
+    }
+
+    /// Mint `mint` trades against in this pool
+    pub fn get_quote_mint(&self, mint: &Pubkey) -> Pubkey {
+        match self.amm_info.coin_vault_mint == *mint {
+            true => self.amm_info.pc_vault_mint,
+            false => self.amm_info.coin_vault_mint,
+        }
+    }
+}
+
+/// Spend exactly `amount_in`, receiving at least `minimum_amount_out`
+#[derive(Debug, Clone, PartialEq, Eq, BorshSerialize, BorshDeserialize)]
+pub struct SwapBaseInInfo {
+    pub instruction: u8,
+    pub amount_in: u64,
+    pub minimum_amount_out: u64,
+}
+
+impl SwapBaseInInfo {
+    pub fn to_buffer(&self) -> anyhow::Result<Vec<u8>> {
+        let mut buffer = Vec::new();
+        self.serialize(&mut buffer)?;
+        Ok(buffer)
+    }
+}
+
+/// Receive exactly `amount_out`, spending at most `max_amount_in`
+#[derive(Debug, Clone, PartialEq, Eq, BorshSerialize, BorshDeserialize)]
+pub struct SwapBaseOutInfo {
+    pub instruction: u8,
+    pub max_amount_in: u64,
+    pub amount_out: u64,
+}
+
+impl SwapBaseOutInfo {
+    pub fn to_buffer(&self) -> anyhow::Result<Vec<u8>> {
+        let mut buffer = Vec::new();
+        self.serialize(&mut buffer)?;
+        Ok(buffer)
+    }
+}
@@ -1,33 +1,70 @@
 use super::{
+    amm_calc::{amm_buy_get_token_out_with_fees, calculate_with_slippage_sell, FeeModel},
     dex_traits::DexTrait,
     raydium_bonk_types::*,
-    types::{Create, PoolInfo, SwapInfo},
+    types::{Create, CreateATA, PoolInfo, SwapInfo},
 };
 use crate::{
-    common::{accounts::PUBKEY_WSOL, trading_endpoint::TradingEndpoint},
+    common::trading_endpoint::{TradingEndpoint, TransactionType, TxSettings},
     errors::trading_endpoint_error::TradingEndpointError,
-    instruction::builder::PriorityFee,
+    instruction::builder::{build_quote_buy_instructions, PriorityFee, QuotePayment},
 };
+use borsh::BorshDeserialize;
+use once_cell::sync::OnceCell;
 use solana_sdk::{
     instruction::{AccountMeta, Instruction},
     pubkey::Pubkey,
     signature::{Keypair, Signature},
     signer::Signer,
 };
-use spl_associated_token_account::get_associated_token_address;
+use spl_associated_token_account::{get_associated_token_address, get_associated_token_address_with_program_id};
 use std::sync::Arc;
 
 pub struct RaydiumBonk {
     pub endpoint: Arc<TradingEndpoint>,
+    pub global_config: OnceCell<Arc<GlobalConfig>>,
+    pub platform_config: OnceCell<Arc<PlatformConfig>>,
+    pub platform: RaydiumBonkPlatform,
+    pub launch_params: LaunchParams,
+    pub share_fee: Option<ShareFee>,
 }
 
 #[async_trait::async_trait]
 impl DexTrait for RaydiumBonk {
     async fn initialize(&self) -> Result<(), TradingEndpointError> {
+        let accounts = self
+            .endpoint
+            .rpc
+            .get_multiple_accounts(&[self.platform.global_config, self.platform.platform_config])
+            .await?;
+        let [Some(global_config), Some(platform_config)] = accounts.as_slice() else {
+            return Err(TradingEndpointError::CustomError("RaydiumBonk config accounts not found".to_string()));
+        };
+
+        let global_config = bincode::deserialize::<GlobalConfig>(&global_config.data).map_err(|e| TradingEndpointError::CustomError(e.to_string()))?;
+        let platform_config = Self::decode_platform_config(&platform_config.data)?;
+        if let Some(share_fee) = &self.share_fee {
+            if share_fee.rate > global_config.max_share_fee_rate {
+                return Err(TradingEndpointError::CustomError(format!(
+                    "Share fee rate {} exceeds the maximum of {}",
+                    share_fee.rate, global_config.max_share_fee_rate
+                )));
+            }
+        }
+
+        self.global_config
+            .set(Arc::new(global_config))
+            .map_err(|_| TradingEndpointError::CustomError("OnceCell already set".to_string()))?;
+        self.platform_config
+            .set(Arc::new(platform_config))
+            .map_err(|_| TradingEndpointError::CustomError("OnceCell already set".to_string()))?;
         Ok(())
     }
 
     fn initialized(&self) -> Result<(), TradingEndpointError> {
+        if self.global_config.get().is_none() || self.platform_config.get().is_none() {
+            return Err(TradingEndpointError::CustomError("RaydiumBonk not initialized".to_string()));
+        }
         Ok(())
     }
 
@@ -35,70 +72,47 @@ impl DexTrait for RaydiumBonk {
         true
     }
 
+    fn buy_is_exact_in(&self) -> bool {
+        true
+    }
+
     fn get_trading_endpoint(&self) -> Arc<TradingEndpoint> {
         self.endpoint.clone()
     }
 
+    fn get_quote_mint(&self, _: &Pubkey) -> (Pubkey, Pubkey) {
+        (self.platform.quote_mint, spl_token::ID)
+    }
+
     async fn get_pool(&self, mint: &Pubkey) -> Result<PoolInfo, TradingEndpointError> {
-        let pool = Self::get_pool_pda(mint)?;
-        let account = self.endpoint.rpc.get_account(&pool).await?;
+        self.initialized()?;
+
+        let pool = Self::get_pool_pda(mint, &self.platform.quote_mint)?;
+        let (account, slot) = self.endpoint.get_account_with_slot(&pool).await?;
         if account.data.is_empty() {
-            return Err(TradingEndpointError::CustomError(format!("Bonding curve not found: {}", pool.to_string())));
+            return Err(TradingEndpointError::CustomError(format!("Bonding curve not found: {}", pool)));
         }
 
-        let bonding_curve = bincode::deserialize::<PoolState>(&account.data).map_err(|e| TradingEndpointError::CustomError(e.to_string()))?;
-
-        Ok(PoolInfo {
-            pool,
-            creator: Some(bonding_curve.creator),
-            creator_vault: None,
-            config: None,
-            token_reserves: bonding_curve.virtual_base,
-            sol_reserves: bonding_curve.virtual_quote,
-        })
+        self.get_pool_info(pool, &Self::decode_pool_state(&account.data)?, slot)
     }
 
-    async fn create(&self, _: Keypair, _: Create, _: Option<PriorityFee>, _: Option<u64>) -> Result<Vec<Signature>, TradingEndpointError> {
-        Err(TradingEndpointError::CustomError("Not supported".to_string()))
+    async fn create(&self, payer: Keypair, create: Create, fee: Option<PriorityFee>, tip: Option<u64>) -> Result<Vec<Signature>, TradingEndpointError> {
+        self.create_with_params(payer, create, &self.launch_params, fee, tip).await
     }
 
     fn build_buy_instruction(
         &self,
         payer: &Keypair,
         mint: &Pubkey,
-        _: Option<&Pubkey>,
-        token_program_account: &Pubkey,
+        custom_ata: Option<&Pubkey>,
+        _: &[AccountMeta],
+        token_program: &Pubkey,
         buy: SwapInfo,
     ) -> Result<Instruction, TradingEndpointError> {
-        self.initialized()?;
-
-        let buy_info: BuyInfo = buy.into();
+        let mut buy_info: BuyInfo = buy.into();
+        buy_info.share_fee_rate = self.share_fee.map(|w| w.rate).unwrap_or_default();
         let buffer = buy_info.to_buffer().map_err(|e| TradingEndpointError::CustomError(e.to_string()))?;
-        let pool_address = Self::get_pool_pda(mint)?;
-        let pool_base_vault = Self::get_pool_mint_vault(mint, &pool_address)?;
-        let pool_quote_vault = Self::get_pool_quote_vault(&PUBKEY_WSOL, &pool_address)?;
-
-        Ok(Instruction::new_with_bytes(
-            PUBKEY_RAYDIUM_BONK,
-            &buffer,
-            vec![
-                AccountMeta::new(payer.pubkey(), true),
-                AccountMeta::new_readonly(PUBKEY_RAYDIUM_BONK_AUTHORITY, false),
-                AccountMeta::new_readonly(PUBKEY_RAYDIUM_BONK_GLOBAL_CONFIG, false),
-                AccountMeta::new_readonly(PUBKEY_RAYDIUM_BONK_PLATFORM_CONFIG, false),
-                AccountMeta::new(pool_address, false),
-                AccountMeta::new(get_associated_token_address(&payer.pubkey(), mint), false),
-                AccountMeta::new(get_associated_token_address(&payer.pubkey(), &PUBKEY_WSOL), false),
-                AccountMeta::new(pool_base_vault, false),
-                AccountMeta::new(pool_quote_vault, false),
-                AccountMeta::new_readonly(*mint, false),
-                AccountMeta::new_readonly(PUBKEY_WSOL, false),
-                AccountMeta::new_readonly(*token_program_account, false),
-                AccountMeta::new_readonly(spl_token::ID, false),
-                AccountMeta::new_readonly(PUBKEY_RAYDIUM_BONK_EVENT_AUTHORITY, false),
-                AccountMeta::new_readonly(PUBKEY_RAYDIUM_BONK, false),
-            ],
-        ))
+        self.build_swap_instruction(payer, mint, custom_ata, token_program, buffer)
     }
 
     fn build_sell_instruction(
@@ -106,51 +120,220 @@ impl DexTrait for RaydiumBonk {
         payer: &Keypair,
         mint: &Pubkey,
         custom_ata: Option<&Pubkey>,
-        _: Option<&Pubkey>,
+        _: &[AccountMeta],
+        token_program: &Pubkey,
         sell: SwapInfo,
+    ) -> Result<Instruction, TradingEndpointError> {
+        let mut sell_info: SellInfo = sell.into();
+        sell_info.share_fee_rate = self.share_fee.map(|w| w.rate).unwrap_or_default();
+        let buffer = sell_info.to_buffer().map_err(|e| TradingEndpointError::CustomError(e.to_string()))?;
+        self.build_swap_instruction(payer, mint, custom_ata, token_program, buffer)
+    }
+}
+
+impl RaydiumBonk {
+    pub fn new(endpoint: Arc<TradingEndpoint>, platform: Option<RaydiumBonkPlatform>) -> Self {
+        Self {
+            endpoint,
+            global_config: OnceCell::new(),
+            platform_config: OnceCell::new(),
+            platform: platform.unwrap_or_default(),
+            launch_params: LaunchParams::default(),
+            share_fee: None,
+        }
+    }
+
+    /// Take `rate` millionths of every trade to `recipient`, checked against the global config on initialize
+    pub fn with_share_fee(mut self, recipient: Pubkey, rate: u64) -> Self {
+        self.share_fee = Some(ShareFee { recipient, rate });
+        self
+    }
+
+    pub fn decode_pool_state(data: &[u8]) -> Result<PoolState, TradingEndpointError> {
+        bincode::deserialize::<PoolState>(data).map_err(|e| TradingEndpointError::CustomError(e.to_string()))
+    }
+
+    pub fn decode_platform_config(data: &[u8]) -> Result<PlatformConfig, TradingEndpointError> {
+        PlatformConfig::deserialize(&mut &data[..]).map_err(|e| TradingEndpointError::CustomError(e.to_string()))
+    }
+
+    fn build_swap_instruction(
+        &self,
+        payer: &Keypair,
+        mint: &Pubkey,
+        custom_ata: Option<&Pubkey>,
+        token_program: &Pubkey,
+        buffer: Vec<u8>,
     ) -> Result<Instruction, TradingEndpointError> {
         self.initialized()?;
         let ata = match custom_ata {
-            None => get_associated_token_address(&payer.pubkey(), mint),
+            None => get_associated_token_address_with_program_id(&payer.pubkey(), mint, token_program),
             Some(t) => *t,
         };
-        let sell_info: SellInfo = sell.into();
-        let buffer = sell_info.to_buffer().map_err(|e| TradingEndpointError::CustomError(e.to_string()))?;
-        let pool_address = Self::get_pool_pda(mint)?;
-        let pool_base_vault = Self::get_pool_mint_vault(mint, &pool_address)?;
-        let pool_quote_vault = Self::get_pool_quote_vault(&PUBKEY_WSOL, &pool_address)?;
+        let quote_mint = self.platform.quote_mint;
+        let pool_address = Self::get_pool_pda(mint, &quote_mint)?;
+
+        let mut accounts = vec![
+            AccountMeta::new(payer.pubkey(), true),
+            AccountMeta::new_readonly(PUBKEY_RAYDIUM_BONK_AUTHORITY, false),
+            AccountMeta::new_readonly(self.platform.global_config, false),
+            AccountMeta::new_readonly(self.platform.platform_config, false),
+            AccountMeta::new(pool_address, false),
+            AccountMeta::new(ata, false),
+            AccountMeta::new(get_associated_token_address(&payer.pubkey(), &quote_mint), false),
+            AccountMeta::new(Self::get_pool_mint_vault(mint, &pool_address)?, false),
+            AccountMeta::new(Self::get_pool_quote_vault(&quote_mint, &pool_address)?, false),
+            AccountMeta::new_readonly(*mint, false),
+            AccountMeta::new_readonly(quote_mint, false),
+            AccountMeta::new_readonly(*token_program, false),
+            AccountMeta::new_readonly(spl_token::ID, false),
+            AccountMeta::new_readonly(PUBKEY_RAYDIUM_BONK_EVENT_AUTHORITY, false),
+            AccountMeta::new_readonly(PUBKEY_RAYDIUM_BONK, false),
+        ];
+        // The share fee is paid into the recipient's quote token account, passed as a remaining account
+        if let Some(share_fee) = &self.share_fee {
+            accounts.push(AccountMeta::new(get_associated_token_address(&share_fee.recipient, &quote_mint), false));
+        }
+
+        Ok(Instruction::new_with_bytes(PUBKEY_RAYDIUM_BONK, &buffer, accounts))
+    }
+
+    pub fn with_launch_params(mut self, launch_params: LaunchParams) -> Self {
+        self.launch_params = launch_params;
+        self
+    }
+
+    /// Launch `create` with `params`, followed by a dev buy of `create.buy_sol_amount` in the same transaction
+    pub async fn create_with_params(
+        &self,
+        payer: Keypair,
+        create: Create,
+        params: &LaunchParams,
+        fee: Option<PriorityFee>,
+        tip: Option<u64>,
+    ) -> Result<Vec<Signature>, TradingEndpointError> {
+        let instructions = self.build_create_instructions(&payer, &create, params)?;
+        let blockhash = self.endpoint.get_latest_blockhash().await?;
+
+        self.endpoint
+            .build_and_broadcast_tx(
+                TransactionType::Create,
+                &payer,
+                instructions,
+                TxSettings::new(blockhash, fee, tip.unwrap_or_default()),
+                Some(vec![&create.mint_private_key]),
+            )
+            .await
+    }
+
+    /// Initialize instruction of `create`, then the dev buy priced against the curve's initial virtual reserves
+    pub fn build_create_instructions(&self, payer: &Keypair, create: &Create, params: &LaunchParams) -> Result<Vec<Instruction>, TradingEndpointError> {
+        self.initialized()?;
+
+        let mint = create.mint_private_key.pubkey();
+        let mut instructions = vec![self.build_initialize_instruction(payer, &mint, InitializeInfo::from_create(create, params))?];
+
+        if let Some(buy_sol_amount) = create.buy_sol_amount {
+            let migrate_fee = self.global_config.get().unwrap().migrate_fee;
+            let (virtual_base, virtual_quote) = params
+                .curve
+                .get_initial_virtual_reserves(migrate_fee, params.vesting.total_locked_amount)
+                .ok_or_else(|| TradingEndpointError::CustomError("Dev buys are only supported on constant curves".to_string()))?;
+            let token_amount = amm_buy_get_token_out_with_fees(virtual_quote, virtual_base, buy_sol_amount, &self.get_fee_model()?);
+            let buy_instruction = self.build_buy_instruction(
+                payer,
+                &mint,
+                None,
+                &[],
+                &spl_token::ID,
+                SwapInfo {
+                    token_amount: calculate_with_slippage_sell(token_amount, create.slippage_basis_points.unwrap_or(0)),
+                    sol_amount: buy_sol_amount,
+                },
+            )?;
+            instructions.extend(
+                build_quote_buy_instructions(
+                    payer,
+                    &mint,
+                    &spl_token::ID,
+                    QuotePayment {
+                        mint: self.platform.quote_mint,
+                        token_program: spl_token::ID,
+                        amount: buy_sol_amount,
+                    },
+                    buy_instruction,
+                    CreateATA::Create,
+                )
+                .map_err(|e| TradingEndpointError::CustomError(e.to_string()))?,
+            );
+        }
+
+        Ok(instructions)
+    }
+
+    /// Create the mint, its metadata and its curve, `payer` being the creator
+    pub fn build_initialize_instruction(&self, payer: &Keypair, mint: &Pubkey, initialize: InitializeInfo) -> Result<Instruction, TradingEndpointError> {
+        let buffer = initialize.to_buffer().map_err(|e| TradingEndpointError::CustomError(e.to_string()))?;
+        let quote_mint = self.platform.quote_mint;
+        let pool_address = Self::get_pool_pda(mint, &quote_mint)?;
 
         Ok(Instruction::new_with_bytes(
             PUBKEY_RAYDIUM_BONK,
             &buffer,
             vec![
                 AccountMeta::new(payer.pubkey(), true),
+                AccountMeta::new_readonly(payer.pubkey(), true),
+                AccountMeta::new_readonly(self.platform.global_config, false),
+                AccountMeta::new_readonly(self.platform.platform_config, false),
                 AccountMeta::new_readonly(PUBKEY_RAYDIUM_BONK_AUTHORITY, false),
-                AccountMeta::new_readonly(PUBKEY_RAYDIUM_BONK_GLOBAL_CONFIG, false),
-                AccountMeta::new_readonly(PUBKEY_RAYDIUM_BONK_PLATFORM_CONFIG, false),
                 AccountMeta::new(pool_address, false),
-                AccountMeta::new(ata, false),
-                AccountMeta::new(get_associated_token_address(&payer.pubkey(), &PUBKEY_WSOL), false),
-                AccountMeta::new(pool_base_vault, false),
-                AccountMeta::new(pool_quote_vault, false),
-                AccountMeta::new_readonly(*mint, false),
-                AccountMeta::new_readonly(PUBKEY_WSOL, false),
+                AccountMeta::new(*mint, true),
+                AccountMeta::new_readonly(quote_mint, false),
+                AccountMeta::new(Self::get_pool_mint_vault(mint, &pool_address)?, false),
+                AccountMeta::new(Self::get_pool_quote_vault(&quote_mint, &pool_address)?, false),
+                AccountMeta::new(mpl_token_metadata::accounts::Metadata::find_pda(mint).0, false),
                 AccountMeta::new_readonly(spl_token::ID, false),
                 AccountMeta::new_readonly(spl_token::ID, false),
+                AccountMeta::new_readonly(mpl_token_metadata::ID, false),
+                AccountMeta::new_readonly(solana_program::system_program::ID, false),
+                AccountMeta::new_readonly(solana_program::sysvar::rent::ID, false),
                 AccountMeta::new_readonly(PUBKEY_RAYDIUM_BONK_EVENT_AUTHORITY, false),
                 AccountMeta::new_readonly(PUBKEY_RAYDIUM_BONK, false),
             ],
         ))
     }
-}
 
-impl RaydiumBonk {
-    pub fn new(endpoint: Arc<TradingEndpoint>) -> Self {
-        Self { endpoint }
+    /// Protocol, platform, creator and share fees, all taken out of the quote side
+    /// Pool info of the curve `pool`, with the reserves it prices trades with at `slot`
+    pub fn get_pool_info(&self, pool: Pubkey, bonding_curve: &PoolState, slot: u64) -> Result<PoolInfo, TradingEndpointError> {
+        let (token_reserves, sol_reserves) = bonding_curve.get_reserves();
+        Ok(PoolInfo {
+            pool,
+            creator: Some(bonding_curve.creator),
+            creator_vault: None,
+            config: None,
+            token_reserves,
+            sol_reserves,
+            fees: self.get_fee_model()?,
+            slot,
+        })
+    }
+
+    pub fn get_fee_model(&self) -> Result<FeeModel, TradingEndpointError> {
+        self.initialized()?;
+        let platform_config = self.platform_config.get().unwrap();
+        Ok(FeeModel {
+            protocol_fee_rate: self.global_config.get().unwrap().trade_fee_rate,
+            lp_fee_rate: 0,
+            creator_fee_rate: platform_config.creator_fee_rate,
+            platform_fee_rate: platform_config.fee_rate,
+            share_fee_rate: self.share_fee.map(|w| w.rate).unwrap_or_default(),
+            charged_on_top: false,
+        })
     }
 
-    pub fn get_pool_pda(mint: &Pubkey) -> Result<Pubkey, TradingEndpointError> {
-        let seeds: &[&[u8]; 3] = &[b"pool", mint.as_ref(), PUBKEY_WSOL.as_ref()];
+    pub fn get_pool_pda(mint: &Pubkey, quote_mint: &Pubkey) -> Result<Pubkey, TradingEndpointError> {
+        let seeds: &[&[u8]; 3] = &[b"pool", mint.as_ref(), quote_mint.as_ref()];
         let pda = Pubkey::try_find_program_address(seeds, &PUBKEY_RAYDIUM_BONK)
             .ok_or(TradingEndpointError::CustomError("Failed to find program address".to_string()))?;
         Ok(pda.0)
@@ -170,3 +353,275 @@ impl RaydiumBonk {
         Ok(pda.0)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::common::accounts::PUBKEY_WSOL;
+    use solana_client::nonblocking::rpc_client::RpcClient;
+
+    /// LetsBonk platform with its configs loaded: 0.25% protocol fee, 1% platform fee, no migrate fee
+    fn bonk() -> RaydiumBonk {
+        let rpc = Arc::new(RpcClient::new("http://127.0.0.1:8899".to_string()));
+        let bonk = RaydiumBonk::new(Arc::new(TradingEndpoint::new(rpc, vec![])), None);
+        bonk.global_config
+            .set(Arc::new(GlobalConfig {
+                discriminator: 0,
+                epoch: 0,
+                curve_type: 0,
+                index: 0,
+                migrate_fee: 0,
+                trade_fee_rate: 2_500,
+                max_share_fee_rate: 10_000,
+                min_base_supply: 0,
+                max_lock_rate: 0,
+                min_base_sell_rate: 0,
+                min_base_migrate_rate: 0,
+                min_quote_fund_raising: 0,
+                quote_mint: PUBKEY_WSOL,
+                protocol_fee_owner: Pubkey::new_unique(),
+                migrate_fee_owner: Pubkey::new_unique(),
+                migrate_to_amm_wallet: Pubkey::new_unique(),
+                migrate_to_cpswap_wallet: Pubkey::new_unique(),
+            }))
+            .unwrap();
+        bonk.platform_config
+            .set(Arc::new(PlatformConfig {
+                discriminator: 0,
+                epoch: 0,
+                platform_fee_wallet: Pubkey::new_unique(),
+                platform_nft_wallet: Pubkey::new_unique(),
+                platform_scale: 0,
+                creator_scale: 0,
+                burn_scale: 0,
+                fee_rate: 10_000,
+                name: [0; 64],
+                web: [0; 256],
+                img: [0; 256],
+                cpswap_config: Pubkey::new_unique(),
+                creator_fee_rate: 0,
+                transfer_fee_extension_auth: Pubkey::new_unique(),
+            }))
+            .unwrap();
+        bonk
+    }
+
+    fn create(buy_sol_amount: Option<u64>) -> Create {
+        Create {
+            name: "Bonk Dog".to_string(),
+            symbol: "BDOG".to_string(),
+            uri: "https://example.com/bdog.json".to_string(),
+            mint_private_key: Keypair::new(),
+            buy_sol_amount,
+            slippage_basis_points: Some(100),
+        }
+    }
+
+    fn pda(seeds: &[&[u8]], program: &Pubkey) -> Pubkey {
+        Pubkey::find_program_address(seeds, program).0
+    }
+
+    fn borsh_string(data: &mut Vec<u8>, value: &str) {
+        data.extend_from_slice(&(value.len() as u32).to_le_bytes());
+        data.extend_from_slice(value.as_bytes());
+    }
+
+    fn flags(instruction: &Instruction) -> Vec<(bool, bool)> {
+        instruction.accounts.iter().map(|w| (w.is_signer, w.is_writable)).collect()
+    }
+
+    #[test]
+    fn initialize_instruction_layout() {
+        let bonk = bonk();
+        let payer = Keypair::new();
+        let create = create(None);
+        let mint = create.mint_private_key.pubkey();
+        let params = LaunchParams {
+            vesting: VestingParams {
+                total_locked_amount: 50_000_000_000_000,
+                cliff_period: 86_400,
+                unlock_period: 2_592_000,
+            },
+            ..Default::default()
+        };
+
+        let instructions = bonk.build_create_instructions(&payer, &create, &params).unwrap();
+        assert_eq!(instructions.len(), 1);
+        let instruction = &instructions[0];
+        assert_eq!(instruction.program_id, PUBKEY_RAYDIUM_BONK);
+
+        let pool = pda(&[b"pool", mint.as_ref(), PUBKEY_WSOL.as_ref()], &PUBKEY_RAYDIUM_BONK);
+        let metadata = pda(&[b"metadata", mpl_token_metadata::ID.as_ref(), mint.as_ref()], &mpl_token_metadata::ID);
+        let keys: Vec<Pubkey> = instruction.accounts.iter().map(|w| w.pubkey).collect();
+        assert_eq!(
+            keys,
+            vec![
+                payer.pubkey(),
+                payer.pubkey(),
+                PUBKEY_RAYDIUM_BONK_GLOBAL_CONFIG,
+                PUBKEY_RAYDIUM_BONK_PLATFORM_CONFIG,
+                PUBKEY_RAYDIUM_BONK_AUTHORITY,
+                pool,
+                mint,
+                PUBKEY_WSOL,
+                pda(&[b"pool_vault", pool.as_ref(), mint.as_ref()], &PUBKEY_RAYDIUM_BONK),
+                pda(&[b"pool_vault", pool.as_ref(), PUBKEY_WSOL.as_ref()], &PUBKEY_RAYDIUM_BONK),
+                metadata,
+                spl_token::ID,
+                spl_token::ID,
+                mpl_token_metadata::ID,
+                solana_program::system_program::ID,
+                solana_program::sysvar::rent::ID,
+                PUBKEY_RAYDIUM_BONK_EVENT_AUTHORITY,
+                PUBKEY_RAYDIUM_BONK,
+            ]
+        );
+
+        // The payer pays and signs as the creator, the new mint signs for itself
+        let mut expected_flags = vec![(false, false); 18];
+        expected_flags[0] = (true, true);
+        expected_flags[1] = (true, false);
+        for writable in [5, 8, 9, 10] {
+            expected_flags[writable] = (false, true);
+        }
+        expected_flags[6] = (true, true);
+        assert_eq!(flags(instruction), expected_flags);
+
+        let mut data = RAYDIUM_BONK_INITIALIZE_DISCRIMINATOR.to_le_bytes().to_vec();
+        data.push(6);
+        borsh_string(&mut data, "Bonk Dog");
+        borsh_string(&mut data, "BDOG");
+        borsh_string(&mut data, "https://example.com/bdog.json");
+        // CurveParams::Constant
+        data.push(0);
+        data.extend_from_slice(&1_000_000_000_000_000u64.to_le_bytes());
+        data.extend_from_slice(&793_100_000_000_000u64.to_le_bytes());
+        data.extend_from_slice(&85_000_000_000u64.to_le_bytes());
+        data.push(MIGRATE_TYPE_CPSWAP);
+        data.extend_from_slice(&50_000_000_000_000u64.to_le_bytes());
+        data.extend_from_slice(&86_400u64.to_le_bytes());
+        data.extend_from_slice(&2_592_000u64.to_le_bytes());
+        assert_eq!(instruction.data, data);
+    }
+
+    #[test]
+    fn dev_buy_follows_initialize() {
+        let bonk = bonk();
+        let payer = Keypair::new();
+        let create = create(Some(1_000_000_000));
+        let mint = create.mint_private_key.pubkey();
+
+        let instructions = bonk.build_create_instructions(&payer, &create, &LaunchParams::default()).unwrap();
+        let programs: Vec<Pubkey> = instructions.iter().map(|w| w.program_id).collect();
+        assert_eq!(
+            programs,
+            vec![
+                PUBKEY_RAYDIUM_BONK,
+                spl_associated_token_account::ID,
+                spl_associated_token_account::ID,
+                solana_program::system_program::ID,
+                spl_token::ID,
+                PUBKEY_RAYDIUM_BONK,
+                spl_token::ID,
+            ]
+        );
+
+        let buy = &instructions[5];
+        let pool = pda(&[b"pool", mint.as_ref(), PUBKEY_WSOL.as_ref()], &PUBKEY_RAYDIUM_BONK);
+        let keys: Vec<Pubkey> = buy.accounts.iter().map(|w| w.pubkey).collect();
+        assert_eq!(
+            keys,
+            vec![
+                payer.pubkey(),
+                PUBKEY_RAYDIUM_BONK_AUTHORITY,
+                PUBKEY_RAYDIUM_BONK_GLOBAL_CONFIG,
+                PUBKEY_RAYDIUM_BONK_PLATFORM_CONFIG,
+                pool,
+                get_associated_token_address(&payer.pubkey(), &mint),
+                get_associated_token_address(&payer.pubkey(), &PUBKEY_WSOL),
+                pda(&[b"pool_vault", pool.as_ref(), mint.as_ref()], &PUBKEY_RAYDIUM_BONK),
+                pda(&[b"pool_vault", pool.as_ref(), PUBKEY_WSOL.as_ref()], &PUBKEY_RAYDIUM_BONK),
+                mint,
+                PUBKEY_WSOL,
+                spl_token::ID,
+                spl_token::ID,
+                PUBKEY_RAYDIUM_BONK_EVENT_AUTHORITY,
+                PUBKEY_RAYDIUM_BONK,
+            ]
+        );
+        let mut expected_flags = vec![(false, false); 15];
+        expected_flags[0] = (true, true);
+        for writable in [4, 5, 6, 7, 8] {
+            expected_flags[writable] = (false, true);
+        }
+        assert_eq!(flags(buy), expected_flags);
+
+        // 1 SOL less 1.25% fees against the curve's initial 30_000_852_951 / 1_073_025_605_595_359 reserves, less 1% slippage
+        let mut data = 17011112658214972154u64.to_le_bytes().to_vec();
+        data.extend_from_slice(&1_000_000_000u64.to_le_bytes());
+        data.extend_from_slice(&33_851_965_586_197u64.to_le_bytes());
+        data.extend_from_slice(&0u64.to_le_bytes());
+        assert_eq!(buy.data, data);
+    }
+
+    #[test]
+    fn quotes_traded_curve_on_effective_reserves() {
+        let bonk = bonk();
+        // Curve that sold 200M tokens for 7 SOL since launch
+        let state = PoolState {
+            discriminator: 0,
+            epoch: 0,
+            auth_bump: 255,
+            status: 0,
+            base_decimals: 6,
+            quote_decimals: 9,
+            migrate_type: 1,
+            supply: 1_000_000_000_000_000,
+            total_base_sell: 793_100_000_000_000,
+            virtual_base: 1_073_025_605_596_382,
+            virtual_quote: 30_000_852_951,
+            real_base: 200_000_000_000_000,
+            real_quote: 7_000_000_000,
+            total_quote_fund_raising: 85_000_000_000,
+            quote_protocol_fee: 0,
+            platform_fee: 0,
+            migrate_fee: 0,
+            vesting_schedule: VestingSchedule {
+                total_locked_amount: 0,
+                cliff_period: 0,
+                unlock_period: 0,
+                start_time: 0,
+                allocated_share_amount: 0,
+            },
+            global_config: Pubkey::new_unique(),
+            platform_config: Pubkey::new_unique(),
+            base_mint: Pubkey::new_unique(),
+            quote_mint: PUBKEY_WSOL,
+            base_vault: Pubkey::new_unique(),
+            quote_vault: Pubkey::new_unique(),
+            creator: Pubkey::new_unique(),
+            padding: [0; 8],
+        };
+
+        let pool_info = bonk.get_pool_info(Pubkey::new_unique(), &state, 0).unwrap();
+        assert_eq!(pool_info.token_reserves, 873_025_605_596_382);
+        assert_eq!(pool_info.sol_reserves, 37_000_852_951);
+
+        // 987_500_000 of 1 SOL reach the curve after the 1.25% fees, buying far fewer tokens than the launch reserves would give
+        let token_out = amm_buy_get_token_out_with_fees(pool_info.sol_reserves, pool_info.token_reserves, 1_000_000_000, &pool_info.fees);
+        assert_eq!(token_out, 22_694_134_347_927);
+        assert_eq!(
+            amm_buy_get_token_out_with_fees(state.virtual_quote, state.virtual_base, 1_000_000_000, &pool_info.fees),
+            34_193_904_632_555
+        );
+    }
+
+    #[test]
+    fn create_requires_loaded_configs() {
+        let rpc = Arc::new(RpcClient::new("http://127.0.0.1:8899".to_string()));
+        let bonk = RaydiumBonk::new(Arc::new(TradingEndpoint::new(rpc, vec![])), None);
+        assert!(bonk
+            .build_create_instructions(&Keypair::new(), &create(None), &LaunchParams::default())
+            .is_err());
+    }
+}
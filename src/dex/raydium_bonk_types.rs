@@ -1,5 +1,7 @@
-use super::types::SwapInfo;
+use super::types::{Create, SwapInfo};
+use crate::common::accounts::PUBKEY_WSOL;
 use borsh::{BorshDeserialize, BorshSerialize};
+use ruint::aliases::U256;
 use serde::{Deserialize, Serialize};
 use solana_sdk::{pubkey, pubkey::Pubkey};
 
@@ -9,6 +11,21 @@ pub const PUBKEY_RAYDIUM_BONK_PLATFORM_CONFIG: Pubkey = pubkey!("FfYek5vEz23cMkW
 pub const PUBKEY_RAYDIUM_BONK_AUTHORITY: Pubkey = pubkey!("WLHv2UAZm6z4KyaaELi5pjdbJh6RESMva1Rnn8pJVVh");
 pub const PUBKEY_RAYDIUM_BONK_EVENT_AUTHORITY: Pubkey = pubkey!("2DPAtwB8L12vrMRExbLuyGnC7n2J5LNoZQSejeQGpwkr");
 
+/// Fee rates are expressed in millionths
+pub const FEE_RATE_DENOMINATOR: u64 = 1_000_000;
+
+pub const RAYDIUM_BONK_INITIALIZE_DISCRIMINATOR: u64 = 17121445590508351407;
+/// Migrate to a Raydium AMM v4 pool once the curve completes
+pub const MIGRATE_TYPE_AMM: u8 = 0;
+/// Migrate to a Raydium CPMM pool once the curve completes
+pub const MIGRATE_TYPE_CPSWAP: u8 = 1;
+/// `PoolState.status` while the curve sells tokens
+pub const POOL_STATUS_FUND: u8 = 0;
+/// `PoolState.status` once the curve raised its target, waiting for migration
+pub const POOL_STATUS_MIGRATE: u8 = 1;
+/// `PoolState.status` once the liquidity migrated to its Raydium pool
+pub const POOL_STATUS_TRADE: u8 = 2;
+
 #[derive(Debug, Clone, PartialEq, Eq, BorshSerialize, BorshDeserialize)]
 pub struct BuyInfo {
     pub discriminator: u64,
@@ -63,6 +80,219 @@ impl SellInfo {
     }
 }
 
+#[derive(Debug, Clone, PartialEq, Eq, BorshSerialize, BorshDeserialize)]
+pub struct MintParams {
+    pub decimals: u8,
+    pub name: String,
+    pub symbol: String,
+    pub uri: String,
+}
+
+/// Constant product curve selling `total_base_sell` of `supply` to raise `total_quote_fund_raising`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, BorshSerialize, BorshDeserialize)]
+pub struct ConstantCurve {
+    pub supply: u64,
+    pub total_base_sell: u64,
+    pub total_quote_fund_raising: u64,
+    pub migrate_type: u8,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, BorshSerialize, BorshDeserialize)]
+pub struct FixedCurve {
+    pub supply: u64,
+    pub total_quote_fund_raising: u64,
+    pub migrate_type: u8,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, BorshSerialize, BorshDeserialize)]
+pub struct LinearCurve {
+    pub supply: u64,
+    pub total_quote_fund_raising: u64,
+    pub migrate_type: u8,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, BorshSerialize, BorshDeserialize)]
+pub enum CurveParams {
+    Constant(ConstantCurve),
+    Fixed(FixedCurve),
+    Linear(LinearCurve),
+}
+
+impl CurveParams {
+    /// Virtual base and quote reserves a constant curve starts with, from the same equations the program solves.
+    /// The curve sells `total_base_sell` for `total_quote_fund_raising`, ending at the price the migrated pool opens at.
+    pub fn get_initial_virtual_reserves(&self, migrate_fee: u64, total_locked_amount: u64) -> Option<(u64, u64)> {
+        let CurveParams::Constant(curve) = self else {
+            return None;
+        };
+
+        let total_sell = curve.total_base_sell as u128;
+        let fund_raising = curve.total_quote_fund_raising as u128;
+        let migrate_base = curve.supply.checked_sub(curve.total_base_sell)?.checked_sub(total_locked_amount)? as u128;
+        let migrate_quote = fund_raising.checked_sub(migrate_fee as u128)?;
+        let denominator = (migrate_quote * total_sell).checked_sub(fund_raising * migrate_base)?;
+        if denominator == 0 {
+            return None;
+        }
+
+        // `migrate_quote * total_sell²` overflows u128 on real curves
+        let virtual_base = U256::from(migrate_quote) * U256::from(total_sell) * U256::from(total_sell) / U256::from(denominator);
+        let virtual_base = u128::try_from(virtual_base).ok()?;
+        let virtual_quote = fund_raising * (virtual_base - total_sell) / total_sell;
+        Some((u64::try_from(virtual_base).ok()?, u64::try_from(virtual_quote).ok()?))
+    }
+}
+
+/// Tokens locked for the creator out of the supply, released linearly after the cliff
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, BorshSerialize, BorshDeserialize)]
+pub struct VestingParams {
+    pub total_locked_amount: u64,
+    pub cliff_period: u64,
+    pub unlock_period: u64,
+}
+
+/// Launch settings `DexTrait::create` uses, defaulting to the LetsBonk ones
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LaunchParams {
+    pub decimals: u8,
+    pub curve: CurveParams,
+    pub vesting: VestingParams,
+}
+
+impl Default for LaunchParams {
+    fn default() -> Self {
+        Self {
+            decimals: 6,
+            curve: CurveParams::Constant(ConstantCurve {
+                supply: 1_000_000_000_000_000,
+                total_base_sell: 793_100_000_000_000,
+                total_quote_fund_raising: 85_000_000_000,
+                migrate_type: MIGRATE_TYPE_CPSWAP,
+            }),
+            vesting: VestingParams::default(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, BorshSerialize, BorshDeserialize)]
+pub struct InitializeInfo {
+    pub discriminator: u64,
+    pub base_mint_param: MintParams,
+    pub curve_param: CurveParams,
+    pub vesting_param: VestingParams,
+}
+
+impl InitializeInfo {
+    pub fn from_create(create: &Create, params: &LaunchParams) -> Self {
+        Self {
+            discriminator: RAYDIUM_BONK_INITIALIZE_DISCRIMINATOR,
+            base_mint_param: MintParams {
+                decimals: params.decimals,
+                name: create.name.to_string(),
+                symbol: create.symbol.to_string(),
+                uri: create.uri.to_string(),
+            },
+            curve_param: params.curve,
+            vesting_param: params.vesting,
+        }
+    }
+
+    pub fn to_buffer(&self) -> anyhow::Result<Vec<u8>> {
+        let mut buffer = Vec::new();
+        self.serialize(&mut buffer)?;
+        Ok(buffer)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GlobalConfig {
+    pub discriminator: u64,
+    pub epoch: u64,
+    pub curve_type: u8,
+    pub index: u16,
+    pub migrate_fee: u64,
+    pub trade_fee_rate: u64,
+    pub max_share_fee_rate: u64,
+    pub min_base_supply: u64,
+    pub max_lock_rate: u64,
+    pub min_base_sell_rate: u64,
+    pub min_base_migrate_rate: u64,
+    pub min_quote_fund_raising: u64,
+    pub quote_mint: Pubkey,
+    pub protocol_fee_owner: Pubkey,
+    pub migrate_fee_owner: Pubkey,
+    pub migrate_to_amm_wallet: Pubkey,
+    pub migrate_to_cpswap_wallet: Pubkey,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, BorshSerialize, BorshDeserialize)]
+pub struct PlatformConfig {
+    pub discriminator: u64,
+    pub epoch: u64,
+    pub platform_fee_wallet: Pubkey,
+    pub platform_nft_wallet: Pubkey,
+    /// Split of the migrated LP between platform, creator and burn
+    pub platform_scale: u64,
+    pub creator_scale: u64,
+    pub burn_scale: u64,
+    /// Platform fee on trades, in millionths
+    pub fee_rate: u64,
+    pub name: [u8; 64],
+    pub web: [u8; 256],
+    pub img: [u8; 256],
+    pub cpswap_config: Pubkey,
+    /// Creator fee on trades, in millionths
+    pub creator_fee_rate: u64,
+    pub transfer_fee_extension_auth: Pubkey,
+}
+
+impl PlatformConfig {
+    pub fn name(&self) -> String {
+        Self::trim(&self.name)
+    }
+
+    pub fn web(&self) -> String {
+        Self::trim(&self.web)
+    }
+
+    pub fn img(&self) -> String {
+        Self::trim(&self.img)
+    }
+
+    fn trim(bytes: &[u8]) -> String {
+        let end = bytes.iter().position(|b| *b == 0).unwrap_or(bytes.len());
+        String::from_utf8_lossy(&bytes[..end]).to_string()
+    }
+}
+
+/// LaunchLab platform to trade and launch on, LetsBonk against SOL by default
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct RaydiumBonkPlatform {
+    /// Global config of the quote mint and curve type
+    pub global_config: Pubkey,
+    pub platform_config: Pubkey,
+    /// WSOL, or a stablecoin such as USD1 or USDC
+    pub quote_mint: Pubkey,
+}
+
+impl Default for RaydiumBonkPlatform {
+    fn default() -> Self {
+        Self {
+            global_config: PUBKEY_RAYDIUM_BONK_GLOBAL_CONFIG,
+            platform_config: PUBKEY_RAYDIUM_BONK_PLATFORM_CONFIG,
+            quote_mint: PUBKEY_WSOL,
+        }
+    }
+}
+
+/// Share of every trade paid to `recipient` in the quote mint, capped by `GlobalConfig.max_share_fee_rate`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ShareFee {
+    pub recipient: Pubkey,
+    /// In millionths
+    pub rate: u64,
+}
+
 #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
 pub struct VestingSchedule {
     pub total_locked_amount: u64,
@@ -101,3 +331,13 @@ pub struct PoolState {
     pub creator: Pubkey,
     pub padding: [u64; 8],
 }
+
+impl PoolState {
+    /// Token and quote reserves the curve prices trades with, the virtual reserves moved by what was traded so far
+    pub fn get_reserves(&self) -> (u64, u64) {
+        (
+            self.virtual_base.saturating_sub(self.real_base),
+            self.virtual_quote.saturating_add(self.real_quote),
+        )
+    }
+}
@@ -0,0 +1,515 @@
+use super::{
+    amm_calc::FeeModel,
+    dex_traits::DexTrait,
+    raydium_cpmm_types::*,
+    types::{BuySwapInfo, BuyVariant, Create, SwapInfo},
+};
+use crate::{
+    common::{accounts::PUBKEY_WSOL, trading_endpoint::TradingEndpoint},
+    errors::trading_endpoint_error::TradingEndpointError,
+    instruction::builder::PriorityFee,
+};
+use parking_lot::RwLock;
+use solana_sdk::{
+    instruction::{AccountMeta, Instruction},
+    pubkey::Pubkey,
+    signature::{Keypair, Signature},
+    signer::Signer,
+};
+use spl_associated_token_account::get_associated_token_address_with_program_id;
+use spl_token_2022::{extension::StateWithExtensions, state::Account as TokenAccount};
+use std::{collections::HashMap, sync::Arc};
+
+pub struct RaydiumCpmm {
+    pub endpoint: Arc<TradingEndpoint>,
+    pools: RwLock<HashMap<Pubkey, PoolKeys>>,
+    configs: RwLock<HashMap<Pubkey, Arc<AmmConfig>>>,
+}
+
+#[async_trait::async_trait]
+impl DexTrait for RaydiumCpmm {
+    async fn initialize(&self) -> Result<(), TradingEndpointError> {
+        Ok(())
+    }
+
+    fn initialized(&self) -> Result<(), TradingEndpointError> {
+        Ok(())
+    }
+
+    fn get_trading_endpoint(&self) -> Arc<TradingEndpoint> {
+        self.endpoint.clone()
+    }
+
+    fn use_wsol(&self) -> bool {
+        true
+    }
+
+    async fn get_pool(&self, mint: &Pubkey) -> Result<super::types::PoolInfo, TradingEndpointError> {
+        let pool = self.get_pool_state(mint).await?;
+        if !pool.pool_state.swap_enabled() {
+            return Err(TradingEndpointError::CustomError(format!(
+                "Swaps disabled on Raydium CPMM pool: {}",
+                pool.pool_address
+            )));
+        }
+
+        let config = self.get_amm_config(&pool.pool_state.amm_config).await?;
+        let (token_reserves, sol_reserves) = pool.get_reserves(mint);
+        Ok(super::types::PoolInfo {
+            pool: pool.pool_address,
+            creator: Some(pool.pool_state.pool_creator),
+            creator_vault: None,
+            config: Some(pool.pool_state.amm_config),
+            token_reserves,
+            sol_reserves,
+            fees: Self::get_fee_model(&config, &pool.pool_state),
+            slot: pool.slot,
+        })
+    }
+
+    fn get_quote_mint(&self, mint: &Pubkey) -> (Pubkey, Pubkey) {
+        match self.pools.read().get(mint) {
+            Some(keys) => (keys.quote_mint, keys.quote_token_program),
+            None => (PUBKEY_WSOL, spl_token::ID),
+        }
+    }
+
+    async fn create(&self, _: Keypair, _: Create, _: Option<PriorityFee>, _: Option<u64>) -> Result<Vec<Signature>, TradingEndpointError> {
+        Err(TradingEndpointError::CustomError("Not supported".to_string()))
+    }
+
+    fn build_buy_instruction(
+        &self,
+        payer: &Keypair,
+        mint: &Pubkey,
+        custom_ata: Option<&Pubkey>,
+        extra_accounts: &[AccountMeta],
+        token_program: &Pubkey,
+        buy: SwapInfo,
+    ) -> Result<Instruction, TradingEndpointError> {
+        self.build_buy_instruction_with_variant(
+            payer,
+            mint,
+            custom_ata,
+            extra_accounts,
+            token_program,
+            buy.with_variant(BuyVariant::ExactTokensOut),
+        )
+    }
+
+    fn build_buy_instruction_with_variant(
+        &self,
+        payer: &Keypair,
+        mint: &Pubkey,
+        custom_ata: Option<&Pubkey>,
+        _: &[AccountMeta],
+        token_program: &Pubkey,
+        buy: BuySwapInfo,
+    ) -> Result<Instruction, TradingEndpointError> {
+        let BuySwapInfo { swap: buy, variant } = buy;
+        let buffer = match variant {
+            BuyVariant::ExactTokensOut => SwapBaseOutputInfo {
+                discriminator: RAYDIUM_CPMM_SWAP_BASE_OUTPUT_DISCRIMINATOR,
+                max_amount_in: buy.sol_amount,
+                amount_out: buy.token_amount,
+            }
+            .to_buffer(),
+            BuyVariant::ExactSolIn => SwapBaseInputInfo {
+                discriminator: RAYDIUM_CPMM_SWAP_BASE_INPUT_DISCRIMINATOR,
+                amount_in: buy.sol_amount,
+                minimum_amount_out: buy.token_amount,
+            }
+            .to_buffer(),
+        }
+        .map_err(|e| TradingEndpointError::CustomError(e.to_string()))?;
+
+        let keys = self.get_pool_keys(mint)?;
+        let ata = match custom_ata {
+            None => get_associated_token_address_with_program_id(&payer.pubkey(), mint, token_program),
+            Some(t) => *t,
+        };
+        let quote_ata = get_associated_token_address_with_program_id(&payer.pubkey(), &keys.quote_mint, &keys.quote_token_program);
+        Ok(Self::build_swap_instruction(
+            payer,
+            &keys,
+            (&quote_ata, &keys.quote_vault, &keys.quote_token_program, &keys.quote_mint),
+            (&ata, &keys.base_vault, token_program, mint),
+            buffer,
+        ))
+    }
+
+    fn build_sell_instruction(
+        &self,
+        payer: &Keypair,
+        mint: &Pubkey,
+        custom_ata: Option<&Pubkey>,
+        _: &[AccountMeta],
+        token_program: &Pubkey,
+        sell: SwapInfo,
+    ) -> Result<Instruction, TradingEndpointError> {
+        let buffer = SwapBaseInputInfo {
+            discriminator: RAYDIUM_CPMM_SWAP_BASE_INPUT_DISCRIMINATOR,
+            amount_in: sell.token_amount,
+            minimum_amount_out: sell.sol_amount,
+        }
+        .to_buffer()
+        .map_err(|e| TradingEndpointError::CustomError(e.to_string()))?;
+
+        let keys = self.get_pool_keys(mint)?;
+        let ata = match custom_ata {
+            None => get_associated_token_address_with_program_id(&payer.pubkey(), mint, token_program),
+            Some(t) => *t,
+        };
+        let quote_ata = get_associated_token_address_with_program_id(&payer.pubkey(), &keys.quote_mint, &keys.quote_token_program);
+        Ok(Self::build_swap_instruction(
+            payer,
+            &keys,
+            (&ata, &keys.base_vault, token_program, mint),
+            (&quote_ata, &keys.quote_vault, &keys.quote_token_program, &keys.quote_mint),
+            buffer,
+        ))
+    }
+}
+
+impl RaydiumCpmm {
+    pub fn new(endpoint: Arc<TradingEndpoint>) -> Self {
+        Self {
+            endpoint,
+            pools: RwLock::new(HashMap::new()),
+            configs: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// Every CPMM pool with `mint` on either side
+    pub async fn get_pools(&self, mint: &Pubkey) -> Result<Vec<PoolInfo>, TradingEndpointError> {
+        let (token_0_pools, token_1_pools) = tokio::try_join!(
+            self.get_pool_accounts(POOL_STATE_TOKEN_0_MINT_OFFSET, mint),
+            self.get_pool_accounts(POOL_STATE_TOKEN_1_MINT_OFFSET, mint)
+        )?;
+        let pools = token_0_pools.into_iter().chain(token_1_pools).collect::<Vec<_>>();
+
+        let vaults = pools
+            .iter()
+            .flat_map(|(_, pool_state)| [pool_state.token_0_vault, pool_state.token_1_vault])
+            .collect::<Vec<_>>();
+        let mut balances = Vec::with_capacity(vaults.len());
+        let mut slot = 0;
+        for chunk in vaults.chunks(100) {
+            let (accounts, chunk_slot) = self.endpoint.get_multiple_accounts_with_slot(chunk).await?;
+            slot = slot.max(chunk_slot);
+            for account in accounts {
+                balances.push(match account {
+                    Some(account) => Self::decode_token_amount(&account.data)?,
+                    None => 0,
+                });
+            }
+        }
+
+        Ok(pools
+            .into_iter()
+            .zip(balances.chunks(2))
+            .map(|((pool_address, pool_state), balances)| {
+                let (token_0_reserve, token_1_reserve) = pool_state.get_reserves(balances[0], balances[1]);
+                PoolInfo {
+                    pool_address,
+                    pool_state,
+                    token_0_reserve,
+                    token_1_reserve,
+                    slot,
+                }
+            })
+            .collect())
+    }
+
+    async fn get_pool_accounts(&self, offset: usize, mint: &Pubkey) -> Result<Vec<(Pubkey, PoolState)>, TradingEndpointError> {
+        let accounts = self
+            .endpoint
+            .rpc
+            .get_program_accounts_with_config(
+                &PUBKEY_RAYDIUM_CPMM,
+                solana_client::rpc_config::RpcProgramAccountsConfig {
+                    filters: Some(vec![
+                        solana_client::rpc_filter::RpcFilterType::Memcmp(solana_client::rpc_filter::Memcmp::new_raw_bytes(
+                            0,
+                            POOL_STATE_DISCRIMINATOR.to_vec(),
+                        )),
+                        solana_client::rpc_filter::RpcFilterType::Memcmp(solana_client::rpc_filter::Memcmp::new_raw_bytes(offset, mint.to_bytes().to_vec())),
+                    ]),
+                    account_config: solana_client::rpc_config::RpcAccountInfoConfig {
+                        encoding: Some(solana_account_decoder::UiAccountEncoding::Base64),
+                        commitment: None,
+                        data_slice: None,
+                        min_context_slot: None,
+                    },
+                    with_context: None,
+                    sort_results: None,
+                },
+            )
+            .await?;
+
+        accounts
+            .iter()
+            .map(|(pool, account)| Ok((*pool, Self::decode_pool_state(&account.data)?)))
+            .collect()
+    }
+
+    /// Deepest pool pairing `mint` with `quote_mint`, or with WSOL when any does and no quote mint is given.
+    /// The pool is remembered for `mint`, so later quotes and trades use it.
+    pub async fn select_pool(&self, mint: &Pubkey, quote_mint: Option<&Pubkey>) -> Result<PoolInfo, TradingEndpointError> {
+        let pools = self.get_pools(mint).await?;
+        let quote_mint = match quote_mint {
+            Some(quote_mint) => Some(*quote_mint),
+            None => pools.iter().any(|pool| pool.get_quote_mint(mint) == PUBKEY_WSOL).then_some(PUBKEY_WSOL),
+        };
+        let pool = pools
+            .into_iter()
+            .filter(|pool| quote_mint.is_none_or(|quote_mint| pool.get_quote_mint(mint) == quote_mint))
+            .max_by_key(|pool| pool.get_reserves(mint).1)
+            .ok_or_else(|| TradingEndpointError::CustomError(format!("No Raydium CPMM pool found for mint: {}", mint)))?;
+
+        self.pools.write().insert(*mint, pool.get_keys(mint));
+        Ok(pool)
+    }
+
+    /// Pool state and reserves of the pool selected for `mint`, selecting the deepest one first if none was
+    pub async fn get_pool_state(&self, mint: &Pubkey) -> Result<PoolInfo, TradingEndpointError> {
+        let cached = self.pools.read().get(mint).copied();
+        let Some(keys) = cached else {
+            return self.select_pool(mint, None).await;
+        };
+
+        let (accounts, slot) = self
+            .endpoint
+            .get_multiple_accounts_with_slot(&[keys.pool, keys.base_vault, keys.quote_vault])
+            .await?;
+        let [pool_account, base_vault_account, quote_vault_account] =
+            <[_; 3]>::try_from(accounts).map_err(|_| TradingEndpointError::CustomError(format!("Pool accounts not found: {}", mint)))?;
+
+        let pool_account = pool_account.ok_or_else(|| TradingEndpointError::CustomError(format!("Pool account not found: {}", mint)))?;
+        let base_vault_account = base_vault_account.ok_or_else(|| TradingEndpointError::CustomError(format!("Pool base vault not found: {}", mint)))?;
+        let quote_vault_account = quote_vault_account.ok_or_else(|| TradingEndpointError::CustomError(format!("Pool quote vault not found: {}", mint)))?;
+
+        let pool_state = Self::decode_pool_state(&pool_account.data)?;
+        let base_amount = Self::decode_token_amount(&base_vault_account.data)?;
+        let quote_amount = Self::decode_token_amount(&quote_vault_account.data)?;
+        let (token_0_reserve, token_1_reserve) = match pool_state.token_0_mint == *mint {
+            true => pool_state.get_reserves(base_amount, quote_amount),
+            false => pool_state.get_reserves(quote_amount, base_amount),
+        };
+        Ok(PoolInfo {
+            pool_address: keys.pool,
+            pool_state,
+            token_0_reserve,
+            token_1_reserve,
+            slot,
+        })
+    }
+
+    /// Accounts of the pool selected for `mint`, which must have been looked up first
+    pub fn get_pool_keys(&self, mint: &Pubkey) -> Result<PoolKeys, TradingEndpointError> {
+        self.pools
+            .read()
+            .get(mint)
+            .copied()
+            .ok_or_else(|| TradingEndpointError::CustomError(format!("No Raydium CPMM pool selected for mint: {}", mint)))
+    }
+
+    /// AMM configs are fetched once and cached, their fee rates being set by the protocol
+    pub async fn get_amm_config(&self, config: &Pubkey) -> Result<Arc<AmmConfig>, TradingEndpointError> {
+        if let Some(amm_config) = self.configs.read().get(config) {
+            return Ok(amm_config.clone());
+        }
+
+        let account = self.endpoint.rpc.get_account(config).await?;
+        let amm_config = Arc::new(Self::decode_amm_config(&account.data)?);
+        self.configs.write().insert(*config, amm_config.clone());
+        Ok(amm_config)
+    }
+
+    pub fn decode_amm_config(data: &[u8]) -> Result<AmmConfig, TradingEndpointError> {
+        bincode::deserialize::<AmmConfig>(data).map_err(|e| TradingEndpointError::CustomError(e.to_string()))
+    }
+
+    pub fn decode_pool_state(data: &[u8]) -> Result<PoolState, TradingEndpointError> {
+        bincode::deserialize::<PoolState>(data).map_err(|e| TradingEndpointError::CustomError(e.to_string()))
+    }
+
+    fn decode_token_amount(data: &[u8]) -> Result<u64, TradingEndpointError> {
+        let state = StateWithExtensions::<TokenAccount>::unpack(data).map_err(|e| TradingEndpointError::CustomError(e.to_string()))?;
+        Ok(state.base.amount)
+    }
+
+    /// Trade fee charged on the amount in, plus the creator fee on pools that enable it
+    pub fn get_fee_model(config: &AmmConfig, pool_state: &PoolState) -> FeeModel {
+        FeeModel {
+            lp_fee_rate: config.trade_fee_rate,
+            creator_fee_rate: match pool_state.enable_creator_fee {
+                true => config.creator_fee_rate,
+                false => 0,
+            },
+            charged_on_top: false,
+            ..Default::default()
+        }
+    }
+
+    /// Swap from the `input` token account, vault, token program and mint into the `output` ones
+    fn build_swap_instruction(
+        payer: &Keypair,
+        keys: &PoolKeys,
+        input: (&Pubkey, &Pubkey, &Pubkey, &Pubkey),
+        output: (&Pubkey, &Pubkey, &Pubkey, &Pubkey),
+        buffer: Vec<u8>,
+    ) -> Instruction {
+        let (input_account, input_vault, input_token_program, input_mint) = input;
+        let (output_account, output_vault, output_token_program, output_mint) = output;
+
+        Instruction::new_with_bytes(
+            PUBKEY_RAYDIUM_CPMM,
+            &buffer,
+            vec![
+                AccountMeta::new(payer.pubkey(), true),
+                AccountMeta::new_readonly(PUBKEY_RAYDIUM_CPMM_AUTHORITY, false),
+                AccountMeta::new_readonly(keys.amm_config, false),
+                AccountMeta::new(keys.pool, false),
+                AccountMeta::new(*input_account, false),
+                AccountMeta::new(*output_account, false),
+                AccountMeta::new(*input_vault, false),
+                AccountMeta::new(*output_vault, false),
+                AccountMeta::new_readonly(*input_token_program, false),
+                AccountMeta::new_readonly(*output_token_program, false),
+                AccountMeta::new_readonly(*input_mint, false),
+                AccountMeta::new_readonly(*output_mint, false),
+                AccountMeta::new(keys.observation, false),
+            ],
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_client::nonblocking::rpc_client::RpcClient;
+
+    /// `AmmConfig` account: 0.25% trade fee and a 0.05% creator fee
+    fn amm_config_bytes() -> Vec<u8> {
+        let mut data = vec![0u8; 236];
+        data[12..20].copy_from_slice(&2_500u64.to_le_bytes());
+        data[20..28].copy_from_slice(&120_000u64.to_le_bytes());
+        data[28..36].copy_from_slice(&40_000u64.to_le_bytes());
+        data[108..116].copy_from_slice(&500u64.to_le_bytes());
+        data
+    }
+
+    /// `PoolState` account of `mint` against WSOL, WSOL being token 0
+    fn pool_state_bytes(mint: &Pubkey, keys: [Pubkey; 4], enable_creator_fee: bool) -> Vec<u8> {
+        let [amm_config, token_0_vault, token_1_vault, observation] = keys;
+        let mut data = vec![0u8; 637];
+        data[..8].copy_from_slice(&POOL_STATE_DISCRIMINATOR);
+        data[8..40].copy_from_slice(amm_config.as_ref());
+        data[72..104].copy_from_slice(token_0_vault.as_ref());
+        data[104..136].copy_from_slice(token_1_vault.as_ref());
+        data[POOL_STATE_TOKEN_0_MINT_OFFSET..POOL_STATE_TOKEN_0_MINT_OFFSET + 32].copy_from_slice(PUBKEY_WSOL.as_ref());
+        data[POOL_STATE_TOKEN_1_MINT_OFFSET..POOL_STATE_TOKEN_1_MINT_OFFSET + 32].copy_from_slice(mint.as_ref());
+        data[232..264].copy_from_slice(spl_token::ID.as_ref());
+        data[264..296].copy_from_slice(spl_token_2022::ID.as_ref());
+        data[296..328].copy_from_slice(observation.as_ref());
+        data[390] = enable_creator_fee as u8;
+        data
+    }
+
+    fn pool_info(mint: &Pubkey, enable_creator_fee: bool) -> PoolInfo {
+        let keys = [Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique()];
+        PoolInfo {
+            pool_address: Pubkey::new_unique(),
+            pool_state: RaydiumCpmm::decode_pool_state(&pool_state_bytes(mint, keys, enable_creator_fee)).unwrap(),
+            token_0_reserve: 0,
+            token_1_reserve: 0,
+            slot: 0,
+        }
+    }
+
+    fn data(discriminator: u64, first: u64, second: u64) -> Vec<u8> {
+        let mut data = discriminator.to_le_bytes().to_vec();
+        data.extend_from_slice(&first.to_le_bytes());
+        data.extend_from_slice(&second.to_le_bytes());
+        data
+    }
+
+    #[test]
+    fn decodes_pool_keys_and_fees() {
+        let mint = Pubkey::new_unique();
+        let pool = pool_info(&mint, true);
+        let keys = pool.get_keys(&mint);
+        assert_eq!(keys.pool, pool.pool_address);
+        assert_eq!(keys.amm_config, pool.pool_state.amm_config);
+        assert_eq!(keys.observation, pool.pool_state.observation_key);
+        assert_eq!(keys.quote_mint, PUBKEY_WSOL);
+        assert_eq!(keys.quote_token_program, spl_token::ID);
+        assert_eq!(keys.base_vault, pool.pool_state.token_1_vault);
+        assert_eq!(keys.quote_vault, pool.pool_state.token_0_vault);
+        assert_eq!(pool.pool_state.token_1_program, spl_token_2022::ID);
+        assert!(pool.pool_state.swap_enabled());
+
+        let config = RaydiumCpmm::decode_amm_config(&amm_config_bytes()).unwrap();
+        assert_eq!(
+            (config.trade_fee_rate, config.protocol_fee_rate, config.fund_fee_rate),
+            (2_500, 120_000, 40_000)
+        );
+        // Protocol and fund fees are cuts of the trade fee, only the creator fee comes on top of it
+        let fees = RaydiumCpmm::get_fee_model(&config, &pool.pool_state);
+        assert_eq!((fees.lp_fee_rate, fees.creator_fee_rate, fees.total_fee_rate()), (2_500, 500, 3_000));
+        let fees = RaydiumCpmm::get_fee_model(&config, &pool_info(&mint, false).pool_state);
+        assert_eq!((fees.lp_fee_rate, fees.creator_fee_rate), (2_500, 0));
+    }
+
+    #[test]
+    fn buy_and_sell_instruction_layout() {
+        let payer = Keypair::new();
+        let mint = Pubkey::new_unique();
+        let keys = pool_info(&mint, true).get_keys(&mint);
+        let rpc = Arc::new(RpcClient::new("http://127.0.0.1:8899".to_string()));
+        let cpmm = RaydiumCpmm::new(Arc::new(TradingEndpoint::new(rpc, vec![])));
+        cpmm.pools.write().insert(mint, keys);
+
+        let ata = get_associated_token_address_with_program_id(&payer.pubkey(), &mint, &spl_token_2022::ID);
+        let wsol_ata = get_associated_token_address_with_program_id(&payer.pubkey(), &PUBKEY_WSOL, &spl_token::ID);
+        let accounts = |input: (Pubkey, Pubkey, Pubkey, Pubkey), output: (Pubkey, Pubkey, Pubkey, Pubkey)| {
+            vec![
+                AccountMeta::new(payer.pubkey(), true),
+                AccountMeta::new_readonly(PUBKEY_RAYDIUM_CPMM_AUTHORITY, false),
+                AccountMeta::new_readonly(keys.amm_config, false),
+                AccountMeta::new(keys.pool, false),
+                AccountMeta::new(input.0, false),
+                AccountMeta::new(output.0, false),
+                AccountMeta::new(input.1, false),
+                AccountMeta::new(output.1, false),
+                AccountMeta::new_readonly(input.2, false),
+                AccountMeta::new_readonly(output.2, false),
+                AccountMeta::new_readonly(input.3, false),
+                AccountMeta::new_readonly(output.3, false),
+                AccountMeta::new(keys.observation, false),
+            ]
+        };
+        let quote_side = (wsol_ata, keys.quote_vault, spl_token::ID, PUBKEY_WSOL);
+        let base_side = (ata, keys.base_vault, spl_token_2022::ID, mint);
+        let swap = || SwapInfo {
+            sol_amount: 1_000_000_000,
+            token_amount: 5_000_000,
+        };
+
+        let exact_out = cpmm.build_buy_instruction(&payer, &mint, None, &[], &spl_token_2022::ID, swap()).unwrap();
+        assert_eq!(exact_out.program_id, PUBKEY_RAYDIUM_CPMM);
+        assert_eq!(exact_out.accounts, accounts(quote_side, base_side));
+        assert_eq!(exact_out.data, data(RAYDIUM_CPMM_SWAP_BASE_OUTPUT_DISCRIMINATOR, 1_000_000_000, 5_000_000));
+
+        let exact_in = cpmm
+            .build_buy_instruction_with_variant(&payer, &mint, None, &[], &spl_token_2022::ID, swap().with_variant(BuyVariant::ExactSolIn))
+            .unwrap();
+        assert_eq!(exact_in.accounts, accounts(quote_side, base_side));
+        assert_eq!(exact_in.data, data(RAYDIUM_CPMM_SWAP_BASE_INPUT_DISCRIMINATOR, 1_000_000_000, 5_000_000));
+
+        let sell = cpmm.build_sell_instruction(&payer, &mint, None, &[], &spl_token_2022::ID, swap()).unwrap();
+        assert_eq!(sell.accounts, accounts(base_side, quote_side));
+        assert_eq!(sell.data, data(RAYDIUM_CPMM_SWAP_BASE_INPUT_DISCRIMINATOR, 5_000_000, 1_000_000_000));
+    }
+}
@@ -0,0 +1,174 @@
+use borsh::{BorshDeserialize, BorshSerialize};
+use serde::{Deserialize, Serialize};
+use solana_sdk::{pubkey, pubkey::Pubkey};
+
+pub const PUBKEY_RAYDIUM_CPMM: Pubkey = pubkey!("CPMMoo8L3F4NbTegBCKVNunggL7H1ZpdTHKxQB5qKP1C");
+pub const PUBKEY_RAYDIUM_CPMM_AUTHORITY: Pubkey = pubkey!("GpMZbSM2GgvTKHJirzeGfMFoaZ8UR2X7F4v8vHTvxFbL");
+
+/// Anchor discriminator of `PoolState` accounts
+pub const POOL_STATE_DISCRIMINATOR: [u8; 8] = [247, 237, 227, 245, 215, 195, 222, 70];
+pub const RAYDIUM_CPMM_SWAP_BASE_INPUT_DISCRIMINATOR: u64 = 16011174931058048655;
+pub const RAYDIUM_CPMM_SWAP_BASE_OUTPUT_DISCRIMINATOR: u64 = 12516711329758894391;
+
+/// Offsets of `PoolState.token_0_mint` and `PoolState.token_1_mint`
+pub const POOL_STATE_TOKEN_0_MINT_OFFSET: usize = 168;
+pub const POOL_STATE_TOKEN_1_MINT_OFFSET: usize = 200;
+/// Bit of `PoolState.status` set while swaps are disabled
+pub const POOL_STATUS_SWAP_DISABLED: u8 = 1 << 2;
+/// Denominator of the fee rates in `AmmConfig`
+pub const FEE_RATE_DENOMINATOR: u64 = 1_000_000;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AmmConfig {
+    pub discriminator: u64,
+    pub bump: u8,
+    pub disable_create_pool: bool,
+    pub index: u16,
+    pub trade_fee_rate: u64,
+    pub protocol_fee_rate: u64,
+    pub fund_fee_rate: u64,
+    pub create_pool_fee: u64,
+    pub protocol_owner: Pubkey,
+    pub fund_owner: Pubkey,
+    pub creator_fee_rate: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PoolState {
+    pub discriminator: u64,
+    pub amm_config: Pubkey,
+    pub pool_creator: Pubkey,
+    pub token_0_vault: Pubkey,
+    pub token_1_vault: Pubkey,
+    pub lp_mint: Pubkey,
+    pub token_0_mint: Pubkey,
+    pub token_1_mint: Pubkey,
+    pub token_0_program: Pubkey,
+    pub token_1_program: Pubkey,
+    pub observation_key: Pubkey,
+    pub auth_bump: u8,
+    pub status: u8,
+    pub lp_mint_decimals: u8,
+    pub mint_0_decimals: u8,
+    pub mint_1_decimals: u8,
+    pub lp_supply: u64,
+    pub protocol_fees_token_0: u64,
+    pub protocol_fees_token_1: u64,
+    pub fund_fees_token_0: u64,
+    pub fund_fees_token_1: u64,
+    pub open_time: u64,
+    pub recent_epoch: u64,
+    pub creator_fee_on: u8,
+    pub enable_creator_fee: bool,
+    pub padding1: [u8; 6],
+    pub creator_fees_token_0: u64,
+    pub creator_fees_token_1: u64,
+}
+
+impl PoolState {
+    /// Vault balances minus the protocol, fund and creator fees they still hold
+    pub fn get_reserves(&self, token_0_vault_amount: u64, token_1_vault_amount: u64) -> (u64, u64) {
+        (
+            token_0_vault_amount
+                .saturating_sub(self.protocol_fees_token_0)
+                .saturating_sub(self.fund_fees_token_0)
+                .saturating_sub(self.creator_fees_token_0),
+            token_1_vault_amount
+                .saturating_sub(self.protocol_fees_token_1)
+                .saturating_sub(self.fund_fees_token_1)
+                .saturating_sub(self.creator_fees_token_1),
+        )
+    }
+
+    pub fn swap_enabled(&self) -> bool {
+        self.status & POOL_STATUS_SWAP_DISABLED == 0
+    }
+}
+
+/// Accounts a swap needs from a pool, resolved once per mint
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PoolKeys {
+    pub pool: Pubkey,
+    pub amm_config: Pubkey,
+    pub observation: Pubkey,
+    pub quote_mint: Pubkey,
+    pub quote_token_program: Pubkey,
+    pub base_vault: Pubkey,
+    pub quote_vault: Pubkey,
+}
+
+pub struct PoolInfo {
+    pub pool_address: Pubkey,
+    pub pool_state: PoolState,
+    pub token_0_reserve: u64,
+    pub token_1_reserve: u64,
+    /// Slot the reserves were read at
+    pub slot: u64,
+}
+
+impl PoolInfo {
+    /// Reserves of `mint` and of the other side of the pool
+    pub fn get_reserves(&self, mint: &Pubkey) -> (u64, u64) {
+        match self.pool_state.token_0_mint == *mint {
+            true => (self.token_0_reserve, self.token_1_reserve),
+            false => (self.token_1_reserve, self.token_0_reserve),
+        }
+    }
+
+    /// Mint `mint` trades against in this pool
+    pub fn get_quote_mint(&self, mint: &Pubkey) -> Pubkey {
+        match self.pool_state.token_0_mint == *mint {
+            true => self.pool_state.token_1_mint,
+            false => self.pool_state.token_0_mint,
+        }
+    }
+
+    pub fn get_keys(&self, mint: &Pubkey) -> PoolKeys {
+        let state = &self.pool_state;
+        let (quote_mint, quote_token_program, base_vault, quote_vault) = match state.token_0_mint == *mint {
+            true => (state.token_1_mint, state.token_1_program, state.token_0_vault, state.token_1_vault),
+            false => (state.token_0_mint, state.token_0_program, state.token_1_vault, state.token_0_vault),
+        };
+        PoolKeys {
+            pool: self.pool_address,
+            amm_config: state.amm_config,
+            observation: state.observation_key,
+            quote_mint,
+            quote_token_program,
+            base_vault,
+            quote_vault,
+        }
+    }
+}
+
+/// Spend exactly `amount_in`, receiving at least `minimum_amount_out`
+#[derive(Debug, Clone, PartialEq, Eq, BorshSerialize, BorshDeserialize)]
+pub struct SwapBaseInputInfo {
+    pub discriminator: u64,
+    pub amount_in: u64,
+    pub minimum_amount_out: u64,
+}
+
+impl SwapBaseInputInfo {
+    pub fn to_buffer(&self) -> anyhow::Result<Vec<u8>> {
+        let mut buffer = Vec::new();
+        self.serialize(&mut buffer)?;
+        Ok(buffer)
+    }
+}
+
+/// Receive exactly `amount_out`, spending at most `max_amount_in`
+#[derive(Debug, Clone, PartialEq, Eq, BorshSerialize, BorshDeserialize)]
+pub struct SwapBaseOutputInfo {
+    pub discriminator: u64,
+    pub max_amount_in: u64,
+    pub amount_out: u64,
+}
+
+impl SwapBaseOutputInfo {
+    pub fn to_buffer(&self) -> anyhow::Result<Vec<u8>> {
+        let mut buffer = Vec::new();
+        self.serialize(&mut buffer)?;
+        Ok(buffer)
+    }
+}
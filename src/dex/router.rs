@@ -0,0 +1,360 @@
+use super::{
+    dex_traits::DexTrait,
+    types::{BuySwapInfo, Create, PoolInfo, Quote, SlippageBuySwapInfo, SlippageSwapInfo, SwapInfo},
+};
+use crate::{common::trading_endpoint::TradingEndpoint, errors::trading_endpoint_error::TradingEndpointError, instruction::builder::PriorityFee};
+use solana_sdk::{
+    instruction::{AccountMeta, Instruction},
+    pubkey::Pubkey,
+    signature::{Keypair, Signature},
+};
+use std::sync::Arc;
+
+/// Proxy program that DEX swaps are sent through, the DEX instruction becoming the payload of a call to `program_id`
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Router {
+    pub program_id: Pubkey,
+    /// Accounts placed before the DEX program and the DEX accounts
+    pub prefix_accounts: Vec<AccountMeta>,
+    /// Accounts placed after the DEX accounts
+    pub suffix_accounts: Vec<AccountMeta>,
+    /// Bytes placed before the DEX data, usually the proxy's discriminator
+    pub buy_data_prefix: Vec<u8>,
+    pub sell_data_prefix: Vec<u8>,
+    pub data_suffix: Vec<u8>,
+    /// Prefix the DEX data with its u32 length, as borsh encodes a `Vec<u8>`
+    pub length_prefixed: bool,
+}
+
+impl Router {
+    pub fn new(program_id: Pubkey) -> Self {
+        Self {
+            program_id,
+            prefix_accounts: vec![],
+            suffix_accounts: vec![],
+            buy_data_prefix: vec![],
+            sell_data_prefix: vec![],
+            data_suffix: vec![],
+            length_prefixed: false,
+        }
+    }
+
+    pub fn with_prefix_accounts(mut self, accounts: Vec<AccountMeta>) -> Self {
+        self.prefix_accounts = accounts;
+        self
+    }
+
+    pub fn with_suffix_accounts(mut self, accounts: Vec<AccountMeta>) -> Self {
+        self.suffix_accounts = accounts;
+        self
+    }
+
+    /// Same prefix for buys and sells
+    pub fn with_data_prefix(mut self, data: Vec<u8>) -> Self {
+        self.buy_data_prefix = data.clone();
+        self.sell_data_prefix = data;
+        self
+    }
+
+    pub fn with_buy_data_prefix(mut self, data: Vec<u8>) -> Self {
+        self.buy_data_prefix = data;
+        self
+    }
+
+    pub fn with_sell_data_prefix(mut self, data: Vec<u8>) -> Self {
+        self.sell_data_prefix = data;
+        self
+    }
+
+    pub fn with_data_suffix(mut self, data: Vec<u8>) -> Self {
+        self.data_suffix = data;
+        self
+    }
+
+    pub fn with_length_prefixed(mut self, length_prefixed: bool) -> Self {
+        self.length_prefixed = length_prefixed;
+        self
+    }
+
+    /// Call to the proxy carrying `instruction`: prefix accounts, the DEX program, the DEX accounts, then suffix accounts
+    pub fn wrap(&self, instruction: Instruction, is_buy: bool) -> Instruction {
+        let mut accounts = Vec::with_capacity(self.prefix_accounts.len() + instruction.accounts.len() + self.suffix_accounts.len() + 1);
+        accounts.extend(self.prefix_accounts.iter().cloned());
+        accounts.push(AccountMeta::new_readonly(instruction.program_id, false));
+        accounts.extend(instruction.accounts);
+        accounts.extend(self.suffix_accounts.iter().cloned());
+
+        let mut data = match is_buy {
+            true => self.buy_data_prefix.clone(),
+            false => self.sell_data_prefix.clone(),
+        };
+        if self.length_prefixed {
+            data.extend((instruction.data.len() as u32).to_le_bytes());
+        }
+        data.extend(instruction.data);
+        data.extend(&self.data_suffix);
+
+        Instruction {
+            program_id: self.program_id,
+            accounts,
+            data,
+        }
+    }
+}
+
+/// DEX whose buy and sell instructions go through a `Router`, everything else delegated to the wrapped DEX
+///
+/// Trades run through the trait's flows on the wrapper, DEX-specific buys coming from `build_custom_buy_instructions`.
+pub struct RoutedDex {
+    pub inner: Arc<dyn DexTrait>,
+    pub router: Router,
+}
+
+impl RoutedDex {
+    pub fn new(inner: Arc<dyn DexTrait>, router: Router) -> Self {
+        Self { inner, router }
+    }
+}
+
+#[async_trait::async_trait]
+impl DexTrait for RoutedDex {
+    async fn initialize(&self) -> Result<(), TradingEndpointError> {
+        self.inner.initialize().await
+    }
+
+    fn initialized(&self) -> Result<(), TradingEndpointError> {
+        self.inner.initialized()
+    }
+
+    fn use_wsol(&self) -> bool {
+        self.inner.use_wsol()
+    }
+
+    fn get_trading_endpoint(&self) -> Arc<TradingEndpoint> {
+        self.inner.get_trading_endpoint()
+    }
+
+    fn buy_is_exact_in(&self) -> bool {
+        self.inner.buy_is_exact_in()
+    }
+
+    async fn get_pool(&self, mint: &Pubkey) -> Result<PoolInfo, TradingEndpointError> {
+        self.inner.get_pool(mint).await
+    }
+
+    fn get_extra_accounts(&self, mint: &Pubkey, pool_info: &PoolInfo) -> Result<Vec<AccountMeta>, TradingEndpointError> {
+        self.inner.get_extra_accounts(mint, pool_info)
+    }
+
+    fn get_quote_mint(&self, mint: &Pubkey) -> (Pubkey, Pubkey) {
+        self.inner.get_quote_mint(mint)
+    }
+
+    async fn create(&self, payer: Keypair, create: Create, fee: Option<PriorityFee>, tip: Option<u64>) -> Result<Vec<Signature>, TradingEndpointError> {
+        self.inner.create(payer, create, fee, tip).await
+    }
+
+    async fn build_custom_buy_instructions(
+        &self,
+        payer: &Keypair,
+        mint: &Pubkey,
+        sol_amount: u64,
+        slippage_basis_points: u64,
+    ) -> Result<Option<(Vec<Instruction>, Instruction)>, TradingEndpointError> {
+        let instructions = self.inner.build_custom_buy_instructions(payer, mint, sol_amount, slippage_basis_points).await?;
+        Ok(instructions.map(|(setup, swap)| (setup, self.router.wrap(swap, true))))
+    }
+
+    fn build_buy_instruction(
+        &self,
+        payer: &Keypair,
+        mint: &Pubkey,
+        custom_ata: Option<&Pubkey>,
+        extra_accounts: &[AccountMeta],
+        token_program: &Pubkey,
+        buy: SwapInfo,
+    ) -> Result<Instruction, TradingEndpointError> {
+        let instruction = self.inner.build_buy_instruction(payer, mint, custom_ata, extra_accounts, token_program, buy)?;
+        Ok(self.router.wrap(instruction, true))
+    }
+
+    fn build_buy_instruction_with_variant(
+        &self,
+        payer: &Keypair,
+        mint: &Pubkey,
+        custom_ata: Option<&Pubkey>,
+        extra_accounts: &[AccountMeta],
+        token_program: &Pubkey,
+        buy: BuySwapInfo,
+    ) -> Result<Instruction, TradingEndpointError> {
+        let instruction = self
+            .inner
+            .build_buy_instruction_with_variant(payer, mint, custom_ata, extra_accounts, token_program, buy)?;
+        Ok(self.router.wrap(instruction, true))
+    }
+
+    fn build_sell_instruction(
+        &self,
+        payer: &Keypair,
+        mint: &Pubkey,
+        custom_ata: Option<&Pubkey>,
+        extra_accounts: &[AccountMeta],
+        token_program: &Pubkey,
+        sell: SwapInfo,
+    ) -> Result<Instruction, TradingEndpointError> {
+        let instruction = self
+            .inner
+            .build_sell_instruction(payer, mint, custom_ata, extra_accounts, token_program, sell)?;
+        Ok(self.router.wrap(instruction, false))
+    }
+
+    fn build_buy_instruction_with_slippage(
+        &self,
+        payer: &Keypair,
+        mint: &Pubkey,
+        custom_ata: Option<&Pubkey>,
+        extra_accounts: &[AccountMeta],
+        token_program: &Pubkey,
+        buy: SlippageBuySwapInfo,
+    ) -> Result<Instruction, TradingEndpointError> {
+        let instruction = self
+            .inner
+            .build_buy_instruction_with_slippage(payer, mint, custom_ata, extra_accounts, token_program, buy)?;
+        Ok(self.router.wrap(instruction, true))
+    }
+
+    fn build_sell_instruction_with_slippage(
+        &self,
+        payer: &Keypair,
+        mint: &Pubkey,
+        custom_ata: Option<&Pubkey>,
+        extra_accounts: &[AccountMeta],
+        token_program: &Pubkey,
+        sell: SlippageSwapInfo,
+    ) -> Result<Instruction, TradingEndpointError> {
+        let instruction = self
+            .inner
+            .build_sell_instruction_with_slippage(payer, mint, custom_ata, extra_accounts, token_program, sell)?;
+        Ok(self.router.wrap(instruction, false))
+    }
+
+    async fn quote_buy(&self, mint: &Pubkey, sol_amount: u64, slippage_basis_points: u64) -> Result<Quote, TradingEndpointError> {
+        self.inner.quote_buy(mint, sol_amount, slippage_basis_points).await
+    }
+
+    async fn quote_buy_exact_tokens(&self, mint: &Pubkey, token_amount: u64, slippage_basis_points: u64) -> Result<Quote, TradingEndpointError> {
+        self.inner.quote_buy_exact_tokens(mint, token_amount, slippage_basis_points).await
+    }
+
+    async fn quote_sell(&self, mint: &Pubkey, token_amount: u64, slippage_basis_points: u64) -> Result<Quote, TradingEndpointError> {
+        self.inner.quote_sell(mint, token_amount, slippage_basis_points).await
+    }
+
+    async fn quote_buys(&self, mint: &Pubkey, sol_amounts: &[u64], slippage_basis_points: u64) -> Result<Vec<Quote>, TradingEndpointError> {
+        self.inner.quote_buys(mint, sol_amounts, slippage_basis_points).await
+    }
+
+    async fn quote_sells(&self, mint: &Pubkey, token_amounts: &[u64], slippage_basis_points: u64) -> Result<Vec<Quote>, TradingEndpointError> {
+        self.inner.quote_sells(mint, token_amounts, slippage_basis_points).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dex::{moonit::Moonit, raydium_bonk::RaydiumBonk};
+    use solana_client::nonblocking::rpc_client::RpcClient;
+    use solana_sdk::signer::Signer;
+
+    fn instruction() -> Instruction {
+        Instruction {
+            program_id: Pubkey::new_unique(),
+            accounts: vec![
+                AccountMeta::new(Pubkey::new_unique(), true),
+                AccountMeta::new_readonly(Pubkey::new_unique(), false),
+            ],
+            data: vec![1, 2, 3],
+        }
+    }
+
+    fn endpoint() -> Arc<TradingEndpoint> {
+        Arc::new(TradingEndpoint::new(Arc::new(RpcClient::new("http://127.0.0.1:8899".to_string())), vec![]))
+    }
+
+    #[test]
+    fn wrap_places_prefix_program_dex_and_suffix_accounts() {
+        let fee_wallet = AccountMeta::new(Pubkey::new_unique(), false);
+        let guard = AccountMeta::new_readonly(Pubkey::new_unique(), false);
+        let referral = AccountMeta::new(Pubkey::new_unique(), false);
+        let router = Router::new(Pubkey::new_unique())
+            .with_prefix_accounts(vec![fee_wallet.clone(), guard.clone()])
+            .with_suffix_accounts(vec![referral.clone()]);
+        let inner = instruction();
+
+        let wrapped = router.wrap(inner.clone(), true);
+        assert_eq!(wrapped.program_id, router.program_id);
+        let mut accounts = vec![fee_wallet, guard, AccountMeta::new_readonly(inner.program_id, false)];
+        accounts.extend(inner.accounts);
+        accounts.push(referral);
+        assert_eq!(wrapped.accounts, accounts);
+        assert_eq!(wrapped.data, inner.data);
+    }
+
+    #[test]
+    fn wrap_uses_the_side_data_prefix() {
+        let router = Router::new(Pubkey::new_unique())
+            .with_buy_data_prefix(vec![10, 11])
+            .with_sell_data_prefix(vec![20]);
+        assert_eq!(router.wrap(instruction(), true).data, vec![10, 11, 1, 2, 3]);
+        assert_eq!(router.wrap(instruction(), false).data, vec![20, 1, 2, 3]);
+
+        let router = router.with_data_prefix(vec![30]);
+        assert_eq!(router.wrap(instruction(), true).data, vec![30, 1, 2, 3]);
+        assert_eq!(router.wrap(instruction(), false).data, vec![30, 1, 2, 3]);
+    }
+
+    #[test]
+    fn wrap_length_prefixes_the_dex_data_before_the_suffix() {
+        let router = Router::new(Pubkey::new_unique())
+            .with_data_prefix(vec![9])
+            .with_length_prefixed(true)
+            .with_data_suffix(vec![7, 7]);
+        assert_eq!(router.wrap(instruction(), true).data, vec![9, 3, 0, 0, 0, 1, 2, 3, 7, 7]);
+    }
+
+    #[test]
+    fn routed_dex_wraps_builders_and_keeps_inner_settings() {
+        let router = Router::new(Pubkey::new_unique()).with_buy_data_prefix(vec![1]).with_sell_data_prefix(vec![2]);
+        let moonit = RoutedDex::new(Arc::new(Moonit::new(endpoint())), router.clone());
+        let payer = Keypair::new();
+        let mint = Pubkey::new_unique();
+        let swap = SwapInfo {
+            token_amount: 1_000,
+            sol_amount: 2_000,
+        };
+
+        let buy = moonit.build_buy_instruction(&payer, &mint, None, &[], &spl_token::ID, swap).unwrap();
+        assert_eq!(buy.program_id, router.program_id);
+        assert_eq!(buy.accounts[0], AccountMeta::new_readonly(crate::dex::moonit_types::PUBKEY_MOONIT, false));
+        assert_eq!(buy.accounts[1], AccountMeta::new(payer.pubkey(), true));
+        assert_eq!(buy.data[0], 1);
+
+        let sell = moonit
+            .build_sell_instruction(
+                &payer,
+                &mint,
+                None,
+                &[],
+                &spl_token::ID,
+                SwapInfo {
+                    token_amount: 1_000,
+                    sol_amount: 0,
+                },
+            )
+            .unwrap();
+        assert_eq!(sell.data[0], 2);
+
+        assert!(!moonit.buy_is_exact_in());
+        assert!(RoutedDex::new(Arc::new(RaydiumBonk::new(endpoint(), None)), router).buy_is_exact_in());
+    }
+}
@@ -1,15 +1,23 @@
-use super::{dex_traits::DexTrait, pumpfun, pumpswap};
+use super::{
+    amm_calc::{
+        amm_buy_get_sol_in, amm_buy_get_token_out, amm_get_price, amm_sell_get_sol_out, calculate_price_impact_bps, calculate_with_slippage_buy,
+        calculate_with_slippage_sell, FeeAmounts, FeeModel,
+    },
+    dex_traits::DexTrait,
+    pumpfun, pumpswap,
+};
 use crate::{
-    common::trading_endpoint::TradingEndpoint,
-    dex::{believe, boopfun, raydium_bonk},
+    common::{mint::MintInfo, trading_endpoint::TradingEndpoint},
+    dex::{believe, boopfun, meteora_damm_v2, meteora_dbc, moonit, raydium_amm, raydium_bonk, raydium_bonk_types, raydium_cpmm, router},
 };
 use serde::{Deserialize, Serialize};
 use solana_client::client_error::ClientError;
 use solana_client::nonblocking::rpc_client::RpcClient;
 use solana_sdk::{pubkey::Pubkey, signature::Keypair};
-use spl_associated_token_account::get_associated_token_address;
+use spl_associated_token_account::get_associated_token_address_with_program_id;
 use std::sync::Arc;
 
+#[derive(Debug, Clone)]
 pub struct PoolInfo {
     pub pool: Pubkey,
     pub creator: Option<Pubkey>,
@@ -17,6 +25,128 @@ pub struct PoolInfo {
     pub config: Option<Pubkey>,
     pub token_reserves: u64,
     pub sol_reserves: u64,
+    pub fees: FeeModel,
+    /// Slot the pool accounts were read at
+    pub slot: u64,
+}
+
+/// Expected outcome of a trade against a pool snapshot
+#[derive(Debug, Clone)]
+pub struct Quote {
+    pub amount_in: u64,
+    /// Most the trade may spend, `amount_in` raised by slippage for buys
+    pub max_amount_in: u64,
+    pub expected_out: u64,
+    pub min_out: u64,
+    /// Fees paid on the trade, on the SOL side unless the pool collects them in the token
+    pub fees: FeeAmounts,
+    /// Tokens withheld by the Token-2022 transfer fee extension
+    pub transfer_fee: u64,
+    /// Prices in lamports per token unit
+    pub price_before: f64,
+    pub price_after: f64,
+    pub price_impact_bps: u64,
+    pub pool_info: PoolInfo,
+    pub mint_info: MintInfo,
+    /// Slot the pool was read at, the quote being priced at that slot
+    pub slot: u64,
+}
+
+impl Quote {
+    /// Spend `sol_amount` on tokens
+    pub fn buy(pool_info: PoolInfo, mint_info: MintInfo, sol_amount: u64, slippage_basis_points: u64) -> Self {
+        let slot = pool_info.slot;
+        let sol_to_curve = pool_info.fees.buy_amount_after_fees(sol_amount);
+        let token_out = amm_buy_get_token_out(pool_info.sol_reserves, pool_info.token_reserves, sol_to_curve);
+        let transfer_fee = mint_info.get_transfer_fee(slot, token_out);
+        let price_before = amm_get_price(pool_info.sol_reserves, pool_info.token_reserves);
+        let price_after = amm_get_price(pool_info.sol_reserves + sol_to_curve, pool_info.token_reserves - token_out);
+
+        Self {
+            amount_in: sol_amount,
+            max_amount_in: calculate_with_slippage_buy(sol_amount, slippage_basis_points),
+            expected_out: token_out - transfer_fee,
+            min_out: calculate_with_slippage_sell(token_out - transfer_fee, slippage_basis_points),
+            fees: pool_info.fees.get_buy_fee_amounts(sol_amount),
+            transfer_fee,
+            price_before,
+            price_after,
+            price_impact_bps: calculate_price_impact_bps(price_before, price_after),
+            pool_info,
+            mint_info,
+            slot,
+        }
+    }
+
+    /// Receive exactly `token_amount`, fees included in the SOL spent
+    pub fn buy_exact_tokens(pool_info: PoolInfo, mint_info: MintInfo, token_amount: u64, slippage_basis_points: u64) -> Self {
+        let slot = pool_info.slot;
+        let transfer_fee = mint_info.get_inverse_transfer_fee(slot, token_amount);
+        let token_out = token_amount + transfer_fee;
+        let sol_to_curve = amm_buy_get_sol_in(pool_info.sol_reserves, pool_info.token_reserves, token_out);
+        let sol_amount = match sol_to_curve {
+            0 => 0,
+            sol_to_curve => pool_info.fees.buy_amount_with_fees(sol_to_curve),
+        };
+        let price_before = amm_get_price(pool_info.sol_reserves, pool_info.token_reserves);
+        let price_after = amm_get_price(pool_info.sol_reserves + sol_to_curve, pool_info.token_reserves.saturating_sub(token_out));
+
+        Self {
+            amount_in: sol_amount,
+            max_amount_in: calculate_with_slippage_buy(sol_amount, slippage_basis_points),
+            expected_out: token_amount,
+            min_out: token_amount,
+            fees: pool_info.fees.get_buy_fee_amounts(sol_amount),
+            transfer_fee,
+            price_before,
+            price_after,
+            price_impact_bps: calculate_price_impact_bps(price_before, price_after),
+            pool_info,
+            mint_info,
+            slot,
+        }
+    }
+
+    /// Sell `token_amount` for SOL
+    pub fn sell(pool_info: PoolInfo, mint_info: MintInfo, token_amount: u64, slippage_basis_points: u64) -> Self {
+        let slot = pool_info.slot;
+        let transfer_fee = mint_info.get_transfer_fee(slot, token_amount);
+        let token_in = token_amount - transfer_fee;
+        let sol_from_curve = amm_sell_get_sol_out(pool_info.sol_reserves, pool_info.token_reserves, token_in);
+        let sol_out = pool_info.fees.sell_amount_after_fees(sol_from_curve);
+        let price_before = amm_get_price(pool_info.sol_reserves, pool_info.token_reserves);
+        let price_after = amm_get_price(pool_info.sol_reserves - sol_from_curve, pool_info.token_reserves + token_in);
+
+        Self {
+            amount_in: token_amount,
+            max_amount_in: token_amount,
+            expected_out: sol_out,
+            min_out: calculate_with_slippage_sell(sol_out, slippage_basis_points),
+            fees: pool_info.fees.get_fee_amounts(sol_from_curve),
+            transfer_fee,
+            price_before,
+            price_after,
+            price_impact_bps: calculate_price_impact_bps(price_before, price_after),
+            pool_info,
+            mint_info,
+            slot,
+        }
+    }
+
+    /// Tokens leaving the pool on a buy, before the transfer fee is withheld
+    pub fn gross_token_out(&self) -> u64 {
+        self.expected_out + self.transfer_fee
+    }
+}
+
+/// Which side of a buy is fixed
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum BuyVariant {
+    /// Receive exactly `token_amount`, spending at most `sol_amount`
+    #[default]
+    ExactTokensOut,
+    /// Spend exactly `sol_amount`, receiving at least `token_amount`
+    ExactSolIn,
 }
 
 pub struct SwapInfo {
@@ -24,6 +154,58 @@ pub struct SwapInfo {
     pub sol_amount: u64,
 }
 
+impl SwapInfo {
+    pub fn with_variant(self, variant: BuyVariant) -> BuySwapInfo {
+        BuySwapInfo { swap: self, variant }
+    }
+}
+
+/// Buy amounts along with which of them is fixed
+pub struct BuySwapInfo {
+    pub swap: SwapInfo,
+    pub variant: BuyVariant,
+}
+
+/// Amounts a swap is expected to trade and the slippage tolerated on them, for programs that check the slippage themselves
+pub struct SlippageSwapInfo {
+    pub expected: SwapInfo,
+    pub slippage_basis_points: u64,
+}
+
+impl SlippageSwapInfo {
+    pub fn with_variant(self, variant: BuyVariant) -> SlippageBuySwapInfo {
+        SlippageBuySwapInfo { swap: self, variant }
+    }
+
+    /// Sell bounds, the SOL out allowed to fall by the slippage
+    pub fn get_sell_bounds(&self) -> SwapInfo {
+        SwapInfo {
+            token_amount: self.expected.token_amount,
+            sol_amount: calculate_with_slippage_sell(self.expected.sol_amount, self.slippage_basis_points),
+        }
+    }
+
+    /// Buy bounds, slippage applied to the side `variant` does not fix
+    pub fn get_buy_bounds(&self, variant: BuyVariant) -> SwapInfo {
+        match variant {
+            BuyVariant::ExactTokensOut => SwapInfo {
+                token_amount: self.expected.token_amount,
+                sol_amount: calculate_with_slippage_buy(self.expected.sol_amount, self.slippage_basis_points),
+            },
+            BuyVariant::ExactSolIn => SwapInfo {
+                token_amount: calculate_with_slippage_sell(self.expected.token_amount, self.slippage_basis_points),
+                sol_amount: self.expected.sol_amount,
+            },
+        }
+    }
+}
+
+/// Expected buy amounts along with which of them is fixed
+pub struct SlippageBuySwapInfo {
+    pub swap: SlippageSwapInfo,
+    pub variant: BuyVariant,
+}
+
 pub struct Create {
     pub name: String,
     pub symbol: String,
@@ -35,39 +217,84 @@ pub struct Create {
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
 pub enum DexType {
+    /// A `Some` program sends the swaps through `Router::new(program)`, prefer `TradingClient::with_router` for anything more
     Pumpfun(Option<Pubkey>),
     PumpSwap,
-    RayBonk,
+    RayBonk(Option<raydium_bonk_types::RaydiumBonkPlatform>),
     Boopfun,
     Believe,
+    MeteoraDBC,
+    MeteoraDammV2,
+    Moonit,
+    RaydiumAmm,
+    RaydiumCpmm,
 }
 
 impl DexType {
     pub fn all() -> Vec<DexType> {
-        vec![DexType::Pumpfun(None), DexType::PumpSwap, DexType::RayBonk, DexType::Boopfun, DexType::Believe]
+        vec![
+            DexType::Pumpfun(None),
+            DexType::PumpSwap,
+            DexType::RayBonk(None),
+            DexType::Boopfun,
+            DexType::Believe,
+            DexType::MeteoraDBC,
+            DexType::MeteoraDammV2,
+            DexType::Moonit,
+            DexType::RaydiumAmm,
+            DexType::RaydiumCpmm,
+        ]
     }
 
     pub fn instantiate(&self, endpoint: Arc<TradingEndpoint>) -> Arc<dyn DexTrait> {
         match self {
-            DexType::Pumpfun(t) => Arc::new(pumpfun::Pumpfun::new(endpoint, *t)),
+            DexType::Pumpfun(None) => Arc::new(pumpfun::Pumpfun::new(endpoint)),
+            DexType::Pumpfun(Some(program)) => Arc::new(router::RoutedDex::new(Arc::new(pumpfun::Pumpfun::new(endpoint)), router::Router::new(*program))),
             DexType::PumpSwap => Arc::new(pumpswap::PumpSwap::new(endpoint)),
-            DexType::RayBonk => Arc::new(raydium_bonk::RaydiumBonk::new(endpoint)),
+            DexType::RayBonk(t) => Arc::new(raydium_bonk::RaydiumBonk::new(endpoint, *t)),
             DexType::Boopfun => Arc::new(boopfun::Boopfun::new(endpoint)),
             DexType::Believe => Arc::new(believe::Believe::new(endpoint)),
+            DexType::MeteoraDBC => Arc::new(meteora_dbc::MeteoraDBC::new(endpoint)),
+            DexType::MeteoraDammV2 => Arc::new(meteora_damm_v2::MeteoraDammV2::new(endpoint)),
+            DexType::Moonit => Arc::new(moonit::Moonit::new(endpoint)),
+            DexType::RaydiumAmm => Arc::new(raydium_amm::RaydiumAmm::new(endpoint)),
+            DexType::RaydiumCpmm => Arc::new(raydium_cpmm::RaydiumCpmm::new(endpoint)),
         }
     }
 }
 
+/// Where a mint is in its launch lifecycle
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum LifecycleStage {
+    /// Trading on its launchpad bonding curve
+    Curve,
+    /// Curve filled and waiting for migration, nothing is tradable
+    Complete,
+    /// Liquidity moved to an AMM pool
+    Migrated,
+}
+
+/// Venue a mint currently trades on, found by `TradingClient::resolve_mint`
+#[derive(Debug, Clone)]
+pub struct ResolvedMint {
+    pub dex_type: DexType,
+    pub stage: LifecycleStage,
+    /// `None` while the curve is complete
+    pub pool_info: Option<PoolInfo>,
+    /// Slot the pool was read at, or the launchpad accounts without a pool
+    pub slot: u64,
+}
+
 pub enum TokenAmountType {
     Percent(u64),
     Amount(u64),
 }
 
 impl TokenAmountType {
-    pub async fn to_amount(&self, rpc: Arc<RpcClient>, payer: &Pubkey, mint: &Pubkey) -> Result<u64, ClientError> {
+    pub async fn to_amount(&self, rpc: Arc<RpcClient>, payer: &Pubkey, mint: &Pubkey, token_program: &Pubkey) -> Result<u64, ClientError> {
         match self {
             TokenAmountType::Percent(percent) => {
-                let ata = get_associated_token_address(payer, mint);
+                let ata = get_associated_token_address_with_program_id(payer, mint, token_program);
                 let balance = rpc.get_token_account_balance(&ata).await?;
                 let balance_u64 = balance.amount.parse::<u64>().unwrap_or(0);
                 Ok((balance_u64 * percent) / 100)
@@ -84,6 +311,13 @@ pub enum CreateATA {
     CreateWithSeed(String),
 }
 
+/// Token account a sell spends from, the payer's ATA when `custom_ata` is unset, and whether to close the ATA afterwards
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SellTokenAccount {
+    pub custom_ata: Option<Pubkey>,
+    pub close_mint_ata: bool,
+}
+
 pub struct BatchBuyParam {
     pub payer: Keypair,
     pub sol_amount: u64,
@@ -0,0 +1,2 @@
+pub mod swqos_error;
+pub mod trading_endpoint_error;
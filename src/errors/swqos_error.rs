@@ -0,0 +1,20 @@
+use solana_client::client_error::ClientError;
+
+#[derive(Debug, thiserror::Error)]
+pub enum SWQoSError {
+    #[error("{0}")]
+    Custom(String),
+    #[error(transparent)]
+    Reqwest(#[from] reqwest::Error),
+    #[error(transparent)]
+    Json(#[from] serde_json::Error),
+    /// Boxed, as it is much larger than the other variants
+    #[error(transparent)]
+    Client(Box<ClientError>),
+}
+
+impl From<ClientError> for SWQoSError {
+    fn from(error: ClientError) -> Self {
+        SWQoSError::Client(Box::new(error))
+    }
+}
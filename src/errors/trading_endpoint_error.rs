@@ -0,0 +1,39 @@
+use crate::{
+    dex::{
+        boopfun_types::BoopfunError, meteora_damm_v2_types::MeteoraDammV2Error, meteora_dbc_types::MeteoraDbcError, moonit_types::MoonitError,
+        pumpfun_types::PumpfunError,
+    },
+    swqos::TipError,
+};
+use solana_client::client_error::ClientError;
+
+#[derive(Debug, thiserror::Error)]
+pub enum TradingEndpointError {
+    #[error("{0}")]
+    CustomError(String),
+    #[error("{0}")]
+    TransactionError(String),
+    /// Boxed, as it is much larger than the other variants
+    #[error(transparent)]
+    SolanaClientError(Box<ClientError>),
+    #[error(transparent)]
+    SWQoSError(#[from] crate::errors::swqos_error::SWQoSError),
+    #[error(transparent)]
+    Tip(#[from] TipError),
+    #[error(transparent)]
+    Pumpfun(#[from] PumpfunError),
+    #[error(transparent)]
+    Boopfun(#[from] BoopfunError),
+    #[error(transparent)]
+    Moonit(#[from] MoonitError),
+    #[error(transparent)]
+    MeteoraDbc(#[from] MeteoraDbcError),
+    #[error(transparent)]
+    MeteoraDammV2(#[from] MeteoraDammV2Error),
+}
+
+impl From<ClientError> for TradingEndpointError {
+    fn from(error: ClientError) -> Self {
+        TradingEndpointError::SolanaClientError(Box::new(error))
+    }
+}
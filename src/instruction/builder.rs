@@ -4,21 +4,22 @@ use crate::{
 };
 use serde::{Deserialize, Serialize};
 use solana_program::program_pack::Pack;
-use solana_program::system_instruction;
 use solana_sdk::{
     hash::Hash,
     instruction::Instruction,
     message::{v0, Message, VersionedMessage},
     pubkey::Pubkey,
+    rent::Rent,
     signature::Keypair,
     signer::Signer,
     transaction::{Transaction as LegacyTransaction, VersionedTransaction},
 };
 use spl_associated_token_account::{
-    get_associated_token_address,
+    get_associated_token_address, get_associated_token_address_with_program_id,
     instruction::{create_associated_token_account, create_associated_token_account_idempotent},
 };
-use spl_token::instruction::{close_account, initialize_account3, sync_native};
+use spl_token_2022::extension::ExtensionType;
+use spl_token_2022::instruction::{close_account, initialize_account3, sync_native};
 use std::ops::Add;
 
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, Default)]
@@ -52,7 +53,7 @@ pub fn build_transaction(
 ) -> anyhow::Result<Transaction> {
     let v0_message: v0::Message = v0::Message::try_compile(&payer.pubkey(), &instructions, &[], blockhash)?;
     let versioned_message: VersionedMessage = VersionedMessage::V0(v0_message);
-    let signers = vec![payer].into_iter().chain(other_signers.unwrap_or_default().into_iter()).collect::<Vec<_>>();
+    let signers = vec![payer].into_iter().chain(other_signers.unwrap_or_default()).collect::<Vec<_>>();
     let transaction = VersionedTransaction::try_new(versioned_message, &signers)?;
 
     Ok(Transaction::Versioned(transaction))
@@ -76,26 +77,40 @@ pub fn build_legacy_transaction(
     Ok(Transaction::Legacy(transaction))
 }
 
-pub fn build_token_account_instructions(payer: &Keypair, mint: &Pubkey, crate_ata: CreateATA) -> anyhow::Result<(Pubkey, Vec<Instruction>)> {
+/// Account extensions a seeded Token-2022 account is sized for, covering the ones required by common mint extensions
+const SEEDED_TOKEN_2022_ACCOUNT_EXTENSIONS: [ExtensionType; 4] = [
+    ExtensionType::TransferFeeAmount,
+    ExtensionType::NonTransferableAccount,
+    ExtensionType::TransferHookAccount,
+    ExtensionType::PausableAccount,
+];
+
+pub fn build_token_account_instructions(
+    payer: &Keypair,
+    mint: &Pubkey,
+    token_program: &Pubkey,
+    crate_ata: CreateATA,
+) -> anyhow::Result<(Pubkey, Vec<Instruction>)> {
     let mut instructions = vec![];
+    let ata = get_associated_token_address_with_program_id(&payer.pubkey(), mint, token_program);
 
     let (token_program, instructions) = match crate_ata {
         CreateATA::Create => {
-            instructions.push(create_associated_token_account(&payer.pubkey(), &payer.pubkey(), &mint, &spl_token::ID));
-            (get_associated_token_address(&payer.pubkey(), mint), instructions)
+            instructions.push(create_associated_token_account(&payer.pubkey(), &payer.pubkey(), mint, token_program));
+            (ata, instructions)
         }
         CreateATA::Idempotent => {
             instructions.push(create_associated_token_account_idempotent(
                 &payer.pubkey(),
                 &payer.pubkey(),
-                &mint,
-                &spl_token::ID,
+                mint,
+                token_program,
             ));
-            (get_associated_token_address(&payer.pubkey(), mint), instructions)
+            (ata, instructions)
         }
-        CreateATA::None => (get_associated_token_address(&payer.pubkey(), mint), vec![]),
+        CreateATA::None => (ata, vec![]),
         CreateATA::CreateWithSeed(seed) => {
-            let (token_program, ixs) = build_seeded_token_address(&payer.pubkey(), &mint, &seed)?;
+            let (token_program, ixs) = build_seeded_token_address(&payer.pubkey(), mint, token_program, &seed)?;
             instructions.extend_from_slice(&ixs);
             (token_program, ixs)
         }
@@ -107,14 +122,15 @@ pub fn build_token_account_instructions(payer: &Keypair, mint: &Pubkey, crate_at
 pub fn build_sol_sell_instructions(
     payer: &Keypair,
     mint: &Pubkey,
+    token_program: &Pubkey,
     sell_instruction: Instruction,
     close_mint_ata: bool,
 ) -> Result<Vec<Instruction>, anyhow::Error> {
     let mut instructions = vec![sell_instruction];
 
     if close_mint_ata {
-        let mint_ata = get_associated_token_address(&payer.pubkey(), &mint);
-        instructions.push(close_account(&spl_token::ID, &mint_ata, &payer.pubkey(), &payer.pubkey(), &[&payer.pubkey()])?);
+        let mint_ata = get_associated_token_address_with_program_id(&payer.pubkey(), mint, token_program);
+        instructions.push(close_account(token_program, &mint_ata, &payer.pubkey(), &payer.pubkey(), &[&payer.pubkey()])?);
     }
 
     Ok(instructions)
@@ -123,40 +139,57 @@ pub fn build_sol_sell_instructions(
 pub fn build_wsol_buy_instructions(
     payer: &Keypair,
     mint: &Pubkey,
+    token_program: &Pubkey,
     amount_sol: u64,
     buy_instruction: Instruction,
     crate_ata: CreateATA,
 ) -> anyhow::Result<Vec<Instruction>> {
-    let mut instructions = vec![];
+    build_quote_buy_instructions(payer, mint, token_program, QuotePayment::wsol(amount_sol), buy_instruction, crate_ata)
+}
 
-    match crate_ata {
-        CreateATA::Create => {
-            instructions.push(create_associated_token_account(&payer.pubkey(), &payer.pubkey(), &mint, &spl_token::ID));
-        }
-        CreateATA::Idempotent => {
-            instructions.push(create_associated_token_account_idempotent(
-                &payer.pubkey(),
-                &payer.pubkey(),
-                &mint,
-                &spl_token::ID,
-            ));
-        }
-        CreateATA::None => {}
-        CreateATA::CreateWithSeed(seed) => {
-            let (_, ixs) = build_seeded_token_address(&payer.pubkey(), &mint, &seed)?;
-            instructions.extend_from_slice(&ixs);
+/// Token a buy is paid in and the most of it the buy spends
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct QuotePayment {
+    pub mint: Pubkey,
+    pub token_program: Pubkey,
+    pub amount: u64,
+}
+
+impl QuotePayment {
+    pub fn wsol(amount: u64) -> Self {
+        Self {
+            mint: PUBKEY_WSOL,
+            token_program: spl_token::ID,
+            amount,
         }
     }
+}
+
+/// Buy paid in `quote.mint`, wrapping `quote.amount` lamports first when the quote mint is WSOL
+pub fn build_quote_buy_instructions(
+    payer: &Keypair,
+    mint: &Pubkey,
+    token_program: &Pubkey,
+    quote: QuotePayment,
+    buy_instruction: Instruction,
+    crate_ata: CreateATA,
+) -> anyhow::Result<Vec<Instruction>> {
+    let (_, mut instructions) = build_token_account_instructions(payer, mint, token_program, crate_ata)?;
 
     instructions.push(create_associated_token_account_idempotent(
         &payer.pubkey(),
         &payer.pubkey(),
-        &PUBKEY_WSOL,
-        &spl_token::ID,
+        &quote.mint,
+        &quote.token_program,
     ));
 
+    if quote.mint != PUBKEY_WSOL {
+        instructions.push(buy_instruction);
+        return Ok(instructions);
+    }
+
     let wsol_ata = get_associated_token_address(&payer.pubkey(), &PUBKEY_WSOL);
-    instructions.push(solana_sdk::system_instruction::transfer(&payer.pubkey(), &wsol_ata, amount_sol));
+    instructions.push(solana_sdk::system_instruction::transfer(&payer.pubkey(), &wsol_ata, quote.amount));
 
     instructions.push(sync_native(&spl_token::ID, &wsol_ata).unwrap());
 
@@ -167,42 +200,68 @@ pub fn build_wsol_buy_instructions(
     Ok(instructions)
 }
 
-pub fn build_wsol_sell_instructions(payer: &Keypair, mint: &Pubkey, sell_instruction: Instruction, close_mint_ata: bool) -> anyhow::Result<Vec<Instruction>> {
-    let mint_ata = get_associated_token_address(&payer.pubkey(), &mint);
-    let wsol_ata = get_associated_token_address(&payer.pubkey(), &PUBKEY_WSOL);
+pub fn build_wsol_sell_instructions(
+    payer: &Keypair,
+    mint: &Pubkey,
+    token_program: &Pubkey,
+    sell_instruction: Instruction,
+    close_mint_ata: bool,
+) -> anyhow::Result<Vec<Instruction>> {
+    build_quote_sell_instructions(payer, mint, token_program, &PUBKEY_WSOL, &spl_token::ID, sell_instruction, close_mint_ata)
+}
+
+/// Sell into `quote_mint`, unwrapping the proceeds when the quote mint is WSOL
+pub fn build_quote_sell_instructions(
+    payer: &Keypair,
+    mint: &Pubkey,
+    token_program: &Pubkey,
+    quote_mint: &Pubkey,
+    quote_token_program: &Pubkey,
+    sell_instruction: Instruction,
+    close_mint_ata: bool,
+) -> anyhow::Result<Vec<Instruction>> {
+    let mint_ata = get_associated_token_address_with_program_id(&payer.pubkey(), mint, token_program);
 
     let mut instructions = vec![];
     instructions.push(create_associated_token_account_idempotent(
         &payer.pubkey(),
         &payer.pubkey(),
-        &PUBKEY_WSOL,
-        &spl_token::ID,
+        quote_mint,
+        quote_token_program,
     ));
 
     instructions.push(sell_instruction);
 
-    instructions.push(close_account(&spl_token::ID, &wsol_ata, &payer.pubkey(), &payer.pubkey(), &[&payer.pubkey()]).unwrap());
+    if *quote_mint == PUBKEY_WSOL {
+        let wsol_ata = get_associated_token_address(&payer.pubkey(), &PUBKEY_WSOL);
+        instructions.push(close_account(&spl_token::ID, &wsol_ata, &payer.pubkey(), &payer.pubkey(), &[&payer.pubkey()]).unwrap());
+    }
 
     if close_mint_ata {
-        instructions.push(close_account(&spl_token::ID, &mint_ata, &payer.pubkey(), &payer.pubkey(), &[&payer.pubkey()]).unwrap());
+        instructions.push(close_account(token_program, &mint_ata, &payer.pubkey(), &payer.pubkey(), &[&payer.pubkey()]).unwrap());
     }
 
     Ok(instructions)
 }
 
-fn build_seeded_token_address(payer: &Pubkey, mint: &Pubkey, seed: &str) -> anyhow::Result<(Pubkey, Vec<Instruction>)> {
+fn build_seeded_token_address(payer: &Pubkey, mint: &Pubkey, token_program: &Pubkey, seed: &str) -> anyhow::Result<(Pubkey, Vec<Instruction>)> {
     let base = payer;
-    let token_program_id = spl_token::id();
+    let token_program_id = *token_program;
 
     // 1. Derive the token account address (on-curve)
-    let token_account = Pubkey::create_with_seed(&base, seed, &token_program_id)?;
+    let token_account = Pubkey::create_with_seed(base, seed, &token_program_id)?;
 
     // 2. Calculate space & rent (works on-chain; for off-chain, hardcode)
-    let account_size = spl_token::state::Account::LEN;
-    let lamports = 2_139_280;
+    let (account_size, lamports) = match token_program_id == spl_token_2022::ID {
+        true => {
+            let account_size = ExtensionType::try_calculate_account_len::<spl_token_2022::state::Account>(&SEEDED_TOKEN_2022_ACCOUNT_EXTENSIONS)?;
+            (account_size, Rent::default().minimum_balance(account_size))
+        }
+        false => (spl_token::state::Account::LEN, 2_139_280),
+    };
 
     // 3. Create account with seed
-    let ix_create = system_instruction::create_account_with_seed(
+    let ix_create = solana_sdk::system_instruction::create_account_with_seed(
         payer,          // from (funder)
         &token_account, // to (new account)
         base,           // base
@@ -222,3 +281,98 @@ fn build_seeded_token_address(payer: &Pubkey, mint: &Pubkey, seed: &str) -> anyh
 
     Ok((token_account, vec![ix_create, ix_init]))
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use solana_sdk::instruction::AccountMeta;
+
+    const ATA_TOKEN_PROGRAM_INDEX: usize = 5;
+
+    fn assert_token_account_instructions(token_program: &Pubkey) {
+        let payer = Keypair::new();
+        let mint = Pubkey::new_unique();
+        let expected_ata = get_associated_token_address_with_program_id(&payer.pubkey(), &mint, token_program);
+
+        for create_ata in [CreateATA::Create, CreateATA::Idempotent] {
+            let (ata, instructions) = build_token_account_instructions(&payer, &mint, token_program, create_ata).unwrap();
+            assert_eq!(ata, expected_ata);
+            assert_eq!(instructions.len(), 1);
+            assert_eq!(instructions[0].program_id, spl_associated_token_account::ID);
+            assert_eq!(instructions[0].accounts[1], AccountMeta::new(expected_ata, false));
+            assert_eq!(instructions[0].accounts[3], AccountMeta::new_readonly(mint, false));
+            assert_eq!(
+                instructions[0].accounts[ATA_TOKEN_PROGRAM_INDEX],
+                AccountMeta::new_readonly(*token_program, false)
+            );
+        }
+
+        let (ata, instructions) = build_token_account_instructions(&payer, &mint, token_program, CreateATA::None).unwrap();
+        assert_eq!(ata, expected_ata);
+        assert!(instructions.is_empty());
+
+        // Closing the mint account after a sell goes through the mint's token program
+        let sell = Instruction::new_with_bytes(Pubkey::new_unique(), &[], vec![]);
+        let instructions = build_sol_sell_instructions(&payer, &mint, token_program, sell, true).unwrap();
+        assert_eq!(instructions.len(), 2);
+        assert_eq!(instructions[1].program_id, *token_program);
+        assert_eq!(instructions[1].accounts[0], AccountMeta::new(expected_ata, false));
+    }
+
+    #[test]
+    fn token_account_instructions_for_legacy_mint() {
+        assert_token_account_instructions(&spl_token::ID);
+    }
+
+    #[test]
+    fn token_account_instructions_for_token_2022_mint() {
+        assert_token_account_instructions(&spl_token_2022::ID);
+    }
+
+    #[test]
+    fn token_2022_ata_differs_from_legacy_ata() {
+        let owner = Pubkey::new_unique();
+        let mint = Pubkey::new_unique();
+        assert_ne!(
+            get_associated_token_address_with_program_id(&owner, &mint, &spl_token::ID),
+            get_associated_token_address_with_program_id(&owner, &mint, &spl_token_2022::ID)
+        );
+        assert_eq!(
+            get_associated_token_address(&owner, &mint),
+            get_associated_token_address_with_program_id(&owner, &mint, &spl_token::ID)
+        );
+    }
+
+    #[test]
+    fn quote_buy_keeps_mint_and_quote_token_programs_apart() {
+        let payer = Keypair::new();
+        let mint = Pubkey::new_unique();
+        let buy = Instruction::new_with_bytes(Pubkey::new_unique(), &[], vec![]);
+        let instructions = build_wsol_buy_instructions(&payer, &mint, &spl_token_2022::ID, 1_000, buy.clone(), CreateATA::Idempotent).unwrap();
+
+        // Token-2022 mint account, then the legacy WSOL account funded, synced, used and closed
+        let wsol_ata = get_associated_token_address(&payer.pubkey(), &PUBKEY_WSOL);
+        assert_eq!(instructions.len(), 6);
+        assert_eq!(instructions[0].accounts[ATA_TOKEN_PROGRAM_INDEX].pubkey, spl_token_2022::ID);
+        assert_eq!(instructions[1].accounts[1].pubkey, wsol_ata);
+        assert_eq!(instructions[1].accounts[ATA_TOKEN_PROGRAM_INDEX].pubkey, spl_token::ID);
+        assert_eq!(instructions[3].program_id, spl_token::ID);
+        assert_eq!(instructions[4], buy);
+        assert_eq!(instructions[5].program_id, spl_token::ID);
+        assert_eq!(instructions[5].accounts[0].pubkey, wsol_ata);
+    }
+
+    #[test]
+    fn seeded_token_account_is_owned_by_the_mint_token_program() {
+        let payer = Keypair::new();
+        let mint = Pubkey::new_unique();
+        for token_program in [spl_token::ID, spl_token_2022::ID] {
+            let (token_account, instructions) =
+                build_token_account_instructions(&payer, &mint, &token_program, CreateATA::CreateWithSeed("seed".to_string())).unwrap();
+            assert_eq!(token_account, Pubkey::create_with_seed(&payer.pubkey(), "seed", &token_program).unwrap());
+            assert_eq!(instructions.len(), 2);
+            assert_eq!(instructions[1].program_id, token_program);
+            assert_eq!(instructions[1].accounts[0].pubkey, token_account);
+        }
+    }
+}
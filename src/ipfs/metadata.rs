@@ -7,7 +7,17 @@ use std::time::Duration;
 use tokio::fs::File;
 use tokio::io::AsyncReadExt;
 
+pub const CREATED_ON_PUMPFUN: &str = "https://pump.fun";
+pub const CREATED_ON_LETSBONK: &str = "https://letsbonk.fun";
+pub const CREATED_ON_BOOPFUN: &str = "https://boop.fun";
+pub const CREATED_ON_BELIEVE: &str = "https://believe.app";
+
 pub async fn create_token_metadata(metadata: CreateTokenMetadata, jwt_token: &str) -> anyhow::Result<TokenMetadataIPFS> {
+    create_token_metadata_for(metadata, jwt_token, CREATED_ON_PUMPFUN).await
+}
+
+/// Upload metadata for a launch on the platform `created_on` points to
+pub async fn create_token_metadata_for(metadata: CreateTokenMetadata, jwt_token: &str, created_on: &str) -> anyhow::Result<TokenMetadataIPFS> {
     let ipfs_url = if metadata.file.starts_with("http") || metadata.metadata_uri.is_some() {
         metadata.file
     } else if metadata.file.starts_with("data:image/png;base64,") {
@@ -23,7 +33,7 @@ pub async fn create_token_metadata(metadata: CreateTokenMetadata, jwt_token: &st
         description: metadata.description,
         image: ipfs_url,
         show_name: true,
-        created_on: "https://pump.fun".to_string(),
+        created_on: created_on.to_string(),
         twitter: metadata.twitter,
         telegram: metadata.telegram,
         website: metadata.website,
@@ -32,7 +42,7 @@ pub async fn create_token_metadata(metadata: CreateTokenMetadata, jwt_token: &st
     if let Some(metadata_uri) = metadata.metadata_uri {
         let token_metadata_ipfs = TokenMetadataIPFS {
             metadata: token_metadata,
-            metadata_uri: metadata_uri,
+            metadata_uri,
         };
         Ok(token_metadata_ipfs)
     } else {
@@ -1,19 +1,11 @@
 use solana_client::nonblocking::rpc_client::RpcClient;
 use solana_sdk::{native_token::sol_str_to_lamports, pubkey::Pubkey, signature::Keypair};
 use solana_trading_sdk::{
-    common::{trading_endpoint::TradingEndpoint, TradingClient, TradingConfig},
-    dex::{
-        dex_traits::DexTrait,
-        pumpfun::Pumpfun,
-        types::{Create, DexType},
-    },
+    common::{TradingClient, TradingConfig},
+    dex::types::DexType,
     errors::trading_endpoint_error::TradingEndpointError,
     instruction::builder::PriorityFee,
-    ipfs::{metadata::create_token_metadata, types::CreateTokenMetadata},
-    swqos::{
-        blox::BLOX_ENDPOINT_FRA, default::DefaultSWQoSClient, jito::JITO_ENDPOINT_MAINNET, nextblock::NEXTBLOCK_ENDPOINT_FRA, temporal::TEMPORAL_ENDPOINT_FRA,
-        zeroslot::ZEROSLOT_ENDPOINT_FRA, SWQoSType,
-    },
+    swqos::default::DefaultSWQoSClient,
 };
 use std::{str::FromStr, sync::Arc};
 
@@ -29,8 +21,7 @@ pub fn get_solana_client() -> Arc<RpcClient> {
 }
 
 pub fn get_swqos_client() -> DefaultSWQoSClient {
-    let swqos_client = DefaultSWQoSClient::new("default", get_solana_client(), RPC_ENDPOINT.to_string(), None, vec![]);
-    swqos_client
+    DefaultSWQoSClient::new("default", get_solana_client(), RPC_ENDPOINT.to_string(), None, vec![])
 }
 
 pub async fn transfer_sol() -> Result<(), TradingEndpointError> {
@@ -67,6 +58,9 @@ pub async fn get_trading_client() -> Result<TradingClient, TradingEndpointError>
     let client = TradingClient::new(&TradingConfig {
         rpc_url: rpc_url.to_string(),
         swqos: vec![],
+        tip_payer: None,
+        leader_lookahead_slots: None,
+        ws_url: None,
     })
     .map_err(|e| TradingEndpointError::CustomError(e.to_string()))?;
 
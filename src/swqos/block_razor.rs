@@ -12,6 +12,8 @@ pub const BLOCK_RAZOR_ENDPOINT_NY: &str = "http://newyork.solana.blockrazor.xyz:
 pub const BLOCK_RAZOR_ENDPOINT_TOKYO: &str = "http://tokyo.solana.blockrazor.xyz:443";
 pub const BLOCK_RAZOR_ENDPOINT_AMS: &str = "http://amsterdam.solana.blockrazor.xyz:443";
 
+pub const BLOCK_RAZOR_MIN_TIP: u64 = 1_000_000;
+
 pub const BLOCK_RAZOR_TIP_ACCOUNTS: &[Pubkey] = &[
     pubkey!("FjmZZrFvhnqqb9ThCuMVnENaM3JGVuGWNyCAxRJcFpg9"),
     pubkey!("6No2i3aawzHsjtThw81iq1EXPJN6rh8eSJCLaYZfKDTG"),
@@ -22,6 +22,8 @@ pub const BLOX_ENDPOINT_UK: &str = "https://uk.solana.dex.blxrbdn.com";
 pub const BLOX_ENDPOINT_LA: &str = "https://la.solana.dex.blxrbdn.com";
 pub const BLOX_ENDPOINT_TOKYO: &str = "https://tokyo.solana.dex.blxrbdn.com";
 
+pub const BLOX_MIN_TIP: u64 = 1_000_000;
+
 #[derive(Clone)]
 pub struct BloxClient {
     pub rpc_client: Arc<RpcClient>,
@@ -81,7 +81,7 @@ impl DefaultSWQoSClient {
         }
     }
 
-    pub async fn transfer(&self, from: &Keypair, to: &Pubkey, amount: u64, fee: Option<PriorityFee>) -> Result<Signature, SWQoSError> {
+    pub async fn transfer(&self, from: &Keypair, to: &Pubkey, amount: u64, _fee: Option<PriorityFee>) -> Result<Signature, SWQoSError> {
         let blockhash = self.rpc_client.get_latest_blockhash().await?;
         let instruction = solana_sdk::system_instruction::transfer(&from.pubkey(), to, amount);
         let transaction = build_transaction(from, vec![instruction], blockhash, None).map_err(|e| SWQoSError::Custom(e.to_string()))?;
@@ -93,7 +93,7 @@ impl DefaultSWQoSClient {
         Ok(signature)
     }
 
-    pub async fn batch_transfer(&self, from: &Keypair, to: Vec<TransferInfo>, fee: Option<PriorityFee>) -> Result<Signature, SWQoSError> {
+    pub async fn batch_transfer(&self, from: &Keypair, to: Vec<TransferInfo>, _fee: Option<PriorityFee>) -> Result<Signature, SWQoSError> {
         let blockhash = self.rpc_client.get_latest_blockhash().await?;
         let instructions = to
             .iter()
@@ -108,11 +108,11 @@ impl DefaultSWQoSClient {
         Ok(signature)
     }
 
-    pub async fn spl_transfer(&self, from: &Keypair, to: &Pubkey, mint: &Pubkey, amount: u64, fee: Option<PriorityFee>) -> Result<Signature, SWQoSError> {
+    pub async fn spl_transfer(&self, from: &Keypair, to: &Pubkey, mint: &Pubkey, amount: u64, _fee: Option<PriorityFee>) -> Result<Signature, SWQoSError> {
         let blockhash = self.rpc_client.get_latest_blockhash().await?;
         let from_ata = get_associated_token_address(&from.pubkey(), mint);
         let to_ata = get_associated_token_address(to, mint);
-        let create_ata = create_associated_token_account_idempotent(&from.pubkey(), to, &mint, &spl_token::ID);
+        let create_ata = create_associated_token_account_idempotent(&from.pubkey(), to, mint, &spl_token::ID);
         let instruction =
             spl_token::instruction::transfer(&spl_token::ID, &from_ata, &to_ata, &from.pubkey(), &[], amount).map_err(|e| SWQoSError::Custom(e.to_string()))?;
         let transaction = build_transaction(from, vec![create_ata, instruction], blockhash, None).map_err(|e| SWQoSError::Custom(e.to_string()))?;
@@ -124,14 +124,14 @@ impl DefaultSWQoSClient {
         Ok(signature)
     }
 
-    pub async fn spl_batch_transfer(&self, from: &Keypair, to: Vec<TransferInfo>, mint: &Pubkey, fee: Option<PriorityFee>) -> Result<Signature, SWQoSError> {
+    pub async fn spl_batch_transfer(&self, from: &Keypair, to: Vec<TransferInfo>, mint: &Pubkey, _fee: Option<PriorityFee>) -> Result<Signature, SWQoSError> {
         let blockhash = self.rpc_client.get_latest_blockhash().await?;
         let from_ata = get_associated_token_address(&from.pubkey(), mint);
         let mut instructions = Vec::new();
 
         for transfer in &to {
             let to_ata = get_associated_token_address(&transfer.to, mint);
-            let create_ata = create_associated_token_account_idempotent(&from.pubkey(), &transfer.to, &mint, &spl_token::ID);
+            let create_ata = create_associated_token_account_idempotent(&from.pubkey(), &transfer.to, mint, &spl_token::ID);
             let instruction = spl_token::instruction::transfer(&spl_token::ID, &from_ata, &to_ata, &from.pubkey(), &[], transfer.amount)
                 .map_err(|e| SWQoSError::Custom(e.to_string()))?;
             instructions.push(create_ata);
@@ -154,7 +154,7 @@ impl DefaultSWQoSClient {
             if let Ok(tx) = self
                 .rpc_client
                 .get_transaction_with_config(
-                    &signature,
+                    signature,
                     RpcTransactionConfig {
                         encoding: Some(UiTransactionEncoding::Json),
                         commitment: Some(CommitmentConfig::confirmed()),
@@ -2,11 +2,13 @@ use super::{
     swqos_rpc::{SWQoSClientTrait, SWQoSRequest},
     SWQoSTrait,
 };
-use crate::{common::transaction::Transaction, errors::swqos_error::SWQoSError};
+use crate::{common::transaction::Transaction, errors::swqos_error::SWQoSError, swqos::chunk_accounts};
+use parking_lot::RwLock;
 use rand::seq::IndexedRandom;
 use solana_client::nonblocking::rpc_client::RpcClient;
 use solana_sdk::{pubkey, pubkey::Pubkey};
-use std::sync::Arc;
+use std::{str::FromStr, sync::Arc};
+use tracing::info;
 
 pub const JITO_TIP_ACCOUNTS: &[Pubkey] = &[
     pubkey!("96gYZGLnJYVFmbjzopPSU6QiEV5fGqZNyN9nmNhvrZU5"),
@@ -33,12 +35,16 @@ pub const JITO_RELAYER_NY: &str = "http://ny.mainnet.relayer.jito.wtf:8100";
 pub const JITO_RELAYER_FRA: &str = "http://frankfurt.mainnet.relayer.jito.wtf:8100";
 pub const JITO_RELAYER_LONDON: &str = "http://london.mainnet.relayer.jito.wtf:8100";
 
+pub const JITO_MIN_TIP: u64 = 1_000;
+
 #[derive(Clone)]
 pub struct JitoClient {
     pub rpc_client: Arc<RpcClient>,
     pub swqos_endpoint: String,
     pub swqos_client: Arc<reqwest::Client>,
-    pub tip_accounts: Vec<Pubkey>,
+    pub tip_accounts: Arc<RwLock<Vec<Pubkey>>>,
+    pub shard_index: usize,
+    pub shard_count: usize,
 }
 
 #[async_trait::async_trait]
@@ -79,8 +85,24 @@ impl SWQoSTrait for JitoClient {
             .await
     }
 
+    async fn refresh_tip_accounts(&self) -> Result<(), SWQoSError> {
+        let accounts = self.fetch_tip_accounts().await?;
+        let shard = chunk_accounts(&accounts, self.shard_count as u64)
+            .into_iter()
+            .nth(self.shard_index)
+            .unwrap_or(accounts);
+        if shard.is_empty() {
+            return Err(SWQoSError::Custom("getTipAccounts returned no accounts".to_string()));
+        }
+
+        info!("jito tip accounts refreshed: {:?}", shard);
+        *self.tip_accounts.write() = shard;
+        Ok(())
+    }
+
     fn get_tip_account(&self) -> Option<Pubkey> {
-        Some(*self.tip_accounts.choose(&mut rand::rng())?)
+        let tip_accounts = self.tip_accounts.read();
+        Some(*tip_accounts.choose(&mut rand::rng())?)
     }
 
     fn get_name(&self) -> &str {
@@ -96,7 +118,157 @@ impl JitoClient {
             rpc_client,
             swqos_endpoint: endpoint,
             swqos_client: Arc::new(swqos_client),
-            tip_accounts,
+            tip_accounts: Arc::new(RwLock::new(tip_accounts)),
+            shard_index: 0,
+            shard_count: 1,
         }
     }
+
+    /// Keep only the `index`-th of `count` shards of the fetched tip accounts on refresh
+    pub fn with_shard(mut self, index: usize, count: usize) -> Self {
+        self.shard_index = index;
+        self.shard_count = count.max(1);
+        self
+    }
+
+    pub async fn fetch_tip_accounts(&self) -> Result<Vec<Pubkey>, SWQoSError> {
+        let body = serde_json::json!({
+            "jsonrpc": "2.0",
+            "method": "getTipAccounts",
+            "params": [],
+            "id": 1,
+        });
+
+        let response = self
+            .swqos_client
+            .post(format!("{}/api/v1/getTipAccounts", self.swqos_endpoint))
+            .json(&body)
+            .send()
+            .await?;
+        let response_json: serde_json::Value = serde_json::from_str(&response.text().await?)?;
+        let accounts = response_json["result"]
+            .as_array()
+            .ok_or_else(|| SWQoSError::Custom(format!("getTipAccounts invalid response: {}", response_json)))?;
+
+        accounts
+            .iter()
+            .map(|account| {
+                let account = account
+                    .as_str()
+                    .ok_or_else(|| SWQoSError::Custom(format!("getTipAccounts invalid account: {}", account)))?;
+                Pubkey::from_str(account).map_err(|e| SWQoSError::Custom(e.to_string()))
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::{
+        io::{AsyncReadExt, AsyncWriteExt},
+        net::TcpListener,
+    };
+
+    /// Serve `body` as the JSON response to a single request, returning the endpoint and the request it received
+    async fn mock_block_engine(body: serde_json::Value) -> (String, tokio::task::JoinHandle<String>) {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let endpoint = format!("http://{}", listener.local_addr().unwrap());
+        let handle = tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let mut request = vec![];
+            let mut buffer = [0u8; 4096];
+            // Read until the end of the headers and the whole body announced by them
+            loop {
+                let read = stream.read(&mut buffer).await.unwrap();
+                request.extend_from_slice(&buffer[..read]);
+                let text = String::from_utf8_lossy(&request).to_string();
+                if let Some(headers_end) = text.find("\r\n\r\n") {
+                    let content_length = text[..headers_end]
+                        .lines()
+                        .find_map(|line| {
+                            line.to_ascii_lowercase()
+                                .strip_prefix("content-length:")
+                                .map(|w| w.trim().parse::<usize>().unwrap())
+                        })
+                        .unwrap_or(0);
+                    if request.len() >= headers_end + 4 + content_length || read == 0 {
+                        break;
+                    }
+                }
+            }
+
+            let body = body.to_string();
+            let response = format!(
+                "HTTP/1.1 200 OK\r\ncontent-type: application/json\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{}",
+                body.len(),
+                body
+            );
+            stream.write_all(response.as_bytes()).await.unwrap();
+            String::from_utf8(request).unwrap()
+        });
+        (endpoint, handle)
+    }
+
+    fn jito_client(endpoint: String) -> JitoClient {
+        JitoClient::new(
+            Arc::new(RpcClient::new("http://127.0.0.1:8899".to_string())),
+            endpoint,
+            JITO_TIP_ACCOUNTS.into(),
+        )
+    }
+
+    #[tokio::test]
+    async fn refresh_replaces_the_tip_accounts_with_the_fetched_ones() {
+        let fetched = [Pubkey::new_unique(), Pubkey::new_unique()];
+        let (endpoint, request) = mock_block_engine(serde_json::json!({
+            "jsonrpc": "2.0",
+            "result": fetched.iter().map(|w| w.to_string()).collect::<Vec<_>>(),
+            "id": 1,
+        }))
+        .await;
+        let client = jito_client(endpoint);
+
+        client.refresh_tip_accounts().await.unwrap();
+        assert_eq!(*client.tip_accounts.read(), fetched.to_vec());
+        assert!(fetched.contains(&client.get_tip_account().unwrap()));
+
+        let request = request.await.unwrap();
+        assert!(request.starts_with("POST /api/v1/getTipAccounts"));
+        assert!(request.contains("\"method\":\"getTipAccounts\""));
+    }
+
+    #[tokio::test]
+    async fn refresh_keeps_only_the_client_shard() {
+        let fetched = [Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique(), Pubkey::new_unique()];
+        let (endpoint, _) = mock_block_engine(serde_json::json!({
+            "jsonrpc": "2.0",
+            "result": fetched.iter().map(|w| w.to_string()).collect::<Vec<_>>(),
+            "id": 1,
+        }))
+        .await;
+        let client = jito_client(endpoint).with_shard(1, 2);
+
+        client.refresh_tip_accounts().await.unwrap();
+        assert_eq!(*client.tip_accounts.read(), fetched[2..].to_vec());
+    }
+
+    #[tokio::test]
+    async fn failed_refresh_keeps_the_built_in_accounts() {
+        let (endpoint, _) = mock_block_engine(serde_json::json!({
+            "jsonrpc": "2.0",
+            "error": { "code": -32601, "message": "Method not found" },
+            "id": 1,
+        }))
+        .await;
+        let client = jito_client(endpoint);
+
+        assert!(client.refresh_tip_accounts().await.is_err());
+        assert_eq!(*client.tip_accounts.read(), JITO_TIP_ACCOUNTS.to_vec());
+
+        let (endpoint, _) = mock_block_engine(serde_json::json!({ "jsonrpc": "2.0", "result": [], "id": 1 })).await;
+        let client = jito_client(endpoint);
+        assert!(client.refresh_tip_accounts().await.is_err());
+        assert_eq!(*client.tip_accounts.read(), JITO_TIP_ACCOUNTS.to_vec());
+    }
 }
@@ -11,10 +11,10 @@ pub mod zeroslot;
 
 use crate::common::lamports::Lamports;
 use crate::instruction::builder::PriorityFee;
-use crate::swqos::block_razor::{BlockRazorClient, BLOCK_RAZOR_TIP_ACCOUNTS};
-use crate::swqos::blox::BLOX_TIP_ACCOUNTS;
-use crate::swqos::jito::JITO_TIP_ACCOUNTS;
-use crate::swqos::nextblock::NEXTBLOCK_TIP_ACCOUNTS;
+use crate::swqos::block_razor::{BlockRazorClient, BLOCK_RAZOR_MIN_TIP, BLOCK_RAZOR_TIP_ACCOUNTS};
+use crate::swqos::blox::{BLOX_MIN_TIP, BLOX_TIP_ACCOUNTS};
+use crate::swqos::jito::{JITO_MIN_TIP, JITO_TIP_ACCOUNTS};
+use crate::swqos::nextblock::{NEXTBLOCK_MIN_TIP, NEXTBLOCK_TIP_ACCOUNTS};
 use blox::BloxClient;
 use default::DefaultSWQoSClient;
 use jito::JitoClient;
@@ -23,8 +23,8 @@ use serde::{Deserialize, Serialize};
 use solana_client::nonblocking::rpc_client::RpcClient;
 use solana_sdk::pubkey::Pubkey;
 use std::{any::Any, sync::Arc};
-use temporal::TEMPORAL_TIP_ACCOUNTS;
-use zeroslot::ZEROSLOT_TIP_ACCOUNTS;
+use temporal::{TEMPORAL_MIN_TIP, TEMPORAL_TIP_ACCOUNTS};
+use zeroslot::{ZEROSLOT_MIN_TIP, ZEROSLOT_TIP_ACCOUNTS};
 
 // (endpoint, auth_token)
 #[derive(Debug, Clone, PartialEq, Eq, Hash, Deserialize, Serialize)]
@@ -38,6 +38,19 @@ pub enum SWQoSType {
     BlockRazor(String, String),
 }
 
+/// Where the tip transfer is placed relative to the trade instructions
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize, Serialize)]
+pub enum TipPlacement {
+    /// Tip right after the compute budget instructions, before the trade
+    #[default]
+    InlineFirst,
+    /// Tip after the trade instructions
+    InlineLast,
+    /// Tip in its own transaction, sent after the trade in the same bundle, only for providers with atomic bundles.
+    /// Trades on a durable nonce keep the tip last in the trade instead, as the nonce only validates one transaction.
+    Separate,
+}
+
 #[derive(Debug, Clone, Deserialize, Serialize)]
 pub struct SWQoSConfig {
     pub kind: SWQoSType,
@@ -50,6 +63,10 @@ pub struct SWQoSConfig {
     pub sell_tip: Option<Lamports>,
     #[serde(default)]
     pub sell_fee: Option<PriorityFee>,
+    #[serde(default)]
+    pub min_tip: Option<Lamports>,
+    #[serde(default)]
+    pub tip_placement: TipPlacement,
 }
 
 pub struct SWQoSRuntime {
@@ -61,10 +78,28 @@ pub struct SWQoSRuntime {
 pub trait SWQoSTrait: Send + Sync + Any {
     async fn send_transaction(&self, transaction: Transaction) -> Result<(), SWQoSError>;
     async fn send_transactions(&self, transactions: Vec<Transaction>) -> Result<(), SWQoSError>;
+    /// Refresh the tip accounts from the provider API, keeping the current ones when none exists
+    async fn refresh_tip_accounts(&self) -> Result<(), SWQoSError> {
+        Ok(())
+    }
     fn get_tip_account(&self) -> Option<Pubkey>;
     fn get_name(&self) -> &str;
 }
 
+#[derive(Debug, thiserror::Error)]
+pub enum TipError {
+    #[error("Tip of {tip} lamports is below the {min_tip} lamports minimum of SWQoS: {provider}")]
+    BelowMinimum { provider: String, tip: u64, min_tip: u64 },
+    #[error("SWQoS {provider} cannot send atomic bundles, so its tip cannot be a separate transaction")]
+    SeparateUnsupported { provider: String },
+}
+
+pub(crate) fn chunk_accounts(accounts: &[Pubkey], threads: u64) -> Vec<Vec<Pubkey>> {
+    let threads = threads.min(accounts.len() as u64).max(1) as usize;
+    let chunk_size = accounts.len().div_ceil(threads).max(1);
+    accounts.chunks(chunk_size).map(|c| c.to_vec()).collect()
+}
+
 impl SWQoSConfig {
     pub fn new(kind: SWQoSType) -> Self {
         Self {
@@ -74,6 +109,8 @@ impl SWQoSConfig {
             buy_fee: None,
             sell_tip: None,
             sell_fee: None,
+            min_tip: None,
+            tip_placement: TipPlacement::default(),
         }
     }
 
@@ -114,12 +151,37 @@ impl SWQoSConfig {
         self
     }
 
+    pub fn with_min_tip(mut self, tip: Lamports) -> Self {
+        self.min_tip = Some(tip);
+        self
+    }
+
+    pub fn with_tip_placement(mut self, tip_placement: TipPlacement) -> Self {
+        self.tip_placement = tip_placement;
+        self
+    }
+
+    /// Reject settings the provider cannot honour, a separate tip needing an atomic bundle to never land without the trade
+    pub fn validate(&self) -> Result<(), TipError> {
+        if self.tip_placement == TipPlacement::Separate && !self.kind.supports_bundles() {
+            return Err(TipError::SeparateUnsupported {
+                provider: self.kind.get_name().to_string(),
+            });
+        }
+        Ok(())
+    }
+
+    /// Minimum tip set with `with_min_tip`, tips below it are rejected before anything is sent
+    pub fn get_min_tip(&self) -> Option<Lamports> {
+        self.min_tip
+    }
+
     pub fn get_buy_config(&self) -> (Option<Lamports>, Option<PriorityFee>) {
-        (self.buy_tip, self.buy_fee.clone())
+        (self.buy_tip, self.buy_fee)
     }
 
     pub fn get_sell_config(&self) -> (Option<Lamports>, Option<PriorityFee>) {
-        (self.sell_tip, self.sell_fee.clone())
+        (self.sell_tip, self.sell_fee)
     }
 
     /// Create multiple SWQoSRuntime instances based on the threads configuration
@@ -161,16 +223,54 @@ impl SWQoSRuntime {
         self.client.send_transactions(transactions).await
     }
 
+    pub async fn refresh_tip_accounts(&self) -> Result<(), SWQoSError> {
+        self.client.refresh_tip_accounts().await
+    }
+
     pub fn get_tip_account(&self) -> Option<Pubkey> {
         self.client.get_tip_account()
     }
 
+    pub fn get_min_tip(&self) -> Option<Lamports> {
+        self.config.get_min_tip()
+    }
+
     pub fn get_client_name(&self) -> &str {
         self.client.get_name()
     }
 }
 
 impl SWQoSType {
+    /// Whether `send_transactions` lands all transactions or none, only Jito's `sendBundle` does
+    pub fn supports_bundles(&self) -> bool {
+        matches!(self, SWQoSType::Jito(_))
+    }
+
+    pub fn get_name(&self) -> &'static str {
+        match self {
+            SWQoSType::Default(_, _) => "default",
+            SWQoSType::Jito(_) => "jito",
+            SWQoSType::NextBlock(_, _) => "nextblock",
+            SWQoSType::Blox(_, _) => "blox",
+            SWQoSType::Temporal(_, _) => "temporal",
+            SWQoSType::ZeroSlot(_, _) => "zeroslot",
+            SWQoSType::BlockRazor(_, _) => "blockrazor",
+        }
+    }
+
+    /// Smallest tip the provider is known to forward, lower tips are only warned about unless a minimum tip is configured
+    pub fn default_min_tip(&self) -> Lamports {
+        match self {
+            SWQoSType::Default(_, _) => Lamports(0),
+            SWQoSType::Jito(_) => Lamports(JITO_MIN_TIP),
+            SWQoSType::NextBlock(_, _) => Lamports(NEXTBLOCK_MIN_TIP),
+            SWQoSType::Blox(_, _) => Lamports(BLOX_MIN_TIP),
+            SWQoSType::Temporal(_, _) => Lamports(TEMPORAL_MIN_TIP),
+            SWQoSType::ZeroSlot(_, _) => Lamports(ZEROSLOT_MIN_TIP),
+            SWQoSType::BlockRazor(_, _) => Lamports(BLOCK_RAZOR_MIN_TIP),
+        }
+    }
+
     fn instantiate(&self, rpc_client: Arc<RpcClient>) -> Arc<dyn SWQoSTrait> {
         match self {
             SWQoSType::Default(endpoint, header) => Arc::new(DefaultSWQoSClient::new("default", rpc_client, endpoint.to_string(), header.clone(), vec![])),
@@ -217,11 +317,6 @@ impl SWQoSType {
 
     fn instantiate_many(&self, rpc_client: Arc<RpcClient>, threads: u64) -> Vec<Arc<dyn SWQoSTrait>> {
         let threads = threads.max(1); // avoid zero threads
-        fn chunk_accounts(accounts: &[Pubkey], threads: u64) -> Vec<Vec<Pubkey>> {
-            let threads = threads.min(accounts.len() as u64).max(1) as usize;
-            let chunk_size = (accounts.len() + threads - 1) / threads;
-            accounts.chunks(chunk_size).map(|c| c.to_vec()).collect()
-        }
 
         match self {
             SWQoSType::Default(endpoint, header) => (0..threads)
@@ -237,15 +332,19 @@ impl SWQoSType {
                 .collect(),
 
             SWQoSType::Jito(endpoint) => {
-                let chunks = chunk_accounts(&JITO_TIP_ACCOUNTS, threads);
+                let chunks = chunk_accounts(JITO_TIP_ACCOUNTS, threads);
+                let shards = chunks.len();
                 chunks
                     .into_iter()
-                    .map(|chunk| Arc::new(JitoClient::new(rpc_client.clone(), endpoint.to_string(), chunk)) as Arc<dyn SWQoSTrait>)
+                    .enumerate()
+                    .map(|(index, chunk)| {
+                        Arc::new(JitoClient::new(rpc_client.clone(), endpoint.to_string(), chunk).with_shard(index, shards)) as Arc<dyn SWQoSTrait>
+                    })
                     .collect()
             }
 
             SWQoSType::NextBlock(endpoint, auth_token) => {
-                let chunks = chunk_accounts(&NEXTBLOCK_TIP_ACCOUNTS, threads);
+                let chunks = chunk_accounts(NEXTBLOCK_TIP_ACCOUNTS, threads);
                 chunks
                     .into_iter()
                     .map(|chunk| Arc::new(NextBlockClient::new(rpc_client.clone(), endpoint.to_string(), auth_token.to_string(), chunk)) as Arc<dyn SWQoSTrait>)
@@ -253,7 +352,7 @@ impl SWQoSType {
             }
 
             SWQoSType::Blox(endpoint, auth_token) => {
-                let chunks = chunk_accounts(&BLOX_TIP_ACCOUNTS, threads);
+                let chunks = chunk_accounts(BLOX_TIP_ACCOUNTS, threads);
                 chunks
                     .into_iter()
                     .map(|chunk| Arc::new(BloxClient::new(rpc_client.clone(), endpoint.to_string(), auth_token.to_string(), chunk)) as Arc<dyn SWQoSTrait>)
@@ -261,7 +360,7 @@ impl SWQoSType {
             }
 
             SWQoSType::BlockRazor(endpoint, auth_token) => {
-                let chunks = chunk_accounts(&BLOCK_RAZOR_TIP_ACCOUNTS, threads);
+                let chunks = chunk_accounts(BLOCK_RAZOR_TIP_ACCOUNTS, threads);
                 chunks
                     .into_iter()
                     .map(|chunk| Arc::new(BlockRazorClient::new(endpoint.to_string(), auth_token.to_string(), chunk)) as Arc<dyn SWQoSTrait>)
@@ -269,7 +368,7 @@ impl SWQoSType {
             }
 
             SWQoSType::ZeroSlot(endpoint, auth_token) => {
-                let chunks = chunk_accounts(&ZEROSLOT_TIP_ACCOUNTS, threads);
+                let chunks = chunk_accounts(ZEROSLOT_TIP_ACCOUNTS, threads);
                 chunks
                     .into_iter()
                     .map(|chunk| {
@@ -285,7 +384,7 @@ impl SWQoSType {
             }
 
             SWQoSType::Temporal(endpoint, auth_token) => {
-                let chunks = chunk_accounts(&TEMPORAL_TIP_ACCOUNTS, threads);
+                let chunks = chunk_accounts(TEMPORAL_TIP_ACCOUNTS, threads);
                 chunks
                     .into_iter()
                     .map(|chunk| {
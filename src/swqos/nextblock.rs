@@ -19,6 +19,8 @@ pub const NEXTBLOCK_TIP_ACCOUNTS: &[Pubkey] = &[
 pub const NEXTBLOCK_ENDPOINT_FRA: &str = "https://fra.nextblock.io";
 pub const NEXTBLOCK_ENDPOINT_NY: &str = "https://ny.nextblock.io";
 
+pub const NEXTBLOCK_MIN_TIP: u64 = 1_000_000;
+
 #[derive(Clone)]
 pub struct NextBlockClient {
     pub rpc_client: Arc<RpcClient>,
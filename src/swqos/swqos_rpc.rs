@@ -112,14 +112,8 @@ impl SWQoSClientTrait for reqwest::Client {
         let response_body = response.text().await?;
 
         let response_json: serde_json::Value = serde_json::from_str(&response_body)?;
-        if let Some(error_value) = response_json.get("error").filter(|e| !e.to_string().is_empty() && e.to_string() != "\"\"") {
-            let error_msg = format!(
-                "swqos_json_post error: {} {} {} error: {}",
-                request.name,
-                txs_hash,
-                http_status,
-                error_value.to_string()
-            );
+        if let Some(error_value) = response_json.get("error").filter(|e| !e.to_string().is_empty() && *e != "") {
+            let error_msg = format!("swqos_json_post error: {} {} {} error: {}", request.name, txs_hash, http_status, error_value);
             error!("{}", error_msg);
             return Err(SWQoSError::Custom(error_msg));
         }
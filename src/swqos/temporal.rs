@@ -24,3 +24,5 @@ pub const TEMPORAL_ENDPOINT_FRA: &str = "http://fra2.nozomi.temporal.xyz";
 pub const TEMPORAL_ENDPOINT_EWR: &str = "http://ewr1.nozomi.temporal.xyz";
 pub const TEMPORAL_ENDPOINT_PITT: &str = "http://pit1.nozomi.temporal.xyz";
 pub const TEMPORAL_ENDPOINT_AMS: &str = "http://ams1.nozomi.temporal.xyz";
+
+pub const TEMPORAL_MIN_TIP: u64 = 1_000_000;
@@ -13,3 +13,5 @@ pub const ZEROSLOT_ENDPOINT_NY: &str = "https://ny.0slot.trade";
 pub const ZEROSLOT_ENDPOINT_AMS: &str = "https://ams.0slot.trade";
 pub const ZEROSLOT_ENDPOINT_TOKYO: &str = "https://jp.0slot.trade";
 pub const ZEROSLOT_ENDPOINT_LA: &str = "https://la.0slot.trade";
+
+pub const ZEROSLOT_MIN_TIP: u64 = 1_000_000;